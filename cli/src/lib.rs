@@ -1,46 +1,169 @@
-use std::{error::Error, fs, path::PathBuf};
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
 
-use belalang_core::{lexer::Lexer, parser::Parser};
-use belalang_eval::evaluator::Evaluator;
+use belalang_core::{
+    diagnostic::Diagnostic, format::format, lexer::Lexer, lint::Linter, parser::Parser,
+    source::SourceMap,
+};
+use belalang_eval::{
+    builtins::Builtins,
+    bytecode::{disassemble, Bytecode},
+    compiler::Compiler,
+    interpreter::Interpreter,
+    pass::Passes,
+    repl::{ReplEngine, Reply},
+};
 use rustyline::{error::ReadlineError, DefaultEditor};
 
-pub fn run_file(filename: PathBuf) -> Result<(), Box<dyn Error>> {
-    let file = fs::read(filename).expect("Unable to read file!");
+/// Runs a script, or a `.blc` file made by [`compile`].
+pub fn run_file(filename: PathBuf, args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let mut interpreter = Interpreter::default();
+    interpreter
+        .evaluator_mut()
+        .set_script_args(Some(&filename.to_string_lossy()), args);
 
-    let lexer = Lexer::new(file.as_slice());
-    let mut parser = Parser::new(lexer);
-    let program = parser.parse_program()?;
+    if filename.extension().is_some_and(|ext| ext == "blc") {
+        let bytecode = Bytecode::load(BufReader::new(File::open(&filename)?))?;
+        interpreter.run_bytecode(&bytecode)?;
+        return Ok(());
+    }
+
+    let file = fs::read_to_string(&filename).expect("Unable to read file!");
+    interpreter.eval_str(&file)?;
+    Ok(())
+}
+
+/// Compiles a script to a `.blc` file, by default next to it, so that
+/// running it doesn't parse and compile it again. Returns where it was
+/// written.
+pub fn compile(filename: PathBuf, output: Option<PathBuf>) -> Result<PathBuf, Box<dyn Error>> {
+    let bytecode = compile_file(&filename)?;
+    let output = output.unwrap_or_else(|| filename.with_extension("blc"));
+
+    bytecode.save(BufWriter::new(File::create(&output)?))?;
+    Ok(output)
+}
+
+fn compile_file(filename: &Path) -> Result<Bytecode, Box<dyn Error>> {
+    let source = fs::read_to_string(filename)?;
+    let mut program = Parser::new(Lexer::new(source.as_bytes()))
+        .parse_program()
+        .map_err(belalang_eval::error::Error::from)?;
+    Passes::standard().run(&mut program);
+
+    let bytecode = Compiler::new()
+        .compile_program(&program)
+        .map_err(belalang_eval::error::Error::from)?;
+
+    Ok(bytecode)
+}
+
+/// Prints the AST of `filename`, or of stdin if it's `-`. The script is
+/// lexed as it's read.
+pub fn dump_ast(filename: PathBuf) -> Result<(), Box<dyn Error>> {
+    let lexer = match filename.as_path() == Path::new("-") {
+        true => Lexer::from_reader(io::stdin().lock()),
+        false => Lexer::from_reader(File::open(&filename)?),
+    };
+    let program = Parser::new(lexer)
+        .parse_program()
+        .map_err(belalang_eval::error::Error::from)?;
+
+    println!("{}", program.to_sexpr());
+    Ok(())
+}
 
-    let mut ev = Evaluator::default();
-    ev.eval_program(program)?;
+/// Prints the bytecode `filename` compiles to instead of running it.
+pub fn dump_bytecode(filename: PathBuf) -> Result<(), Box<dyn Error>> {
+    let bytecode = compile_file(&filename)?;
+    print!("{}", disassemble(&bytecode));
     Ok(())
 }
 
+/// Formats `files` in place, or with `check` only lists the ones that
+/// aren't formatted. Returns whether all of them were.
+pub fn fmt(files: &[PathBuf], check: bool) -> Result<bool, Box<dyn Error>> {
+    let mut formatted = true;
+
+    for file in files {
+        let source = fs::read_to_string(file)?;
+        let output = format(&source).map_err(belalang_eval::error::Error::from)?;
+
+        if output == source {
+            continue;
+        }
+
+        formatted = false;
+        match check {
+            true => println!("would reformat {}", file.display()),
+            false => fs::write(file, output)?,
+        }
+    }
+
+    Ok(formatted || !check)
+}
+
+/// The config [`lint`] reads when not given one, if it exists.
+pub const LINT_CONFIG: &str = "belalang-lint.toml";
+
+/// Lints `files`, returning the diagnostics and the sources they point into.
+/// A file that doesn't parse gets a single error.
+pub fn lint(
+    files: &[PathBuf],
+    config: Option<PathBuf>,
+) -> Result<(SourceMap, Vec<Diagnostic>), Box<dyn Error>> {
+    let builtins = Builtins::default();
+    let mut linter = Linter::new().with_builtins(builtins.names());
+
+    match config {
+        Some(config) => linter.configure(&fs::read_to_string(config)?)?,
+        None => {
+            if let Ok(config) = fs::read_to_string(LINT_CONFIG) {
+                linter.configure(&config)?;
+            }
+        }
+    }
+
+    let mut sources = SourceMap::new();
+    let mut diagnostics = Vec::new();
+
+    for file in files {
+        let id = sources.add(file.display().to_string(), fs::read_to_string(file)?);
+        let source = sources.get(id);
+
+        let lexer = Lexer::new(source.text().as_bytes()).with_base(source.base());
+        match Parser::new(lexer).parse_program() {
+            Ok(program) => diagnostics.extend(linter.lint(&program)),
+            Err(err) => {
+                diagnostics.push(Diagnostic::from(&err).with_note(format!("in {}", file.display())))
+            }
+        }
+    }
+
+    Ok((sources, diagnostics))
+}
+
 pub fn repl() -> Result<(), Box<dyn Error>> {
     println!("Welcome to Belalang REPL v{}!\n", env!("CARGO_PKG_VERSION"));
 
     let mut rl = DefaultEditor::new()?;
-    let mut ev = Evaluator::default();
+    let mut engine = ReplEngine::default();
 
     loop {
-        match rl.readline(">> ") {
+        match rl.readline(engine.prompt()) {
             Ok(line) => {
                 let _ = rl.add_history_entry(line.as_str());
 
-                let lexer = Lexer::new(line.as_bytes().into());
-                let mut parser = Parser::new(lexer);
-
-                match parser.parse_program() {
-                    Ok(program) => match ev.eval_program(program) {
-                        Ok(evaluated) => println!("{}", evaluated),
-                        Err(msg) => println!("{}", msg),
-                    },
-                    Err(err) => {
-                        println!("{}", err);
-                    }
-                };
+                match engine.eval_line(&line) {
+                    Reply::Value(value) | Reply::Error(value) => println!("{}", value),
+                    Reply::Incomplete => {}
+                }
             }
-            Err(ReadlineError::Interrupted) => {}
+            Err(ReadlineError::Interrupted) => engine.cancel(),
             Err(ReadlineError::Eof) => {
                 println!("\nSee you, space cowboy...");
                 break;
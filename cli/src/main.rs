@@ -1,22 +1,123 @@
-use belalang_cli::{repl, run_file};
+use belalang_cli::{compile, dump_ast, dump_bytecode, fmt, lint, repl, run_file};
+use belalang_core::diagnostic::{self, Diagnostic, Severity};
+use belalang_eval::error::{Error as EvalError, EvaluatorError};
 use clap::Parser;
 use std::{error::Error, path::PathBuf};
 
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ErrorFormat {
+    Human,
+    Json,
+}
+
 #[derive(clap::Parser)]
-struct CLI {
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     filename: Option<PathBuf>,
+
+    /// Arguments passed to the script as `ARGS`
+    #[arg(
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        requires = "filename"
+    )]
+    args: Vec<String>,
+
+    /// Print the bytecode the script compiles to instead of running it
+    #[arg(long, requires = "filename")]
+    dump_bytecode: bool,
+
+    /// How to print script errors; `json` prints a diagnostic object
+    #[arg(long, value_enum, default_value = "human", global = true)]
+    error_format: ErrorFormat,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Print the syntax tree of a script as s-expressions
+    Ast {
+        /// The script, or `-` for stdin
+        filename: PathBuf,
+    },
+
+    /// Compile a script to a `.blc` file, which runs without parsing it again
+    Compile {
+        filename: PathBuf,
+
+        /// Where to write it [default: the script's path with a `.blc`
+        /// extension]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Format scripts in place
+    Fmt {
+        /// Don't write the files, fail if any isn't formatted
+        #[arg(long)]
+        check: bool,
+
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+
+    /// Check scripts for likely mistakes
+    Lint {
+        /// Rule levels, one `rule = "level"` per line [default:
+        /// belalang-lint.toml if it exists]
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let cli = CLI::parse();
+    let cli = Cli::parse();
 
-    let result = match cli.filename {
-        Some(filename) => run_file(filename),
-        None => repl(),
+    let result = match (cli.command, cli.filename) {
+        (Some(Command::Ast { filename }), _) => dump_ast(filename),
+        (Some(Command::Compile { filename, output }), _) => compile(filename, output).map(|_| ()),
+        (Some(Command::Fmt { check, files }), _) => match fmt(&files, check) {
+            Ok(false) => std::process::exit(1),
+            result => result.map(|_| ()),
+        },
+        (Some(Command::Lint { config, files }), _) => match lint(&files, config) {
+            Ok((sources, diagnostics)) => {
+                match cli.error_format {
+                    ErrorFormat::Human => diagnostics
+                        .iter()
+                        .for_each(|diagnostic| println!("{}", diagnostic.render(&sources))),
+                    ErrorFormat::Json => println!("{}", diagnostic::to_json(&diagnostics)),
+                }
+
+                if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+            Err(err) => Err(err),
+        },
+        (None, Some(filename)) if cli.dump_bytecode => dump_bytecode(filename),
+        (None, Some(filename)) => run_file(filename, cli.args),
+        (None, None) => repl(),
     };
 
     if let Err(err) = result {
-        eprintln!("{}", err);
+        match (cli.error_format, err.downcast_ref::<EvalError>()) {
+            (ErrorFormat::Json, Some(err)) => eprintln!("{}", Diagnostic::from(err).to_json()),
+            // The text of a stack overflow is followed by where it happened.
+            (_, Some(EvalError::Evaluator(eval_err))) => match eval_err.inner() {
+                EvaluatorError::StackOverflow(_, trace) => eprintln!("{}\n{}", err, trace),
+                _ => eprintln!("{}", err),
+            },
+            _ => eprintln!("{}", err),
+        }
+
+        std::process::exit(1);
     }
 
     Ok(())
@@ -4,6 +4,24 @@ use std::fs;
 fn examples_work() {
     for filename in fs::read_dir("examples").unwrap() {
         let filename = filename.unwrap().path();
-        belalang_cli::run_file(filename).unwrap();
+        belalang_cli::run_file(filename, Vec::new()).unwrap();
     }
 }
+
+#[test]
+fn compiled_examples_work() {
+    let dir = std::env::temp_dir().join(format!("belalang-compiled-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    for filename in fs::read_dir("examples").unwrap() {
+        let filename = filename.unwrap().path();
+        let output = dir
+            .join(filename.file_name().unwrap())
+            .with_extension("blc");
+
+        let output = belalang_cli::compile(filename, Some(output)).unwrap();
+        belalang_cli::run_file(output, Vec::new()).unwrap();
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
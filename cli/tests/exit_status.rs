@@ -0,0 +1,36 @@
+use std::{fs, process::Command};
+
+fn belalang(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_belalang_cli"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn errors_exit_nonzero() {
+    let dir = std::env::temp_dir().join(format!("belalang-exit-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let invalid = dir.join("invalid.bl");
+    fs::write(&invalid, "x := ;").unwrap();
+    let invalid = invalid.to_str().unwrap();
+
+    let output = belalang(&["fmt", "--check", invalid]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown prefix operator"));
+
+    assert!(!belalang(&[invalid]).status.success());
+
+    let failing = dir.join("failing.bl");
+    fs::write(&failing, "1 + \"a\";").unwrap();
+    assert!(!belalang(&[failing.to_str().unwrap()]).status.success());
+
+    let valid = dir.join("valid.bl");
+    fs::write(&valid, "x := 1;\n").unwrap();
+    let valid = valid.to_str().unwrap();
+    assert!(belalang(&["fmt", "--check", valid]).status.success());
+    assert!(belalang(&[valid]).status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,29 @@
+use belalang_core::lexer::Lexer;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+// Mostly number literals and names, the tokens that carry text. Names are
+// interned; number literals still allocate a `String` each.
+const SOURCE: &str = r#"
+total := 0;
+for (i in range(1000)) {
+    scaled := i * 2.5 + 100;
+    label := "item";
+    total = total + scaled - 42;
+}
+"#;
+
+fn lex(input: &str) -> usize {
+    Lexer::new(input.as_bytes()).map(Result::unwrap).count()
+}
+
+fn lexer(c: &mut Criterion) {
+    let source = SOURCE.repeat(100);
+
+    let mut group = c.benchmark_group("lexer");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.bench_function("tokens", |b| b.iter(|| lex(&source)));
+    group.finish();
+}
+
+criterion_group!(benches, lexer);
+criterion_main!(benches);
@@ -3,7 +3,7 @@ use std::{
     io::{self, Write},
 };
 
-use belalang_core::{lexer::Lexer, token::Token};
+use belalang_core::lexer::Lexer;
 
 fn main() -> Result<(), Box<dyn Error>> {
     loop {
@@ -15,11 +15,8 @@ fn main() -> Result<(), Box<dyn Error>> {
             .read_line(&mut input)
             .expect("Error reading from STDIN");
 
-        let mut lexer = Lexer::new(input.as_bytes());
-
-        loop {
-            match lexer.next_token() {
-                Ok(Token::EOF) => break,
+        for token in Lexer::new(input.as_bytes()) {
+            match token {
                 Ok(token) => println!("{:?}", token),
                 Err(err) => println!("ERROR: {:?}", err),
             };
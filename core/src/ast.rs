@@ -1,9 +1,11 @@
 mod expressions;
 mod program;
+mod sexpr;
 mod statements;
+pub mod visit;
 
 pub use expressions::*;
-pub use program::Program;
+pub use program::{Comment, Program};
 pub use statements::*;
 
 pub enum Node {
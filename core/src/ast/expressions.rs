@@ -1,10 +1,14 @@
-use crate::token;
+use std::fmt::Write;
+
+use crate::{source::Span, symbol::Symbol, token};
 
 use super::Statement;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BooleanExpression {
     pub token: token::Token,
+    pub span: Span,
     pub value: bool,
 }
 
@@ -15,8 +19,10 @@ impl std::fmt::Display for BooleanExpression {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntegerLiteral {
     pub token: token::Token,
+    pub span: Span,
     pub value: i64,
 }
 
@@ -27,32 +33,57 @@ impl std::fmt::Display for IntegerLiteral {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FloatLiteral {
     pub token: token::Token,
+    pub span: Span,
     pub value: f64,
 }
 
 impl std::fmt::Display for FloatLiteral {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", &self.value)
+        // keep the `.` so that it reads back as a float
+        let value = self.value.to_string();
+        match value.contains('.') || !self.value.is_finite() {
+            true => f.write_str(&value),
+            false => write!(f, "{}.0", value),
+        }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringLiteral {
     pub token: token::Token,
+    pub span: Span,
     pub value: String,
 }
 
 impl std::fmt::Display for StringLiteral {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.value)
+        f.write_char('"')?;
+
+        for c in self.value.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_ascii_control() => write!(f, "\\x{:02x}", c as u8)?,
+                c => f.write_char(c)?,
+            }
+        }
+
+        f.write_char('"')
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NullLiteral {
     pub token: token::Token,
+    pub span: Span,
 }
 
 impl std::fmt::Display for NullLiteral {
@@ -62,8 +93,10 @@ impl std::fmt::Display for NullLiteral {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ArrayLiteral {
     pub token: token::Token,
+    pub span: Span,
     pub elements: Vec<Expression>,
 }
 
@@ -81,21 +114,52 @@ impl std::fmt::Display for ArrayLiteral {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DictLiteral {
+    pub token: token::Token,
+    pub span: Span,
+    pub pairs: Vec<(Expression, Expression)>,
+}
+
+impl std::fmt::Display for DictLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.pairs.is_empty() {
+            return f.write_str("[:]");
+        }
+
+        let pairs = self
+            .pairs
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        write!(f, "[{}]", pairs)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VarExpression {
     pub token: token::Token,
+    pub span: Span,
     pub name: Identifier,
     pub value: Box<Expression>,
+    /// The `##` comment before a `:=` declaration.
+    pub doc: Option<String>,
 }
 
 impl std::fmt::Display for VarExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {} {};", self.name, self.token, self.value)
+        write!(f, "{} {} {}", self.name, self.token, self.value)
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CallExpression {
     pub token: token::Token,
+    pub span: Span,
     pub function: Box<Expression>,
     pub args: Vec<Expression>,
 }
@@ -109,26 +173,77 @@ impl std::fmt::Display for CallExpression {
             .collect::<Vec<_>>()
             .join(", ");
 
-        write!(f, "{}({})", self.function, args)
+        match *self.function {
+            // at the start of a statement `if` would begin an if statement
+            Expression::If(_) => write!(f, "({})({})", self.function, args),
+            _ => write!(f, "{}({})", self.function, args),
+        }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IndexExpression {
     pub token: token::Token,
+    pub span: Span,
     pub left: Box<Expression>,
     pub index: Box<Expression>,
 }
 
 impl std::fmt::Display for IndexExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}[{}])", self.left, self.index)
+        write!(f, "({}{}{}])", self.left, self.token, self.index)
+    }
+}
+
+/// Assignment to an element of a collection, `left[index] = value`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IndexAssignExpression {
+    pub token: token::Token,
+    pub span: Span,
+    pub left: Box<Expression>,
+    pub index: Box<Expression>,
+    pub value: Box<Expression>,
+}
+
+impl std::fmt::Display for IndexAssignExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let left = match *self.left {
+            Expression::If(_) => format!("({})", self.left),
+            _ => self.left.to_string(),
+        };
+
+        write!(f, "{}[{}] {} {}", left, self.index, self.token, self.value)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemberExpression {
+    pub token: token::Token,
+    pub span: Span,
+    pub object: Box<Expression>,
+    pub property: Identifier,
+}
+
+impl std::fmt::Display for MemberExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self.object {
+            // `1.x` would read as the float `1.` followed by `x`
+            Expression::Integer(_) => {
+                write!(f, "(({}){}{})", self.object, self.token, self.property)
+            }
+            _ => write!(f, "({}{}{})", self.object, self.token, self.property),
+        }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionLiteral {
     pub token: token::Token,
+    pub span: Span,
     pub params: Vec<Identifier>,
     pub body: BlockExpression,
 }
@@ -147,9 +262,11 @@ impl std::fmt::Display for FunctionLiteral {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Identifier {
     pub token: token::Token,
-    pub value: String,
+    pub span: Span,
+    pub value: Symbol,
 }
 
 impl std::fmt::Display for Identifier {
@@ -159,8 +276,10 @@ impl std::fmt::Display for Identifier {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IfExpression {
     pub token: token::Token,
+    pub span: Span,
     pub condition: Box<Expression>,
     pub consequence: BlockExpression,
     pub alternative: Option<Box<Expression>>,
@@ -168,22 +287,20 @@ pub struct IfExpression {
 
 impl std::fmt::Display for IfExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "if ({}) {} else {}",
-            self.condition,
-            self.consequence,
-            match &self.alternative {
-                Some(alt) => alt.to_string(),
-                None => "{}".into(),
-            }
-        )
+        write!(f, "if ({}) {}", self.condition, self.consequence)?;
+
+        match &self.alternative {
+            Some(alt) => write!(f, " else {}", alt),
+            None => Ok(()),
+        }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InfixExpression {
     pub token: token::Token,
+    pub span: Span,
     pub left: Box<Expression>,
     pub operator: token::Token,
     pub right: Box<Expression>,
@@ -196,8 +313,10 @@ impl std::fmt::Display for InfixExpression {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PrefixExpression {
     pub token: token::Token,
+    pub span: Span,
     pub operator: token::Token,
     pub right: Box<Expression>,
 }
@@ -208,26 +327,60 @@ impl std::fmt::Display for PrefixExpression {
     }
 }
 
+/// `import "path"`, evaluating to the module's namespace.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImportExpression {
+    pub token: token::Token,
+    pub span: Span,
+    pub path: Box<Expression>,
+}
+
+impl std::fmt::Display for ImportExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self.path {
+            // the path binds tighter than a call
+            Expression::Call(_) => write!(f, "(import ({}))", self.path),
+            _ => write!(f, "(import {})", self.path),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlockExpression {
     pub token: token::Token,
+    pub span: Span,
     pub statements: Vec<Statement>,
 }
 
 impl std::fmt::Display for BlockExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let statements = self
-            .statements
-            .iter()
-            .map(|statement| statement.to_string())
-            .collect::<Vec<_>>()
-            .join(" ");
-
-        write!(f, "{{ {} }}", statements)
+        let (last, rest) = match self.statements.split_last() {
+            Some((last, rest)) => (Some(last), rest),
+            None => (None, &self.statements[..]),
+        };
+        let mut statements = rest.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        // The last statement is the block's value. When the block doesn't
+        // end in an expression, the parser adds a null literal for it, which
+        // is left out here and comes back when the output is parsed again.
+        match last {
+            Some(last) if last.is_implicit_null() => (),
+            Some(Statement::Expression(last)) => statements.push(last.expression.to_string()),
+            Some(last) => statements.push(last.to_string()),
+            None => (),
+        }
+
+        match statements.is_empty() {
+            true => f.write_str("{}"),
+            false => write!(f, "{{ {} }}", statements.join(" ")),
+        }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expression {
     Boolean(BooleanExpression),
     Integer(IntegerLiteral),
@@ -235,15 +388,69 @@ pub enum Expression {
     String(StringLiteral),
     Null(NullLiteral),
     Array(ArrayLiteral),
+    Dict(DictLiteral),
     Var(VarExpression),
     Call(CallExpression),
     Index(IndexExpression),
+    IndexAssign(IndexAssignExpression),
+    Member(MemberExpression),
     Function(FunctionLiteral),
     Identifier(Identifier),
     If(IfExpression),
     Infix(InfixExpression),
     Prefix(PrefixExpression),
     Block(BlockExpression),
+    Import(ImportExpression),
+}
+
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Boolean(v) => v.span,
+            Expression::Integer(v) => v.span,
+            Expression::Float(v) => v.span,
+            Expression::String(v) => v.span,
+            Expression::Null(v) => v.span,
+            Expression::Array(v) => v.span,
+            Expression::Dict(v) => v.span,
+            Expression::Var(v) => v.span,
+            Expression::Call(v) => v.span,
+            Expression::Index(v) => v.span,
+            Expression::IndexAssign(v) => v.span,
+            Expression::Member(v) => v.span,
+            Expression::Function(v) => v.span,
+            Expression::Identifier(v) => v.span,
+            Expression::If(v) => v.span,
+            Expression::Infix(v) => v.span,
+            Expression::Prefix(v) => v.span,
+            Expression::Block(v) => v.span,
+            Expression::Import(v) => v.span,
+        }
+    }
+
+    pub fn span_mut(&mut self) -> &mut Span {
+        match self {
+            Expression::Boolean(v) => &mut v.span,
+            Expression::Integer(v) => &mut v.span,
+            Expression::Float(v) => &mut v.span,
+            Expression::String(v) => &mut v.span,
+            Expression::Null(v) => &mut v.span,
+            Expression::Array(v) => &mut v.span,
+            Expression::Dict(v) => &mut v.span,
+            Expression::Var(v) => &mut v.span,
+            Expression::Call(v) => &mut v.span,
+            Expression::Index(v) => &mut v.span,
+            Expression::IndexAssign(v) => &mut v.span,
+            Expression::Member(v) => &mut v.span,
+            Expression::Function(v) => &mut v.span,
+            Expression::Identifier(v) => &mut v.span,
+            Expression::If(v) => &mut v.span,
+            Expression::Infix(v) => &mut v.span,
+            Expression::Prefix(v) => &mut v.span,
+            Expression::Block(v) => &mut v.span,
+            Expression::Import(v) => &mut v.span,
+        }
+    }
 }
 
 impl std::fmt::Display for Expression {
@@ -255,14 +462,18 @@ impl std::fmt::Display for Expression {
             Expression::String(v) => v.to_string(),
             Expression::Null(v) => v.to_string(),
             Expression::Array(v) => v.to_string(),
+            Expression::Dict(v) => v.to_string(),
             Expression::Var(v) => v.to_string(),
+            Expression::IndexAssign(v) => v.to_string(),
             Expression::Call(v) => v.to_string(),
             Expression::Index(v) => v.to_string(),
+            Expression::Member(v) => v.to_string(),
             Expression::Function(v) => v.to_string(),
             Expression::Identifier(v) => v.to_string(),
             Expression::If(v) => v.to_string(),
             Expression::Infix(v) => v.to_string(),
             Expression::Prefix(v) => v.to_string(),
+            Expression::Import(v) => v.to_string(),
             Expression::Block(v) => v.to_string(),
         })
     }
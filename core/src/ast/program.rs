@@ -1,14 +1,55 @@
 use super::Statement;
+use crate::source::Span;
 
-#[derive(Default)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Program {
     pub statements: Vec<Statement>,
+    /// Every comment in the source, in order. They aren't part of the tree,
+    /// their spans say where they go back.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub comments: Vec<Comment>,
+}
+
+/// A `#`, `##` or `/* */` comment.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Comment {
+    pub span: Span,
+    /// The comment as written, without the trailing whitespace of a line
+    /// comment.
+    pub text: String,
 }
 
 impl Program {
     pub fn add_stmt(&mut self, stmt: Statement) {
         self.statements.push(stmt);
     }
+
+    /// The AST as JSON, with every node's `token` and `span`. Enums are
+    /// externally tagged, e.g. `{"Integer":{"token":{"Int":"1"},...}}`.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("the AST is always serializable")
+    }
+
+    /// Reads a program written by [`Program::to_json`].
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<Program, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+impl IntoIterator for Program {
+    type Item = Statement;
+    type IntoIter = std::vec::IntoIter<Statement>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.statements.into_iter()
+    }
 }
 
 impl std::fmt::Display for Program {
@@ -0,0 +1,189 @@
+//! A compact tree dump of the AST, e.g. `(infix + (int 1) (int 2))`, for
+//! debugging the parser and writing short parser tests.
+
+use std::fmt::Write;
+
+use super::{BlockExpression, Expression, Program, Statement};
+use crate::token::Token;
+
+impl Program {
+    /// One line per top-level statement.
+    pub fn to_sexpr(&self) -> String {
+        let lines = self.statements.iter().map(Statement::to_sexpr);
+        lines.collect::<Vec<_>>().join("\n")
+    }
+}
+
+impl Statement {
+    /// An expression statement dumps as its bare expression.
+    pub fn to_sexpr(&self) -> String {
+        let mut out = String::new();
+        write_statement(&mut out, self);
+        out
+    }
+}
+
+impl Expression {
+    pub fn to_sexpr(&self) -> String {
+        let mut out = String::new();
+        write_expression(&mut out, self);
+        out
+    }
+}
+
+fn write_statement(out: &mut String, statement: &Statement) {
+    match statement {
+        Statement::Expression(stmt) => write_expression(out, &stmt.expression),
+        Statement::Return(stmt) => {
+            out.push_str("(return ");
+            write_expression(out, &stmt.return_value);
+            out.push(')');
+        }
+        Statement::Yield(stmt) => {
+            out.push_str("(yield ");
+            write_expression(out, &stmt.value);
+            out.push(')');
+        }
+        Statement::While(stmt) => {
+            out.push_str("(while ");
+            write_expression(out, &stmt.condition);
+            out.push(' ');
+            write_block(out, &stmt.block);
+            out.push(')');
+        }
+        Statement::For(stmt) => {
+            let _ = write!(out, "(for {} ", stmt.variable);
+            write_expression(out, &stmt.iterable);
+            out.push(' ');
+            write_block(out, &stmt.block);
+            out.push(')');
+        }
+    }
+}
+
+fn write_expression(out: &mut String, expression: &Expression) {
+    match expression {
+        Expression::Boolean(v) => {
+            let _ = write!(out, "(bool {})", v.value);
+        }
+        Expression::Integer(v) => {
+            let _ = write!(out, "(int {})", v.value);
+        }
+        Expression::Float(v) => {
+            let _ = write!(out, "(float {:?})", v.value);
+        }
+        Expression::String(v) => {
+            let _ = write!(out, "(string {:?})", v.value);
+        }
+        Expression::Null(_) => out.push_str("(null)"),
+        Expression::Identifier(v) => {
+            let _ = write!(out, "(ident {})", v.value);
+        }
+        Expression::Array(v) => {
+            out.push_str("(array");
+            for element in &v.elements {
+                out.push(' ');
+                write_expression(out, element);
+            }
+            out.push(')');
+        }
+        Expression::Dict(v) => {
+            out.push_str("(dict");
+            for (key, value) in &v.pairs {
+                out.push_str(" (pair ");
+                write_expression(out, key);
+                out.push(' ');
+                write_expression(out, value);
+                out.push(')');
+            }
+            out.push(')');
+        }
+        Expression::Var(v) => {
+            let _ = write!(out, "(var {} {} ", v.token, v.name);
+            write_expression(out, &v.value);
+            out.push(')');
+        }
+        Expression::Call(v) => {
+            out.push_str("(call ");
+            write_expression(out, &v.function);
+            for arg in &v.args {
+                out.push(' ');
+                write_expression(out, arg);
+            }
+            out.push(')');
+        }
+        Expression::Index(v) => {
+            out.push_str(match v.token {
+                Token::OptionalBracket => "(index? ",
+                _ => "(index ",
+            });
+            write_expression(out, &v.left);
+            out.push(' ');
+            write_expression(out, &v.index);
+            out.push(')');
+        }
+        Expression::IndexAssign(v) => {
+            out.push_str("(index-assign ");
+            write_expression(out, &v.left);
+            out.push(' ');
+            write_expression(out, &v.index);
+            out.push(' ');
+            write_expression(out, &v.value);
+            out.push(')');
+        }
+        Expression::Member(v) => {
+            out.push_str(match v.token {
+                Token::OptionalDot => "(member? ",
+                _ => "(member ",
+            });
+            write_expression(out, &v.object);
+            let _ = write!(out, " {})", v.property);
+        }
+        Expression::Function(v) => {
+            out.push_str("(fn (");
+            let params = v.params.iter().map(|param| param.to_string());
+            out.push_str(&params.collect::<Vec<_>>().join(" "));
+            out.push_str(") ");
+            write_block(out, &v.body);
+            out.push(')');
+        }
+        Expression::If(v) => {
+            out.push_str("(if ");
+            write_expression(out, &v.condition);
+            out.push(' ');
+            write_block(out, &v.consequence);
+            if let Some(alternative) = &v.alternative {
+                out.push(' ');
+                write_expression(out, alternative);
+            }
+            out.push(')');
+        }
+        Expression::Infix(v) => {
+            let _ = write!(out, "(infix {} ", v.operator);
+            write_expression(out, &v.left);
+            out.push(' ');
+            write_expression(out, &v.right);
+            out.push(')');
+        }
+        Expression::Prefix(v) => {
+            let _ = write!(out, "(prefix {} ", v.operator);
+            write_expression(out, &v.right);
+            out.push(')');
+        }
+        Expression::Block(v) => write_block(out, v),
+        Expression::Import(v) => {
+            out.push_str("(import ");
+            write_expression(out, &v.path);
+            out.push(')');
+        }
+    }
+}
+
+fn write_block(out: &mut String, block: &BlockExpression) {
+    out.push_str("(block");
+    for statement in &block.statements {
+        out.push(' ');
+        write_statement(out, statement);
+    }
+    out.push(')');
+}
@@ -1,10 +1,12 @@
-use crate::token;
+use crate::{source::Span, token};
 
-use super::{BlockExpression, Expression};
+use super::{BlockExpression, Expression, Identifier};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExpressionStatement {
     pub token: token::Token,
+    pub span: Span,
     pub expression: Expression,
 }
 
@@ -15,8 +17,10 @@ impl std::fmt::Display for ExpressionStatement {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReturnStatement {
     pub token: token::Token,
+    pub span: Span,
     pub return_value: Expression,
 }
 
@@ -26,9 +30,27 @@ impl std::fmt::Display for ReturnStatement {
     }
 }
 
+/// Produces the next item of a generator, see the evaluator's `generator`
+/// module.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct YieldStatement {
+    pub token: token::Token,
+    pub span: Span,
+    pub value: Expression,
+}
+
+impl std::fmt::Display for YieldStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "yield {};", self.value)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WhileStatement {
     pub token: token::Token,
+    pub span: Span,
     pub condition: Box<Expression>,
     pub block: BlockExpression,
 }
@@ -40,10 +62,68 @@ impl std::fmt::Display for WhileStatement {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForStatement {
+    pub token: token::Token,
+    pub span: Span,
+    pub variable: Identifier,
+    pub iterable: Box<Expression>,
+    pub block: BlockExpression,
+}
+
+impl std::fmt::Display for ForStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "for ({} in {}) {}",
+            self.variable, self.iterable, self.block
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Statement {
     Expression(ExpressionStatement),
     Return(ReturnStatement),
+    Yield(YieldStatement),
     While(WhileStatement),
+    For(ForStatement),
+}
+
+impl Statement {
+    /// Whether this is the null the parser adds to blocks that don't end in
+    /// an expression, to be their value.
+    pub fn is_implicit_null(&self) -> bool {
+        matches!(
+            self,
+            Statement::Expression(ExpressionStatement {
+                token: token::Token::RightBrace,
+                expression: Expression::Null(_),
+                ..
+            })
+        )
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Expression(v) => v.span,
+            Statement::Return(v) => v.span,
+            Statement::Yield(v) => v.span,
+            Statement::While(v) => v.span,
+            Statement::For(v) => v.span,
+        }
+    }
+
+    pub fn span_mut(&mut self) -> &mut Span {
+        match self {
+            Statement::Expression(v) => &mut v.span,
+            Statement::Return(v) => &mut v.span,
+            Statement::Yield(v) => &mut v.span,
+            Statement::While(v) => &mut v.span,
+            Statement::For(v) => &mut v.span,
+        }
+    }
 }
 
 impl std::fmt::Display for Statement {
@@ -51,7 +131,9 @@ impl std::fmt::Display for Statement {
         let value = match self {
             Statement::Expression(v) => v.to_string(),
             Statement::Return(v) => v.to_string(),
+            Statement::Yield(v) => v.to_string(),
             Statement::While(v) => v.to_string(),
+            Statement::For(v) => v.to_string(),
         };
 
         f.write_str(&value)
@@ -0,0 +1,230 @@
+//! Traversal of the AST. Implement the methods for the nodes you care about
+//! and call the matching `walk_*` function from them to keep descending.
+
+use super::{BlockExpression, Expression, Identifier, Program, Statement};
+
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+
+    fn visit_block(&mut self, block: &BlockExpression) {
+        walk_block(self, block);
+    }
+
+    /// Names that are bound or read, including parameters, `for` variables
+    /// and the left-hand side of assignments. Member properties are not
+    /// visited.
+    fn visit_identifier(&mut self, _identifier: &Identifier) {}
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for statement in &program.statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Expression(stmt) => visitor.visit_expression(&stmt.expression),
+        Statement::Return(stmt) => visitor.visit_expression(&stmt.return_value),
+        Statement::Yield(stmt) => visitor.visit_expression(&stmt.value),
+        Statement::While(stmt) => {
+            visitor.visit_expression(&stmt.condition);
+            visitor.visit_block(&stmt.block);
+        }
+        Statement::For(stmt) => {
+            visitor.visit_identifier(&stmt.variable);
+            visitor.visit_expression(&stmt.iterable);
+            visitor.visit_block(&stmt.block);
+        }
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::Boolean(_)
+        | Expression::Integer(_)
+        | Expression::Float(_)
+        | Expression::String(_)
+        | Expression::Null(_) => {}
+        Expression::Array(array) => {
+            for element in &array.elements {
+                visitor.visit_expression(element);
+            }
+        }
+        Expression::Dict(dict) => {
+            for (key, value) in &dict.pairs {
+                visitor.visit_expression(key);
+                visitor.visit_expression(value);
+            }
+        }
+        Expression::Var(var) => {
+            visitor.visit_identifier(&var.name);
+            visitor.visit_expression(&var.value);
+        }
+        Expression::Call(call) => {
+            visitor.visit_expression(&call.function);
+            for arg in &call.args {
+                visitor.visit_expression(arg);
+            }
+        }
+        Expression::Index(index) => {
+            visitor.visit_expression(&index.left);
+            visitor.visit_expression(&index.index);
+        }
+        Expression::IndexAssign(assign) => {
+            visitor.visit_expression(&assign.left);
+            visitor.visit_expression(&assign.index);
+            visitor.visit_expression(&assign.value);
+        }
+        Expression::Member(member) => visitor.visit_expression(&member.object),
+        Expression::Function(function) => {
+            for param in &function.params {
+                visitor.visit_identifier(param);
+            }
+            visitor.visit_block(&function.body);
+        }
+        Expression::Identifier(identifier) => visitor.visit_identifier(identifier),
+        Expression::If(if_expr) => {
+            visitor.visit_expression(&if_expr.condition);
+            visitor.visit_block(&if_expr.consequence);
+            if let Some(alternative) = &if_expr.alternative {
+                visitor.visit_expression(alternative);
+            }
+        }
+        Expression::Infix(infix) => {
+            visitor.visit_expression(&infix.left);
+            visitor.visit_expression(&infix.right);
+        }
+        Expression::Prefix(prefix) => visitor.visit_expression(&prefix.right),
+        Expression::Block(block) => visitor.visit_block(block),
+        Expression::Import(import) => visitor.visit_expression(&import.path),
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &BlockExpression) {
+    for statement in &block.statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+/// Like [`Visitor`], but can rewrite the nodes in place.
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program);
+    }
+
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement);
+    }
+
+    fn visit_expression_mut(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut BlockExpression) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_identifier_mut(&mut self, _identifier: &mut Identifier) {}
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    for statement in &mut program.statements {
+        visitor.visit_statement_mut(statement);
+    }
+}
+
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut Statement) {
+    match statement {
+        Statement::Expression(stmt) => visitor.visit_expression_mut(&mut stmt.expression),
+        Statement::Return(stmt) => visitor.visit_expression_mut(&mut stmt.return_value),
+        Statement::Yield(stmt) => visitor.visit_expression_mut(&mut stmt.value),
+        Statement::While(stmt) => {
+            visitor.visit_expression_mut(&mut stmt.condition);
+            visitor.visit_block_mut(&mut stmt.block);
+        }
+        Statement::For(stmt) => {
+            visitor.visit_identifier_mut(&mut stmt.variable);
+            visitor.visit_expression_mut(&mut stmt.iterable);
+            visitor.visit_block_mut(&mut stmt.block);
+        }
+    }
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut Expression) {
+    match expression {
+        Expression::Boolean(_)
+        | Expression::Integer(_)
+        | Expression::Float(_)
+        | Expression::String(_)
+        | Expression::Null(_) => {}
+        Expression::Array(array) => {
+            for element in &mut array.elements {
+                visitor.visit_expression_mut(element);
+            }
+        }
+        Expression::Dict(dict) => {
+            for (key, value) in &mut dict.pairs {
+                visitor.visit_expression_mut(key);
+                visitor.visit_expression_mut(value);
+            }
+        }
+        Expression::Var(var) => {
+            visitor.visit_identifier_mut(&mut var.name);
+            visitor.visit_expression_mut(&mut var.value);
+        }
+        Expression::Call(call) => {
+            visitor.visit_expression_mut(&mut call.function);
+            for arg in &mut call.args {
+                visitor.visit_expression_mut(arg);
+            }
+        }
+        Expression::Index(index) => {
+            visitor.visit_expression_mut(&mut index.left);
+            visitor.visit_expression_mut(&mut index.index);
+        }
+        Expression::IndexAssign(assign) => {
+            visitor.visit_expression_mut(&mut assign.left);
+            visitor.visit_expression_mut(&mut assign.index);
+            visitor.visit_expression_mut(&mut assign.value);
+        }
+        Expression::Member(member) => visitor.visit_expression_mut(&mut member.object),
+        Expression::Function(function) => {
+            for param in &mut function.params {
+                visitor.visit_identifier_mut(param);
+            }
+            visitor.visit_block_mut(&mut function.body);
+        }
+        Expression::Identifier(identifier) => visitor.visit_identifier_mut(identifier),
+        Expression::If(if_expr) => {
+            visitor.visit_expression_mut(&mut if_expr.condition);
+            visitor.visit_block_mut(&mut if_expr.consequence);
+            if let Some(alternative) = &mut if_expr.alternative {
+                visitor.visit_expression_mut(alternative);
+            }
+        }
+        Expression::Infix(infix) => {
+            visitor.visit_expression_mut(&mut infix.left);
+            visitor.visit_expression_mut(&mut infix.right);
+        }
+        Expression::Prefix(prefix) => visitor.visit_expression_mut(&mut prefix.right),
+        Expression::Block(block) => visitor.visit_block_mut(block),
+        Expression::Import(import) => visitor.visit_expression_mut(&mut import.path),
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut BlockExpression) {
+    for statement in &mut block.statements {
+        visitor.visit_statement_mut(statement);
+    }
+}
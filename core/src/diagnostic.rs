@@ -0,0 +1,190 @@
+//! Errors and warnings in a form editors and CI tooling can consume, see
+//! [`Diagnostic::to_json`].
+
+use crate::{
+    error::{LexError, SyntaxError},
+    source::SourceMap,
+};
+use std::fmt::Write;
+
+pub use crate::source::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Identifies the kind of problem, e.g. `unexpected-token`, and stays
+    /// the same when the message is reworded.
+    pub code: String,
+    pub message: String,
+    /// Where the problem is, if known.
+    pub span: Option<Span>,
+    /// More context, e.g. the module a problem was found in.
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: code.into(),
+            message: message.into(),
+            span: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(code: &str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, message)
+    }
+
+    pub fn warning(code: &str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, code, message)
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// The diagnostic for people, e.g. `main.bl:2:5: error[unknown-variable]:
+    /// unknown variable: x`, followed by a `note:` line for each note.
+    pub fn render(&self, sources: &SourceMap) -> String {
+        let mut text = String::new();
+
+        if let Some(place) = self.span.and_then(|span| sources.describe(span)) {
+            let _ = write!(text, "{place}: ");
+        }
+
+        let _ = write!(
+            text,
+            "{}[{}]: {}",
+            self.severity.as_str(),
+            self.code,
+            self.message
+        );
+
+        for note in &self.notes {
+            let _ = write!(text, "\n  note: {note}");
+        }
+
+        text
+    }
+
+    /// A JSON object with the same fields as the struct, e.g.
+    /// `{"severity":"error","code":"unexpected-eof","message":"unexpected
+    /// EOF","span":null,"notes":[]}`. Doesn't need the `serde` feature.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+
+        json.push_str("{\"severity\":");
+        write_json_string(&mut json, self.severity.as_str());
+        json.push_str(",\"code\":");
+        write_json_string(&mut json, &self.code);
+        json.push_str(",\"message\":");
+        write_json_string(&mut json, &self.message);
+
+        json.push_str(",\"span\":");
+        match self.span {
+            Some(span) => {
+                let _ = write!(json, "{{\"start\":{},\"end\":{}}}", span.start, span.end);
+            }
+            None => json.push_str("null"),
+        }
+
+        json.push_str(",\"notes\":[");
+        for (i, note) in self.notes.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write_json_string(&mut json, note);
+        }
+        json.push_str("]}");
+
+        json
+    }
+}
+
+/// A JSON array of [`Diagnostic::to_json`] objects.
+pub fn to_json(diagnostics: &[Diagnostic]) -> String {
+    let objects = diagnostics.iter().map(Diagnostic::to_json);
+    format!("[{}]", objects.collect::<Vec<_>>().join(","))
+}
+
+fn write_json_string(json: &mut String, s: &str) {
+    json.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+}
+
+impl SyntaxError {
+    /// See [`Diagnostic::code`].
+    pub fn code(&self) -> &'static str {
+        match self {
+            SyntaxError::UnexpectedToken(..) => "unexpected-token",
+            SyntaxError::UnexpectedEOF(_) => "unexpected-eof",
+            SyntaxError::UnknownPrefixOperator(..) => "unknown-prefix-operator",
+            SyntaxError::InvalidLHS(_) => "invalid-assignment-target",
+            SyntaxError::ParsingInteger(..) => "invalid-integer",
+            SyntaxError::ParsingFloat(..) => "invalid-float",
+            SyntaxError::TooDeep(_) => "too-deep",
+            SyntaxError::Lex(err) => err.code(),
+        }
+    }
+}
+
+impl LexError {
+    /// See [`Diagnostic::code`].
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexError::UnexpectedChar(..) | LexError::UnexpectedByte(..) => "unexpected-character",
+            LexError::UnknownEscapeString(..) => "unknown-escape",
+            LexError::UnclosedString(..) => "unclosed-string",
+            LexError::UnclosedComment(..) => "unclosed-comment",
+            LexError::InvalidUtf8(_) => "invalid-utf8",
+            LexError::Read(..) => "read-error",
+        }
+    }
+}
+
+impl From<&SyntaxError> for Diagnostic {
+    fn from(err: &SyntaxError) -> Self {
+        Diagnostic::error(err.code(), err.to_string()).with_span(err.span())
+    }
+}
@@ -1,32 +1,102 @@
 use crate::ast::Expression;
+use crate::source::{Position, Span};
 use crate::token::Token;
 
+/// An error in the input's characters, before it is parsed. The span is
+/// where in the input it is.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum LexError {
+    #[error("unexpected character {0:?} at {1}")]
+    UnexpectedChar(char, Position, Span),
+
+    /// A byte that doesn't start a valid UTF-8 character.
+    #[error("unexpected byte {0:#04x} at {1}")]
+    UnexpectedByte(u8, Position, Span),
+
+    #[error(r"unknown escape string: \{0}")]
+    UnknownEscapeString(String, Span),
+
+    /// The position is where the string starts.
+    #[error("unclosed string starting at {0}")]
+    UnclosedString(Position, Span),
+
+    /// The position is where the comment starts.
+    #[error("unclosed block comment starting at {0}")]
+    UnclosedComment(Position, Span),
+
+    #[error("string is not valid UTF-8")]
+    InvalidUtf8(Span),
+
+    #[error("error reading input: {0}")]
+    Read(String, Span),
+}
+
+impl LexError {
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnexpectedChar(_, _, span)
+            | LexError::UnexpectedByte(_, _, span)
+            | LexError::UnknownEscapeString(_, span)
+            | LexError::UnclosedString(_, span)
+            | LexError::UnclosedComment(_, span)
+            | LexError::InvalidUtf8(span)
+            | LexError::Read(_, span) => *span,
+        }
+    }
+}
+
+/// An error in the input's tokens. The span is the token the parser was
+/// at, or for [`SyntaxError::InvalidLHS`] the expression's.
 #[derive(thiserror::Error, Debug)]
 pub enum SyntaxError {
     #[error("unexpected token: {0}")]
-    UnexpectedToken(Token),
+    UnexpectedToken(Token, Span),
 
     #[error("unexpected EOF")]
-    UnexpectedEOF,
-
-    #[error(r"unknown escape string: \{0}")]
-    UnknownEscapeString(String),
+    UnexpectedEOF(Span),
 
     #[error("unknown prefix operator: {0}")]
-    UnknownPrefixOperator(Token),
-
-    #[error("unknown token: {0}")]
-    UnknownToken(String),
+    UnknownPrefixOperator(Token, Span),
 
     #[error("invalid lhs: {0}")]
-    InvalidLHS(Expression),
+    InvalidLHS(Box<Expression>),
 
     #[error("error parsing integer: could not parse {0} as integer")]
-    ParsingInteger(String),
+    ParsingInteger(String, Span),
 
     #[error("error parsing float: could not parse {0} as float")]
-    ParsingFloat(String),
+    ParsingFloat(String, Span),
+
+    #[error(transparent)]
+    Lex(#[from] LexError),
+
+    #[error("expression nested too deeply")]
+    TooDeep(Span),
+}
+
+impl SyntaxError {
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxError::UnexpectedToken(_, span)
+            | SyntaxError::UnexpectedEOF(span)
+            | SyntaxError::UnknownPrefixOperator(_, span)
+            | SyntaxError::ParsingInteger(_, span)
+            | SyntaxError::ParsingFloat(_, span)
+            | SyntaxError::TooDeep(span) => *span,
+            SyntaxError::InvalidLHS(expr) => expr.span(),
+            SyntaxError::Lex(err) => err.span(),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum LintConfigError {
+    #[error("line {0}: expected `rule = \"level\"`")]
+    InvalidLine(usize),
+
+    #[error("unknown lint rule: {0}")]
+    UnknownRule(String),
 
-    #[error("unclosed string")]
-    UnclosedString(),
+    #[error("unknown lint level: {0}, expected off, warning or error")]
+    UnknownLevel(String),
 }
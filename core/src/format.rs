@@ -0,0 +1,427 @@
+//! The formatter: parses a program and prints it back with consistent
+//! indentation and spacing, keeping `#` comments and single blank lines.
+
+use crate::{
+    ast::{BlockExpression, Comment, Expression, Identifier, Program, Statement},
+    error::SyntaxError,
+    lexer::Lexer,
+    parser::{Parser, Precedence},
+};
+
+/// Binds tighter than any operator: literals, names, arrays and dicts.
+const PRIMARY: u8 = Precedence::Index as u8 + 1;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Spaces per indentation level.
+    pub indent: usize,
+    /// Argument lists, parameters, arrays and dicts that would go past this
+    /// column are split over several lines.
+    pub width: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            width: 80,
+        }
+    }
+}
+
+pub fn format(source: &str) -> Result<String, SyntaxError> {
+    format_with(source, &Config::default())
+}
+
+pub fn format_with(source: &str, config: &Config) -> Result<String, SyntaxError> {
+    let program = Parser::new(Lexer::new(source.as_bytes())).parse_program()?;
+
+    let mut printer = Printer::new(source, config, &program.comments);
+    printer.program(&program);
+
+    Ok(printer.out)
+}
+
+enum Item<'a> {
+    Expression(&'a Expression),
+    Pair(&'a Expression, &'a Expression),
+    Param(&'a Identifier),
+}
+
+struct Printer<'a> {
+    source: &'a str,
+    config: &'a Config,
+    out: String,
+    level: usize,
+
+    comments: &'a [Comment],
+    next_comment: usize,
+    /// End of the last statement or comment printed.
+    cursor: usize,
+
+    /// Never split lists, to measure how long they'd be on one line.
+    flat: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn new(source: &'a str, config: &'a Config, comments: &'a [Comment]) -> Self {
+        Self {
+            source,
+            config,
+            out: String::new(),
+            level: 0,
+            comments,
+            next_comment: 0,
+            cursor: 0,
+            flat: false,
+        }
+    }
+
+    fn program(&mut self, program: &Program) {
+        self.statements(&program.statements, usize::MAX);
+    }
+
+    fn column(&self) -> usize {
+        let line = self.out.rfind('\n').map_or(0, |newline| newline + 1);
+        self.out[line..].chars().count()
+    }
+
+    fn indent(&mut self) {
+        let spaces = self.level * self.config.indent;
+        self.out.extend(std::iter::repeat_n(' ', spaces));
+    }
+
+    /// Keeps one blank line where the source has at least one before `upto`.
+    fn blank_line(&mut self, upto: usize) {
+        let between = self.source.get(self.cursor..upto).unwrap_or_default();
+
+        if between.matches('\n').count() >= 2 && !self.out.is_empty() && !self.out.ends_with("{\n")
+        {
+            self.out.push('\n');
+        }
+    }
+
+    /// Prints the comments before `pos` on lines of their own.
+    fn comments_before(&mut self, pos: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.span.start >= pos {
+                break;
+            }
+
+            self.blank_line(comment.span.start);
+            self.indent();
+            self.out.push_str(&comment.text);
+            self.out.push('\n');
+
+            self.next_comment += 1;
+            self.cursor = comment.span.end;
+        }
+    }
+
+    /// Prints a comment following `end` on the same line after it, and any
+    /// comments left inside the statement ending at `end` below it.
+    fn trailing_comments(&mut self, end: usize) {
+        let mut inner = Vec::new();
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.span.start >= end {
+                break;
+            }
+            inner.push(&comment.text);
+            self.next_comment += 1;
+        }
+
+        if let Some(comment) = self.comments.get(self.next_comment) {
+            if !self.source[end..comment.span.start].contains('\n') {
+                self.out.push_str("  ");
+                self.out.push_str(&comment.text);
+                self.cursor = comment.span.end;
+                self.next_comment += 1;
+            }
+        }
+
+        for text in inner {
+            self.out.push('\n');
+            self.indent();
+            self.out.push_str(text);
+        }
+    }
+
+    /// Prints the statements of a program or block, one per line. `end` is
+    /// where the block ends, comments before it belong to the block.
+    fn statements(&mut self, statements: &[Statement], end: usize) {
+        let count = match statements.last() {
+            Some(last) if last.is_implicit_null() => statements.len() - 1,
+            _ => statements.len(),
+        };
+
+        for (i, statement) in statements[..count].iter().enumerate() {
+            let span = statement.span();
+
+            self.comments_before(span.start);
+            self.blank_line(span.start);
+
+            // The last statement of a block is its value unless the parser
+            // added a null after it.
+            let in_block = end != usize::MAX;
+            let tail = in_block && count == statements.len() && i == count - 1;
+            let before_null = in_block && count < statements.len() && i == count - 1;
+
+            self.indent();
+            self.statement(statement, tail, before_null);
+
+            self.cursor = span.end;
+            self.trailing_comments(span.end);
+            self.out.push('\n');
+        }
+
+        self.comments_before(end);
+    }
+
+    fn statement(&mut self, statement: &Statement, tail: bool, before_null: bool) {
+        match statement {
+            Statement::Expression(stmt) => {
+                self.expression(&stmt.expression, Precedence::Lowest as u8, false);
+
+                let needs_semicolon = match stmt.expression {
+                    Expression::If(_) => before_null,
+                    _ => !tail,
+                };
+                if needs_semicolon {
+                    self.out.push(';');
+                }
+            }
+            Statement::Return(stmt) => {
+                self.out.push_str("return ");
+                self.expression(&stmt.return_value, Precedence::Lowest as u8, false);
+                self.out.push(';');
+            }
+            Statement::Yield(stmt) => {
+                self.out.push_str("yield ");
+                self.expression(&stmt.value, Precedence::Lowest as u8, false);
+                self.out.push(';');
+            }
+            Statement::While(stmt) => {
+                self.out.push_str("while (");
+                self.expression(&stmt.condition, Precedence::Lowest as u8, false);
+                self.out.push_str(") ");
+                self.block(&stmt.block);
+            }
+            Statement::For(stmt) => {
+                self.out.push_str(&format!("for ({} in ", stmt.variable));
+                self.expression(&stmt.iterable, Precedence::Lowest as u8, false);
+                self.out.push_str(") ");
+                self.block(&stmt.block);
+            }
+        }
+    }
+
+    fn block(&mut self, block: &BlockExpression) {
+        let empty = block.statements.iter().all(Statement::is_implicit_null);
+        let has_comments = self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.span.start < block.span.end);
+
+        if empty && !has_comments {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push_str("{\n");
+        self.level += 1;
+        self.statements(&block.statements, block.span.end);
+        self.level -= 1;
+        self.indent();
+        self.out.push('}');
+    }
+
+    /// Prints `expression`, in parentheses if it binds looser than `min`, or
+    /// as loose as `min` when `strict`.
+    fn expression(&mut self, expression: &Expression, min: u8, strict: bool) {
+        let precedence = precedence(expression);
+
+        if precedence < min || (strict && precedence == min) {
+            self.out.push('(');
+            self.expression(expression, Precedence::Lowest as u8, false);
+            self.out.push(')');
+            return;
+        }
+
+        match expression {
+            Expression::Boolean(v) => self.out.push_str(&v.to_string()),
+            Expression::Integer(v) => self.out.push_str(&v.to_string()),
+            Expression::Float(v) => self.out.push_str(&v.to_string()),
+            Expression::String(v) => self.out.push_str(&v.to_string()),
+            Expression::Null(_) => self.out.push_str("null"),
+            Expression::Identifier(v) => self.out.push_str(v.value.as_str()),
+            Expression::Array(v) => {
+                let items = v.elements.iter().map(Item::Expression).collect::<Vec<_>>();
+                self.list("[", "]", &items);
+            }
+            Expression::Dict(v) if v.pairs.is_empty() => self.out.push_str("[:]"),
+            Expression::Dict(v) => {
+                let items = v.pairs.iter().map(|(k, v)| Item::Pair(k, v));
+                self.list("[", "]", &items.collect::<Vec<_>>());
+            }
+            Expression::Var(v) => {
+                self.out.push_str(v.name.value.as_str());
+
+                match &*v.value {
+                    // `x += 1` is parsed as `x = x + 1`
+                    Expression::Infix(infix) if infix.token != infix.operator => {
+                        self.out.push_str(&format!(" {} ", infix.token));
+                        self.expression(&infix.right, Precedence::Lowest as u8, false);
+                    }
+                    value => {
+                        self.out.push_str(&format!(" {} ", v.token));
+                        self.expression(value, Precedence::Lowest as u8, false);
+                    }
+                }
+            }
+            Expression::IndexAssign(v) => {
+                self.expression(&v.left, Precedence::Index as u8, false);
+                self.out.push('[');
+                self.expression(&v.index, Precedence::Lowest as u8, false);
+                self.out.push_str(&format!("] {} ", v.token));
+                self.expression(&v.value, Precedence::Lowest as u8, false);
+            }
+            Expression::Call(v) => {
+                self.expression(&v.function, Precedence::Call as u8, false);
+                let items = v.args.iter().map(Item::Expression).collect::<Vec<_>>();
+                self.list("(", ")", &items);
+            }
+            Expression::Index(v) => {
+                self.expression(&v.left, Precedence::Index as u8, false);
+                self.out.push_str(&v.token.to_string());
+                self.expression(&v.index, Precedence::Lowest as u8, false);
+                self.out.push(']');
+            }
+            Expression::Member(v) => {
+                // `1.x` would read as the float `1.` followed by `x`
+                match *v.object {
+                    Expression::Integer(_) => self.expression(&v.object, PRIMARY, true),
+                    _ => self.expression(&v.object, Precedence::Index as u8, false),
+                }
+                self.out.push_str(&v.token.to_string());
+                self.out.push_str(v.property.value.as_str());
+            }
+            Expression::Function(v) => {
+                self.out.push_str("fn");
+                let items = v.params.iter().map(Item::Param).collect::<Vec<_>>();
+                self.list("(", ")", &items);
+                self.out.push(' ');
+                self.block(&v.body);
+            }
+            Expression::If(v) => {
+                self.out.push_str("if (");
+                self.expression(&v.condition, Precedence::Lowest as u8, false);
+                self.out.push_str(") ");
+                self.block(&v.consequence);
+
+                match v.alternative.as_deref() {
+                    Some(Expression::Block(alternative)) => {
+                        self.out.push_str(" else ");
+                        self.block(alternative);
+                    }
+                    Some(alternative) => {
+                        self.out.push_str(" else ");
+                        self.expression(alternative, Precedence::Lowest as u8, false);
+                    }
+                    None => (),
+                }
+            }
+            Expression::Infix(v) => {
+                let precedence = Precedence::from(&v.operator) as u8;
+                self.expression(&v.left, precedence, false);
+                self.out.push_str(&format!(" {} ", v.operator));
+                self.expression(&v.right, precedence, true);
+            }
+            Expression::Prefix(v) => {
+                self.out.push_str(&v.operator.to_string());
+                self.expression(&v.right, Precedence::Prefix as u8, false);
+            }
+            Expression::Block(v) => self.block(v),
+            Expression::Import(v) => {
+                self.out.push_str("import ");
+                self.expression(&v.path, Precedence::Index as u8, true);
+            }
+        }
+    }
+
+    fn item(&mut self, item: &Item) {
+        match item {
+            Item::Expression(expression) => {
+                self.expression(expression, Precedence::Lowest as u8, false)
+            }
+            Item::Pair(key, value) => {
+                self.expression(key, Precedence::Lowest as u8, false);
+                self.out.push_str(": ");
+                self.expression(value, Precedence::Lowest as u8, false);
+            }
+            Item::Param(param) => self.out.push_str(param.value.as_str()),
+        }
+    }
+
+    /// Prints `items` on one line if the line fits, or one per line with a
+    /// trailing comma.
+    fn list(&mut self, open: &str, close: &str, items: &[Item]) {
+        if self.flat || items.is_empty() {
+            self.flat_list(open, close, items);
+            return;
+        }
+
+        let mut flat = Printer::new(self.source, self.config, &[]);
+        flat.level = self.level;
+        flat.flat = true;
+        flat.flat_list(open, close, items);
+
+        let first_line = flat.out.lines().next().unwrap_or_default();
+        if self.column() + first_line.chars().count() <= self.config.width {
+            self.flat_list(open, close, items);
+            return;
+        }
+
+        self.out.push_str(open);
+        self.out.push('\n');
+        self.level += 1;
+
+        for item in items {
+            self.indent();
+            self.item(item);
+            self.out.push_str(",\n");
+        }
+
+        self.level -= 1;
+        self.indent();
+        self.out.push_str(close);
+    }
+
+    fn flat_list(&mut self, open: &str, close: &str, items: &[Item]) {
+        self.out.push_str(open);
+
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.item(item);
+        }
+
+        self.out.push_str(close);
+    }
+}
+
+fn precedence(expression: &Expression) -> u8 {
+    match expression {
+        Expression::Var(_) | Expression::IndexAssign(_) => Precedence::AssignmentOps as u8,
+        Expression::Infix(v) => Precedence::from(&v.operator) as u8,
+        Expression::Prefix(_) => Precedence::Prefix as u8,
+        Expression::Call(_) => Precedence::Call as u8,
+        Expression::Index(_) | Expression::Member(_) => Precedence::Index as u8,
+        // these end in a block, which anything after would attach to
+        Expression::If(_) | Expression::Function(_) | Expression::Block(_) => {
+            Precedence::Lowest as u8
+        }
+        Expression::Import(_) => Precedence::Lowest as u8,
+        _ => PRIMARY,
+    }
+}
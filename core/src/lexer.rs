@@ -1,38 +1,125 @@
+mod input;
+
+use std::io::Read;
+
+use input::Input;
+
 use crate::{
-    error::SyntaxError,
+    ast::Comment,
+    error::LexError,
+    source::{Position, Span},
     token::Token,
     utils::{digits, hex_byte_to_u8, letters, unwrap_or_return},
 };
 
 pub struct Lexer<'a> {
-    input: &'a [u8],
+    input: Input<'a>,
     position: usize,
     read_position: usize,
     ch: u8,
+
+    /// Where the last token returned by `next_token` starts.
+    start: usize,
+    /// Added to every span, see [`Lexer::with_base`].
+    base: usize,
+    /// Where `ch` is.
+    line: usize,
+    column: usize,
+    /// Where the last token starts.
+    start_position: Position,
+    /// The `##` lines right before the last token.
+    doc: Option<String>,
+    /// The comments skipped so far, see [`Lexer::take_comments`].
+    comments: Vec<Comment>,
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(input: &'a [u8]) -> Lexer {
+    pub fn new(input: &'a [u8]) -> Lexer<'a> {
+        Self::with_input(Input::Bytes(input))
+    }
+
+    /// Lexes what `reader` reads, a chunk at a time, so the whole input is
+    /// never in memory at once. It doesn't need to be buffered.
+    pub fn from_reader(reader: impl Read + 'a) -> Lexer<'a> {
+        Self::with_input(Input::reader(reader))
+    }
+
+    fn with_input(input: Input<'a>) -> Lexer<'a> {
         Lexer {
             input,
             position: 0,
             read_position: 0,
             ch: 0,
+
+            start: 0,
+            base: 0,
+            doc: None,
+            comments: Vec::new(),
+            line: 1,
+            column: 0,
+            start_position: Position { line: 1, column: 1 },
         }
     }
 
-    pub fn next_token(&mut self) -> Result<Token, SyntaxError> {
-        if !self.skip_whitespace_and_comments() {
+    /// Offsets spans by `base`, for input added to a
+    /// [`SourceMap`](crate::source::SourceMap).
+    pub fn with_base(mut self, base: usize) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// The span of the last token returned by `next_token`.
+    pub fn span(&self) -> Span {
+        self.span_from(self.start)
+    }
+
+    /// From `start` to the end of the last character read.
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(self.base + start, self.base + self.read_position)
+    }
+
+    /// The doc comment right before the last token returned by
+    /// `next_token`: consecutive `##` lines with no blank line or other
+    /// comment between them and the token.
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    /// The comments skipped since the last call, in order.
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        std::mem::take(&mut self.comments)
+    }
+
+    pub fn next_token(&mut self) -> Result<Token, LexError> {
+        let token = self.read_token();
+
+        // what was read before an error is lexed first
+        match token {
+            Ok(Token::EOF) => match self.input.take_error() {
+                Some(err) => Err(LexError::Read(err.to_string(), self.span())),
+                None => token,
+            },
+            token => token,
+        }
+    }
+
+    fn read_token(&mut self) -> Result<Token, LexError> {
+        if !self.skip_whitespace_and_comments()? {
+            self.start = self.read_position;
             return Ok(Token::EOF);
         }
 
+        self.start = self.position;
+        self.start_position = self.line_column();
+        self.input.discard(self.start);
+
         match self.ch {
             b':' => match self.peek_char() {
                 Some(b'=') => {
                     self.read_char();
                     Ok(Token::ColonAssign)
                 }
-                _ => Err(SyntaxError::UnknownToken(":".into())),
+                _ => Ok(Token::Colon),
             },
             b'=' => match self.peek_char() {
                 Some(b'=') => {
@@ -146,6 +233,21 @@ impl<'a> Lexer<'a> {
                 }
                 _ => Ok(Token::Mod),
             },
+            b'?' => match self.peek_char() {
+                Some(b'?') => {
+                    self.read_char();
+                    Ok(Token::NullCoalesce)
+                }
+                Some(b'.') => {
+                    self.read_char();
+                    Ok(Token::OptionalDot)
+                }
+                Some(b'[') => {
+                    self.read_char();
+                    Ok(Token::OptionalBracket)
+                }
+                _ => Err(self.unexpected_char()),
+            },
             b'(' => Ok(Token::LeftParen),
             b')' => Ok(Token::RightParen),
             b'{' => Ok(Token::LeftBrace),
@@ -155,54 +257,181 @@ impl<'a> Lexer<'a> {
             b';' => Ok(Token::Semicolon),
             b',' => Ok(Token::Comma),
             b'\\' => Ok(Token::Backslash),
+            b'.' => Ok(Token::Dot),
             b'"' => self.read_string(),
             letters!() => Ok(self.read_identifier()?),
             digits!() => Ok(self.read_number()?),
-            _ => Err(SyntaxError::UnknownToken(
-                String::from_utf8(vec![self.ch]).unwrap(),
-            )),
+            _ => Err(self.unexpected_char()),
+        }
+    }
+
+    /// Where the current character is.
+    pub fn line_column(&self) -> Position {
+        Position {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    /// An error for the character starting at `ch`, which is skipped.
+    fn unexpected_char(&mut self) -> LexError {
+        let position = self.line_column();
+
+        let len = match self.ch {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+        let bytes = (self.position..self.position + len)
+            .map_while(|pos| self.input.get(pos))
+            .collect::<Vec<_>>();
+
+        match std::str::from_utf8(&bytes)
+            .ok()
+            .and_then(|s| s.chars().next())
+        {
+            Some(ch) => {
+                for _ in 1..len {
+                    self.read_char();
+                }
+                LexError::UnexpectedChar(ch, position, self.span())
+            }
+            None => LexError::UnexpectedByte(self.ch, position, self.span()),
         }
     }
 
     pub fn read_char(&mut self) -> Option<u8> {
+        let prev = self.ch;
         self.ch = self.peek_char()?;
 
+        if prev == b'\n' {
+            self.line += 1;
+            self.column = 0;
+        }
+        // UTF-8 continuation bytes are part of the previous character
+        if self.ch & 0xc0 != 0x80 {
+            self.column += 1;
+        }
+
         self.position = self.read_position;
         self.read_position += 1;
 
         Some(self.ch)
     }
 
-    pub fn peek_char(&self) -> Option<u8> {
-        self.input.get(self.read_position).copied()
+    pub fn peek_char(&mut self) -> Option<u8> {
+        self.input.get(self.read_position)
     }
 
     /// Return false if it encounters an EOF.
-    pub fn skip_whitespace_and_comments(&mut self) -> bool {
-        loop {
+    pub fn skip_whitespace_and_comments(&mut self) -> Result<bool, LexError> {
+        let mut doc = Vec::new();
+        let mut newlines = 0;
+
+        let found = loop {
             match self.read_char() {
-                Some(b' ' | b'\t' | b'\n' | b'\r') => (),
-                Some(b'#') => self.skip_comment(),
-                None => return false,
-                _ => return true,
+                Some(b' ' | b'\t' | b'\r') => (),
+                Some(b'\n') => {
+                    newlines += 1;
+                    if newlines > 1 {
+                        doc.clear();
+                    }
+                }
+                Some(b'#') => {
+                    let start = self.position;
+                    let line = self.read_line();
+                    let line = line.trim_ascii_end();
+                    let text = format!("#{}", String::from_utf8_lossy(line));
+
+                    match text.strip_prefix("##") {
+                        Some(line) => doc.push(line.strip_prefix(' ').unwrap_or(line).to_string()),
+                        None => doc.clear(),
+                    }
+
+                    self.comments.push(Comment {
+                        span: Span::new(self.base + start, self.base + start + 1 + line.len()),
+                        text,
+                    });
+
+                    newlines = 1;
+                }
+                Some(b'/') if self.peek_char() == Some(b'*') => {
+                    let start = self.position;
+                    self.skip_block_comment()?;
+                    doc.clear();
+
+                    let text = self.input.slice(start, self.read_position);
+                    self.comments.push(Comment {
+                        span: self.span_from(start),
+                        text: String::from_utf8_lossy(text).into_owned(),
+                    });
+                }
+                None => break false,
+                _ => break true,
             };
+        };
+
+        self.doc = (!doc.is_empty()).then(|| doc.join("\n"));
+        Ok(found)
+    }
+
+    /// Skips a `/* ... */` comment, which may contain others.
+    pub fn skip_block_comment(&mut self) -> Result<(), LexError> {
+        let (start, start_position) = (self.position, self.line_column());
+        self.read_char(); // consume the '*'
+        let mut depth = 1;
+
+        while depth > 0 {
+            match self.read_char() {
+                Some(b'/') if self.peek_char() == Some(b'*') => {
+                    self.read_char();
+                    depth += 1;
+                }
+                Some(b'*') if self.peek_char() == Some(b'/') => {
+                    self.read_char();
+                    depth -= 1;
+                }
+                Some(_) => (),
+                None => {
+                    return Err(LexError::UnclosedComment(
+                        start_position,
+                        self.span_from(start),
+                    ))
+                }
+            }
         }
+
+        Ok(())
     }
 
     pub fn skip_comment(&mut self) {
-        while let b'#' = self.ch {
-            while let Some(ch) = self.read_char() {
-                if ch == b'\n' {
-                    break;
-                }
+        while let Some(ch) = self.read_char() {
+            if ch == b'\n' {
+                break;
+            }
+        }
+    }
+
+    /// The rest of the line, which is skipped like a comment.
+    fn read_line(&mut self) -> Vec<u8> {
+        let mut line = Vec::new();
+        while let Some(ch) = self.read_char() {
+            if ch == b'\n' {
+                break;
             }
+            line.push(ch);
         }
+        line
     }
 
-    pub fn read_string(&mut self) -> Result<Token, SyntaxError> {
+    pub fn read_string(&mut self) -> Result<Token, LexError> {
         let mut result = Vec::<u8>::new();
 
         loop {
+            // where an escape would start
+            let escape = self.read_position;
+
             match self.read_char() {
                 Some(b'\\') => match self.peek_char() {
                     Some(b'n') => {
@@ -228,54 +457,57 @@ impl<'a> Lexer<'a> {
                     Some(b'x') => {
                         self.read_char(); // consume the 'x'
 
-                        let hi_c =
-                            unwrap_or_return!(self.read_char(), Err(SyntaxError::UnexpectedEOF));
-                        let lo_c =
-                            unwrap_or_return!(self.read_char(), Err(SyntaxError::UnexpectedEOF));
-
-                        let hi = unwrap_or_return!(
-                            hex_byte_to_u8(hi_c),
-                            Err(SyntaxError::UnknownEscapeString(
-                                String::from_utf8(vec![b'x', hi_c, lo_c]).unwrap(),
-                            ))
+                        let hi_c = unwrap_or_return!(
+                            self.read_char(),
+                            Err(LexError::UnclosedString(self.start_position, self.span()))
                         );
-
-                        let lo = unwrap_or_return!(
-                            hex_byte_to_u8(lo_c),
-                            Err(SyntaxError::UnknownEscapeString(
-                                String::from_utf8(vec![b'x', hi_c, lo_c]).unwrap(),
-                            ))
+                        let lo_c = unwrap_or_return!(
+                            self.read_char(),
+                            Err(LexError::UnclosedString(self.start_position, self.span()))
                         );
 
+                        let unknown = || {
+                            LexError::UnknownEscapeString(
+                                String::from_utf8_lossy(&[b'x', hi_c, lo_c]).into_owned(),
+                                self.span_from(escape),
+                            )
+                        };
+                        let hi = unwrap_or_return!(hex_byte_to_u8(hi_c), Err(unknown()));
+                        let lo = unwrap_or_return!(hex_byte_to_u8(lo_c), Err(unknown()));
+
                         result.push((hi << 4) | lo);
                     }
                     Some(c) => {
-                        return Err(SyntaxError::UnknownEscapeString(
-                            String::from_utf8(vec![c]).unwrap(),
-                        ))
+                        self.read_char();
+                        return Err(LexError::UnknownEscapeString(
+                            String::from_utf8_lossy(&[c]).into_owned(),
+                            self.span_from(escape),
+                        ));
                     }
-                    None => return Err(SyntaxError::UnclosedString()),
+                    None => return Err(LexError::UnclosedString(self.start_position, self.span())),
                 },
                 Some(b'"') => break,
                 Some(c) => result.push(c),
-                None => return Err(SyntaxError::UnclosedString()),
+                None => return Err(LexError::UnclosedString(self.start_position, self.span())),
             }
         }
 
-        Ok(Token::String(String::from_utf8(result).unwrap()))
+        String::from_utf8(result)
+            .map(Token::String)
+            .map_err(|_| LexError::InvalidUtf8(self.span()))
     }
 
-    pub fn read_identifier(&mut self) -> Result<Token, SyntaxError> {
+    pub fn read_identifier(&mut self) -> Result<Token, LexError> {
         let position = self.position;
 
         while matches!(self.peek_char(), Some(letters!() | digits!())) {
             self.read_char();
         }
 
-        Ok(Token::from(&self.input[position..self.read_position]))
+        Ok(Token::from(self.input.slice(position, self.read_position)))
     }
 
-    pub fn read_number(&mut self) -> Result<Token, SyntaxError> {
+    pub fn read_number(&mut self) -> Result<Token, LexError> {
         let mut has_decimal = false;
         let position = self.position;
 
@@ -294,13 +526,26 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let num = &self.input[position..self.read_position];
-        let num = std::str::from_utf8(num).unwrap();
+        let num = self.input.slice(position, self.read_position);
+        // only digits and '.', so always valid
+        let num = String::from_utf8_lossy(num).into_owned();
 
         Ok(if has_decimal {
-            Token::Float(String::from(num))
+            Token::Float(num)
         } else {
-            Token::Int(String::from(num))
+            Token::Int(num)
         })
     }
 }
+
+/// The tokens up to, but not including, [`Token::EOF`].
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_token() {
+            Ok(Token::EOF) => None,
+            result => Some(result),
+        }
+    }
+}
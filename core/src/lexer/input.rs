@@ -0,0 +1,83 @@
+use std::io::{self, Read};
+
+/// How much is read from a reader at a time.
+const CHUNK: usize = 8 * 1024;
+
+/// The bytes being lexed: either all of them up front, or a reader that is
+/// read a chunk at a time as the lexer gets to it.
+pub(super) enum Input<'a> {
+    Bytes(&'a [u8]),
+    Reader {
+        reader: Box<dyn Read + 'a>,
+        /// The bytes from `offset` up to what has been read so far.
+        buf: Vec<u8>,
+        offset: usize,
+        /// The first error reading, kept for the lexer. Reading stops at
+        /// it, like at the end of the input.
+        error: Option<io::Error>,
+    },
+}
+
+impl Input<'_> {
+    pub(super) fn reader<'a>(reader: impl Read + 'a) -> Input<'a> {
+        Input::Reader {
+            reader: Box::new(reader),
+            buf: Vec::new(),
+            offset: 0,
+            error: None,
+        }
+    }
+
+    /// The byte at `pos`, or `None` at the end of the input.
+    pub(super) fn get(&mut self, pos: usize) -> Option<u8> {
+        match self {
+            Input::Bytes(bytes) => bytes.get(pos).copied(),
+            Input::Reader {
+                reader,
+                buf,
+                offset,
+                error,
+            } => {
+                while pos >= *offset + buf.len() {
+                    match reader.by_ref().take(CHUNK as u64).read_to_end(buf) {
+                        Ok(0) => {}
+                        Ok(_) => continue,
+                        Err(err) => *error = Some(err),
+                    }
+
+                    // don't ask a terminal for more after the end
+                    *reader = Box::new(io::empty());
+                    break;
+                }
+
+                buf.get(pos.checked_sub(*offset)?).copied()
+            }
+        }
+    }
+
+    /// The bytes from `start` to `end`, which must have been read and not
+    /// discarded yet.
+    pub(super) fn slice(&self, start: usize, end: usize) -> &[u8] {
+        match self {
+            Input::Bytes(bytes) => &bytes[start..end],
+            Input::Reader { buf, offset, .. } => &buf[start - offset..end - offset],
+        }
+    }
+
+    /// Lets go of the bytes before `pos`, which won't be looked at again.
+    pub(super) fn discard(&mut self, pos: usize) {
+        if let Input::Reader { buf, offset, .. } = self {
+            if pos >= *offset + CHUNK {
+                buf.drain(..pos - *offset);
+                *offset = pos;
+            }
+        }
+    }
+
+    pub(super) fn take_error(&mut self) -> Option<io::Error> {
+        match self {
+            Input::Bytes(_) => None,
+            Input::Reader { error, .. } => error.take(),
+        }
+    }
+}
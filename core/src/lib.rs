@@ -1,7 +1,14 @@
 pub mod ast;
+pub mod diagnostic;
 pub mod error;
+pub mod format;
 pub mod lexer;
+pub mod lint;
 pub mod parser;
+pub mod source;
+pub mod symbol;
+#[cfg(feature = "test-utils")]
+pub mod testing;
 pub mod token;
 
 mod utils;
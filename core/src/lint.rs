@@ -0,0 +1,318 @@
+//! Warnings about code that runs but is probably wrong. Each [`Rule`] can be
+//! turned off or made an error, in code or with a config file of
+//! `rule-name = "level"` lines.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{
+        visit::{self, Visitor},
+        BlockExpression, Expression, Identifier, Program, Statement,
+    },
+    diagnostic::{Diagnostic, Severity, Span},
+    error::LintConfigError,
+    symbol::Symbol,
+    token::Token,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// A local variable, parameter or loop variable that is never read.
+    /// Names starting with `_` are left alone.
+    UnusedVariable,
+    /// A binding named like a builtin, which hides it.
+    ShadowedBuiltin,
+    /// An `if` or `while` condition that is a literal. `while (true)` is
+    /// allowed.
+    ConstantCondition,
+    /// An `if`, `else`, loop or bare block with nothing in it.
+    EmptyBlock,
+    /// `x == true` and the like.
+    BoolComparison,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 5] = [
+        Rule::UnusedVariable,
+        Rule::ShadowedBuiltin,
+        Rule::ConstantCondition,
+        Rule::EmptyBlock,
+        Rule::BoolComparison,
+    ];
+
+    /// Also the code of its diagnostics.
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::UnusedVariable => "unused-variable",
+            Rule::ShadowedBuiltin => "shadowed-builtin",
+            Rule::ConstantCondition => "constant-condition",
+            Rule::EmptyBlock => "empty-block",
+            Rule::BoolComparison => "bool-comparison",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Off,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "off" => Some(Level::Off),
+            "warning" => Some(Level::Warning),
+            "error" => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Linter {
+    levels: HashMap<Rule, Level>,
+    builtins: HashSet<String>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self {
+            levels: Rule::ALL.map(|rule| (rule, Level::Warning)).into(),
+            builtins: HashSet::new(),
+        }
+    }
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names for [`Rule::ShadowedBuiltin`]; without them the rule finds
+    /// nothing.
+    pub fn with_builtins<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.builtins = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn set_level(&mut self, rule: Rule, level: Level) {
+        self.levels.insert(rule, level);
+    }
+
+    pub fn level(&self, rule: Rule) -> Level {
+        self.levels[&rule]
+    }
+
+    /// Applies a config file, e.g.
+    ///
+    /// ```text
+    /// # comments and blank lines are skipped
+    /// unused-variable = "error"
+    /// empty-block = "off"
+    /// ```
+    pub fn configure(&mut self, config: &str) -> Result<(), LintConfigError> {
+        for (i, line) in config.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, level) = line
+                .split_once('=')
+                .ok_or(LintConfigError::InvalidLine(i + 1))?;
+            let (name, level) = (name.trim(), level.trim().trim_matches('"'));
+
+            let rule =
+                Rule::from_name(name).ok_or_else(|| LintConfigError::UnknownRule(name.into()))?;
+            let level = Level::from_name(level)
+                .ok_or_else(|| LintConfigError::UnknownLevel(level.into()))?;
+
+            self.set_level(rule, level);
+        }
+
+        Ok(())
+    }
+
+    /// The diagnostics for `program`, in source order.
+    pub fn lint(&self, program: &Program) -> Vec<Diagnostic> {
+        let mut lint = Lint {
+            linter: self,
+            scopes: vec![Scope::default()],
+            diagnostics: Vec::new(),
+        };
+
+        lint.visit_program(program);
+        // top-level variables are the module's exports, so they are never
+        // unused
+        lint.scopes.pop();
+
+        let mut diagnostics = lint.diagnostics;
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.map(|span| span.start));
+        diagnostics
+    }
+}
+
+#[derive(Default)]
+struct Scope {
+    /// Where each variable is declared, and whether it is read.
+    variables: HashMap<Symbol, (Span, bool)>,
+}
+
+struct Lint<'a> {
+    linter: &'a Linter,
+    scopes: Vec<Scope>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Lint<'_> {
+    fn report(&mut self, rule: Rule, span: Span, message: String) {
+        let severity = match self.linter.level(rule) {
+            Level::Off => return,
+            Level::Warning => Severity::Warning,
+            Level::Error => Severity::Error,
+        };
+
+        let diagnostic = Diagnostic::new(severity, rule.name(), message).with_span(span);
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn declare(&mut self, name: &Identifier) {
+        if self.linter.builtins.contains(name.value.as_str()) {
+            let message = format!("`{}` hides the builtin of the same name", name.value);
+            self.report(Rule::ShadowedBuiltin, name.span, message);
+        }
+
+        let scope = self.scopes.last_mut().expect("there is always a scope");
+        scope.variables.insert(name.value, (name.span, false));
+    }
+
+    fn read(&mut self, name: Symbol) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some((_, used)) = scope.variables.get_mut(&name) {
+                *used = true;
+                return;
+            }
+        }
+    }
+
+    /// Runs `f` in a new scope, then reports the variables it never read.
+    fn scope(&mut self, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(Scope::default());
+        f(self);
+
+        let scope = self.scopes.pop().expect("pushed above");
+        for (name, (span, used)) in scope.variables {
+            if !used && !name.as_str().starts_with('_') {
+                self.report(
+                    Rule::UnusedVariable,
+                    span,
+                    format!("`{name}` is never used"),
+                );
+            }
+        }
+    }
+
+    fn check_empty(&mut self, block: &BlockExpression) {
+        if block.statements.iter().all(Statement::is_implicit_null) {
+            self.report(Rule::EmptyBlock, block.span, "empty block".into());
+        }
+    }
+
+    fn check_condition(&mut self, condition: &Expression, allow_true: bool) {
+        let constant = match condition {
+            Expression::Boolean(v) => !(allow_true && v.value),
+            Expression::Integer(_)
+            | Expression::Float(_)
+            | Expression::String(_)
+            | Expression::Null(_)
+            | Expression::Array(_)
+            | Expression::Dict(_)
+            | Expression::Function(_) => true,
+            _ => false,
+        };
+
+        if constant {
+            let message = format!("the condition `{condition}` is constant");
+            self.report(Rule::ConstantCondition, condition.span(), message);
+        }
+    }
+}
+
+impl Visitor for Lint<'_> {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::While(stmt) => {
+                self.check_condition(&stmt.condition, true);
+                self.check_empty(&stmt.block);
+                self.visit_expression(&stmt.condition);
+                self.visit_block(&stmt.block);
+            }
+            // the loop variable lives in the body's scope
+            Statement::For(stmt) => {
+                self.check_empty(&stmt.block);
+                self.visit_expression(&stmt.iterable);
+                self.scope(|lint| {
+                    lint.declare(&stmt.variable);
+                    visit::walk_block(lint, &stmt.block);
+                });
+            }
+            _ => visit::walk_statement(self, statement),
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(identifier) => self.read(identifier.value),
+            Expression::Var(var) => {
+                self.visit_expression(&var.value);
+                if var.token == Token::ColonAssign {
+                    self.declare(&var.name);
+                }
+            }
+            // parameters live in the body's scope
+            Expression::Function(function) => self.scope(|lint| {
+                for param in &function.params {
+                    lint.declare(param);
+                }
+                visit::walk_block(lint, &function.body);
+            }),
+            Expression::If(if_expr) => {
+                self.check_condition(&if_expr.condition, false);
+                self.check_empty(&if_expr.consequence);
+                // an `else` block is checked as a block expression
+                visit::walk_expression(self, expression);
+            }
+            Expression::Block(block) => {
+                self.check_empty(block);
+                self.visit_block(block);
+            }
+            Expression::Infix(infix) if matches!(infix.operator, Token::Eq | Token::Ne) => {
+                let literal = [&infix.left, &infix.right]
+                    .into_iter()
+                    .find(|side| matches!(***side, Expression::Boolean(_)));
+
+                if let Some(literal) = literal {
+                    let message = format!("comparison with `{literal}`, use the value directly");
+                    self.report(Rule::BoolComparison, infix.span, message);
+                }
+
+                visit::walk_expression(self, expression);
+            }
+            _ => visit::walk_expression(self, expression),
+        }
+    }
+
+    fn visit_block(&mut self, block: &BlockExpression) {
+        self.scope(|lint| visit::walk_block(lint, block));
+    }
+}
@@ -1,14 +1,17 @@
 use crate::{
     ast::{self, Expression, Statement},
-    error::SyntaxError,
+    diagnostic::Diagnostic,
+    error::{LexError, SyntaxError},
     lexer,
-    token::{arithmetic_tokens, assignment_tokens, bitwise_tokens, comparison_tokens, Token},
+    source::Span,
+    token::Token,
 };
 
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub enum Precedence {
     Lowest,
     AssignmentOps,
+    NullCoalesce,
     LogicalOr,
     LogicalAnd,
     BitOr,
@@ -25,23 +28,9 @@ pub enum Precedence {
 }
 
 impl From<&Token> for Precedence {
+    /// How tightly the built-in grammar binds `value` after an expression.
     fn from(value: &Token) -> Self {
-        match value {
-            assignment_tokens!() => Self::AssignmentOps,
-            Token::Or => Self::LogicalOr,
-            Token::And => Self::LogicalAnd,
-            Token::BitOr => Self::BitOr,
-            Token::BitXor => Self::BitXor,
-            Token::BitAnd => Self::BitAnd,
-            Token::Eq | Token::Ne => Self::Equality,
-            Token::Lt | Token::Le | Token::Gt | Token::Ge => Self::Relational,
-            Token::ShiftLeft | Token::ShiftRight => Self::Shift,
-            Token::Add | Token::Sub => Self::Additive,
-            Token::Div | Token::Mul | Token::Mod => Self::Multiplicative,
-            Token::LeftParen => Self::Call,
-            Token::LeftBracket => Self::Index,
-            _ => Self::Lowest,
-        }
+        Parselets::builtin().precedence(value)
     }
 }
 
@@ -51,13 +40,14 @@ macro_rules! expect_peek {
             $self.next_token()?;
             true
         } else {
-            return Err(SyntaxError::UnexpectedToken($self.peek_token.clone()));
+            return Err(SyntaxError::UnexpectedToken(
+                $self.peek_token.clone(),
+                $self.peek_span,
+            ));
         }
     };
 }
 
-pub(super) use expect_peek;
-
 macro_rules! optional_peek {
     ($self:expr, $token:pat) => {
         if matches!($self.peek_token, $token) {
@@ -69,39 +59,132 @@ macro_rules! optional_peek {
     };
 }
 
-pub(super) use optional_peek;
+// after the macros, which the parselets use too
+mod incremental;
+mod parselets;
+
+pub use parselets::{InfixParselet, Parselets, PrefixParselet};
 
 pub struct Parser<'a> {
     lexer: lexer::Lexer<'a>,
+    parselets: &'a Parselets,
     curr_token: Token,
     peek_token: Token,
+    curr_span: Span,
+    peek_span: Span,
+    curr_doc: Option<String>,
+    peek_doc: Option<String>,
 
     depth: i32,
     has_semicolon: bool,
+    /// How deep the parser recursed into the expression being parsed, see
+    /// [`MAX_NESTING`].
+    nesting: usize,
+    /// How tall the tree of the expression being parsed will be, see
+    /// [`MAX_HEIGHT`].
+    height: usize,
+    /// Where lexing errors go instead of stopping the parser, when parsing
+    /// lossily.
+    lex_errors: Option<Vec<LexError>>,
 }
 
-impl Parser<'_> {
-    pub fn new(lexer: lexer::Lexer<'_>) -> Parser {
+/// Deeper expressions are a [`SyntaxError::TooDeep`], so that parsing,
+/// printing and evaluating them can't overflow the stack.
+pub const MAX_NESTING: usize = 64;
+
+/// Expressions whose tree would be taller are a [`SyntaxError::TooDeep`]
+/// too. Chains like `1 + 1 + 1` don't nest, but each operator in them is a
+/// level of the tree that printing and evaluating recurse into.
+pub const MAX_HEIGHT: usize = MAX_NESTING * 2;
+
+impl<'a> Parser<'a> {
+    pub fn new(lexer: lexer::Lexer<'a>) -> Parser<'a> {
+        Parser::with_parselets(lexer, Parselets::builtin())
+    }
+
+    /// A parser for a grammar with other operators than the built-in one.
+    pub fn with_parselets(lexer: lexer::Lexer<'a>, parselets: &'a Parselets) -> Parser<'a> {
         Parser {
             lexer,
+            parselets,
             curr_token: Token::default(),
             peek_token: Token::default(),
+            curr_span: Span::default(),
+            peek_span: Span::default(),
+            curr_doc: None,
+            peek_doc: None,
 
             depth: 0,
             has_semicolon: false,
+            nesting: 0,
+            height: 0,
+            lex_errors: None,
         }
     }
 
-    fn next_token(&mut self) -> Result<(), SyntaxError> {
+    pub fn curr_token(&self) -> &Token {
+        &self.curr_token
+    }
+
+    pub fn peek_token(&self) -> &Token {
+        &self.peek_token
+    }
+
+    pub fn curr_span(&self) -> Span {
+        self.curr_span
+    }
+
+    pub fn next_token(&mut self) -> Result<(), SyntaxError> {
         self.curr_token = std::mem::take(&mut self.peek_token);
-        self.peek_token = self.lexer.next_token()?;
+        self.curr_span = self.peek_span;
+        self.curr_doc = self.peek_doc.take();
+        self.peek_token = self.lex()?;
+        self.peek_span = self.lexer.span();
+        self.peek_doc = self.lexer.doc().map(String::from);
 
         Ok(())
     }
 
+    fn lex(&mut self) -> Result<Token, SyntaxError> {
+        loop {
+            match self.lexer.next() {
+                Some(Ok(token)) => return Ok(token),
+                Some(Err(err)) => match &mut self.lex_errors {
+                    // the lexer is past the error, so it can go on
+                    Some(errors) => errors.push(err),
+                    None => return Err(err.into()),
+                },
+                None => return Ok(Token::EOF),
+            }
+        }
+    }
+
+    /// After an item of a comma-separated list closed by `close`, moves to
+    /// the next item if there is one. A trailing comma is skipped, leaving
+    /// `close` as the next token.
+    fn list_continues(&mut self, close: Token) -> Result<bool, SyntaxError> {
+        if self.peek_token != Token::Comma {
+            return Ok(false);
+        }
+
+        self.next_token()?;
+        if self.peek_token == close {
+            return Ok(false);
+        }
+
+        self.next_token()?;
+        Ok(true)
+    }
+
+    /// From `start` to the end of the current token.
+    pub fn span_from(&self, start: usize) -> Span {
+        Span::new(start, self.curr_span.end)
+    }
+
     pub fn parse_program(&mut self) -> Result<ast::Program, SyntaxError> {
-        self.curr_token = self.lexer.next_token()?;
-        self.peek_token = self.lexer.next_token()?;
+        // fills the peek token, then moves it to the current one
+        self.next_token()?;
+        self.next_token()?;
 
         let mut program = ast::Program::default();
 
@@ -110,14 +193,108 @@ impl Parser<'_> {
             self.next_token()?;
         }
 
+        program.comments = self.lexer.take_comments();
+
         Ok(program)
     }
 
+    /// Parses `input` as a single expression, e.g. a formula, with nothing
+    /// after it but an optional `;`.
+    pub fn parse_expression_str(input: &str) -> Result<Expression, SyntaxError> {
+        let mut parser = Parser::new(lexer::Lexer::new(input.as_bytes()));
+        parser.next_token()?;
+        parser.next_token()?;
+
+        if parser.curr_token == Token::EOF {
+            return Err(SyntaxError::UnexpectedEOF(parser.curr_span));
+        }
+
+        let expression = parser.parse_expression(Precedence::Lowest)?;
+
+        optional_peek!(parser, Token::Semicolon);
+        expect_peek!(parser, Token::EOF);
+
+        Ok(expression)
+    }
+
+    /// Parses as much of `input` as it can, for fuzzing and editors. Any
+    /// bytes are fine and every error is a diagnostic: a statement with an
+    /// error is left out of the program, up to the next `;`.
+    pub fn parse_program_lossy(input: &[u8]) -> (ast::Program, Vec<Diagnostic>) {
+        let mut parser = Parser::new(lexer::Lexer::new(input));
+        parser.lex_errors = Some(Vec::new());
+
+        let mut program = ast::Program::default();
+        let mut diagnostics = Vec::new();
+
+        if let Err(err) = parser.parse_statements_lossy(&mut program, &mut diagnostics) {
+            diagnostics.push(Diagnostic::from(&err));
+        }
+
+        program.comments = parser.lexer.take_comments();
+
+        let lex_errors = parser.lex_errors.take().unwrap_or_default();
+        diagnostics.extend(
+            lex_errors
+                .into_iter()
+                .map(|err| (&SyntaxError::from(err)).into()),
+        );
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.map(|span| span.start));
+
+        (program, diagnostics)
+    }
+
+    fn parse_statements_lossy(
+        &mut self,
+        program: &mut ast::Program,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<(), SyntaxError> {
+        self.next_token()?;
+        self.next_token()?;
+
+        while !matches!(self.curr_token, Token::EOF) {
+            match self.parse_statement() {
+                Ok(statement) => program.add_stmt(statement),
+                Err(err) => {
+                    diagnostics.push(Diagnostic::from(&err));
+
+                    // the error may be anywhere inside blocks
+                    self.depth = 0;
+                    self.nesting = 0;
+                    self.height = 0;
+
+                    while !matches!(self.curr_token, Token::Semicolon | Token::EOF) {
+                        self.next_token()?;
+                    }
+                }
+            }
+
+            self.next_token()?;
+        }
+
+        Ok(())
+    }
+
+    fn curr_identifier(&self) -> Result<ast::Identifier, SyntaxError> {
+        match self.curr_token {
+            Token::Ident(value) => Ok(ast::Identifier {
+                token: self.curr_token.clone(),
+                span: self.curr_span,
+                value,
+            }),
+            _ => Err(SyntaxError::UnexpectedToken(
+                self.curr_token.clone(),
+                self.curr_span,
+            )),
+        }
+    }
+
     fn parse_statement(&mut self) -> Result<Statement, SyntaxError> {
         match self.curr_token {
             // parse_return
             Token::Return => {
                 let token = self.curr_token.clone();
+                let start = self.curr_span.start;
 
                 self.next_token()?;
                 let return_value = self.parse_expression(Precedence::Lowest)?;
@@ -126,13 +303,32 @@ impl Parser<'_> {
 
                 Ok(Statement::Return(ast::ReturnStatement {
                     token,
+                    span: self.span_from(start),
                     return_value,
                 }))
             }
 
+            // parse_yield
+            Token::Yield => {
+                let token = self.curr_token.clone();
+                let start = self.curr_span.start;
+
+                self.next_token()?;
+                let value = self.parse_expression(Precedence::Lowest)?;
+
+                self.has_semicolon = expect_peek!(self, Token::Semicolon);
+
+                Ok(Statement::Yield(ast::YieldStatement {
+                    token,
+                    span: self.span_from(start),
+                    value,
+                }))
+            }
+
             // parse_while
             Token::While => {
                 let token = self.curr_token.clone();
+                let start = self.curr_span.start;
 
                 expect_peek!(self, Token::LeftParen);
 
@@ -149,28 +345,62 @@ impl Parser<'_> {
 
                 Ok(Statement::While(ast::WhileStatement {
                     token,
+                    span: self.span_from(start),
                     condition: Box::new(condition),
                     block,
                 }))
             }
 
+            // parse_for
+            Token::For => {
+                let token = self.curr_token.clone();
+                let start = self.curr_span.start;
+
+                expect_peek!(self, Token::LeftParen);
+
+                expect_peek!(self, Token::Ident(_));
+                let variable = self.curr_identifier()?;
+
+                expect_peek!(self, Token::In);
+
+                self.next_token()?;
+                let iterable = self.parse_expression(Precedence::Lowest)?;
+
+                expect_peek!(self, Token::RightParen);
+
+                expect_peek!(self, Token::LeftBrace);
+
+                let block = self.parse_block()?;
+
+                self.has_semicolon = optional_peek!(self, Token::Semicolon);
+
+                Ok(Statement::For(ast::ForStatement {
+                    token,
+                    span: self.span_from(start),
+                    variable,
+                    iterable: Box::new(iterable),
+                    block,
+                }))
+            }
+
             // parse_if: parse if expression as statement
             Token::If => {
+                let start = self.curr_span.start;
                 let expression = self.parse_if()?;
 
                 self.has_semicolon = optional_peek!(self, Token::Semicolon);
 
                 Ok(Statement::Expression(ast::ExpressionStatement {
                     token: Token::If,
+                    span: self.span_from(start),
                     expression,
                 }))
             }
 
             _ => {
-                let stmt = ast::ExpressionStatement {
-                    token: self.curr_token.clone(),
-                    expression: self.parse_expression(Precedence::Lowest)?,
-                };
+                let token = self.curr_token.clone();
+                let start = self.curr_span.start;
+                let expression = self.parse_expression(Precedence::Lowest)?;
 
                 self.has_semicolon = if self.depth == 0 {
                     expect_peek!(self, Token::Semicolon)
@@ -178,43 +408,106 @@ impl Parser<'_> {
                     optional_peek!(self, Token::Semicolon)
                 };
 
-                Ok(Statement::Expression(stmt))
+                Ok(Statement::Expression(ast::ExpressionStatement {
+                    token,
+                    span: self.span_from(start),
+                    expression,
+                }))
             }
         }
     }
 
-    fn parse_expression(&mut self, precedence: Precedence) -> Result<Expression, SyntaxError> {
+    pub fn parse_expression(&mut self, precedence: Precedence) -> Result<Expression, SyntaxError> {
+        let (nesting, height) = (self.nesting, self.height);
+        let expr = self.parse_nested_expression(precedence);
+        (self.nesting, self.height) = (nesting, height);
+        expr
+    }
+
+    /// Every operand parsed by recursing adds a level: the operands of
+    /// prefix and infix operators, grouped expressions and call arguments.
+    /// Chains like `1 + 1 + 1` are parsed in a loop and stay at one level.
+    fn nest(&mut self) -> Result<(), SyntaxError> {
+        self.nesting += 1;
+        if self.nesting > MAX_NESTING {
+            return Err(SyntaxError::TooDeep(self.curr_span));
+        }
+
+        self.grow()
+    }
+
+    /// Adds a level to the tree without recursing, for an operator of a
+    /// chain.
+    fn grow(&mut self) -> Result<(), SyntaxError> {
+        self.height += 1;
+        if self.height > MAX_HEIGHT {
+            return Err(SyntaxError::TooDeep(self.curr_span));
+        }
+
+        Ok(())
+    }
+
+    fn parse_nested_expression(
+        &mut self,
+        precedence: Precedence,
+    ) -> Result<Expression, SyntaxError> {
+        self.nest()?;
         let mut left_expr = self.parse_prefix()?;
 
-        while precedence < Precedence::from(&self.peek_token) {
-            match self.parse_infix(&left_expr)? {
-                Some(expr) => left_expr = expr,
-                None => return Ok(left_expr),
-            };
+        while let Some((infix_precedence, parselet)) = self.parselets.infix(&self.peek_token) {
+            if precedence >= infix_precedence {
+                break;
+            }
+
+            self.grow()?;
+            left_expr = parselet(self, left_expr)?;
         }
 
         Ok(left_expr)
     }
 
+    pub fn parse_prefix(&mut self) -> Result<Expression, SyntaxError> {
+        match self.parselets.prefix(&self.curr_token) {
+            Some(parselet) => parselet(self),
+            None => Err(SyntaxError::UnknownPrefixOperator(
+                self.curr_token.clone(),
+                self.curr_span,
+            )),
+        }
+    }
+
     fn parse_block(&mut self) -> Result<ast::BlockExpression, SyntaxError> {
         let token = self.curr_token.clone();
+        let start = self.curr_span.start;
         let mut statements = Vec::new();
 
+        // a block and the statement in it are two levels
+        let (nesting, height) = (self.nesting, self.height);
+        self.nest()?;
+
         self.next_token()?;
 
         self.depth += 1;
         loop {
-            if matches!(self.curr_token, Token::RightBrace | Token::EOF) {
+            if self.curr_token == Token::EOF {
+                return Err(SyntaxError::UnexpectedEOF(self.curr_span));
+            }
+
+            if self.curr_token == Token::RightBrace {
                 if let Some(Statement::Expression(_)) = statements.last() {
                     if !self.has_semicolon {
                         break;
                     }
                 }
 
+                // the implicit null is empty, right before the `}`
+                let span = Span::new(self.curr_span.start, self.curr_span.start);
                 statements.push(Statement::Expression(ast::ExpressionStatement {
                     token: self.curr_token.clone(),
+                    span,
                     expression: Expression::Null(ast::NullLiteral {
                         token: self.curr_token.clone(),
+                        span,
                     }),
                 }));
 
@@ -225,12 +518,18 @@ impl Parser<'_> {
             self.next_token()?;
         }
         self.depth -= 1;
+        (self.nesting, self.height) = (nesting, height);
 
-        Ok(ast::BlockExpression { statements, token })
+        Ok(ast::BlockExpression {
+            statements,
+            token,
+            span: self.span_from(start),
+        })
     }
 
     fn parse_if(&mut self) -> Result<Expression, SyntaxError> {
         let token = self.curr_token.clone();
+        let start = self.curr_span.start;
 
         expect_peek!(self, Token::LeftParen);
 
@@ -250,7 +549,12 @@ impl Parser<'_> {
             Some(Box::new(match self.curr_token {
                 Token::If => self.parse_if()?,
                 Token::LeftBrace => Expression::Block(self.parse_block()?),
-                _ => return Err(SyntaxError::UnexpectedToken(self.curr_token.clone())),
+                _ => {
+                    return Err(SyntaxError::UnexpectedToken(
+                        self.curr_token.clone(),
+                        self.curr_span,
+                    ))
+                }
             }))
         } else {
             None
@@ -258,294 +562,10 @@ impl Parser<'_> {
 
         Ok(Expression::If(ast::IfExpression {
             token,
+            span: self.span_from(start),
             condition: Box::new(condition),
             consequence,
             alternative,
         }))
     }
-
-    pub fn parse_infix(&mut self, left: &Expression) -> Result<Option<Expression>, SyntaxError> {
-        match self.peek_token {
-            // parse_infix: parse infix expression
-            arithmetic_tokens!()
-            | comparison_tokens!()
-            | bitwise_tokens!()
-            | Token::Or
-            | Token::And => {
-                self.next_token()?;
-
-                let token = self.curr_token.clone();
-                let operator = self.curr_token.clone();
-                let precedence = Precedence::from(&self.curr_token);
-
-                self.next_token()?;
-
-                let right = self.parse_expression(precedence)?;
-
-                Ok(Some(Expression::Infix(ast::InfixExpression {
-                    token,
-                    left: Box::new(left.clone()),
-                    operator,
-                    right: Box::new(right),
-                })))
-            }
-
-            // parse_call: parse call expression
-            Token::LeftParen => {
-                self.next_token()?;
-                self.next_token()?;
-
-                let mut args = Vec::new();
-
-                if !matches!(self.curr_token, Token::RightParen) {
-                    loop {
-                        args.push(self.parse_expression(Precedence::Lowest)?);
-
-                        if !matches!(self.peek_token, Token::Comma) {
-                            break;
-                        }
-
-                        self.next_token()?;
-                        self.next_token()?;
-                    }
-
-                    expect_peek!(self, Token::RightParen);
-                }
-
-                Ok(Some(Expression::Call(ast::CallExpression {
-                    token: self.curr_token.clone(),
-                    function: Box::new(left.clone()),
-                    args,
-                })))
-            }
-
-            Token::LeftBracket => {
-                let token = self.curr_token.clone();
-
-                self.next_token()?;
-                self.next_token()?;
-
-                let index = Box::new(self.parse_expression(Precedence::Lowest)?);
-
-                expect_peek!(self, Token::RightBracket);
-
-                Ok(Some(Expression::Index(ast::IndexExpression {
-                    token,
-                    left: Box::new(left.clone()),
-                    index,
-                })))
-            }
-
-            Token::ColonAssign | Token::Assign => {
-                if !matches!(left, Expression::Identifier(_)) {
-                    return Err(SyntaxError::InvalidLHS(left.clone()));
-                }
-
-                let name = ast::Identifier {
-                    token: self.curr_token.clone(),
-                    value: self.curr_token.to_string(),
-                };
-
-                self.next_token()?;
-                let token = self.curr_token.clone();
-
-                self.next_token()?;
-                let value = Box::new(self.parse_expression(Precedence::Lowest)?);
-
-                Ok(Some(Expression::Var(ast::VarExpression {
-                    token,
-                    name,
-                    value,
-                })))
-            }
-
-            Token::AddAssign
-            | Token::SubAssign
-            | Token::MulAssign
-            | Token::DivAssign
-            | Token::ModAssign
-            | Token::ShiftLeftAssign
-            | Token::ShiftRightAssign => {
-                if !matches!(left, Expression::Identifier(_)) {
-                    return Err(SyntaxError::InvalidLHS(left.clone()));
-                }
-
-                let name = ast::Identifier {
-                    token: self.curr_token.clone(),
-                    value: self.curr_token.to_string(),
-                };
-
-                self.next_token()?;
-                let token = self.curr_token.clone();
-
-                self.next_token()?;
-                let value = self.parse_expression(Precedence::Lowest)?;
-
-                // probably need to change this monstrosity.
-                Ok(Some(Expression::Var(ast::VarExpression {
-                    token: Token::Assign,
-                    name: name.clone(),
-                    value: Box::new(Expression::Infix(ast::InfixExpression {
-                        left: Box::new(Expression::Identifier(name)),
-                        operator: match &token {
-                            Token::AddAssign => Token::Add,
-                            Token::SubAssign => Token::Sub,
-                            Token::MulAssign => Token::Mul,
-                            Token::DivAssign => Token::Div,
-                            Token::ModAssign => Token::Mod,
-                            Token::ShiftLeftAssign => Token::ShiftLeft,
-                            Token::ShiftRightAssign => Token::ShiftRight,
-                            _ => unreachable!(),
-                        },
-                        token,
-                        right: Box::new(value),
-                    })),
-                })))
-            }
-
-            _ => Ok(None),
-        }
-    }
-
-    pub fn parse_prefix(&mut self) -> Result<Expression, SyntaxError> {
-        match self.curr_token {
-            // parse_identifier: parse current token as identifier
-            Token::Ident(ref i) => Ok(Expression::Identifier(ast::Identifier {
-                token: self.curr_token.clone(),
-                value: i.into(),
-            })),
-
-            // parse_integer: parse current token as integer
-            Token::Int(ref i) => match i.parse::<i64>() {
-                Ok(lit) => Ok(Expression::Integer(ast::IntegerLiteral {
-                    token: self.curr_token.clone(),
-                    value: lit,
-                })),
-                Err(_) => Err(SyntaxError::ParsingInteger(i.into())),
-            },
-
-            // parse_float: parse current token as float
-            Token::Float(ref f) => match f.parse::<f64>() {
-                Ok(lit) => Ok(Expression::Float(ast::FloatLiteral {
-                    token: self.curr_token.clone(),
-                    value: lit,
-                })),
-                Err(_) => Err(SyntaxError::ParsingFloat(f.into())),
-            },
-
-            // parse_boolean: parse current token as boolean
-            Token::True | Token::False => Ok(Expression::Boolean(ast::BooleanExpression {
-                token: self.curr_token.clone(),
-                value: matches!(self.curr_token, Token::True),
-            })),
-
-            // parse_string: parse current expression as string
-            Token::String(ref s) => Ok(Expression::String(ast::StringLiteral {
-                token: self.curr_token.clone(),
-                value: s.into(),
-            })),
-
-            // parse_array
-            Token::LeftBracket => Ok(Expression::Array(ast::ArrayLiteral {
-                token: self.curr_token.clone(),
-                elements: {
-                    self.next_token()?;
-
-                    let mut elements = Vec::new();
-
-                    if !matches!(self.curr_token, Token::RightBracket) {
-                        loop {
-                            elements.push(self.parse_expression(Precedence::Lowest)?);
-
-                            if !matches!(self.peek_token, Token::Comma) {
-                                break;
-                            }
-
-                            self.next_token()?;
-                            self.next_token()?;
-                        }
-
-                        expect_peek!(self, Token::RightBracket);
-                    }
-
-                    elements
-                },
-            })),
-
-            // parse_prefix: parse current expression with prefix
-            Token::Not | Token::Sub => {
-                let prev_token = self.curr_token.clone();
-
-                self.next_token()?;
-
-                let right = self.parse_expression(Precedence::Prefix).unwrap();
-
-                Ok(Expression::Prefix(ast::PrefixExpression {
-                    operator: prev_token.clone(),
-                    token: prev_token,
-                    right: Box::new(right),
-                }))
-            }
-
-            // parse_grouped: parse grouped expression
-            Token::LeftParen => {
-                self.next_token()?;
-                let expr = self.parse_expression(Precedence::Lowest);
-
-                expect_peek!(self, Token::RightParen);
-
-                expr
-            }
-
-            // parse_block
-            Token::LeftBrace => {
-                let block = self.parse_block()?;
-                Ok(Expression::Block(block))
-            }
-
-            // parse_if: parse current if expression
-            Token::If => self.parse_if(),
-
-            // parse_function: parse current expression as function
-            Token::Function => {
-                let token = self.curr_token.clone();
-                let mut params = Vec::new();
-
-                expect_peek!(self, Token::LeftParen);
-
-                self.next_token()?;
-
-                if !matches!(self.curr_token, Token::RightParen) {
-                    params.push(ast::Identifier {
-                        token: self.curr_token.clone(),
-                        value: self.curr_token.to_string(),
-                    });
-
-                    while matches!(self.peek_token, Token::Comma) {
-                        self.next_token()?;
-                        self.next_token()?;
-
-                        params.push(ast::Identifier {
-                            token: self.curr_token.clone(),
-                            value: self.curr_token.to_string(),
-                        });
-                    }
-
-                    expect_peek!(self, Token::RightParen);
-                }
-
-                expect_peek!(self, Token::LeftBrace);
-
-                let body = self.parse_block()?;
-
-                Ok(Expression::Function(ast::FunctionLiteral {
-                    token,
-                    params,
-                    body,
-                }))
-            }
-
-            _ => Err(SyntaxError::UnknownPrefixOperator(self.curr_token.clone())),
-        }
-    }
 }
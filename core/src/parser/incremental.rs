@@ -0,0 +1,144 @@
+use super::Parser;
+use crate::{
+    ast::{
+        self,
+        visit::{walk_block_mut, walk_expression_mut, walk_statement_mut, VisitorMut},
+        BlockExpression, Expression, Identifier, Statement,
+    },
+    error::SyntaxError,
+    lexer::Lexer,
+    source::{Edit, Span},
+};
+
+impl Parser<'_> {
+    /// Parses `old` with `edit` made, given `program` parsed from `old`.
+    ///
+    /// Top-level statements away from the edit are reused, only moved by
+    /// how much longer or shorter the text got. The ones around it are
+    /// lexed and parsed again on their own, or the whole text is if that
+    /// doesn't work out, e.g. because the edit opened a string.
+    pub fn reparse(
+        program: &ast::Program,
+        old: &str,
+        edit: &Edit,
+    ) -> Result<ast::Program, SyntaxError> {
+        let source = edit.apply(old);
+        let statements = &program.statements;
+        let mut shift = Shift(edit.text.len() as isize - edit.span.len() as isize);
+
+        let before = statements
+            .iter()
+            .take_while(|statement| statement.span().end < edit.span.start)
+            .count();
+
+        // The statement right after the edit is parsed again too, since a
+        // doc comment before it may have changed. So is every one after it
+        // on the same line, which a `#` in the edit may have commented out.
+        let after = statements
+            .iter()
+            .position(|statement| statement.span().start > edit.span.end)
+            .and_then(|first| {
+                (first + 1..statements.len()).find(|&i| {
+                    old[statements[i - 1].span().end..statements[i].span().start].contains('\n')
+                })
+            })
+            .unwrap_or(statements.len());
+
+        let start = before
+            .checked_sub(1)
+            .map_or(0, |i| statements[i].span().end);
+        let end = statements.get(after).map_or(old.len(), |s| s.span().start);
+
+        let lexer = Lexer::new(&source.as_bytes()[start..shift.apply(end)]).with_base(start);
+        let mut parser = Parser::new(lexer);
+        let changed = parser.parse_program();
+
+        // a comment in the edit may have become the doc of the next one
+        let doc = statements.get(after).and_then(doc);
+        let (Ok(changed), true) = (changed, parser.curr_doc.as_deref() == doc) else {
+            return Parser::new(Lexer::new(source.as_bytes())).parse_program();
+        };
+
+        let mut reparsed = ast::Program::default();
+        reparsed.statements.extend_from_slice(&statements[..before]);
+        reparsed.statements.extend(changed.statements);
+        for statement in &statements[after..] {
+            let mut statement = statement.clone();
+            shift.visit_statement_mut(&mut statement);
+            reparsed.statements.push(statement);
+        }
+
+        let comments = &program.comments;
+        reparsed.comments.extend(
+            comments
+                .iter()
+                .take_while(|comment| comment.span.end <= start)
+                .cloned(),
+        );
+        reparsed.comments.extend(changed.comments);
+        reparsed.comments.extend(
+            comments
+                .iter()
+                .filter(|comment| comment.span.start >= end)
+                .map(|comment| ast::Comment {
+                    span: shift.span(comment.span),
+                    text: comment.text.clone(),
+                }),
+        );
+
+        Ok(reparsed)
+    }
+}
+
+/// The doc comment of a statement declaring a variable.
+fn doc(statement: &Statement) -> Option<&str> {
+    match statement {
+        Statement::Expression(ast::ExpressionStatement {
+            expression: Expression::Var(var),
+            ..
+        }) => var.doc.as_deref(),
+        _ => None,
+    }
+}
+
+/// Moves every span by the same amount.
+#[derive(Clone, Copy)]
+struct Shift(isize);
+
+impl Shift {
+    fn apply(self, offset: usize) -> usize {
+        offset.saturating_add_signed(self.0)
+    }
+
+    fn span(self, span: Span) -> Span {
+        Span::new(self.apply(span.start), self.apply(span.end))
+    }
+}
+
+impl VisitorMut for Shift {
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        *statement.span_mut() = self.span(statement.span());
+        walk_statement_mut(self, statement);
+    }
+
+    fn visit_expression_mut(&mut self, expression: &mut Expression) {
+        // identifiers and blocks are moved when they're visited themselves
+        if !matches!(expression, Expression::Identifier(_) | Expression::Block(_)) {
+            *expression.span_mut() = self.span(expression.span());
+        }
+        // properties aren't walked, they aren't variables
+        if let Expression::Member(member) = expression {
+            member.property.span = self.span(member.property.span);
+        }
+        walk_expression_mut(self, expression);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut BlockExpression) {
+        block.span = self.span(block.span);
+        walk_block_mut(self, block);
+    }
+
+    fn visit_identifier_mut(&mut self, identifier: &mut Identifier) {
+        identifier.span = self.span(identifier.span);
+    }
+}
@@ -0,0 +1,544 @@
+use std::{
+    collections::HashMap,
+    mem::{discriminant, Discriminant},
+    sync::OnceLock,
+};
+
+use super::{Parser, Precedence};
+use crate::{
+    ast::{self, Expression},
+    error::SyntaxError,
+    symbol::Symbol,
+    token::Token,
+};
+
+/// Parses an expression that starts at the current token.
+pub type PrefixParselet = fn(&mut Parser<'_>) -> Result<Expression, SyntaxError>;
+
+/// Parses the rest of an expression after `left`. The operator is still the
+/// peek token when it's called.
+pub type InfixParselet = fn(&mut Parser<'_>, Expression) -> Result<Expression, SyntaxError>;
+
+/// The parselets the parser dispatches to, by the kind of token an
+/// expression starts or continues with.
+///
+/// [`Parselets::default`] is the grammar of the language. New operators are
+/// added by registering them, without touching the parser itself.
+#[derive(Clone)]
+pub struct Parselets {
+    prefix: HashMap<Discriminant<Token>, PrefixParselet>,
+    infix: HashMap<Discriminant<Token>, (Precedence, InfixParselet)>,
+}
+
+impl Parselets {
+    /// No parselets at all, which can't parse anything.
+    pub fn empty() -> Self {
+        Parselets {
+            prefix: HashMap::new(),
+            infix: HashMap::new(),
+        }
+    }
+
+    /// The grammar of the language, shared by every parser that doesn't
+    /// bring its own.
+    pub fn builtin() -> &'static Parselets {
+        static BUILTIN: OnceLock<Parselets> = OnceLock::new();
+        BUILTIN.get_or_init(Parselets::default)
+    }
+
+    /// Parses expressions starting with `token` with `parselet`. Only the
+    /// kind of the token matters, so `Token::Ident` with any name registers
+    /// all identifiers.
+    pub fn register_prefix(&mut self, token: Token, parselet: PrefixParselet) {
+        self.prefix.insert(discriminant(&token), parselet);
+    }
+
+    /// Parses `token` after an expression with `parselet`, binding as
+    /// tightly as `precedence`.
+    pub fn register_infix(
+        &mut self,
+        token: Token,
+        precedence: Precedence,
+        parselet: InfixParselet,
+    ) {
+        self.infix
+            .insert(discriminant(&token), (precedence, parselet));
+    }
+
+    pub fn prefix(&self, token: &Token) -> Option<PrefixParselet> {
+        self.prefix.get(&discriminant(token)).copied()
+    }
+
+    pub fn infix(&self, token: &Token) -> Option<(Precedence, InfixParselet)> {
+        self.infix.get(&discriminant(token)).copied()
+    }
+
+    /// How tightly `token` binds after an expression, [`Precedence::Lowest`]
+    /// if it doesn't continue one.
+    pub fn precedence(&self, token: &Token) -> Precedence {
+        self.infix(token)
+            .map_or(Precedence::Lowest, |(precedence, _)| precedence)
+    }
+}
+
+impl Default for Parselets {
+    fn default() -> Self {
+        let mut parselets = Parselets::empty();
+
+        let name = Symbol::intern("");
+        parselets.register_prefix(Token::Ident(name), identifier);
+        parselets.register_prefix(Token::Int(String::new()), integer);
+        parselets.register_prefix(Token::Float(String::new()), float);
+        parselets.register_prefix(Token::String(String::new()), string);
+        parselets.register_prefix(Token::True, boolean);
+        parselets.register_prefix(Token::False, boolean);
+        parselets.register_prefix(Token::LeftBracket, array);
+        parselets.register_prefix(Token::Not, prefix_operator);
+        parselets.register_prefix(Token::Sub, prefix_operator);
+        parselets.register_prefix(Token::Import, import);
+        parselets.register_prefix(Token::LeftParen, grouped);
+        parselets.register_prefix(Token::LeftBrace, block);
+        parselets.register_prefix(Token::If, if_expression);
+        parselets.register_prefix(Token::Function, function);
+
+        let binary = [
+            (Token::NullCoalesce, Precedence::NullCoalesce),
+            (Token::Or, Precedence::LogicalOr),
+            (Token::And, Precedence::LogicalAnd),
+            (Token::BitOr, Precedence::BitOr),
+            (Token::BitXor, Precedence::BitXor),
+            (Token::BitAnd, Precedence::BitAnd),
+            (Token::Eq, Precedence::Equality),
+            (Token::Ne, Precedence::Equality),
+            (Token::Lt, Precedence::Relational),
+            (Token::Le, Precedence::Relational),
+            (Token::Gt, Precedence::Relational),
+            (Token::Ge, Precedence::Relational),
+            (Token::In, Precedence::Relational),
+            (Token::Is, Precedence::Relational),
+            (Token::ShiftLeft, Precedence::Shift),
+            (Token::ShiftRight, Precedence::Shift),
+            (Token::Add, Precedence::Additive),
+            (Token::Sub, Precedence::Additive),
+            (Token::Mul, Precedence::Multiplicative),
+            (Token::Div, Precedence::Multiplicative),
+            (Token::Mod, Precedence::Multiplicative),
+        ];
+        for (token, precedence) in binary {
+            parselets.register_infix(token, precedence, infix_operator);
+        }
+
+        parselets.register_infix(Token::LeftParen, Precedence::Call, call);
+        parselets.register_infix(Token::LeftBracket, Precedence::Index, index);
+        parselets.register_infix(Token::OptionalBracket, Precedence::Index, index);
+        parselets.register_infix(Token::Dot, Precedence::Index, member);
+        parselets.register_infix(Token::OptionalDot, Precedence::Index, member);
+        parselets.register_infix(Token::Assign, Precedence::AssignmentOps, assign);
+        parselets.register_infix(Token::ColonAssign, Precedence::AssignmentOps, assign);
+
+        for token in [
+            Token::AddAssign,
+            Token::SubAssign,
+            Token::MulAssign,
+            Token::DivAssign,
+            Token::ModAssign,
+            Token::ShiftLeftAssign,
+            Token::ShiftRightAssign,
+        ] {
+            parselets.register_infix(token, Precedence::AssignmentOps, compound_assign);
+        }
+
+        parselets
+    }
+}
+
+fn identifier(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    Ok(Expression::Identifier(parser.curr_identifier()?))
+}
+
+fn integer(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    let Token::Int(ref i) = parser.curr_token else {
+        return Err(SyntaxError::UnexpectedToken(
+            parser.curr_token.clone(),
+            parser.curr_span,
+        ));
+    };
+
+    match i.parse::<i64>() {
+        Ok(lit) => Ok(Expression::Integer(ast::IntegerLiteral {
+            token: parser.curr_token.clone(),
+            span: parser.curr_span,
+            value: lit,
+        })),
+        Err(_) => Err(SyntaxError::ParsingInteger(i.to_string(), parser.curr_span)),
+    }
+}
+
+fn float(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    let Token::Float(ref f) = parser.curr_token else {
+        return Err(SyntaxError::UnexpectedToken(
+            parser.curr_token.clone(),
+            parser.curr_span,
+        ));
+    };
+
+    match f.parse::<f64>() {
+        Ok(lit) => Ok(Expression::Float(ast::FloatLiteral {
+            token: parser.curr_token.clone(),
+            span: parser.curr_span,
+            value: lit,
+        })),
+        Err(_) => Err(SyntaxError::ParsingFloat(f.to_string(), parser.curr_span)),
+    }
+}
+
+fn boolean(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    Ok(Expression::Boolean(ast::BooleanExpression {
+        token: parser.curr_token.clone(),
+        span: parser.curr_span,
+        value: matches!(parser.curr_token, Token::True),
+    }))
+}
+
+fn string(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    let Token::String(ref s) = parser.curr_token else {
+        return Err(SyntaxError::UnexpectedToken(
+            parser.curr_token.clone(),
+            parser.curr_span,
+        ));
+    };
+
+    Ok(Expression::String(ast::StringLiteral {
+        token: parser.curr_token.clone(),
+        span: parser.curr_span,
+        value: s.into(),
+    }))
+}
+
+/// An array or dict literal.
+fn array(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    let token = parser.curr_token.clone();
+    let start = parser.curr_span.start;
+
+    parser.next_token()?;
+
+    match parser.curr_token {
+        // [:] is the empty dict
+        Token::Colon => {
+            expect_peek!(parser, Token::RightBracket);
+
+            return Ok(Expression::Dict(ast::DictLiteral {
+                token,
+                span: parser.span_from(start),
+                pairs: Vec::new(),
+            }));
+        }
+        Token::RightBracket => {
+            return Ok(Expression::Array(ast::ArrayLiteral {
+                token,
+                span: parser.span_from(start),
+                elements: Vec::new(),
+            }));
+        }
+        _ => (),
+    }
+
+    let first = parser.parse_expression(Precedence::Lowest)?;
+
+    if !matches!(parser.peek_token, Token::Colon) {
+        let mut elements = vec![first];
+
+        while parser.list_continues(Token::RightBracket)? {
+            elements.push(parser.parse_expression(Precedence::Lowest)?);
+        }
+
+        expect_peek!(parser, Token::RightBracket);
+
+        return Ok(Expression::Array(ast::ArrayLiteral {
+            token,
+            span: parser.span_from(start),
+            elements,
+        }));
+    }
+
+    let mut pairs = Vec::new();
+    let mut key = first;
+
+    loop {
+        expect_peek!(parser, Token::Colon);
+        parser.next_token()?;
+
+        let value = parser.parse_expression(Precedence::Lowest)?;
+        pairs.push((key, value));
+
+        if !parser.list_continues(Token::RightBracket)? {
+            break;
+        }
+
+        key = parser.parse_expression(Precedence::Lowest)?;
+    }
+
+    expect_peek!(parser, Token::RightBracket);
+
+    Ok(Expression::Dict(ast::DictLiteral {
+        token,
+        span: parser.span_from(start),
+        pairs,
+    }))
+}
+
+fn prefix_operator(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    let prev_token = parser.curr_token.clone();
+    let start = parser.curr_span.start;
+
+    parser.next_token()?;
+
+    let right = parser.parse_expression(Precedence::Prefix)?;
+
+    Ok(Expression::Prefix(ast::PrefixExpression {
+        operator: prev_token.clone(),
+        token: prev_token,
+        span: parser.span_from(start),
+        right: Box::new(right),
+    }))
+}
+
+/// The path binds tighter than anything after it, so `import("m").f` is a
+/// member of the module.
+fn import(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    let token = parser.curr_token.clone();
+    let start = parser.curr_span.start;
+
+    parser.next_token()?;
+    let path = parser.parse_expression(Precedence::Index)?;
+
+    Ok(Expression::Import(ast::ImportExpression {
+        token,
+        span: parser.span_from(start),
+        path: Box::new(path),
+    }))
+}
+
+fn grouped(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    parser.next_token()?;
+    let expr = parser.parse_expression(Precedence::Lowest)?;
+
+    expect_peek!(parser, Token::RightParen);
+
+    Ok(expr)
+}
+
+fn block(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    Ok(Expression::Block(parser.parse_block()?))
+}
+
+fn if_expression(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    parser.parse_if()
+}
+
+fn function(parser: &mut Parser<'_>) -> Result<Expression, SyntaxError> {
+    let token = parser.curr_token.clone();
+    let start = parser.curr_span.start;
+    let mut params = Vec::new();
+
+    expect_peek!(parser, Token::LeftParen);
+
+    parser.next_token()?;
+
+    if !matches!(parser.curr_token, Token::RightParen) {
+        params.push(parser.curr_identifier()?);
+
+        while parser.list_continues(Token::RightParen)? {
+            params.push(parser.curr_identifier()?);
+        }
+
+        expect_peek!(parser, Token::RightParen);
+    }
+
+    expect_peek!(parser, Token::LeftBrace);
+
+    let body = parser.parse_block()?;
+
+    Ok(Expression::Function(ast::FunctionLiteral {
+        token,
+        span: parser.span_from(start),
+        params,
+        body,
+    }))
+}
+
+fn infix_operator(parser: &mut Parser<'_>, left: Expression) -> Result<Expression, SyntaxError> {
+    let start = left.span().start;
+
+    parser.next_token()?;
+
+    let token = parser.curr_token.clone();
+    let operator = parser.curr_token.clone();
+    let precedence = parser.parselets.precedence(&parser.curr_token);
+
+    parser.next_token()?;
+
+    let right = parser.parse_expression(precedence)?;
+
+    Ok(Expression::Infix(ast::InfixExpression {
+        token,
+        span: parser.span_from(start),
+        left: Box::new(left),
+        operator,
+        right: Box::new(right),
+    }))
+}
+
+fn call(parser: &mut Parser<'_>, left: Expression) -> Result<Expression, SyntaxError> {
+    let start = left.span().start;
+
+    parser.next_token()?;
+    parser.next_token()?;
+
+    let mut args = Vec::new();
+
+    if !matches!(parser.curr_token, Token::RightParen) {
+        loop {
+            args.push(parser.parse_expression(Precedence::Lowest)?);
+
+            if !parser.list_continues(Token::RightParen)? {
+                break;
+            }
+        }
+
+        expect_peek!(parser, Token::RightParen);
+    }
+
+    Ok(Expression::Call(ast::CallExpression {
+        token: parser.curr_token.clone(),
+        span: parser.span_from(start),
+        function: Box::new(left),
+        args,
+    }))
+}
+
+fn index(parser: &mut Parser<'_>, left: Expression) -> Result<Expression, SyntaxError> {
+    let start = left.span().start;
+
+    parser.next_token()?;
+    let token = parser.curr_token.clone();
+
+    parser.next_token()?;
+
+    let index = Box::new(parser.parse_expression(Precedence::Lowest)?);
+
+    expect_peek!(parser, Token::RightBracket);
+
+    Ok(Expression::Index(ast::IndexExpression {
+        token,
+        span: parser.span_from(start),
+        left: Box::new(left),
+        index,
+    }))
+}
+
+fn member(parser: &mut Parser<'_>, left: Expression) -> Result<Expression, SyntaxError> {
+    let start = left.span().start;
+
+    parser.next_token()?;
+    let token = parser.curr_token.clone();
+
+    expect_peek!(parser, Token::Ident(_));
+
+    let property = parser.curr_identifier()?;
+
+    Ok(Expression::Member(ast::MemberExpression {
+        token,
+        span: parser.span_from(start),
+        object: Box::new(left),
+        property,
+    }))
+}
+
+fn assign(parser: &mut Parser<'_>, left: Expression) -> Result<Expression, SyntaxError> {
+    let name = match left {
+        Expression::Identifier(name) => name,
+        Expression::Index(index)
+            if index.token == Token::LeftBracket && parser.peek_token == Token::Assign =>
+        {
+            return index_assign(parser, index);
+        }
+        _ => return Err(SyntaxError::InvalidLHS(Box::new(left))),
+    };
+
+    // the name is still the current token
+    let doc = parser.curr_doc.take();
+
+    parser.next_token()?;
+    let token = parser.curr_token.clone();
+    let doc = doc.filter(|_| token == Token::ColonAssign);
+
+    parser.next_token()?;
+    let value = Box::new(parser.parse_expression(Precedence::Lowest)?);
+
+    Ok(Expression::Var(ast::VarExpression {
+        token,
+        span: parser.span_from(name.span.start),
+        name,
+        value,
+        doc,
+    }))
+}
+
+fn index_assign(
+    parser: &mut Parser<'_>,
+    index: ast::IndexExpression,
+) -> Result<Expression, SyntaxError> {
+    parser.next_token()?;
+    let token = parser.curr_token.clone();
+
+    parser.next_token()?;
+    let value = Box::new(parser.parse_expression(Precedence::Lowest)?);
+
+    Ok(Expression::IndexAssign(ast::IndexAssignExpression {
+        token,
+        span: parser.span_from(index.span.start),
+        left: index.left,
+        index: index.index,
+        value,
+    }))
+}
+
+fn compound_assign(parser: &mut Parser<'_>, left: Expression) -> Result<Expression, SyntaxError> {
+    let Expression::Identifier(name) = left else {
+        return Err(SyntaxError::InvalidLHS(Box::new(left)));
+    };
+
+    parser.next_token()?;
+    let token = parser.curr_token.clone();
+    let token_span = parser.curr_span;
+
+    parser.next_token()?;
+    let value = parser.parse_expression(Precedence::Lowest)?;
+
+    let span = parser.span_from(name.span.start);
+
+    // probably need to change this monstrosity.
+    Ok(Expression::Var(ast::VarExpression {
+        token: Token::Assign,
+        span,
+        doc: None,
+        name: name.clone(),
+        value: Box::new(Expression::Infix(ast::InfixExpression {
+            span,
+            left: Box::new(Expression::Identifier(name)),
+            operator: match &token {
+                Token::AddAssign => Token::Add,
+                Token::SubAssign => Token::Sub,
+                Token::MulAssign => Token::Mul,
+                Token::DivAssign => Token::Div,
+                Token::ModAssign => Token::Mod,
+                Token::ShiftLeftAssign => Token::ShiftLeft,
+                Token::ShiftRightAssign => Token::ShiftRight,
+                _ => return Err(SyntaxError::UnexpectedToken(token, token_span)),
+            },
+            token,
+            right: Box::new(value),
+        })),
+    }))
+}
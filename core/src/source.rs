@@ -0,0 +1,191 @@
+//! Where source code comes from. Each file or REPL input added to a
+//! [`SourceMap`] gets its own range of offsets, so a [`Span`] alone tells
+//! which source it points into.
+
+/// Byte offsets, `end` exclusive. Offsets are relative to the start of the
+/// source unless the source belongs to a [`SourceMap`], whose offsets are
+/// shared by all of its sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// The smallest span covering both.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SourceId(u32);
+
+#[derive(Debug)]
+pub struct Source {
+    name: String,
+    text: String,
+    base: usize,
+    /// Offsets into `text` where lines start.
+    lines: Vec<usize>,
+}
+
+impl Source {
+    /// The file path, or a name like `<repl:3>` for other input.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The offset of the first byte of the source in its map.
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.base, self.base + self.text.len())
+    }
+}
+
+/// A change to a text: what `span` covers is replaced with `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub span: Span,
+    pub text: String,
+}
+
+impl Edit {
+    pub fn new(span: Span, text: impl Into<String>) -> Self {
+        Self {
+            span,
+            text: text.into(),
+        }
+    }
+
+    /// `source` with the edit made. Panics if the span isn't in `source`
+    /// or not on character boundaries, like slicing it would.
+    pub fn apply(&self, source: &str) -> String {
+        let mut edited = String::with_capacity(source.len() + self.text.len());
+        edited.push_str(&source[..self.span.start]);
+        edited.push_str(&self.text);
+        edited.push_str(&source[self.span.end..]);
+        edited
+    }
+}
+
+/// A line and a column in one input, both starting at 1. Columns count
+/// characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Like [`Position`], in one of the sources of a [`SourceMap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub source: SourceId,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    sources: Vec<Source>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, text: impl Into<String>) -> SourceId {
+        let text = text.into();
+
+        // One past the end of the previous source, so that a span ending
+        // there can't be taken for one starting here.
+        let base = self.sources.last().map_or(0, |last| last.span().end + 1);
+
+        let lines = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        let id = SourceId(self.sources.len() as u32);
+        self.sources.push(Source {
+            name: name.into(),
+            text,
+            base,
+            lines,
+        });
+
+        id
+    }
+
+    pub fn get(&self, id: SourceId) -> &Source {
+        &self.sources[id.0 as usize]
+    }
+
+    /// The source containing `offset`, or ending right before it.
+    pub fn find(&self, offset: usize) -> Option<SourceId> {
+        let i = self
+            .sources
+            .partition_point(|source| source.base <= offset)
+            .checked_sub(1)?;
+
+        (offset <= self.sources[i].span().end).then_some(SourceId(i as u32))
+    }
+
+    pub fn location(&self, offset: usize) -> Option<Location> {
+        let id = self.find(offset)?;
+        let source = self.get(id);
+        let offset = offset - source.base;
+
+        let line = source.lines.partition_point(|&start| start <= offset);
+        let line_start = source.lines[line - 1];
+        let column = source.text.get(line_start..offset)?.chars().count() + 1;
+
+        Some(Location {
+            source: id,
+            line,
+            column,
+        })
+    }
+
+    /// The text a span covers, if it lies within one source.
+    pub fn snippet(&self, span: Span) -> Option<&str> {
+        let source = self.get(self.find(span.start)?);
+        let start = span.start - source.base;
+
+        source.text.get(start..span.end.checked_sub(source.base)?)
+    }
+
+    /// Where a span starts, e.g. `src/main.bl:3:14`.
+    pub fn describe(&self, span: Span) -> Option<String> {
+        let location = self.location(span.start)?;
+        let name = self.get(location.source).name();
+
+        Some(format!("{name}:{}:{}", location.line, location.column))
+    }
+}
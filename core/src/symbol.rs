@@ -0,0 +1,145 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+    sync::{Mutex, OnceLock},
+};
+
+/// An interned string, used for identifiers.
+///
+/// Symbols are cheap to copy, compare and hash. The interner is global and
+/// never frees its strings, which is fine for identifiers since a program
+/// only ever has a handful of distinct names.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+#[derive(Default)]
+struct Interner {
+    symbols: HashMap<&'static str, Symbol>,
+    names: Vec<&'static str>,
+}
+
+fn interner() -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(Default::default)
+}
+
+/// FxHash, much faster than the default hasher for short names.
+#[derive(Default)]
+struct FastHasher(u64);
+
+impl Hasher for FastHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0.rotate_left(5) ^ byte as u64).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// What this thread has seen of the interner, so that most lookups don't
+/// need the lock.
+#[derive(Default)]
+struct Cache {
+    symbols: HashMap<&'static str, Symbol, BuildHasherDefault<FastHasher>>,
+    names: Vec<Option<&'static str>>,
+}
+
+thread_local! {
+    static CACHE: RefCell<Cache> = RefCell::default();
+}
+
+impl Symbol {
+    pub fn intern(name: &str) -> Symbol {
+        CACHE.with_borrow_mut(|cache| {
+            if let Some(symbol) = cache.symbols.get(name) {
+                return *symbol;
+            }
+
+            let (name, symbol) = Self::intern_global(name);
+            cache.symbols.insert(name, symbol);
+            symbol
+        })
+    }
+
+    fn intern_global(name: &str) -> (&'static str, Symbol) {
+        let mut interner = interner().lock().unwrap();
+
+        if let Some((name, symbol)) = interner.symbols.get_key_value(name) {
+            return (name, *symbol);
+        }
+
+        let name: &'static str = Box::leak(name.into());
+        let symbol = Symbol(interner.names.len() as u32);
+
+        interner.names.push(name);
+        interner.symbols.insert(name, symbol);
+
+        (name, symbol)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        let index = self.0 as usize;
+
+        CACHE.with_borrow_mut(|cache| {
+            if let Some(Some(name)) = cache.names.get(index) {
+                return *name;
+            }
+
+            let name = interner().lock().unwrap().names[index];
+            if cache.names.len() <= index {
+                cache.names.resize(index + 1, None);
+            }
+            cache.names[index] = Some(name);
+            name
+        })
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Symbol::intern(value)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl std::fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = std::borrow::Cow::<'de, str>::deserialize(deserializer)?;
+        Ok(Symbol::intern(&name))
+    }
+}
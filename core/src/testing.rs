@@ -0,0 +1,57 @@
+//! Helpers for testing code built on the parser, behind the `test-utils`
+//! feature.
+
+use crate::{ast, lexer::Lexer, parser::Parser};
+
+/// Parses `input`, panicking on a syntax error.
+pub fn parse(input: &str) -> ast::Program {
+    Parser::new(Lexer::new(input.as_bytes()))
+        .parse_program()
+        .unwrap_or_else(|err| panic!("parser errors: {err}"))
+}
+
+/// The value in `$value`, which must be a `$variant`.
+#[macro_export]
+macro_rules! as_variant {
+    ($value:expr, $variant:path) => {
+        if let $variant(x) = $value {
+            x
+        } else {
+            panic!("unmatching variant! got={}", stringify!($variant));
+        }
+    };
+}
+
+/// Checks that an identifier is named `$expected`.
+#[macro_export]
+macro_rules! ident_has_name {
+    ($value:expr, $expected:expr) => {
+        assert_eq!($value.value, $expected);
+        assert_eq!($value.token.to_string(), $expected.to_string());
+    };
+}
+
+/// Checks that an expression is a `$variant` literal of `$expected`, or an
+/// infix or prefix expression of them.
+#[macro_export]
+macro_rules! expr_variant {
+    ($value:expr, $variant:path = $expected:expr) => {
+        let v = $crate::as_variant!($value, $variant);
+
+        assert_eq!(v.value, $expected);
+        assert_eq!(v.token.to_string(), $expected.to_string());
+    };
+    ($value:expr, Infix => ($left_variant:path = $left:expr, $op:expr, $right_variant:path = $right:expr)) => {
+        let v = $crate::as_variant!($value, $crate::ast::Expression::Infix);
+
+        $crate::expr_variant!(&*v.left, $left_variant = $left);
+        $crate::expr_variant!(&*v.right, $right_variant = $right);
+        assert_eq!(v.operator, $op);
+    };
+    ($value: expr, Prefix => ($op:expr, $right_variant:path = $right:expr)) => {
+        let v = $crate::as_variant!($value, $crate::ast::Expression::Prefix);
+
+        $crate::expr_variant!(&*v.right, $right_variant = $right);
+        assert_eq!(v.operator, $op);
+    };
+}
@@ -1,66 +1,17 @@
-macro_rules! arithmetic_tokens {
-    () => {
-        crate::token::Token::Add
-            | crate::token::Token::Sub
-            | crate::token::Token::Mul
-            | crate::token::Token::Div
-            | crate::token::Token::Mod
-    };
-}
-
-pub(super) use arithmetic_tokens;
-
-macro_rules! comparison_tokens {
-    () => {
-        crate::token::Token::Eq
-            | crate::token::Token::Ne
-            | crate::token::Token::Gt
-            | crate::token::Token::Ge
-            | crate::token::Token::Lt
-            | crate::token::Token::Le
-    };
-}
-
-pub(super) use comparison_tokens;
-
-macro_rules! assignment_tokens {
-    () => {
-        crate::token::Token::Assign
-            | crate::token::Token::ColonAssign
-            | crate::token::Token::AddAssign
-            | crate::token::Token::SubAssign
-            | crate::token::Token::MulAssign
-            | crate::token::Token::DivAssign
-            | crate::token::Token::ModAssign
-            | crate::token::Token::BitAndAssign
-            | crate::token::Token::BitOrAssign
-            | crate::token::Token::BitXorAssign
-            | crate::token::Token::ShiftLeftAssign
-            | crate::token::Token::ShiftRightAssign
-    };
-}
-
-pub(super) use assignment_tokens;
-
-macro_rules! bitwise_tokens {
-    () => {
-        crate::token::Token::BitAnd
-            | crate::token::Token::BitOr
-            | crate::token::Token::BitXor
-            | crate::token::Token::ShiftLeft
-            | crate::token::Token::ShiftRight
-    };
-}
-
-pub(super) use bitwise_tokens;
+use crate::symbol::Symbol;
 
 #[derive(PartialEq, Eq, Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Token {
     #[default]
     EOF,
     Empty,
 
-    Ident(String),
+    // Names are interned so lexing them doesn't allocate. Number literals
+    // aren't, since the interner never frees what it holds and there's no
+    // end to the numbers a program can contain. Strings are owned since
+    // escapes make them differ from the source.
+    Ident(Symbol),
     Int(String),
     Float(String),
     String(String),
@@ -91,6 +42,8 @@ pub enum Token {
     And, // &&
     Or,  // ||
 
+    NullCoalesce, // ??
+
     // Bitwise operators
     BitAnd, // &
     BitOr,  // |
@@ -115,19 +68,30 @@ pub enum Token {
     LeftBracket,  // [
     RightBracket, // ]
 
+    // Optional chaining
+    OptionalDot,     // ?.
+    OptionalBracket, // ?[
+
     // Keywords
     Function, // fn
     While,    // while
+    For,      // for
     If,       // if
     Else,     // else
     Return,   // return
+    Yield,    // yield
+    Import,   // import
     True,     // true
     False,    // false
+    In,       // in
+    Is,       // is
 
     // Other tokens
     Comma,     // ,
+    Colon,     // :
     Semicolon, // ;
     Backslash, // \
+    Dot,       // .
 }
 
 impl From<&[u8]> for Token {
@@ -135,12 +99,17 @@ impl From<&[u8]> for Token {
         match value {
             b"fn" => Token::Function,
             b"while" => Token::While,
+            b"for" => Token::For,
             b"true" => Token::True,
             b"false" => Token::False,
             b"if" => Token::If,
             b"else" => Token::Else,
             b"return" => Token::Return,
-            _ => Token::Ident(String::from_utf8(value.to_vec()).unwrap()),
+            b"yield" => Token::Yield,
+            b"import" => Token::Import,
+            b"in" => Token::In,
+            b"is" => Token::Is,
+            _ => Token::Ident(Symbol::intern(std::str::from_utf8(value).unwrap())),
         }
     }
 }
@@ -151,7 +120,7 @@ impl std::fmt::Display for Token {
             Token::Empty => "<empty>",
             Token::EOF => "EOF",
 
-            Token::Ident(s) => s,
+            Token::Ident(s) => s.as_str(),
             Token::Int(s) => s,
             Token::Float(s) => s,
             Token::String(s) => s,
@@ -179,6 +148,8 @@ impl std::fmt::Display for Token {
             Token::And => "&&",
             Token::Or => "||",
 
+            Token::NullCoalesce => "??",
+
             Token::BitAnd => "&",
             Token::BitOr => "|",
             // Token::BitNot => "~", TODO
@@ -200,17 +171,27 @@ impl std::fmt::Display for Token {
             Token::LeftBracket => "[",
             Token::RightBracket => "]",
 
+            Token::OptionalDot => "?.",
+            Token::OptionalBracket => "?[",
+
             Token::Function => "fn",
             Token::While => "while",
+            Token::For => "for",
             Token::If => "if",
             Token::Else => "else",
             Token::Return => "return",
+            Token::Yield => "yield",
+            Token::Import => "import",
             Token::True => "true",
             Token::False => "false",
+            Token::In => "in",
+            Token::Is => "is",
 
             Token::Comma => ",",
+            Token::Colon => ":",
             Token::Semicolon => ";",
             Token::Backslash => r"\",
+            Token::Dot => ".",
         })
     }
 }
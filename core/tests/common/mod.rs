@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+pub mod strategies;
+
 use belalang_core::{
     ast,
     lexer::{self, Lexer},
@@ -8,8 +10,7 @@ use belalang_core::{
 };
 
 pub fn test_parse(input: &str) -> ast::Program {
-    let input = input.as_bytes().into();
-    let lexer = lexer::Lexer::new(input);
+    let lexer = lexer::Lexer::new(input.as_bytes());
     let mut parser = parser::Parser::new(lexer);
 
     parser.parse_program().expect("parser errors")
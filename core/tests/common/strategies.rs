@@ -0,0 +1,94 @@
+//! Generators of source code that parses.
+
+use proptest::prelude::*;
+
+/// A statement made of random expressions.
+pub fn statement() -> impl Strategy<Value = String> {
+    statement_of(expression().boxed())
+}
+
+pub fn expression() -> impl Strategy<Value = String> {
+    let leaf = prop_oneof![
+        (0u32..1000).prop_map(|i| i.to_string()),
+        (0u32..100, 0u32..100).prop_map(|(i, f)| format!("{i}.{f}")),
+        Just("true".to_string()),
+        Just("x".to_string()),
+        Just("foo".to_string()),
+        Just("[]".to_string()),
+        Just("[:]".to_string()),
+        prop::collection::vec(
+            prop::sample::select(vec!["a", " ", "\\n", "\\\"", "\\\\", "\\x41", "\\t", "é"]),
+            0..4,
+        )
+        .prop_map(|parts| format!("\"{}\"", parts.concat())),
+    ];
+
+    leaf.prop_recursive(4, 32, 3, |inner| {
+        let op = prop::sample::select(vec![
+            "+", "-", "*", "/", "%", "==", "!=", "<", ">=", "&&", "||", "??", "&", "|", "^", "<<",
+            ">>", "in", "is",
+        ]);
+
+        prop_oneof![
+            (inner.clone(), op, inner.clone()).prop_map(|(a, op, b)| format!("{a} {op} {b}")),
+            (prop::sample::select(vec!["-", "!"]), inner.clone())
+                .prop_map(|(op, a)| format!("{op}{a}")),
+            inner.clone().prop_map(|a| format!("({a})")),
+            (
+                inner.clone(),
+                prop::collection::vec(inner.clone(), 0..3),
+                trailing_comma()
+            )
+                .prop_map(|(f, args, comma)| {
+                    let comma = if args.is_empty() { "" } else { comma };
+                    format!("({f})({}{comma})", args.join(", "))
+                }),
+            (inner.clone(), inner.clone()).prop_map(|(a, i)| format!("({a})[{i}]")),
+            (inner.clone(), inner.clone()).prop_map(|(a, i)| format!("({a})?[{i}]")),
+            inner.clone().prop_map(|a| format!("({a}).y")),
+            inner.clone().prop_map(|a| format!("({a})?.y")),
+            (prop::collection::vec(inner.clone(), 1..3), trailing_comma())
+                .prop_map(|(elements, comma)| format!("[{}{comma}]", elements.join(", "))),
+            (inner.clone(), inner.clone()).prop_map(|(k, v)| format!("[{k}: {v}]")),
+            block(inner.clone()).prop_map(|body| format!("fn(x, y) {body}")),
+            (inner.clone(), block(inner.clone()), block(inner.clone()))
+                .prop_map(|(c, a, b)| format!("if ({c}) {a} else {b}")),
+            (inner.clone(), block(inner.clone())).prop_map(|(c, a)| format!("if ({c}) {a}")),
+            inner.clone().prop_map(|a| format!("import {a}")),
+        ]
+    })
+}
+
+fn statement_of(expression: BoxedStrategy<String>) -> impl Strategy<Value = String> {
+    prop_oneof![
+        expression.clone().prop_map(|e| format!("{e};")),
+        expression.clone().prop_map(|e| format!("x := {e};")),
+        expression.clone().prop_map(|e| format!("x += {e};")),
+        (expression.clone(), expression.clone()).prop_map(|(i, e)| format!("x[{i}] = {e};")),
+        expression.clone().prop_map(|e| format!("return {e};")),
+        expression.clone().prop_map(|e| format!("yield {e};")),
+        expression
+            .clone()
+            .prop_map(|e| format!("while ({e}) {{ x; }}")),
+        expression.prop_map(|e| format!("for (i in {e}) {{ i }}")),
+    ]
+}
+
+fn block(expression: BoxedStrategy<String>) -> impl Strategy<Value = String> {
+    (
+        prop::collection::vec(statement_of(expression.clone()), 0..3),
+        prop::option::of(expression),
+    )
+        .prop_map(|(statements, tail)| {
+            format!(
+                "{{ {} {} }}",
+                statements.join(" "),
+                tail.unwrap_or_default()
+            )
+        })
+}
+
+/// Lists may end in a comma, unless they're empty.
+fn trailing_comma() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec!["", ","])
+}
@@ -0,0 +1,69 @@
+use belalang_core::{
+    diagnostic::{self, Diagnostic, Span},
+    lexer::Lexer,
+    parser::Parser,
+};
+
+#[test]
+fn syntax_error() {
+    let err = Parser::new(Lexer::new(b"if (x) {"))
+        .parse_program()
+        .err()
+        .unwrap();
+
+    let diagnostic = Diagnostic::from(&err);
+    assert_eq!(diagnostic.code, "unexpected-eof");
+    assert_eq!(
+        diagnostic.to_json(),
+        r#"{"severity":"error","code":"unexpected-eof","message":"unexpected EOF","span":{"start":8,"end":8},"notes":[]}"#
+    );
+}
+
+#[test]
+fn parse_error_span() {
+    let err = Parser::new(Lexer::new(b"x := ;"))
+        .parse_program()
+        .err()
+        .unwrap();
+
+    let diagnostic = Diagnostic::from(&err);
+    assert_eq!(diagnostic.code, "unknown-prefix-operator");
+    assert!(diagnostic
+        .to_json()
+        .contains(r#""span":{"start":5,"end":6}"#));
+
+    let err = Parser::new(Lexer::new(b"f(1 2);"))
+        .parse_program()
+        .err()
+        .unwrap();
+
+    assert_eq!(err.span(), Span { start: 4, end: 5 });
+}
+
+#[test]
+fn lex_error_span() {
+    let err = Parser::new(Lexer::new(b"x := 1 @ 2;"))
+        .parse_program()
+        .err()
+        .unwrap();
+
+    let diagnostic = Diagnostic::from(&err);
+    assert_eq!(diagnostic.code, "unexpected-character");
+    assert_eq!(diagnostic.span, Some(Span { start: 7, end: 8 }));
+}
+
+#[test]
+fn to_json() {
+    let diagnostic = Diagnostic::error("custom", "a \"quoted\"\tline\n\u{1}")
+        .with_span(Span { start: 3, end: 7 })
+        .with_note("C:\\path");
+
+    assert_eq!(
+        diagnostic::to_json(&[diagnostic.clone(), diagnostic]),
+        format!(
+            "[{0},{0}]",
+            r#"{"severity":"error","code":"custom","message":"a \"quoted\"\tline\n\u0001","span":{"start":3,"end":7},"notes":["C:\\path"]}"#
+        )
+    );
+    assert_eq!(diagnostic::to_json(&[]), "[]");
+}
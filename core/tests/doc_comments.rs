@@ -0,0 +1,51 @@
+#[macro_use]
+mod common;
+
+use belalang_core::ast;
+use common::test_parse;
+
+fn docs(input: &str) -> Vec<Option<String>> {
+    test_parse(input)
+        .statements
+        .iter()
+        .map(|stmt| {
+            let stmt = as_variant!(stmt, ast::Statement::Expression);
+            let var = as_variant!(&stmt.expression, ast::Expression::Var);
+            var.doc.clone()
+        })
+        .collect()
+}
+
+#[test]
+fn attached_to_declarations() {
+    assert_eq!(
+        docs("## Adds one.\n##\n##  Indented.\nadd_one := fn(x) { x + 1 };"),
+        [Some("Adds one.\n\n Indented.".into())]
+    );
+    assert_eq!(
+        docs("## first\na := 1;\nb := 2;"),
+        [Some("first".into()), None]
+    );
+}
+
+#[test]
+fn only_immediately_before() {
+    assert_eq!(docs("## detached\n\nx := 1;"), [None]);
+    assert_eq!(docs("## doc\n# plain\nx := 1;"), [None]);
+    assert_eq!(docs("# plain\nx := 1;"), [None]);
+    assert_eq!(docs("x := 1;\n## reassigned\nx = 2;"), [None, None]);
+}
+
+#[test]
+fn nested_declarations() {
+    let program = test_parse("f := fn() {\n    ## inner\n    y := 1;\n    y\n};");
+
+    let stmt = as_variant!(&program.statements[0], ast::Statement::Expression);
+    let var = as_variant!(&stmt.expression, ast::Expression::Var);
+    let function = as_variant!(&*var.value, ast::Expression::Function);
+    let stmt = as_variant!(&function.body.statements[0], ast::Statement::Expression);
+    let inner = as_variant!(&stmt.expression, ast::Expression::Var);
+
+    assert_eq!(var.doc, None);
+    assert_eq!(inner.doc.as_deref(), Some("inner"));
+}
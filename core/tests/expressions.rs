@@ -15,7 +15,7 @@ fn boolean_true() {
 
     let bool_expr = as_variant!(&expr.expression, ast::Expression::Boolean);
 
-    assert_eq!(bool_expr.value, true);
+    assert!(bool_expr.value);
     assert_eq!(bool_expr.token, token::Token::True);
 }
 
@@ -29,7 +29,7 @@ fn boolean_false() {
 
     let bool_expr = as_variant!(&expr.expression, ast::Expression::Boolean);
 
-    assert_eq!(bool_expr.value, false);
+    assert!(!bool_expr.value);
     assert_eq!(bool_expr.token, token::Token::False);
 }
 
@@ -112,6 +112,42 @@ fn array() {
     expr_variant!(&array.elements[2], ast::Expression::Integer = 3);
 }
 
+#[test]
+fn dict() {
+    let program = test_parse(r#"["a": 1, "b": 2];"#);
+
+    assert_eq!(program.statements.len(), 1);
+
+    let stmt = as_variant!(&program.statements[0], ast::Statement::Expression);
+    let dict = as_variant!(&stmt.expression, ast::Expression::Dict);
+
+    assert_eq!(dict.pairs.len(), 2);
+
+    expr_variant!(&dict.pairs[0].1, ast::Expression::Integer = 1);
+    expr_variant!(&dict.pairs[1].1, ast::Expression::Integer = 2);
+
+    let program = test_parse("[:];");
+
+    let stmt = as_variant!(&program.statements[0], ast::Statement::Expression);
+    let dict = as_variant!(&stmt.expression, ast::Expression::Dict);
+
+    assert!(dict.pairs.is_empty());
+}
+
+#[test]
+fn trailing_commas() {
+    test_parse_to_string(
+        "f := fn(x, y,) { [x, y,] }; f(1, [\"a\": 2,],);",
+        r#"f := fn(x, y) { [x, y] };f(1, ["a": 2]);"#,
+    );
+}
+
+#[test]
+#[should_panic]
+fn lone_comma() {
+    test_parse("f(,);");
+}
+
 #[test]
 fn array_indexing() {
     let program = test_parse("arr[1];");
@@ -127,6 +163,53 @@ fn array_indexing() {
     ident_has_name!(ident, "arr");
 }
 
+#[test]
+fn index_assign() {
+    let program = test_parse("arr[1] = 2;");
+
+    assert_eq!(program.statements.len(), 1);
+
+    let stmt = as_variant!(&program.statements[0], ast::Statement::Expression);
+    let assign = as_variant!(&stmt.expression, ast::Expression::IndexAssign);
+
+    expr_variant!(&*assign.index, ast::Expression::Integer = 1);
+    expr_variant!(&*assign.value, ast::Expression::Integer = 2);
+
+    let ident = as_variant!(&*assign.left, ast::Expression::Identifier);
+    ident_has_name!(ident, "arr");
+}
+
+#[test]
+fn member() {
+    let program = test_parse("conn.query(1);");
+
+    assert_eq!(program.statements.len(), 1);
+
+    let stmt = as_variant!(&program.statements[0], ast::Statement::Expression);
+    let call = as_variant!(&stmt.expression, ast::Expression::Call);
+    let member = as_variant!(&*call.function, ast::Expression::Member);
+
+    expr_variant!(&*member.object, ast::Expression::Identifier = "conn");
+    ident_has_name!(member.property, "query");
+
+    test_parse_to_string("a.b.c + 1;", "(((a.b).c) + 1);");
+    test_parse_to_string("a.b[0];", "((a.b)[0]);");
+}
+
+#[test]
+fn import() {
+    let program = test_parse(r#"import "lib/utils";"#);
+
+    let stmt = as_variant!(&program.statements[0], ast::Statement::Expression);
+    let import = as_variant!(&stmt.expression, ast::Expression::Import);
+
+    assert_eq!(import.token, token::Token::Import);
+    expr_variant!(&*import.path, ast::Expression::String = "lib/utils");
+
+    test_parse_to_string(r#"m := import("m");"#, r#"m := (import "m");"#);
+    test_parse_to_string(r#"import("m").f(1);"#, r#"((import "m").f)(1);"#);
+}
+
 #[test]
 fn function() {
     let program = test_parse("fn(x, y) { x + y; };");
@@ -341,6 +424,12 @@ fn infix_var_assign() {
     expr_variant!(&*expr.value, ast::Expression::Integer = 5);
 }
 
+#[test]
+#[should_panic]
+fn optional_index_assign() {
+    test_parse("a?[0] = 1;");
+}
+
 #[test]
 #[should_panic]
 fn infix_on_invalid_lhs() {
@@ -369,6 +458,10 @@ fn infix_operator_precedence() {
     test_parse_to_string("false;", "false;");
     test_parse_to_string("3 > 5 == false;", "((3 > 5) == false);");
     test_parse_to_string("3 < 5 == true;", "((3 < 5) == true);");
+    test_parse_to_string("a + 1 in b == true;", "(((a + 1) in b) == true);");
+    test_parse_to_string("a ?? b || c ?? d;", "((a ?? (b || c)) ?? d);");
+    test_parse_to_string("a?.b.c?[d];", "(((a?.b).c)?[d]);");
+    test_parse_to_string("a is Int == !b is Bool;", "((a is Int) == ((!b) is Bool));");
     test_parse_to_string("1 + (2 + 3) + 4;", "((1 + (2 + 3)) + 4);");
     test_parse_to_string("(5 + 5) * 2;", "((5 + 5) * 2);");
     test_parse_to_string("2 / (5 + 5);", "(2 / (5 + 5));");
@@ -459,3 +552,27 @@ fn string() {
 
     expr_variant!(&expr.expression, ast::Expression::String = "Hello, World!");
 }
+
+#[test]
+fn expression_str() {
+    use belalang_core::{error::SyntaxError, parser::Parser};
+
+    let expr = Parser::parse_expression_str("price * (1 + tax)").unwrap();
+    assert_eq!(expr.to_string(), "(price * (1 + tax))");
+
+    let expr = Parser::parse_expression_str("x := [1, 2];").unwrap();
+    as_variant!(&expr, ast::Expression::Var);
+
+    assert!(matches!(
+        Parser::parse_expression_str("1 2"),
+        Err(SyntaxError::UnexpectedToken(token::Token::Int(_), _))
+    ));
+    assert!(matches!(
+        Parser::parse_expression_str("1; 2;"),
+        Err(SyntaxError::UnexpectedToken(token::Token::Int(_), _))
+    ));
+    assert!(matches!(
+        Parser::parse_expression_str(" "),
+        Err(SyntaxError::UnexpectedEOF(_))
+    ));
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use belalang_core::format::{format, format_with, Config};
+use common::{strategies::statement, test_parse};
+use proptest::prelude::*;
+
+fn test_format(input: &str, expected: &str) {
+    let formatted = format(input).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(format(&formatted).unwrap(), expected);
+}
+
+#[test]
+fn spacing() {
+    test_format(
+        "x:=1+2*3;y  :=  -x;z:=(1+2)*3;w := x - (y - z);",
+        "x := 1 + 2 * 3;\ny := -x;\nz := (1 + 2) * 3;\nw := x - (y - z);\n",
+    );
+    test_format(
+        r#"a.b?.c(d[0]?[1], "s\n", 1.0); (1).x; (import "m").f; i += 1;"#,
+        "a.b?.c(d[0]?[1], \"s\\n\", 1.0);\n(1).x;\n(import \"m\").f;\ni += 1;\n",
+    );
+}
+
+#[test]
+fn indentation() {
+    test_format(
+        "f := fn(n) { if (n < 2) { return n; } else { while (true) { n = n - 1; } }; n };",
+        concat!(
+            "f := fn(n) {\n",
+            "    if (n < 2) {\n",
+            "        return n;\n",
+            "    } else {\n",
+            "        while (true) {\n",
+            "            n = n - 1;\n",
+            "        }\n",
+            "    }\n",
+            "    n\n",
+            "};\n",
+        ),
+    );
+
+    test_format("g := fn() {}; { 1; };", "g := fn() {};\n{\n    1;\n};\n");
+}
+
+#[test]
+fn comments() {
+    test_format(
+        concat!(
+            "# header\n",
+            "x := 1; # one\n",
+            "\n",
+            "\n",
+            "f := fn() {\n",
+            "  # inside\n",
+            "  x\n",
+            "  # at the end\n",
+            "};\n",
+            "# trailing\n",
+        ),
+        concat!(
+            "# header\n",
+            "x := 1;  # one\n",
+            "\n",
+            "f := fn() {\n",
+            "    # inside\n",
+            "    x\n",
+            "    # at the end\n",
+            "};\n",
+            "# trailing\n",
+        ),
+    );
+
+    test_format(
+        "s := \"# not a comment\";\nl := [1, # one\n 2];",
+        "s := \"# not a comment\";\nl := [1, 2];\n# one\n",
+    );
+
+    test_format(
+        "/* a /* nested */ header\n   # kept */\nx := 1; /* one */\ny := x;",
+        "/* a /* nested */ header\n   # kept */\nx := 1;  /* one */\ny := x;\n",
+    );
+}
+
+#[test]
+fn wrapping() {
+    let config = Config {
+        width: 20,
+        ..Config::default()
+    };
+
+    assert_eq!(
+        format_with("f(first, second, [third, 4]);", &config).unwrap(),
+        "f(\n    first,\n    second,\n    [third, 4],\n);\n",
+    );
+    assert_eq!(
+        format_with("f(1, fn(x) { x });", &config).unwrap(),
+        "f(1, fn(x) {\n    x\n});\n",
+    );
+}
+
+#[test]
+fn syntax_error() {
+    assert!(format("x := ;").is_err());
+}
+
+proptest! {
+    #[test]
+    fn format_preserves_meaning(
+        statements in prop::collection::vec(statement(), 1..4),
+        width in 10usize..80,
+    ) {
+        let input = statements.join(" # note\n");
+        let Ok(formatted) = format_with(&input, &Config { width, ..Config::default() }) else {
+            return Ok(());
+        };
+
+        prop_assert_eq!(test_parse(&formatted).to_sexpr(), test_parse(&input).to_sexpr());
+        prop_assert_eq!(formatted.matches("# note").count(), statements.len() - 1);
+
+        let again = format_with(&formatted, &Config { width, ..Config::default() }).unwrap();
+        prop_assert_eq!(again, formatted);
+    }
+}
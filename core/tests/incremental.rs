@@ -0,0 +1,116 @@
+use belalang_core::{
+    ast::Program,
+    error::SyntaxError,
+    lexer::Lexer,
+    parser::Parser,
+    source::{Edit, Span},
+};
+use proptest::prelude::*;
+
+fn parse(source: &str) -> Result<Program, SyntaxError> {
+    Parser::new(Lexer::new(source.as_bytes())).parse_program()
+}
+
+/// Reparses `old` with `edit` made, checking it's the same as parsing the
+/// edited text from scratch.
+fn check(old: &str, edit: Edit) {
+    let program = parse(old).unwrap();
+    let source = edit.apply(old);
+
+    let reparsed = Parser::reparse(&program, old, &edit)
+        .map(|program| format!("{program:?}"))
+        .map_err(|err| err.to_string());
+    let parsed = parse(&source)
+        .map(|program| format!("{program:?}"))
+        .map_err(|err| err.to_string());
+
+    assert_eq!(reparsed, parsed, "{old:?} edited to {source:?}");
+}
+
+#[test]
+fn edits() {
+    let old = "## one\nx := 1;\ny := fn(a) { a + x }; # y\nz := y(2);\n";
+
+    check(old, Edit::new(Span::new(12, 13), "10"));
+    check(old, Edit::new(Span::new(0, 0), "w := 0;\n"));
+    check(old, Edit::new(Span::new(15, 15), "q := [1, 2];"));
+    check(old, Edit::new(Span::new(28, 31), "a * 2"));
+    check(old, Edit::new(Span::new(49, 49), " # z"));
+    check(old, Edit::new(Span::new(0, 6), "## two"));
+    check(old, Edit::new(Span::new(7, 15), ""));
+    check(
+        old,
+        Edit::new(Span::new(old.len(), old.len()), "if (z) { z }"),
+    );
+}
+
+#[test]
+fn edits_that_change_the_rest() {
+    let old = "x := 1;\ny := 2;\nz := 3;\n";
+
+    check(old, Edit::new(Span::new(5, 5), "\""));
+    check(old, Edit::new(Span::new(7, 7), "/*"));
+    check(old, Edit::new(Span::new(7, 7), "f := fn() {"));
+    check(old, Edit::new(Span::new(0, 0), "#"));
+    check(old, Edit::new(Span::new(6, 7), ""));
+
+    // comments out the rest of the line, up to a doc comment
+    let old = "## doc\nd := 1; f := 1; ## doc\nd := 1;";
+    check(old, Edit::new(Span::new(3, 9), "+if, "));
+}
+
+#[test]
+fn reused_statements_move() {
+    let old = "x := 1;\ny := [x, 2];";
+    let program = parse(old).unwrap();
+
+    let edit = Edit::new(Span::new(5, 6), "100");
+    let reparsed = Parser::reparse(&program, old, &edit).unwrap();
+
+    let y = &reparsed.statements[1];
+    assert_eq!(
+        &edit.apply(old)[y.span().start..y.span().end],
+        "y := [x, 2];"
+    );
+}
+
+fn statement() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec![
+        "x := 1;",
+        "y = x + 2 * 3;",
+        "f := fn(a, b) { a - b };",
+        "## doc\nd := 1;",
+        "if (x) { 1 } else { 2 }",
+        "while (x < 3) { x += 1; }",
+        "for (i in [1, 2]) { yield i; };",
+        "return f(1)[0].y;",
+        "# comment",
+        "/* block */",
+        "s := \"a;b\";",
+    ])
+}
+
+fn fragment() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec![
+        "", "x", "1", ";", " ", "\n", "{", "}", "(", ")", "\"", "#", "##", "/*", "*/", ":=", "+",
+        "if", "else", "fn", "[", "]", ",",
+    ])
+}
+
+proptest! {
+    #[test]
+    fn same_as_parsing_again(
+        statements in prop::collection::vec(statement(), 0..8),
+        separator in prop::sample::select(vec![" ", "\n", "\n\n"]),
+        start in 0.0..1.0f64,
+        len in 0..8usize,
+        text in prop::collection::vec(fragment(), 0..4),
+    ) {
+        let old = statements.join(separator);
+        prop_assume!(parse(&old).is_ok());
+
+        let start = (start * old.len() as f64) as usize;
+        let end = (start + len).min(old.len());
+        check(&old, Edit::new(Span::new(start, end), text.concat()));
+    }
+}
@@ -1,13 +1,21 @@
 #[macro_use]
 mod common;
 
-use belalang_core::token::Token;
+use std::io::{self, Read};
+
+use belalang_core::{
+    error::LexError,
+    lexer::Lexer,
+    source::{Position, Span},
+    symbol::Symbol,
+    token::Token,
+};
 use common::test_tokens;
 
 #[test]
 fn tokens() {
     test_tokens(
-        "=+(){}[],;!-/*5;5 < 10 > 5;:= >= <= += -= /= %= *= || &&",
+        "=+(){}[],;!-/ *5;5 < 10 > 5;:= >= <= += -= /= %= *= || && ?? ?. ?[",
         vec![
             Token::Assign,
             Token::Add,
@@ -41,6 +49,9 @@ fn tokens() {
             Token::MulAssign,
             Token::Or,
             Token::And,
+            Token::NullCoalesce,
+            Token::OptionalDot,
+            Token::OptionalBracket,
         ],
     );
 }
@@ -69,3 +80,250 @@ fn escape_strings() {
     test_tokens(r#""\x0A""#, vec![Token::String("\n".into())]);
     test_tokens(r#""\x41""#, vec![Token::String("A".into())]);
 }
+
+#[test]
+fn interned_identifiers() {
+    let mut lexer = Lexer::new(b"count + count");
+
+    let first = lexer.next_token().unwrap();
+    lexer.next_token().unwrap();
+    let second = lexer.next_token().unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(first, Token::Ident(Symbol::intern("count")));
+    assert_eq!(first.to_string(), "count");
+}
+
+#[test]
+fn numbers() {
+    let tokens = Lexer::new(b"12 1.5")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(tokens[0], Token::Int("12".into()));
+    assert_eq!(tokens[1], Token::Float("1.5".into()));
+}
+
+#[test]
+fn spans() {
+    let mut lexer = Lexer::new(b"  x := \"hi\"; # done\n").with_base(10);
+
+    let mut spans = Vec::new();
+    while lexer.next_token().unwrap() != Token::EOF {
+        spans.push(lexer.span());
+    }
+
+    assert_eq!(
+        spans,
+        [
+            Span::new(12, 13),
+            Span::new(14, 16),
+            Span::new(17, 21),
+            Span::new(21, 22)
+        ]
+    );
+    assert_eq!(lexer.span(), Span::new(30, 30));
+}
+
+#[test]
+fn block_comments() {
+    test_tokens(
+        "1 /* one */ + /* two\nlines */ 2",
+        vec![Token::Int("1".into()), Token::Add, Token::Int("2".into())],
+    );
+    test_tokens(
+        "x /* outer /* inner */ still a comment */ y",
+        vec![Token::Ident("x".into()), Token::Ident("y".into())],
+    );
+    test_tokens(
+        "\"/* not a comment */\"",
+        vec![Token::String("/* not a comment */".into())],
+    );
+}
+
+#[test]
+fn unclosed_block_comment() {
+    for input in ["x /* never closed", "x /* outer /* inner */ y"] {
+        let mut lexer = Lexer::new(input.as_bytes());
+        lexer.next_token().unwrap();
+
+        assert_eq!(
+            lexer.next_token(),
+            Err(LexError::UnclosedComment(
+                Position { line: 1, column: 3 },
+                Span::new(2, input.len())
+            ))
+        );
+    }
+}
+
+#[test]
+fn comment_at_eof() {
+    test_tokens("x #", vec![Token::Ident("x".into()), Token::EOF]);
+    test_tokens("x ##", vec![Token::Ident("x".into()), Token::EOF]);
+}
+
+#[test]
+fn iterator() {
+    let tokens = Lexer::new(b"x := 1;").collect::<Result<Vec<_>, _>>();
+    assert_eq!(
+        tokens,
+        Ok(vec![
+            Token::Ident("x".into()),
+            Token::ColonAssign,
+            Token::Int("1".into()),
+            Token::Semicolon,
+        ])
+    );
+
+    assert_eq!(Lexer::new(b"  # only a comment").next(), None);
+}
+
+#[test]
+fn errors_are_values() {
+    assert_eq!(
+        Lexer::new(b"x @ y").collect::<Vec<_>>(),
+        [
+            Ok(Token::Ident("x".into())),
+            Err(LexError::UnexpectedChar(
+                '@',
+                Position { line: 1, column: 3 },
+                Span::new(2, 3)
+            )),
+            Ok(Token::Ident("y".into())),
+        ]
+    );
+    assert_eq!(
+        Lexer::new(b"x \"open").collect::<Vec<_>>(),
+        [
+            Ok(Token::Ident("x".into())),
+            Err(LexError::UnclosedString(
+                Position { line: 1, column: 3 },
+                Span::new(2, 7)
+            )),
+        ]
+    );
+    assert_eq!(
+        Lexer::new(br#""a\q""#).next(),
+        Some(Err(LexError::UnknownEscapeString(
+            "q".into(),
+            Span::new(2, 4)
+        )))
+    );
+}
+
+/// Reads a byte at a time, then fails if `fail` is set.
+struct Trickle<'a> {
+    input: &'a [u8],
+    fail: bool,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.input.split_first() {
+            Some((&byte, rest)) => {
+                buf[0] = byte;
+                self.input = rest;
+                Ok(1)
+            }
+            None if self.fail => Err(io::Error::other("connection reset")),
+            None => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn from_reader() {
+    let source = "## doc\nname := \"a string\";\n/* block */ x += 10.5;\n".repeat(2000);
+    let expected = Lexer::new(source.as_bytes()).collect::<Vec<_>>();
+
+    let mut lexer = Lexer::from_reader(source.as_bytes());
+    let mut chunked = Vec::new();
+    while let Some(token) = lexer.next() {
+        chunked.push((token, lexer.span(), lexer.doc().map(String::from)));
+    }
+
+    let mut lexer = Lexer::new(source.as_bytes());
+    let mut whole = Vec::new();
+    while let Some(token) = lexer.next() {
+        whole.push((token, lexer.span(), lexer.doc().map(String::from)));
+    }
+
+    assert_eq!(chunked, whole);
+    assert_eq!(chunked.len(), expected.len());
+
+    let trickle = Trickle {
+        input: source.as_bytes(),
+        fail: false,
+    };
+    assert_eq!(Lexer::from_reader(trickle).collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn reader_error() {
+    let trickle = Trickle {
+        input: b"x y",
+        fail: true,
+    };
+
+    assert_eq!(
+        Lexer::from_reader(trickle).collect::<Vec<_>>(),
+        [
+            Ok(Token::Ident("x".into())),
+            Ok(Token::Ident("y".into())),
+            Err(LexError::Read("connection reset".into(), Span::new(3, 3))),
+        ]
+    );
+}
+
+#[test]
+fn unexpected_characters() {
+    let mut lexer = Lexer::new("x := 1;\n  y := é + @;".as_bytes());
+    let errors = lexer.by_ref().filter_map(Result::err).collect::<Vec<_>>();
+
+    assert_eq!(
+        errors,
+        [
+            LexError::UnexpectedChar('é', Position { line: 2, column: 8 }, Span::new(15, 17)),
+            LexError::UnexpectedChar(
+                '@',
+                Position {
+                    line: 2,
+                    column: 12
+                },
+                Span::new(20, 21)
+            ),
+        ]
+    );
+    assert_eq!(
+        errors[1].to_string(),
+        "unexpected character '@' at line 2, column 12"
+    );
+
+    let err = Lexer::new(&[b'x', 0xff]).nth(1).unwrap().unwrap_err();
+    assert_eq!(err.to_string(), "unexpected byte 0xff at line 1, column 2");
+}
+
+#[test]
+fn unclosed_string_start() {
+    let err = Lexer::new(b"x := 1;\ny := \"one\ntwo")
+        .find_map(Result::err)
+        .unwrap();
+
+    assert_eq!(
+        err,
+        LexError::UnclosedString(Position { line: 2, column: 6 }, Span::new(13, 21))
+    );
+    assert_eq!(
+        err.to_string(),
+        "unclosed string starting at line 2, column 6"
+    );
+
+    let err = Lexer::new(b"x /* one\n/* two */")
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "unclosed block comment starting at line 1, column 3"
+    );
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use belalang_core::{
+    diagnostic::Severity,
+    lint::{Level, Linter, Rule},
+};
+use common::test_parse;
+
+fn test_lint(linter: &Linter, input: &str, expected: &[(&str, &str)]) {
+    let diagnostics = linter.lint(&test_parse(input));
+
+    let found = diagnostics
+        .iter()
+        .map(|d| {
+            let span = d.span.unwrap();
+            (d.code.as_str(), &input[span.start..span.end])
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn unused_variables() {
+    test_lint(
+        &Linter::new(),
+        "top := 1; f := fn(a, b, _c) { d := a; e := 2; g := fn() { e }; for (i in g()) { 1 } };",
+        &[
+            ("unused-variable", "b"),
+            ("unused-variable", "d"),
+            ("unused-variable", "i"),
+        ],
+    );
+
+    // assigning isn't reading, and inner scopes see outer variables
+    test_lint(
+        &Linter::new(),
+        "fn() { n := 0; m := 0; { n = 1; m + 1 } };",
+        &[("unused-variable", "n")],
+    );
+}
+
+#[test]
+fn shadowed_builtins() {
+    let linter = Linter::new().with_builtins(["len", "print"]);
+
+    test_lint(
+        &linter,
+        "fn(len) { len }; for (print in []) { print };",
+        &[("shadowed-builtin", "len"), ("shadowed-builtin", "print")],
+    );
+}
+
+#[test]
+fn constant_conditions() {
+    test_lint(
+        &Linter::new(),
+        "if (1) { 1 } else if (x) { 2 }; while (true) { x; } while (false) { x; } if (\"s\") { 1 }",
+        &[
+            ("constant-condition", "1"),
+            ("constant-condition", "false"),
+            ("constant-condition", "\"s\""),
+        ],
+    );
+}
+
+#[test]
+fn empty_blocks() {
+    test_lint(
+        &Linter::new(),
+        "if (x) {} else {}; while (x) {} f := fn() {};",
+        &[
+            ("empty-block", "{}"),
+            ("empty-block", "{}"),
+            ("empty-block", "{}"),
+        ],
+    );
+}
+
+#[test]
+fn bool_comparisons() {
+    test_lint(
+        &Linter::new(),
+        "x == true; false != x; x == y;",
+        &[
+            ("bool-comparison", "x == true"),
+            ("bool-comparison", "false != x"),
+        ],
+    );
+}
+
+#[test]
+fn levels() {
+    let mut linter = Linter::new();
+    linter.set_level(Rule::BoolComparison, Level::Off);
+    linter.set_level(Rule::EmptyBlock, Level::Error);
+
+    let diagnostics = linter.lint(&test_parse("if (x == true) {}"));
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, "empty-block");
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+}
+
+#[test]
+fn config() {
+    let mut linter = Linter::new();
+    linter
+        .configure("# rules\nunused-variable = \"error\"\n\nempty-block = off\n")
+        .unwrap();
+
+    assert_eq!(linter.level(Rule::UnusedVariable), Level::Error);
+    assert_eq!(linter.level(Rule::EmptyBlock), Level::Off);
+    assert_eq!(linter.level(Rule::BoolComparison), Level::Warning);
+
+    let err = |config| Linter::new().configure(config).unwrap_err().to_string();
+    assert_eq!(
+        err("no-such-rule = \"off\""),
+        "unknown lint rule: no-such-rule"
+    );
+    assert_eq!(
+        err("empty-block = \"loud\""),
+        "unknown lint level: loud, expected off, warning or error"
+    );
+    assert_eq!(err("empty-block"), "line 1: expected `rule = \"level\"`");
+}
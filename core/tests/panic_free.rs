@@ -0,0 +1,167 @@
+mod common;
+
+use belalang_core::{
+    error::SyntaxError,
+    format::format,
+    lexer::Lexer,
+    parser::{Parser, MAX_NESTING},
+};
+use proptest::prelude::*;
+
+fn parse(input: &[u8]) -> Result<String, SyntaxError> {
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program()?;
+    Ok(program.to_string())
+}
+
+fn nested(open: &str, inner: &str, close: &str, depth: usize) -> String {
+    format!("{}{inner}{};", open.repeat(depth), close.repeat(depth))
+}
+
+#[test]
+fn malformed_input() {
+    for input in [
+        "-",
+        "!;",
+        "x := -;",
+        "fn(",
+        "fn(x,",
+        "[1: 2, 3]",
+        "a.b.",
+        "if (",
+        "while (true) {",
+        "\"\\x",
+        "\"\\xZZ\"",
+        "\"\\xff\"",
+        "é",
+        "x := 99999999999999999999;",
+    ] {
+        assert!(parse(input.as_bytes()).is_err(), "{input:?} parsed");
+    }
+
+    assert!(parse(&[b'"', 0xff, b'"']).is_err());
+    assert!(parse(&[0xc3]).is_err());
+}
+
+#[test]
+fn unknown_characters() {
+    let err = parse("x := é;".as_bytes()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unexpected character 'é' at line 1, column 6"
+    );
+}
+
+#[test]
+fn too_deep() {
+    let cases = [
+        ("(", "1", ")"),
+        ("-", "1", ""),
+        ("[", "1", "]"),
+        ("{", "1", "}"),
+        ("fn() { ", "1", " }"),
+        ("if (true) { ", "1", " }"),
+        ("", "1", " + 1"),
+        ("", "f", "(1)"),
+    ];
+
+    for (open, inner, close) in cases {
+        // the deepest input that parses must not overflow the stack either
+        let mut depth = 1;
+        while parse(nested(open, inner, close, depth).as_bytes()).is_ok() {
+            depth += 1;
+        }
+        assert!(depth > MAX_NESTING / 4, "{open:?} stopped at {depth}");
+
+        let deep = nested(open, inner, close, depth);
+        assert!(matches!(
+            parse(deep.as_bytes()),
+            Err(SyntaxError::TooDeep(_))
+        ));
+        assert!(format(&deep).is_err());
+
+        let deeper = nested(open, inner, close, MAX_NESTING * 64);
+        assert!(matches!(
+            parse(deeper.as_bytes()),
+            Err(SyntaxError::TooDeep(_))
+        ));
+    }
+}
+
+#[test]
+fn long_chains() {
+    // operators of a chain are parsed in a loop, not by recursing
+    for input in [
+        format!("{}1;", "1 + ".repeat(MAX_NESTING)),
+        format!("{}\"a\";", "\"a\" + ".repeat(99)),
+        format!("x := {}f(1);", "f(1) * ".repeat(MAX_NESTING)),
+        format!("(({}1));", "1 || ".repeat(MAX_NESTING)),
+    ] {
+        assert!(parse(input.as_bytes()).is_ok(), "{input}");
+    }
+}
+
+#[test]
+fn lossy() {
+    let input = "x := ;\ny := 2;\nf(1 2);\nz := @ + 1;\n{ a := ; b; };\nw := 3; # end";
+    let (program, diagnostics) = Parser::parse_program_lossy(input.as_bytes());
+
+    assert_eq!(program.to_string(), "y := 2;b;w := 3;");
+    assert_eq!(program.comments.len(), 1);
+
+    let found = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let span = diagnostic.span.unwrap();
+            (diagnostic.code.as_str(), &input[span.start..span.end])
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        found,
+        [
+            ("unknown-prefix-operator", ";"),
+            ("unexpected-token", "2"),
+            ("unexpected-character", "@"),
+            ("unknown-prefix-operator", "+"),
+            ("unknown-prefix-operator", ";"),
+            ("unknown-prefix-operator", "}"),
+        ]
+    );
+
+    let valid = "f := fn(x) { x * 2 }; f(3);";
+    let (program, diagnostics) = Parser::parse_program_lossy(valid.as_bytes());
+    assert!(diagnostics.is_empty());
+    assert_eq!(program.to_string(), parse(valid.as_bytes()).unwrap());
+}
+
+fn fragment() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec![
+        "x", "1", "2.5", "\"s\"", "true", "null", "fn", "if", "else", "while", "for", "in",
+        "return", "yield", "import", "(", ")", "{", "}", "[", "]", ",", ";", ":", ":=", "=", "+=",
+        "+", "-", "!", "*", "==", "&&", "??", ".", "?.", "?[", "\\", "#", "##", "/*", "*/", "\n",
+        " ",
+    ])
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_bytes(input in prop::collection::vec(any::<u8>(), 0..64)) {
+        let _ = parse(&input);
+
+        let (_, diagnostics) = Parser::parse_program_lossy(&input);
+        prop_assert_eq!(diagnostics.is_empty(), parse(&input).is_ok());
+    }
+
+    #[test]
+    fn arbitrary_tokens(fragments in prop::collection::vec(fragment(), 0..48)) {
+        let input = fragments.concat();
+        if let Ok(printed) = parse(input.as_bytes()) {
+            let _ = parse(printed.as_bytes());
+        }
+        let _ = format(&input);
+
+        let (_, diagnostics) = Parser::parse_program_lossy(input.as_bytes());
+        prop_assert_eq!(diagnostics.is_empty(), parse(input.as_bytes()).is_ok());
+    }
+}
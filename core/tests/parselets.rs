@@ -0,0 +1,85 @@
+#[macro_use]
+mod common;
+
+use belalang_core::{
+    ast::{self, Expression},
+    error::SyntaxError,
+    lexer::Lexer,
+    parser::{Parselets, Parser, Precedence},
+    symbol::Symbol,
+    token::Token,
+};
+
+fn parse_with(parselets: &Parselets, input: &str) -> Result<ast::Program, SyntaxError> {
+    Parser::with_parselets(Lexer::new(input.as_bytes()), parselets).parse_program()
+}
+
+/// `x \ f` calls `f` with `x`.
+fn pipe(parser: &mut Parser<'_>, left: Expression) -> Result<Expression, SyntaxError> {
+    let start = left.span().start;
+
+    parser.next_token()?;
+    let token = parser.curr_token().clone();
+
+    parser.next_token()?;
+    let function = parser.parse_expression(Precedence::LogicalOr)?;
+
+    Ok(Expression::Call(ast::CallExpression {
+        token,
+        span: parser.span_from(start),
+        function: Box::new(function),
+        args: vec![left],
+    }))
+}
+
+#[test]
+fn custom_infix() {
+    let mut parselets = Parselets::default();
+    parselets.register_infix(Token::Backslash, Precedence::LogicalOr, pipe);
+
+    let program = parse_with(&parselets, r"1 + 2 \ f \ g;").unwrap();
+    let expr = as_variant!(&program.statements[0], ast::Statement::Expression);
+
+    let outer = as_variant!(&expr.expression, Expression::Call);
+    let g = as_variant!(&*outer.function, Expression::Identifier);
+    ident_has_name!(g, "g");
+
+    let inner = as_variant!(&outer.args[0], Expression::Call);
+    let f = as_variant!(&*inner.function, Expression::Identifier);
+    ident_has_name!(f, "f");
+    assert_eq!(inner.args[0].to_string(), "(1 + 2)");
+
+    assert!(Parser::new(Lexer::new(br"1 \ f;")).parse_program().is_err());
+}
+
+#[test]
+fn custom_prefix() {
+    let mut parselets = Parselets::default();
+    parselets.register_prefix(Token::Add, |parser| {
+        parser.next_token()?;
+        parser.parse_expression(Precedence::Prefix)
+    });
+
+    let program = parse_with(&parselets, "+1 * +2;").unwrap();
+    assert_eq!(program.to_string(), "(1 * 2);");
+
+    assert!(matches!(
+        Parser::new(Lexer::new(b"+1;")).parse_program(),
+        Err(SyntaxError::UnknownPrefixOperator(Token::Add, _))
+    ));
+}
+
+#[test]
+fn precedences() {
+    let parselets = Parselets::builtin();
+
+    assert_eq!(
+        parselets.precedence(&Token::Mul),
+        Precedence::Multiplicative
+    );
+    assert_eq!(parselets.precedence(&Token::LeftParen), Precedence::Call);
+    assert_eq!(parselets.precedence(&Token::Semicolon), Precedence::Lowest);
+    assert!(parselets
+        .prefix(&Token::Ident(Symbol::intern("x")))
+        .is_some());
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use belalang_core::{ast::Program, lexer::Lexer, parser::Parser};
+use common::{strategies::statement, test_parse, test_parse_to_string};
+use proptest::prelude::*;
+
+fn parse(input: &str) -> Option<Program> {
+    Parser::new(Lexer::new(input.as_bytes()))
+        .parse_program()
+        .ok()
+}
+
+#[test]
+fn literals() {
+    test_parse_to_string(
+        r#"x := "a\"b\\c\n\x01"; y := 2.0; z := 1.25;"#,
+        r#"x := "a\"b\\c\n\x01";y := 2.0;z := 1.25;"#,
+    );
+}
+
+#[test]
+fn blocks() {
+    test_parse_to_string("f := fn() { 1 };", "f := fn() { 1 };");
+    test_parse_to_string("f := fn() { 1; };", "f := fn() { 1; };");
+    test_parse_to_string("f := fn() {};", "f := fn() {};");
+    test_parse_to_string(
+        "if (x) { while (y) { y -= 1; } }",
+        "if (x) { while (y) { y = (y - 1); } };",
+    );
+}
+
+#[test]
+fn reparse() {
+    let input = r#"
+        counter := fn(start) {
+            n := start;
+            fn() { n += 1; n }
+        };
+
+        for (i in [1, 2.5, "s"]) {
+            d := ["k": [i, -i, !true]];
+            d["k"][0] = d?["k"]?.len;
+            if (i is "string") { yield i; } else if (i > 2) { return i; } else { import "m" }
+        }
+    "#;
+
+    let program = test_parse(input);
+    let printed = program.to_string();
+
+    assert_eq!(test_parse(&printed).to_sexpr(), program.to_sexpr());
+    assert_eq!(test_parse(&printed).to_string(), printed);
+}
+
+proptest! {
+    #[test]
+    fn parse_print_parse(
+        statements in prop::collection::vec(statement(), 1..4)
+    ) {
+        let input = statements.join("\n");
+        let program = parse(&input);
+        prop_assume!(program.is_some());
+        let program = program.unwrap();
+
+        let printed = program.to_string();
+        let reparsed = parse(&printed);
+        prop_assert!(reparsed.is_some(), "{} doesn't parse", printed);
+        let reparsed = reparsed.unwrap();
+
+        prop_assert_eq!(reparsed.to_sexpr(), program.to_sexpr());
+        prop_assert_eq!(reparsed.to_string(), printed);
+    }
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use belalang_core::ast::Program;
+use common::test_parse;
+
+#[test]
+fn to_json() {
+    let program = test_parse("-x;");
+
+    assert_eq!(
+        program.to_json(),
+        concat!(
+            r#"{"statements":[{"Expression":{"token":"Sub","span":{"start":0,"end":3},"#,
+            r#""expression":{"Prefix":{"token":"Sub","span":{"start":0,"end":2},"operator":"Sub","#,
+            r#""right":{"Identifier":{"token":{"Ident":"x"},"span":{"start":1,"end":2},"value":"x"}}}}}}]}"#,
+        )
+    );
+}
+
+#[test]
+fn round_trip() {
+    let input =
+        r#"f := fn(a) { if (a > 1.5) { [a: "b"] } else { a?.c } }; for (i in f(2)) { yield i; }"#;
+    let program = test_parse(input);
+
+    let json = program.to_json();
+    let parsed = Program::from_json(&json).unwrap();
+
+    assert_eq!(parsed.to_string(), program.to_string());
+    assert_eq!(parsed.to_json(), json);
+}
+
+#[test]
+fn invalid_json() {
+    assert!(Program::from_json(r#"{"statements":[{"Loop":{}}]}"#).is_err());
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::test_parse;
+
+fn test_sexpr(input: &str, expected: &str) {
+    assert_eq!(test_parse(input).to_sexpr(), expected);
+}
+
+#[test]
+fn literals() {
+    test_sexpr(
+        r#"1; 1.0; true; "a\"b"; [1, x]; ["k": null]; [:];"#,
+        concat!(
+            "(int 1)\n",
+            "(float 1.0)\n",
+            "(bool true)\n",
+            "(string \"a\\\"b\")\n",
+            "(array (int 1) (ident x))\n",
+            "(dict (pair (string \"k\") (ident null)))\n",
+            "(dict)",
+        ),
+    );
+}
+
+#[test]
+fn operators() {
+    test_sexpr(
+        "1 + 2 * -x;",
+        "(infix + (int 1) (infix * (int 2) (prefix - (ident x))))",
+    );
+    test_sexpr("x += 1;", "(var = x (infix + (ident x) (int 1)))");
+    test_sexpr(
+        "a[0] = b?[1].c?.d;",
+        "(index-assign (ident a) (int 0) (member? (member (index? (ident b) (int 1)) c) d))",
+    );
+}
+
+#[test]
+fn statements() {
+    test_sexpr(
+        "f := fn(a, b) { return a(b); }; while (x) { yield 1; } for (i in import \"m\") { if (i) { 1 } else { 2 } }",
+        concat!(
+            "(var := f (fn (a b) (block (return (call (ident a) (ident b))) (null))))\n",
+            "(while (ident x) (block (yield (int 1)) (null)))\n",
+            "(for i (import (string \"m\")) (block (if (ident i) (block (int 1)) (block (int 2)))))",
+        ),
+    );
+}
@@ -0,0 +1,37 @@
+use belalang_core::{
+    diagnostic::Diagnostic,
+    source::{SourceMap, Span},
+};
+
+#[test]
+fn source_map() {
+    let mut sources = SourceMap::new();
+    let main = sources.add("main.bl", "x := 1;\ny := né + x;\n");
+    let repl = sources.add("<repl:1>", "z;");
+
+    let base = sources.get(repl).base();
+    assert!(base > sources.get(main).span().end);
+    assert_eq!(sources.find(0), Some(main));
+    assert_eq!(sources.find(base + 1), Some(repl));
+    assert_eq!(sources.find(base + 10), None);
+
+    let location = sources.location(16).unwrap();
+    assert_eq!(
+        (location.source, location.line, location.column),
+        (main, 2, 8)
+    );
+    assert_eq!(sources.snippet(Span::new(13, 16)), Some("né"));
+    assert_eq!(sources.snippet(Span::new(base, base + 1)), Some("z"));
+    assert_eq!(
+        sources.describe(Span::new(base, base + 1)).unwrap(),
+        "<repl:1>:1:1"
+    );
+
+    let diagnostic = Diagnostic::error("unknown-variable", "unknown variable: z")
+        .with_span(Span::new(base, base + 1))
+        .with_note("defined nowhere");
+    assert_eq!(
+        diagnostic.render(&sources),
+        "<repl:1>:1:1: error[unknown-variable]: unknown variable: z\n  note: defined nowhere"
+    );
+}
@@ -0,0 +1,87 @@
+#[macro_use]
+mod common;
+
+use belalang_core::{ast, source::Span};
+use common::test_parse;
+
+fn text(input: &str, span: Span) -> &str {
+    &input[span.start..span.end]
+}
+
+#[test]
+fn statements() {
+    let input = "x := 1 + 2;\nreturn f(x, [1, 2])[0];\nwhile (x) { x = x - 1; }";
+    let program = test_parse(input);
+
+    let texts = program
+        .statements
+        .iter()
+        .map(|stmt| text(input, stmt.span()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        texts,
+        [
+            "x := 1 + 2;",
+            "return f(x, [1, 2])[0];",
+            "while (x) { x = x - 1; }"
+        ]
+    );
+}
+
+#[test]
+fn expressions() {
+    let input = "a.b(-c) ?? fn(x) { x } + 1;";
+    let program = test_parse(input);
+
+    let stmt = as_variant!(&program.statements[0], ast::Statement::Expression);
+    let coalesce = as_variant!(&stmt.expression, ast::Expression::Infix);
+    assert_eq!(text(input, coalesce.span), "a.b(-c) ?? fn(x) { x } + 1");
+
+    let call = as_variant!(&*coalesce.left, ast::Expression::Call);
+    assert_eq!(text(input, call.span), "a.b(-c)");
+    assert_eq!(text(input, call.function.span()), "a.b");
+    assert_eq!(text(input, call.args[0].span()), "-c");
+
+    let add = as_variant!(&*coalesce.right, ast::Expression::Infix);
+    let function = as_variant!(&*add.left, ast::Expression::Function);
+    assert_eq!(text(input, function.span), "fn(x) { x }");
+    assert_eq!(text(input, function.params[0].span), "x");
+    assert_eq!(text(input, function.body.span), "{ x }");
+}
+
+#[test]
+fn implicit_null() {
+    let input = "if (x) { y; }";
+    let program = test_parse(input);
+
+    let stmt = as_variant!(&program.statements[0], ast::Statement::Expression);
+    let if_expr = as_variant!(&stmt.expression, ast::Expression::If);
+    let null = if_expr.consequence.statements.last().unwrap();
+
+    assert!(null.span().is_empty());
+    assert_eq!(null.span().start, input.len() - 1);
+    assert_eq!(text(input, stmt.span), input);
+}
+
+#[test]
+fn comments() {
+    let input = "## doc\nx := \"# no\"; # one  \n/* a /* b */ */ y;\n#";
+    let program = test_parse(input);
+
+    let comments = program
+        .comments
+        .iter()
+        .map(|comment| (comment.text.as_str(), text(input, comment.span)))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        comments,
+        [
+            ("## doc", "## doc"),
+            ("# one", "# one"),
+            ("/* a /* b */ */", "/* a /* b */ */"),
+            ("#", "#"),
+        ]
+    );
+}
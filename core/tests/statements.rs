@@ -59,6 +59,18 @@ fn r#return() {
     assert_eq!(val.value, 12);
 }
 
+#[test]
+fn r#yield() {
+    let program = test_parse("yield x + 1;");
+
+    assert_eq!(program.statements.len(), 1);
+
+    let stmt = as_variant!(&program.statements[0], ast::Statement::Yield);
+
+    assert_eq!(stmt.token, token::Token::Yield);
+    assert_eq!(stmt.to_string(), "yield (x + 1);");
+}
+
 #[test]
 fn r#while() {
     let program = test_parse("while (true) { 12; }");
@@ -76,3 +88,19 @@ fn r#while() {
 
     expr_variant!(&expr_0.expression, ast::Expression::Integer = 12);
 }
+
+#[test]
+fn r#for() {
+    let program = test_parse("for (x in xs) { x; }");
+
+    assert_eq!(program.statements.len(), 1);
+
+    let stmt = as_variant!(&program.statements[0], ast::Statement::For);
+
+    ident_has_name!(stmt.variable, "x");
+    expr_variant!(&*stmt.iterable, ast::Expression::Identifier = "xs");
+
+    let expr_0 = as_variant!(&stmt.block.statements[0], ast::Statement::Expression);
+
+    expr_variant!(&expr_0.expression, ast::Expression::Identifier = "x");
+}
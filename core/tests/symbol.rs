@@ -0,0 +1,31 @@
+use std::thread;
+
+use belalang_core::symbol::Symbol;
+
+#[test]
+fn same_across_threads() {
+    let here = Symbol::intern("shared_between_threads");
+    let there = thread::spawn(|| {
+        let there = Symbol::intern("shared_between_threads");
+        (there, Symbol::intern("made_in_a_thread"))
+    });
+    let (there, made) = there.join().unwrap();
+
+    assert_eq!(here, there);
+    assert_eq!(made.as_str(), "made_in_a_thread");
+    assert_eq!(Symbol::intern("made_in_a_thread"), made);
+}
+
+#[test]
+fn distinct_names() {
+    let names = (0..1000).map(|i| format!("name_{i}")).collect::<Vec<_>>();
+    let symbols = names
+        .iter()
+        .map(|name| Symbol::intern(name))
+        .collect::<Vec<_>>();
+
+    for (name, symbol) in names.iter().zip(&symbols) {
+        assert_eq!(symbol.as_str(), name);
+        assert_eq!(Symbol::intern(name), *symbol);
+    }
+}
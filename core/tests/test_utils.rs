@@ -0,0 +1,22 @@
+#![cfg(feature = "test-utils")]
+
+use belalang_core::{as_variant, ast, expr_variant, ident_has_name, testing::parse, token::Token};
+
+#[test]
+fn macros() {
+    let program = parse("x := -1 + 2;");
+
+    let stmt = as_variant!(&program.statements[0], ast::Statement::Expression);
+    let var = as_variant!(&stmt.expression, ast::Expression::Var);
+    ident_has_name!(var.name, "x");
+
+    let infix = as_variant!(&*var.value, ast::Expression::Infix);
+    expr_variant!(&*infix.left, Prefix => (Token::Sub, ast::Expression::Integer = 1));
+    expr_variant!(&*infix.right, ast::Expression::Integer = 2);
+}
+
+#[test]
+#[should_panic(expected = "parser errors")]
+fn syntax_error() {
+    parse("x := ;");
+}
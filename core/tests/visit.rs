@@ -0,0 +1,79 @@
+#[macro_use]
+mod common;
+
+use belalang_core::{
+    ast::{
+        self,
+        visit::{self, Visitor, VisitorMut},
+    },
+    symbol::Symbol,
+};
+use common::test_parse;
+
+#[test]
+fn visitor() {
+    #[derive(Default)]
+    struct Names(Vec<String>);
+
+    impl Visitor for Names {
+        fn visit_identifier(&mut self, identifier: &ast::Identifier) {
+            self.0.push(identifier.value.to_string());
+        }
+    }
+
+    let program =
+        test_parse("f := fn(a, b) { a + b.c; }; for (x in [y: z]) { while (w) { v[0] = u; } };");
+
+    let mut names = Names::default();
+    names.visit_program(&program);
+
+    assert_eq!(
+        names.0,
+        ["f", "a", "b", "a", "b", "x", "y", "z", "w", "v", "u"]
+    );
+}
+
+#[test]
+fn visitor_override() {
+    // Counts calls, without looking inside function bodies.
+    #[derive(Default)]
+    struct Calls(usize);
+
+    impl Visitor for Calls {
+        fn visit_expression(&mut self, expression: &ast::Expression) {
+            match expression {
+                ast::Expression::Function(_) => {}
+                ast::Expression::Call(_) => {
+                    self.0 += 1;
+                    visit::walk_expression(self, expression);
+                }
+                _ => visit::walk_expression(self, expression),
+            }
+        }
+    }
+
+    let program = test_parse("f(g(1)); h := fn() { i(); }; if (j()) { k(); }");
+
+    let mut calls = Calls::default();
+    calls.visit_program(&program);
+
+    assert_eq!(calls.0, 4);
+}
+
+#[test]
+fn visitor_mut() {
+    struct Rename;
+
+    impl VisitorMut for Rename {
+        fn visit_identifier_mut(&mut self, identifier: &mut ast::Identifier) {
+            if identifier.value == "x" {
+                identifier.value = Symbol::intern("y");
+            }
+        }
+    }
+
+    let mut program = test_parse("x := 1; fn(x) { x * 2; }(x);");
+    Rename.visit_program_mut(&mut program);
+
+    assert_eq!(program.to_string(), "y := 1;fn(y) { (y * 2); }(y);");
+}
@@ -0,0 +1,122 @@
+//! `#[derive(ScriptObject)]`, see `belalang_eval::native::ScriptObject`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr};
+
+/// Implements `ScriptObject` for a struct. Takes `#[script(name = "...")]`
+/// to rename the type and `#[script(methods(...))]` to expose methods on
+/// the struct, and `#[script(skip)]` on fields to hide them.
+#[proc_macro_derive(ScriptObject, attributes(script))]
+pub fn derive_script_object(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let mut type_name = ident.to_string();
+    let mut methods = Vec::<Ident>::new();
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("script"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                type_name = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else if meta.path.is_ident("methods") {
+                meta.parse_nested_meta(|method| {
+                    methods.push(method.path.require_ident()?.clone());
+                    Ok(())
+                })
+            } else {
+                Err(meta.error("expected `name` or `methods`"))
+            }
+        })?;
+    }
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "ScriptObject can only be derived for structs",
+        ));
+    };
+
+    let mut fields = Vec::new();
+    if let Fields::Named(named) = &data.fields {
+        for field in &named.named {
+            let mut skip = false;
+
+            for attr in field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("script"))
+            {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("skip") {
+                        skip = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `skip`"))
+                    }
+                })?;
+            }
+
+            if !skip {
+                fields.push(field.ident.clone().expect("named field"));
+            }
+        }
+    }
+
+    let field_names = fields.iter().map(Ident::to_string);
+    let method_names = methods.iter().map(Ident::to_string).collect::<Vec<_>>();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::belalang_eval::native::ScriptObject for #ident #ty_generics #where_clause {
+            fn type_name(&self) -> &str {
+                #type_name
+            }
+
+            fn field(&self, name: &str) -> ::core::option::Option<::belalang_eval::object::Object> {
+                match name {
+                    #(#field_names => ::core::option::Option::Some(
+                        ::core::convert::Into::into(::core::clone::Clone::clone(&self.#fields)),
+                    ),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            fn has_method(&self, name: &str) -> bool {
+                matches!(name, #(#method_names)|*)
+            }
+
+            fn call_method(
+                &self,
+                name: &str,
+                args: ::std::vec::Vec<::belalang_eval::object::Object>,
+            ) -> ::core::result::Result<
+                ::belalang_eval::object::Object,
+                ::belalang_eval::error::EvaluatorError,
+            > {
+                match name {
+                    #(#method_names => ::belalang_eval::native::ScriptMethod::invoke(
+                        &Self::#methods,
+                        self,
+                        args,
+                    ),)*
+                    _ => ::core::result::Result::Err(
+                        ::belalang_eval::error::EvaluatorError::NotAFunction,
+                    ),
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,34 @@
+use belalang_core::{lexer::Lexer, parser::Parser};
+use belalang_eval::evaluator::Evaluator;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+// Every iteration reads `text` and passes it through a function call, which
+// copies the string value twice. With shared string storage those copies are
+// reference count bumps instead of allocations proportional to the length.
+const STRING_HEAVY: &str = r#"
+text := "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat.";
+identity := fn(s) { s };
+
+n := 0;
+while (n < 500) {
+    copy := identity(text);
+    again := identity(copy);
+    n = n + 1;
+}
+"#;
+
+fn eval(input: &str) {
+    let lexer = Lexer::new(input.as_bytes());
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program().unwrap();
+
+    let mut ev = Evaluator::default();
+    ev.eval_program(program).unwrap();
+}
+
+fn strings(c: &mut Criterion) {
+    c.bench_function("string copies", |b| b.iter(|| eval(STRING_HEAVY)));
+}
+
+criterion_group!(benches, strings);
+criterion_main!(benches);
@@ -1,50 +1,588 @@
-use super::object::Object;
-use lazy_static::lazy_static;
-use std::{collections::HashMap, sync::Mutex};
-
-pub type BuiltinFn = Box<dyn Fn(Vec<Object>) -> Object + Sync + Send>;
-
-lazy_static! {
-    pub static ref BUILTIN_FUNCTIONS: Mutex<HashMap<String, BuiltinFn>> = {
-        let mut m = HashMap::<String, BuiltinFn>::new();
-
-        m.insert(
-            "println".into(),
-            Box::new(|args| {
-                println!(
-                    "{}",
-                    args.iter()
-                        .map(|arg| arg.to_string())
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                );
-                Object::Null
-            }),
-        );
+use crate::{
+    error::EvaluatorError,
+    evaluator::Evaluator,
+    object::{HashKey, Object},
+    sandbox::{self, Profile},
+    sync::{Locked, Shareable, Shared},
+};
+use belalang_core::symbol::Symbol;
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+mod arrays;
+mod conversions;
+mod csv;
+mod datetime;
+mod dicts;
+// The process environment, the filesystem and the clocks aren't there on
+// wasm32-unknown-unknown, e.g. in the browser.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod env;
+mod format;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod fs;
+mod iteration;
+mod math;
+mod random;
+mod strings;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod time;
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+/// A builtin function. Builtins get the calling evaluator so they can look
+/// at the current scope and call back into script functions.
+#[cfg(not(feature = "sync"))]
+pub type BuiltinFn = Shared<dyn Fn(&mut Evaluator, Vec<Object>) -> Result<Object, EvaluatorError>>;
+#[cfg(feature = "sync")]
+pub type BuiltinFn =
+    Shared<dyn Fn(&mut Evaluator, Vec<Object>) -> Result<Object, EvaluatorError> + Send + Sync>;
+
+/// Where a script's output goes, see [`Builtins::set_stdout`] and
+/// [`Builtins::set_stderr`].
+#[cfg(not(feature = "sync"))]
+pub type Output = Shared<Locked<dyn Write>>;
+#[cfg(feature = "sync")]
+pub type Output = Shared<Locked<dyn Write + Send + Sync>>;
+
+/// Where a script's input comes from, see [`Builtins::set_stdin`].
+#[cfg(not(feature = "sync"))]
+pub type Input = Shared<Locked<dyn BufRead>>;
+#[cfg(feature = "sync")]
+pub type Input = Shared<Locked<dyn BufRead + Send + Sync>>;
+
+/// Builtin functions and constants under a common name. The global ones
+/// form a module of their own; the others are reached with `import
+/// "std/<name>"`, which keeps them out of the global namespace.
+#[derive(Clone, Default)]
+pub struct Module {
+    fns: HashMap<String, BuiltinFn>,
+    constants: HashMap<String, Object>,
+}
+
+impl Module {
+    /// Registers a builtin, replacing any existing one with the same name.
+    pub fn register<F>(&mut self, name: &str, f: F)
+    where
+        F: Fn(&mut Evaluator, Vec<Object>) -> Result<Object, EvaluatorError> + Shareable + 'static,
+    {
+        self.fns.insert(name.into(), Shared::new(f));
+    }
+
+    /// Registers a builtin that mutates its captured state, see
+    /// [`Builtins::register_mut`].
+    pub fn register_mut<F>(&mut self, name: &str, f: F)
+    where
+        F: FnMut(&mut Evaluator, Vec<Object>) -> Result<Object, EvaluatorError>
+            + Shareable
+            + 'static,
+    {
+        let f = Locked::new(f);
+        let builtin = name.to_string();
+
+        self.register(name, move |ev, args| match f.try_borrow_mut() {
+            Ok(mut f) => f(ev, args),
+            Err(_) => Err(EvaluatorError::BuiltinRunning(builtin.clone())),
+        });
+    }
+
+    /// Registers a builtin taking and returning Rust values, see
+    /// [`Builtins::register_fn`].
+    pub fn register_fn<F, Args>(&mut self, name: &str, f: F)
+    where
+        F: TypedFn<Args> + Shareable + 'static,
+    {
+        self.register(name, move |_, args| f.call(args));
+    }
+
+    /// Registers a builtin returning a future, see
+    /// [`Builtins::register_async`].
+    #[cfg(feature = "tokio")]
+    pub fn register_async<F, Fut>(&mut self, name: &str, f: F)
+    where
+        F: Fn(Vec<Object>) -> Fut + Shareable + 'static,
+        Fut: std::future::Future<Output = Result<Object, EvaluatorError>> + Send + 'static,
+    {
+        self.register(name, move |ev, args| block_on(ev, f(args))?);
+    }
+
+    /// Registers a constant, replacing any existing one with the same name.
+    pub fn register_constant(&mut self, name: &str, value: Object) {
+        self.constants.insert(name.into(), value);
+    }
+}
+
+/// A Rust function usable as a builtin: its arguments convert from objects
+/// with `TryFrom<Object>` and its result, or the `Ok` value of it, converts
+/// to an object with `Into<Object>`. `Args` is the tuple of argument types.
+pub trait TypedFn<Args> {
+    fn call(&self, args: Vec<Object>) -> Result<Object, EvaluatorError>;
+}
+
+/// What a [`TypedFn`] can return.
+pub trait IntoResult {
+    fn into_result(self) -> Result<Object, EvaluatorError>;
+}
+
+impl<T: Into<Object>> IntoResult for T {
+    fn into_result(self) -> Result<Object, EvaluatorError> {
+        Ok(self.into())
+    }
+}
+
+impl<T: Into<Object>, E: Into<EvaluatorError>> IntoResult for Result<T, E> {
+    fn into_result(self) -> Result<Object, EvaluatorError> {
+        self.map(Into::into).map_err(Into::into)
+    }
+}
+
+macro_rules! typed_fn {
+    ($($arg:ident),*) => {
+        impl<F, R, $($arg),*> TypedFn<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R,
+            R: IntoResult,
+            $($arg: TryFrom<Object>, $arg::Error: Into<EvaluatorError>,)*
+        {
+            #[allow(non_snake_case)]
+            fn call(&self, args: Vec<Object>) -> Result<Object, EvaluatorError> {
+                let [$($arg),*] = arguments(args)?;
+                self($($arg::try_from($arg).map_err(Into::into)?),*).into_result()
+            }
+        }
+    };
+}
+
+typed_fn!();
+typed_fn!(A);
+typed_fn!(A, B);
+typed_fn!(A, B, C);
+typed_fn!(A, B, C, D);
+typed_fn!(A, B, C, D, E);
+typed_fn!(A, B, C, D, E, G);
 
-        Mutex::new(m)
+/// Waits for an async builtin's future. Evaluation itself is synchronous,
+/// so inside a runtime this has to block a thread the runtime can spare: a
+/// blocking thread, which is where [`Evaluator::evaluate_async`] puts it, or
+/// a worker of a multi-threaded runtime, which hands its tasks over first.
+/// Outside of a runtime one is started for the call.
+#[cfg(feature = "tokio")]
+fn block_on<F: std::future::Future>(
+    ev: &Evaluator,
+    future: F,
+) -> Result<F::Output, EvaluatorError> {
+    use tokio::runtime::{Builder, Handle, RuntimeFlavor};
+
+    let Ok(handle) = Handle::try_current() else {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(EvaluatorError::Runtime)?;
+        return Ok(runtime.block_on(future));
     };
+
+    match handle.runtime_flavor() {
+        RuntimeFlavor::MultiThread => Ok(tokio::task::block_in_place(|| handle.block_on(future))),
+        _ if ev.is_blocking() => Ok(handle.block_on(future)),
+        _ => Err(EvaluatorError::BlockedRuntime),
+    }
 }
 
-pub struct Builtins;
+#[derive(Clone)]
+pub struct Builtins {
+    globals: Module,
+    modules: HashMap<String, Module>,
+    stdout: Output,
+    stderr: Output,
+    stdin: Input,
+    profile: Profile,
+}
 
 impl Default for Builtins {
     fn default() -> Self {
-        Self
+        let mut builtins = Self {
+            globals: Module::default(),
+            modules: HashMap::new(),
+            stdout: Shared::new(Locked::new(io::stdout())),
+            stderr: Shared::new(Locked::new(io::stderr())),
+            stdin: Shared::new(Locked::new(io::BufReader::new(io::stdin()))),
+            profile: Profile::Full,
+        };
+
+        builtins.register("print", |ev, args| {
+            let text = join(ev, args)?;
+            write!(ev.builtins().stdout().borrow_mut(), "{}", text)?;
+            Ok(Object::Null)
+        });
+
+        builtins.register("println", |ev, args| {
+            let text = join(ev, args)?;
+            writeln!(ev.builtins().stdout().borrow_mut(), "{}", text)?;
+            Ok(Object::Null)
+        });
+
+        builtins.register("eprint", |ev, args| {
+            let text = join(ev, args)?;
+            write!(ev.builtins().stderr().borrow_mut(), "{}", text)?;
+            Ok(Object::Null)
+        });
+
+        builtins.register("eprintln", |ev, args| {
+            let text = join(ev, args)?;
+            writeln!(ev.builtins().stderr().borrow_mut(), "{}", text)?;
+            Ok(Object::Null)
+        });
+
+        // `input(prompt)` prints the prompt first. Both return null at the end
+        // of the input.
+        builtins.register("input", |ev, args| {
+            if args.len() > 1 {
+                return Err(EvaluatorError::WrongArgumentCount(1, args.len()));
+            }
+
+            if let Some(prompt) = args.first() {
+                let prompt = ev.stringify(prompt)?;
+                let stdout = ev.builtins().stdout();
+                let mut stdout = stdout.borrow_mut();
+                write!(stdout, "{}", prompt)?;
+                stdout.flush()?;
+            }
+
+            read_line(ev)
+        });
+
+        builtins.register("read_line", |ev, args| {
+            let [] = arguments(args)?;
+            read_line(ev)
+        });
+
+        builtins.register("type", |_, args| {
+            let [arg] = arguments(args)?;
+            Ok(Object::Type(arg.type_of()))
+        });
+
+        builtins.register("len", |_, args| {
+            let [arg] = arguments(args)?;
+
+            let len = match arg {
+                Object::String(ref s) => s.chars().count(),
+                Object::Array(ref elements) => elements.borrow().len(),
+                Object::Dict(ref dict) => dict.borrow().len(),
+                _ => return Err(EvaluatorError::UnexpectedType("String, Array or Dict", arg)),
+            };
+
+            Ok(Object::Integer(len as i64))
+        });
+
+        builtins.register("clone", |ev, args| {
+            let [arg] = arguments(args)?;
+            ev.allocated(arg.shallow_copy())
+        });
+
+        // Only the outermost copy counts towards the memory limit.
+        builtins.register("deepcopy", |ev, args| {
+            let [arg] = arguments(args)?;
+            ev.allocated(arg.deep_copy())
+        });
+
+        builtins.register("freeze", |_, args| {
+            let [arg] = arguments(args)?;
+
+            match arg {
+                Object::Array(ref elements) => elements.freeze(),
+                Object::Dict(ref dict) => dict.freeze(),
+                _ => (),
+            }
+
+            Ok(arg)
+        });
+
+        builtins.register("locals", |ev, _| {
+            let bindings = ev.env().locals();
+            scope(ev, bindings)
+        });
+        builtins.register("globals", |ev, _| {
+            let bindings = ev.env().globals();
+            scope(ev, bindings)
+        });
+
+        arrays::register(&mut builtins.globals);
+        conversions::register(&mut builtins.globals);
+        dicts::register(&mut builtins.globals);
+        format::register(&mut builtins.globals);
+        iteration::register(&mut builtins.globals);
+
+        csv::register(builtins.module_mut("csv"));
+        datetime::register(builtins.module_mut("datetime"));
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        env::register(builtins.module_mut("env"));
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        fs::register(builtins.module_mut("fs"));
+        math::register(builtins.module_mut("math"));
+        random::register(builtins.module_mut("random"));
+        strings::register(builtins.module_mut("strings"));
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        time::register(builtins.module_mut("time"));
+        #[cfg(feature = "toml")]
+        toml::register(builtins.module_mut("toml"));
+        #[cfg(feature = "yaml")]
+        yaml::register(builtins.module_mut("yaml"));
+
+        builtins
+    }
+}
+
+/// Destructures the arguments of a builtin taking exactly `N` of them.
+pub(crate) fn arguments<const N: usize>(args: Vec<Object>) -> Result<[Object; N], EvaluatorError> {
+    <[Object; N]>::try_from(args).map_err(|args| EvaluatorError::WrongArgumentCount(N, args.len()))
+}
+
+/// The arguments of `print` and `println`, separated by spaces.
+fn join(ev: &mut Evaluator, args: Vec<Object>) -> Result<String, EvaluatorError> {
+    let args = args
+        .iter()
+        .map(|arg| ev.stringify(arg))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(args.join(" "))
+}
+
+/// The next line of input without its line break.
+fn read_line(ev: &mut Evaluator) -> Result<Object, EvaluatorError> {
+    let mut line = String::new();
+
+    if ev.builtins().stdin().borrow_mut().read_line(&mut line)? == 0 {
+        return Ok(Object::Null);
+    }
+
+    if line.ends_with('\n') {
+        line.pop();
+
+        if line.ends_with('\r') {
+            line.pop();
+        }
     }
+
+    Ok(Object::String(line.into()))
+}
+
+/// A dict of the values of `bindings` by name, for `locals` and `globals`.
+fn scope(ev: &mut Evaluator, bindings: Vec<(Symbol, Object)>) -> Result<Object, EvaluatorError> {
+    let pairs = bindings
+        .into_iter()
+        .map(|(name, value)| (HashKey::String(name.as_str().into()), value))
+        .collect();
+
+    ev.allocated(Object::dict(pairs))
 }
 
 impl Builtins {
-    pub fn has_fn(&self, name: &String) -> bool {
-        let fns = BUILTIN_FUNCTIONS.lock().unwrap();
-        fns.contains_key(name)
+    /// Registers a builtin, replacing any existing one with the same name.
+    pub fn register<F>(&mut self, name: &str, f: F)
+    where
+        F: Fn(&mut Evaluator, Vec<Object>) -> Result<Object, EvaluatorError> + Shareable + 'static,
+    {
+        self.globals.register(name, f);
+    }
+
+    /// Registers a builtin that mutates its captured state, e.g. to collect
+    /// what a script reports back to the host. Clones of these builtins
+    /// share that state. A script calling the builtin again from inside it,
+    /// through a callback, fails with [`EvaluatorError::BuiltinRunning`].
+    pub fn register_mut<F>(&mut self, name: &str, f: F)
+    where
+        F: FnMut(&mut Evaluator, Vec<Object>) -> Result<Object, EvaluatorError>
+            + Shareable
+            + 'static,
+    {
+        self.globals.register_mut(name, f);
+    }
+
+    /// Registers a builtin taking and returning Rust values. Arguments are
+    /// converted and counted before `f` is called, e.g.
+    ///
+    /// ```
+    /// # use belalang_eval::builtins::Builtins;
+    /// let mut builtins = Builtins::default();
+    /// builtins.register_fn("clamp", |x: i64, lo: i64, hi: i64| x.max(lo).min(hi));
+    /// ```
+    ///
+    /// Use [`Builtins::register`] for builtins that need the evaluator or
+    /// take a variable number of arguments.
+    pub fn register_fn<F, Args>(&mut self, name: &str, f: F)
+    where
+        F: TypedFn<Args> + Shareable + 'static,
+    {
+        self.globals.register_fn(name, f);
+    }
+
+    /// Registers a builtin that returns a future, e.g. to wait on a timer or
+    /// a network request. Scripts call it like any other builtin and get the
+    /// future's output; evaluate them with [`Evaluator::evaluate_async`] so
+    /// the wait doesn't hold up the runtime.
+    #[cfg(feature = "tokio")]
+    pub fn register_async<F, Fut>(&mut self, name: &str, f: F)
+    where
+        F: Fn(Vec<Object>) -> Fut + Shareable + 'static,
+        Fut: std::future::Future<Output = Result<Object, EvaluatorError>> + Send + 'static,
+    {
+        self.globals.register_async(name, f);
+    }
+
+    /// Removes a global builtin. Returns whether it existed.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.globals.fns.remove(name).is_some()
+    }
+
+    /// Registers a constant, replacing any existing one with the same name.
+    /// Like builtin functions, constants can't be reassigned by scripts.
+    pub fn register_constant(&mut self, name: &str, value: Object) {
+        self.globals.register_constant(name, value);
+    }
+
+    pub fn constant(&self, name: &str) -> Option<Object> {
+        self.globals.constants.get(name).cloned()
+    }
+
+    /// Registers a module, replacing any existing one with the same name.
+    /// Scripts import it like the standard modules, with `import
+    /// "std/<name>"`, so its functions never collide with global names.
+    pub fn register_module(&mut self, name: &str, module: Module) {
+        self.modules.insert(name.into(), module);
+    }
+
+    /// Loads a plugin, a shared library adding builtins, see
+    /// [`crate::plugin`]. Plugins stay loaded until the process exits.
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialization code, and the plugin must
+    /// have been built with the same compiler, version and features of this
+    /// crate as the host.
+    #[cfg(feature = "plugins")]
+    pub unsafe fn load_plugin(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), crate::error::PluginError> {
+        crate::plugin::load(self, path.as_ref())
+    }
+
+    /// Removes a module, e.g. to keep untrusted scripts from reaching the
+    /// filesystem or the process environment. Returns whether it existed.
+    pub fn unregister_module(&mut self, name: &str) -> bool {
+        self.modules.remove(name).is_some()
+    }
+
+    /// Disables what `profile` doesn't allow: its builtins fail when called
+    /// and, without [`sandbox::Capability::FilesystemRead`], so do imports of
+    /// script files. Builtins registered afterwards aren't affected, so
+    /// restrict first and then add what the host vouches for. Restricting
+    /// can't be undone, a wider profile than the current one does nothing.
+    pub fn restrict(&mut self, profile: Profile) {
+        sandbox::restrict(self, profile);
+        self.profile = self.profile.min(profile);
     }
 
-    pub fn call(&self, name: String, args: Vec<Object>) -> Object {
-        let fns = BUILTIN_FUNCTIONS.lock().unwrap();
-        match fns.get(&name) {
-            Some(f) => f(args),
-            None => Object::Null,
+    pub fn profile(&self) -> Profile {
+        self.profile
+    }
+
+    pub(crate) fn globals_mut(&mut self) -> &mut Module {
+        &mut self.globals
+    }
+
+    pub(crate) fn module_mut(&mut self, name: &str) -> &mut Module {
+        self.modules.entry(name.into()).or_default()
+    }
+
+    /// What `import "std/<name>"` returns: a frozen dict of the module's
+    /// functions and constants.
+    pub fn namespace(&self, name: &str) -> Option<Object> {
+        let module = self.modules.get(name)?;
+
+        let mut members = module
+            .fns
+            .keys()
+            .map(|f| (f.clone(), Object::Builtin(format!("{name}.{f}"))))
+            .chain(module.constants.clone())
+            .collect::<Vec<_>>();
+        members.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let namespace = Object::dict(
+            members
+                .into_iter()
+                .map(|(member, value)| (HashKey::String(member.into()), value))
+                .collect(),
+        );
+
+        if let Object::Dict(ref dict) = namespace {
+            dict.freeze();
         }
+
+        Some(namespace)
+    }
+
+    /// Sets where `print` and `println` write to, the process stdout by
+    /// default. Keep a clone of `out` to read what a script printed.
+    pub fn set_stdout<W: Write + Shareable + 'static>(&mut self, out: Shared<Locked<W>>) {
+        self.stdout = out;
+    }
+
+    pub fn stdout(&self) -> Output {
+        Shared::clone(&self.stdout)
+    }
+
+    /// Sets where `eprint` and `eprintln` write to, the process stderr by
+    /// default.
+    pub fn set_stderr<W: Write + Shareable + 'static>(&mut self, out: Shared<Locked<W>>) {
+        self.stderr = out;
+    }
+
+    pub fn stderr(&self) -> Output {
+        Shared::clone(&self.stderr)
+    }
+
+    /// Sets where `input` and `read_line` read from, the process stdin by
+    /// default.
+    pub fn set_stdin<R: BufRead + Shareable + 'static>(&mut self, input: Shared<Locked<R>>) {
+        self.stdin = input;
+    }
+
+    pub fn stdin(&self) -> Input {
+        Shared::clone(&self.stdin)
+    }
+
+    /// The names of the global builtins and constants, which scripts
+    /// can't assign to.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        let fns = self.globals.fns.keys();
+        fns.chain(self.globals.constants.keys()).map(String::as_str)
+    }
+
+    /// The values of the global constants and those of every module.
+    pub(crate) fn constants(&self) -> impl Iterator<Item = &Object> {
+        self.globals
+            .constants
+            .values()
+            .chain(self.modules.values().flat_map(|m| m.constants.values()))
+    }
+
+    /// Whether there's a global builtin with this name.
+    pub fn has_fn(&self, name: &str) -> bool {
+        self.globals.fns.contains_key(name)
+    }
+
+    /// Looks up a global builtin, or a module's as `module.name`.
+    pub fn get(&self, name: &str) -> Option<BuiltinFn> {
+        let (module, name) = match name.split_once('.') {
+            Some((module, name)) => (self.modules.get(module)?, name),
+            None => (&self.globals, name),
+        };
+
+        module.fns.get(name).cloned()
     }
 }
@@ -0,0 +1,290 @@
+//! `push`, `pop`, `insert` and `remove` modify the array in place; the
+//! other builtins leave it alone and return a new value.
+//!
+//! `map`, `filter`, `reduce`, `each` and `sort` iterate over a copy of the array, so
+//! the callback may modify the array without affecting the iteration. `map`
+//! and `filter` also take iterators, which they consume lazily.
+
+use super::{arguments, Module};
+use crate::{
+    error::EvaluatorError,
+    evaluator::Evaluator,
+    iterator::{iterate, Iter},
+    object::{Collection, Object, ARRAY_SLOT},
+    sync::Shared,
+};
+use belalang_core::token::Token;
+use std::cmp::Ordering;
+
+pub fn register(module: &mut Module) {
+    module.register("push", |ev, args| {
+        let [a, value] = arguments(args)?;
+        let a = array(a)?;
+        let mut elements = a.borrow_mut()?;
+
+        ev.count_memory(ARRAY_SLOT)?;
+        elements.push(value);
+        Ok(Object::Null)
+    });
+
+    module.register("pop", |_, args| {
+        let [a] = arguments(args)?;
+        Ok(array(a)?.borrow_mut()?.pop().unwrap_or(Object::Null))
+    });
+
+    module.register("insert", |ev, args| {
+        let [a, index, value] = arguments(args)?;
+        let (a, index) = (array(a)?, integer(index)?);
+        let mut elements = a.borrow_mut()?;
+
+        match usize::try_from(index) {
+            Ok(i) if i <= elements.len() => {
+                ev.count_memory(ARRAY_SLOT)?;
+                elements.insert(i, value);
+            }
+            _ => return Err(EvaluatorError::IndexOutOfBounds(index, elements.len())),
+        }
+
+        Ok(Object::Null)
+    });
+
+    module.register("remove", |_, args| {
+        let [a, index] = arguments(args)?;
+        let (a, index) = (array(a)?, integer(index)?);
+        let mut elements = a.borrow_mut()?;
+
+        match usize::try_from(index) {
+            Ok(i) if i < elements.len() => Ok(elements.remove(i)),
+            _ => Err(EvaluatorError::IndexOutOfBounds(index, elements.len())),
+        }
+    });
+
+    // Negative bounds count from the end and out of range bounds are
+    // clamped, so slicing never fails.
+    module.register("slice", |ev, args| {
+        let [a, start, end] = arguments(args)?;
+        let (a, start, end) = (array(a)?, integer(start)?, integer(end)?);
+        let elements = a.borrow();
+
+        let start = clamp(start, elements.len());
+        let end = clamp(end, elements.len()).max(start);
+
+        ev.allocated(Object::array(elements[start..end].to_vec()))
+    });
+
+    module.register("concat", |ev, args| {
+        let [a, b] = arguments(args)?;
+        let (a, b) = (array(a)?, array(b)?);
+
+        let mut elements = a.borrow().clone();
+        elements.extend(b.borrow().iter().cloned());
+
+        ev.allocated(Object::array(elements))
+    });
+
+    module.register("reverse", |ev, args| {
+        let [a] = arguments(args)?;
+        let elements = array(a)?.borrow().iter().rev().cloned().collect();
+
+        ev.allocated(Object::array(elements))
+    });
+
+    module.register("index_of", |_, args| {
+        let [a, value] = arguments(args)?;
+        let position = array(a)?.borrow().iter().position(|e| *e == value);
+
+        Ok(match position {
+            Some(i) => Object::Integer(i as i64),
+            None => Object::Null,
+        })
+    });
+    // Lazy when given an iterator, otherwise returns a new array.
+    module.register("map", |ev, args| {
+        let [a, f] = arguments(args)?;
+
+        if let Object::Iterator(ref source) = a {
+            let source = Shared::clone(source);
+            let sources = vec![a, f.clone()];
+
+            return Ok(Object::Iterator(Shared::new(Iter::new(
+                sources,
+                move |ev| match source.next(ev)? {
+                    Some(item) => ev.call_function(f.clone(), vec![item]).map(Some),
+                    None => Ok(None),
+                },
+            ))));
+        }
+
+        let elements = array(a)?.borrow().clone();
+
+        let mapped = elements
+            .into_iter()
+            .map(|element| ev.call_function(f.clone(), vec![element]))
+            .collect::<Result<_, _>>()?;
+
+        ev.allocated(Object::array(mapped))
+    });
+
+    // Like `if`, only keeps elements for which the predicate returns `true`.
+    // Lazy when given an iterator, otherwise returns a new array.
+    module.register("filter", |ev, args| {
+        let [a, f] = arguments(args)?;
+
+        if let Object::Iterator(ref source) = a {
+            let source = Shared::clone(source);
+            let sources = vec![a, f.clone()];
+
+            return Ok(Object::Iterator(Shared::new(Iter::new(
+                sources,
+                move |ev| {
+                    while let Some(item) = source.next(ev)? {
+                        if let Object::Boolean(true) =
+                            ev.call_function(f.clone(), vec![item.clone()])?
+                        {
+                            return Ok(Some(item));
+                        }
+                    }
+
+                    Ok(None)
+                },
+            ))));
+        }
+
+        let elements = array(a)?.borrow().clone();
+
+        let mut kept = Vec::new();
+        for element in elements {
+            if let Object::Boolean(true) = ev.call_function(f.clone(), vec![element.clone()])? {
+                kept.push(element);
+            }
+        }
+
+        ev.allocated(Object::array(kept))
+    });
+
+    module.register("reduce", |ev, args| {
+        let [a, f, initial] = arguments(args)?;
+        let iter = iterate(a)?;
+
+        let mut acc = initial;
+        while let Some(item) = iter.next(ev)? {
+            acc = ev.call_function(f.clone(), vec![acc, item])?;
+        }
+
+        Ok(acc)
+    });
+
+    module.register("each", |ev, args| {
+        let [a, f] = arguments(args)?;
+        let iter = iterate(a)?;
+
+        while let Some(item) = iter.next(ev)? {
+            ev.call_function(f.clone(), vec![item])?;
+        }
+
+        Ok(Object::Null)
+    });
+
+    // Stable. Without a comparator, sorts numbers or strings in ascending
+    // order. The comparator is called with two elements and returns either
+    // an Int (negative, zero or positive, as in "a - b") or whether the
+    // first element goes before the second.
+    module.register("sort", |ev, mut args| {
+        let comparator = if args.len() == 2 { args.pop() } else { None };
+        let [a] = arguments(args)?;
+        let elements = array(a)?.borrow().clone();
+
+        let sorted = merge_sort(elements, &mut |a, b| match &comparator {
+            Some(f) => compare_with(ev, f, a, b),
+            None => compare(a, b),
+        })?;
+
+        ev.allocated(Object::array(sorted))
+    });
+}
+
+/// A stable merge sort that stops at the first error. Unlike
+/// `slice::sort_by` it doesn't panic when `compare` isn't a total order,
+/// which a script's comparator needn't be.
+fn merge_sort<T, E>(
+    mut items: Vec<T>,
+    compare: &mut impl FnMut(&T, &T) -> Result<Ordering, E>,
+) -> Result<Vec<T>, E> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+
+    let right = items.split_off(items.len() / 2);
+    let mut left = merge_sort(items, compare)?.into_iter().peekable();
+    let mut right = merge_sort(right, compare)?.into_iter().peekable();
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        // on ties the left one goes first, which keeps the sort stable
+        if compare(r, l)? == Ordering::Less {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+fn compare(a: &Object, b: &Object) -> Result<Ordering, EvaluatorError> {
+    let ordering = match (a, b) {
+        (Object::Integer(a), Object::Integer(b)) => Some(a.cmp(b)),
+        (Object::Float(a), Object::Float(b)) => a.partial_cmp(b),
+        (Object::Integer(a), Object::Float(b)) => (*a as f64).partial_cmp(b),
+        (Object::Float(a), Object::Integer(b)) => a.partial_cmp(&(*b as f64)),
+        (Object::String(a), Object::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+
+    ordering.ok_or_else(|| EvaluatorError::UnknownInfixOperator(a.clone(), Token::Lt, b.clone()))
+}
+
+fn compare_with(
+    ev: &mut Evaluator,
+    comparator: &Object,
+    a: &Object,
+    b: &Object,
+) -> Result<Ordering, EvaluatorError> {
+    let mut before =
+        |a: &Object, b: &Object| ev.call_function(comparator.clone(), vec![a.clone(), b.clone()]);
+
+    match before(a, b)? {
+        Object::Integer(i) => Ok(i.cmp(&0)),
+        Object::Boolean(true) => Ok(Ordering::Less),
+        Object::Boolean(false) => match before(b, a)? {
+            Object::Boolean(true) => Ok(Ordering::Greater),
+            Object::Boolean(false) => Ok(Ordering::Equal),
+            result => Err(EvaluatorError::UnexpectedType("Int or Bool", result)),
+        },
+        result => Err(EvaluatorError::UnexpectedType("Int or Bool", result)),
+    }
+}
+
+fn array(arg: Object) -> Result<Shared<Collection<Vec<Object>>>, EvaluatorError> {
+    match arg {
+        Object::Array(elements) => Ok(elements),
+        _ => Err(EvaluatorError::UnexpectedType("Array", arg)),
+    }
+}
+
+fn integer(arg: Object) -> Result<i64, EvaluatorError> {
+    match arg {
+        Object::Integer(i) => Ok(i),
+        _ => Err(EvaluatorError::UnexpectedType("Int", arg)),
+    }
+}
+
+fn clamp(index: i64, len: usize) -> usize {
+    if index < 0 {
+        len.saturating_sub(index.unsigned_abs() as usize)
+    } else {
+        (index as usize).min(len)
+    }
+}
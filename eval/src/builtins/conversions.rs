@@ -0,0 +1,60 @@
+use super::{arguments, Module};
+use crate::{
+    error::EvaluatorError,
+    object::{Object, Type},
+};
+
+pub fn register(module: &mut Module) {
+    module.register("int", |_, args| {
+        let [arg] = arguments(args)?;
+
+        match arg {
+            Object::Integer(_) => Ok(arg),
+            Object::Float(f) => match float_to_int(f.trunc()) {
+                Some(i) => Ok(Object::Integer(i)),
+                None => Err(EvaluatorError::InvalidConversion(arg, Type::Int)),
+            },
+            Object::Boolean(b) => Ok(Object::Integer(b as i64)),
+            Object::String(ref s) => match s.trim().parse() {
+                Ok(i) => Ok(Object::Integer(i)),
+                Err(_) => Err(EvaluatorError::InvalidConversion(arg, Type::Int)),
+            },
+            _ => Err(EvaluatorError::InvalidConversion(arg, Type::Int)),
+        }
+    });
+
+    module.register("float", |_, args| {
+        let [arg] = arguments(args)?;
+
+        match arg {
+            Object::Integer(i) => Ok(Object::Float(i as f64)),
+            Object::Float(_) => Ok(arg),
+            Object::Boolean(b) => Ok(Object::Float(b as i64 as f64)),
+            Object::String(ref s) => match s.trim().parse() {
+                Ok(f) => Ok(Object::Float(f)),
+                Err(_) => Err(EvaluatorError::InvalidConversion(arg, Type::Float)),
+            },
+            _ => Err(EvaluatorError::InvalidConversion(arg, Type::Float)),
+        }
+    });
+
+    module.register("str", |ev, args| {
+        let [arg] = arguments(args)?;
+        let s = ev.stringify(&arg)?;
+        ev.allocated(Object::String(s.into()))
+    });
+
+    module.register("bool", |_, args| {
+        let [arg] = arguments(args)?;
+        Ok(Object::Boolean(arg.is_truthy()))
+    });
+}
+
+/// `f` as an int, `None` if it is NaN, infinite, or out of range. `as` would
+/// saturate those instead.
+pub(super) fn float_to_int(f: f64) -> Option<i64> {
+    // i64::MIN is a power of two, so it and its negation are exact floats.
+    const BOUND: f64 = -(i64::MIN as f64);
+
+    (-BOUND..BOUND).contains(&f).then_some(f as i64)
+}
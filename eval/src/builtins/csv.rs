@@ -0,0 +1,126 @@
+//! CSV, with every field read as a string.
+
+use super::{arguments, Module};
+use crate::{
+    error::EvaluatorError,
+    evaluator::Evaluator,
+    object::{HashKey, Object},
+};
+use indexmap::IndexMap;
+
+pub fn register(module: &mut Module) {
+    // `csv_parse(text)` returns the rows as arrays. `csv_parse(text, true)`
+    // takes the first row as the header and returns the others as dicts.
+    module.register("csv_parse", |_, args| {
+        let (text, headers) = match <[Object; 2]>::try_from(args) {
+            Ok([text, Object::Boolean(headers)]) => (text, headers),
+            Ok([_, headers]) => return Err(EvaluatorError::UnexpectedType("Bool", headers)),
+            Err(args) => {
+                let [text] = arguments(args)?;
+                (text, false)
+            }
+        };
+
+        let Object::String(text) = text else {
+            return Err(EvaluatorError::UnexpectedType("String", text));
+        };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(headers)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+
+        let header = match headers {
+            true => Some(reader.headers().map_err(invalid)?.clone()),
+            false => None,
+        };
+
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(invalid)?;
+            let fields = record.iter().map(|field| Object::String(field.into()));
+
+            rows.push(match header {
+                Some(ref header) => Object::dict(
+                    header
+                        .iter()
+                        .map(|name| HashKey::String(name.into()))
+                        .zip(fields)
+                        .collect(),
+                ),
+                None => Object::array(fields.collect()),
+            });
+        }
+
+        Ok(Object::array(rows))
+    });
+
+    // Rows are arrays, or dicts whose keys make the header. Null fields are
+    // written empty.
+    module.register("csv_write", |ev, args| {
+        let [rows] = arguments(args)?;
+        let Object::Array(rows) = rows else {
+            return Err(EvaluatorError::UnexpectedType("Array", rows));
+        };
+
+        let rows = rows.borrow().clone();
+        let mut writer = csv::WriterBuilder::new()
+            .flexible(true)
+            .from_writer(Vec::new());
+
+        let mut header: Option<Vec<HashKey>> = None;
+        for row in rows {
+            let fields = match row {
+                Object::Array(fields) => fields.borrow().clone(),
+                Object::Dict(dict) => {
+                    let dict = dict.borrow().clone();
+                    let header = match header {
+                        Some(ref header) => header,
+                        None => {
+                            let keys = dict.keys().cloned().collect::<Vec<_>>();
+                            writer
+                                .write_record(record(ev, keys.iter().cloned().map(Object::from))?)
+                                .map_err(invalid)?;
+                            header.insert(keys)
+                        }
+                    };
+
+                    fields_of(&dict, header)
+                }
+                _ => return Err(EvaluatorError::UnexpectedType("Array or Dict", row)),
+            };
+
+            writer
+                .write_record(record(ev, fields.into_iter())?)
+                .map_err(invalid)?;
+        }
+
+        let bytes = writer
+            .into_inner()
+            .map_err(|err| invalid(err.into_error()))?;
+        Ok(Object::String(String::from_utf8_lossy(&bytes).into()))
+    });
+}
+
+fn fields_of(dict: &IndexMap<HashKey, Object>, header: &[HashKey]) -> Vec<Object> {
+    header
+        .iter()
+        .map(|key| dict.get(key).cloned().unwrap_or(Object::Null))
+        .collect()
+}
+
+fn record(
+    ev: &mut Evaluator,
+    values: impl Iterator<Item = Object>,
+) -> Result<Vec<String>, EvaluatorError> {
+    values
+        .map(|value| match value {
+            Object::Null => Ok(String::new()),
+            value => ev.stringify(&value),
+        })
+        .collect()
+}
+
+fn invalid(err: impl std::fmt::Display) -> EvaluatorError {
+    EvaluatorError::InvalidData("CSV", err.to_string())
+}
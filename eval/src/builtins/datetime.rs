@@ -0,0 +1,228 @@
+//! Dates and times, as `DateTime` natives without a time zone; `now()` and
+//! timestamps are in UTC. Durations are numbers of seconds: adding one to a
+//! date moves it, subtracting two dates gives the seconds between them.
+
+use super::{arguments, Module};
+use crate::{error::EvaluatorError, native::NativeObject, object::Object, sync::Shared};
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime as ChronoDateTime, Datelike, NaiveDate, NaiveDateTime, TimeDelta, Timelike,
+};
+use std::any::Any;
+
+pub fn register(module: &mut Module) {
+    // `datetime(timestamp)` or `datetime(year, month, day[, hour, minute,
+    // second])`.
+    module.register("datetime", |_, args| {
+        if let [ref timestamp] = args[..] {
+            let seconds = seconds(timestamp.clone())?;
+            return datetime(EPOCH.checked_add_signed(seconds));
+        }
+
+        if !(3..=6).contains(&args.len()) {
+            return Err(EvaluatorError::WrongArgumentCount(6, args.len()));
+        }
+
+        let mut parts = [0, 1, 1, 0, 0, 0];
+        for (part, arg) in parts.iter_mut().zip(args) {
+            *part = match arg {
+                Object::Integer(i) => i,
+                _ => return Err(EvaluatorError::UnexpectedType("Int", arg)),
+            };
+        }
+
+        from_parts(parts).map(to_object).ok_or_else(|| {
+            let [y, mo, d, h, mi, s] = parts;
+            EvaluatorError::InvalidDate(format!("{y}-{mo:02}-{d:02} {h:02}:{mi:02}:{s:02}"))
+        })
+    });
+
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    module.register("now", |_, args| {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let [] = arguments(args)?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .and_then(|since| TimeDelta::from_std(since).ok())
+            .and_then(|since| EPOCH.checked_add_signed(since));
+
+        datetime(now)
+    });
+
+    // Parses a date, or a date and time, in a `strftime` style format.
+    module.register("parse_datetime", |_, args| {
+        let [s, format] = arguments(args)?;
+        let (s, format) = (string(s)?, string(format)?);
+
+        NaiveDateTime::parse_from_str(&s, &format)
+            .or_else(|err| match NaiveDate::parse_from_str(&s, &format) {
+                Ok(date) => Ok(date.and_time(Default::default())),
+                Err(_) => Err(err),
+            })
+            .map(to_object)
+            .map_err(|err| EvaluatorError::InvalidDate(format!("{s:?} ({err})")))
+    });
+}
+
+const EPOCH: NaiveDateTime = ChronoDateTime::UNIX_EPOCH.naive_utc();
+
+#[derive(Debug)]
+struct DateTime(NaiveDateTime);
+
+impl std::fmt::Display for DateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.format("%Y-%m-%dT%H:%M:%S%.f"))
+    }
+}
+
+impl NativeObject for DateTime {
+    fn type_name(&self) -> &str {
+        "DateTime"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn equals(&self, other: &Object) -> bool {
+        from_object(other) == Some(self.0)
+    }
+
+    fn get(&self, name: &str) -> Option<Object> {
+        let dt = self.0;
+
+        Some(Object::Integer(match name {
+            "year" => dt.year().into(),
+            "month" => dt.month().into(),
+            "day" => dt.day().into(),
+            "hour" => dt.hour().into(),
+            "minute" => dt.minute().into(),
+            "second" => dt.second().into(),
+            // 1 for Monday through 7 for Sunday.
+            "weekday" => dt.weekday().number_from_monday().into(),
+            "timestamp" => dt.and_utc().timestamp(),
+            "format" | "__add__" | "__sub__" | "__lt__" | "__le__" | "__gt__" | "__ge__" => {
+                return Some(Object::Native(Shared::new(Method {
+                    dt,
+                    name: name.into(),
+                })));
+            }
+            _ => return None,
+        }))
+    }
+}
+
+/// A method of a `DateTime`, e.g. `dt.format`, bound to its value.
+#[derive(Debug)]
+struct Method {
+    dt: NaiveDateTime,
+    name: Box<str>,
+}
+
+impl std::fmt::Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<method DateTime.{}>", self.name)
+    }
+}
+
+impl NativeObject for Method {
+    fn type_name(&self) -> &str {
+        "Method"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn call(&self, args: Vec<Object>) -> Result<Object, EvaluatorError> {
+        let dt = self.dt;
+
+        if &*self.name == "format" {
+            let [format] = arguments(args)?;
+            let format = string(format)?;
+
+            let items = StrftimeItems::new(&format).collect::<Vec<_>>();
+            if items.contains(&Item::Error) {
+                return Err(EvaluatorError::InvalidFormat(format.to_string()));
+            }
+
+            return Ok(Object::String(
+                dt.format_with_items(items.iter()).to_string().into(),
+            ));
+        }
+
+        // Operator hooks get the date itself first.
+        let [_, other] = arguments(args)?;
+
+        if let (Some(other), "__sub__") = (from_object(&other), &*self.name) {
+            return Ok(Object::Float((dt - other).as_seconds_f64()));
+        }
+
+        match &*self.name {
+            "__add__" => datetime(dt.checked_add_signed(seconds(other)?)),
+            "__sub__" => datetime(dt.checked_sub_signed(seconds(other)?)),
+            hook => {
+                let Some(other) = from_object(&other) else {
+                    return Err(EvaluatorError::UnexpectedType("DateTime", other));
+                };
+
+                Ok(Object::Boolean(match hook {
+                    "__lt__" => dt < other,
+                    "__le__" => dt <= other,
+                    "__gt__" => dt > other,
+                    _ => dt >= other,
+                }))
+            }
+        }
+    }
+}
+
+fn from_parts(parts: [i64; 6]) -> Option<NaiveDateTime> {
+    let [year, month, day, hour, minute, second] = parts;
+    let part = |part: i64| u32::try_from(part).ok();
+
+    NaiveDate::from_ymd_opt(year.try_into().ok()?, part(month)?, part(day)?)?.and_hms_opt(
+        part(hour)?,
+        part(minute)?,
+        part(second)?,
+    )
+}
+
+fn to_object(dt: NaiveDateTime) -> Object {
+    Object::Native(Shared::new(DateTime(dt)))
+}
+
+fn from_object(object: &Object) -> Option<NaiveDateTime> {
+    match object {
+        Object::Native(native) => native.as_any().downcast_ref::<DateTime>().map(|dt| dt.0),
+        _ => None,
+    }
+}
+
+/// `None` from date arithmetic means the result is out of range.
+fn datetime(dt: Option<NaiveDateTime>) -> Result<Object, EvaluatorError> {
+    dt.map(to_object).ok_or(EvaluatorError::DateOutOfRange)
+}
+
+fn seconds(duration: Object) -> Result<TimeDelta, EvaluatorError> {
+    let delta = match duration {
+        Object::Integer(s) => TimeDelta::try_seconds(s),
+        Object::Float(s) if (s * 1e6).abs() < i64::MAX as f64 => {
+            Some(TimeDelta::microseconds((s * 1e6).round() as i64))
+        }
+        Object::Float(_) => None,
+        _ => return Err(EvaluatorError::UnexpectedType("Int or Float", duration)),
+    };
+
+    delta.ok_or(EvaluatorError::DateOutOfRange)
+}
+
+fn string(arg: Object) -> Result<Shared<str>, EvaluatorError> {
+    match arg {
+        Object::String(s) => Ok(s),
+        _ => Err(EvaluatorError::UnexpectedType("String", arg)),
+    }
+}
@@ -0,0 +1,65 @@
+//! `delete` modifies the dict in place; the other builtins leave it alone
+//! and return a new value.
+
+use super::{arguments, Module};
+use crate::{
+    error::EvaluatorError,
+    object::{Collection, HashKey, Object},
+    sync::Shared,
+};
+use indexmap::IndexMap;
+
+pub fn register(module: &mut Module) {
+    module.register("keys", |ev, args| {
+        let [d] = arguments(args)?;
+        let keys = dict(d)?
+            .borrow()
+            .keys()
+            .cloned()
+            .map(Object::from)
+            .collect();
+
+        ev.allocated(Object::array(keys))
+    });
+
+    module.register("values", |ev, args| {
+        let [d] = arguments(args)?;
+        let values = dict(d)?.borrow().values().cloned().collect();
+
+        ev.allocated(Object::array(values))
+    });
+
+    module.register("has_key", |_, args| {
+        let [d, key] = arguments(args)?;
+        let key = HashKey::try_from(key)?;
+
+        Ok(Object::Boolean(dict(d)?.borrow().contains_key(&key)))
+    });
+
+    // Returns the removed value, or null if the key wasn't there.
+    module.register("delete", |_, args| {
+        let [d, key] = arguments(args)?;
+        let key = HashKey::try_from(key)?;
+
+        let removed = dict(d)?.borrow_mut()?.shift_remove(&key);
+        Ok(removed.unwrap_or(Object::Null))
+    });
+
+    // Entries of the second dict win over those of the first.
+    module.register("merge", |ev, args| {
+        let [a, b] = arguments(args)?;
+        let (a, b) = (dict(a)?, dict(b)?);
+
+        let mut merged: IndexMap<_, _> = a.borrow().clone();
+        merged.extend(b.borrow().iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        ev.allocated(Object::dict(merged))
+    });
+}
+
+fn dict(arg: Object) -> Result<Shared<Collection<IndexMap<HashKey, Object>>>, EvaluatorError> {
+    match arg {
+        Object::Dict(dict) => Ok(dict),
+        _ => Err(EvaluatorError::UnexpectedType("Dict", arg)),
+    }
+}
@@ -0,0 +1,64 @@
+//! The process environment. Hosts running untrusted scripts can take these
+//! away with [`Builtins::unregister_module`](super::Builtins::unregister_module).
+
+use super::{arguments, Module};
+use crate::{
+    error::EvaluatorError,
+    object::{HashKey, Object},
+    sync::Shared,
+};
+use std::env;
+
+pub fn register(module: &mut Module) {
+    // Null if the variable isn't set.
+    module.register("env_get", |_, args| {
+        let [name] = arguments(args)?;
+        let name = string(name)?;
+
+        Ok(match env::var_os(&*name) {
+            Some(value) => Object::String(value.to_string_lossy().into()),
+            None => Object::Null,
+        })
+    });
+
+    module.register("env_set", |_, args| {
+        let [name, value] = arguments(args)?;
+        let (name, value) = (string(name)?, string(value)?);
+
+        if name.is_empty() || name.contains(['=', '\0']) || value.contains('\0') {
+            return Err(EvaluatorError::InvalidData(
+                "environment variable",
+                format!("{name}={value}"),
+            ));
+        }
+
+        env::set_var(&*name, &*value);
+        Ok(Object::Null)
+    });
+
+    // Sorted by name.
+    module.register("env_all", |_, args| {
+        let [] = arguments(args)?;
+
+        let mut vars = env::vars_os()
+            .map(|(name, value)| {
+                let name = name.to_string_lossy().into_owned();
+                (name, Object::String(value.to_string_lossy().into()))
+            })
+            .collect::<Vec<_>>();
+        vars.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Ok(Object::dict(
+            vars.into_iter()
+                .map(|(name, value)| (HashKey::String(name.into()), value))
+                .collect(),
+        ))
+    });
+}
+
+fn string(arg: Object) -> Result<Shared<str>, EvaluatorError> {
+    match arg {
+        Object::String(s) => Ok(s),
+        _ => Err(EvaluatorError::UnexpectedType("String", arg)),
+    }
+}
@@ -0,0 +1,190 @@
+use super::Module;
+use crate::{error::EvaluatorError, evaluator::Evaluator, object::Object};
+
+pub fn register(module: &mut Module) {
+    module.register("format", |ev, args| {
+        let Some((template, args)) = args.split_first() else {
+            return Err(EvaluatorError::WrongArgumentCount(1, 0));
+        };
+        let Object::String(template) = template else {
+            return Err(EvaluatorError::UnexpectedType("String", template.clone()));
+        };
+
+        let formatted = format(ev, template, args)?;
+        ev.allocated(Object::String(formatted.into()))
+    });
+}
+
+fn invalid(message: impl Into<String>) -> EvaluatorError {
+    EvaluatorError::InvalidFormat(message.into())
+}
+
+/// Fills the placeholders of `template` with `args`.
+///
+/// A placeholder is `{[position][:spec]}`. Without a position, placeholders
+/// take the arguments in order. The spec is `[[fill]align][width][.precision]`
+/// where align is one of `<`, `^` and `>`; numbers are right-aligned and
+/// everything else left-aligned by default. Precision is the number of
+/// decimals of a number or the maximum length of a string. `{{` and `}}`
+/// are literal braces. Every argument has to be used.
+pub(crate) fn format(
+    ev: &mut Evaluator,
+    template: &str,
+    args: &[Object],
+) -> Result<String, EvaluatorError> {
+    let mut result = String::new();
+    let mut chars = template.chars().peekable();
+    let mut next = 0;
+    let mut used = vec![false; args.len()];
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                result.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                result.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(invalid("unclosed `{`")),
+                    }
+                }
+
+                let (position, spec) = placeholder.split_once(':').unwrap_or((&placeholder, ""));
+
+                let index = if position.is_empty() {
+                    next += 1;
+                    next - 1
+                } else {
+                    position
+                        .parse::<usize>()
+                        .map_err(|_| invalid(format!("invalid argument position `{position}`")))?
+                };
+
+                let Some(arg) = args.get(index) else {
+                    return Err(invalid(format!(
+                        "argument {index} is used, but only {} given",
+                        args.len()
+                    )));
+                };
+                used[index] = true;
+
+                result.push_str(&Spec::parse(spec)?.apply(ev, arg)?);
+            }
+            '}' => return Err(invalid("unmatched `}`")),
+            c => result.push(c),
+        }
+    }
+
+    if let Some(unused) = used.iter().position(|used| !used) {
+        return Err(invalid(format!("argument {unused} is never used")));
+    }
+
+    Ok(result)
+}
+
+#[derive(Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<char>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Result<Spec, EvaluatorError> {
+        let mut result = Spec::default();
+        let mut rest = spec;
+
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(fill), Some(align @ ('<' | '^' | '>'))) => {
+                result.fill = Some(fill);
+                result.align = Some(align);
+                rest = chars.as_str();
+            }
+            (Some(align @ ('<' | '^' | '>')), _) => {
+                result.align = Some(align);
+                rest = &rest[1..];
+            }
+            _ => (),
+        }
+
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits > 0 {
+            result.width = rest[..digits]
+                .parse()
+                .map_err(|_| invalid(format!("width too large in `{spec}`")))?;
+            rest = &rest[digits..];
+        }
+
+        if let Some(precision) = rest.strip_prefix('.') {
+            let digits = precision.len()
+                - precision
+                    .trim_start_matches(|c: char| c.is_ascii_digit())
+                    .len();
+            if digits == 0 {
+                return Err(invalid(format!("missing precision in `{spec}`")));
+            }
+
+            result.precision = Some(
+                precision[..digits]
+                    .parse()
+                    .map_err(|_| invalid(format!("precision too large in `{spec}`")))?,
+            );
+            rest = &precision[digits..];
+        }
+
+        if !rest.is_empty() {
+            return Err(invalid(format!("unknown format spec `{spec}`")));
+        }
+
+        Ok(result)
+    }
+
+    fn apply(&self, ev: &mut Evaluator, arg: &Object) -> Result<String, EvaluatorError> {
+        let text = match (self.precision, arg) {
+            (None, arg) => ev.stringify(arg)?,
+            (Some(p), Object::Float(f)) => format!("{f:.p$}"),
+            (Some(p), Object::Integer(i)) => format!("{:.p$}", *i as f64),
+            (Some(p), Object::String(s)) => s.chars().take(p).collect(),
+            (Some(_), arg) => {
+                return Err(invalid(format!(
+                    "precision is not supported for {}",
+                    arg.type_of()
+                )))
+            }
+        };
+
+        let len = text.chars().count();
+        if len >= self.width {
+            return Ok(text);
+        }
+
+        let numeric = matches!(arg, Object::Integer(_) | Object::Float(_));
+        let padding = self.width - len;
+        let (left, right) = match self.align {
+            Some('<') => (0, padding),
+            Some('^') => (padding / 2, padding - padding / 2),
+            Some('>') => (padding, 0),
+            _ if numeric => (padding, 0),
+            _ => (0, padding),
+        };
+
+        let fill = self.fill.unwrap_or(' ').to_string();
+        Ok(format!(
+            "{}{}{}",
+            fill.repeat(left),
+            text,
+            fill.repeat(right)
+        ))
+    }
+}
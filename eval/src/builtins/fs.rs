@@ -0,0 +1,82 @@
+//! File management. Paths are strings, relative to the working directory of
+//! the process.
+
+use super::{arguments, Module};
+use crate::{error::EvaluatorError, object::Object, sync::Shared};
+use std::{fs, io};
+
+pub fn register(module: &mut Module) {
+    module.register("exists", |_, args| {
+        let [path] = arguments(args)?;
+        let path = path_of(path)?;
+
+        let exists = fs::exists(&*path).map_err(|err| file_error(&path, err))?;
+        Ok(Object::Boolean(exists))
+    });
+
+    // Names of the entries of a directory, sorted.
+    module.register("list_dir", |_, args| {
+        let [path] = arguments(args)?;
+        let path = path_of(path)?;
+
+        let mut names = fs::read_dir(&*path)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .map_err(|err| file_error(&path, err))?;
+        names.sort();
+
+        Ok(Object::array(
+            names
+                .into_iter()
+                .map(|name| Object::String(name.into()))
+                .collect(),
+        ))
+    });
+
+    // Creates missing parent directories too.
+    module.register("mkdir", |_, args| {
+        let [path] = arguments(args)?;
+        let path = path_of(path)?;
+
+        fs::create_dir_all(&*path).map_err(|err| file_error(&path, err))?;
+        Ok(Object::Null)
+    });
+
+    // Removes a file or an empty directory.
+    module.register("remove", |_, args| {
+        let [path] = arguments(args)?;
+        let path = path_of(path)?;
+
+        let result = match fs::metadata(&*path) {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir(&*path),
+            _ => fs::remove_file(&*path),
+        };
+
+        result.map_err(|err| file_error(&path, err))?;
+        Ok(Object::Null)
+    });
+
+    // Copies a file, overwriting the destination. Returns the number of
+    // bytes copied.
+    module.register("copy", |_, args| {
+        let [from, to] = arguments(args)?;
+        let (from, to) = (path_of(from)?, path_of(to)?);
+
+        let copied = fs::copy(&*from, &*to).map_err(|err| file_error(&from, err))?;
+        Ok(Object::Integer(copied as i64))
+    });
+}
+
+fn path_of(arg: Object) -> Result<Shared<str>, EvaluatorError> {
+    match arg {
+        Object::String(path) => Ok(path),
+        _ => Err(EvaluatorError::UnexpectedType("String", arg)),
+    }
+}
+
+fn file_error(path: &str, err: io::Error) -> EvaluatorError {
+    EvaluatorError::File(path.to_string(), err)
+}
@@ -0,0 +1,169 @@
+use super::{arguments, Module};
+use crate::{
+    error::EvaluatorError,
+    iterator::{drain, iterate, Iter},
+    object::Object,
+    sync::Shared,
+};
+
+pub fn register(module: &mut Module) {
+    // `range(stop)`, `range(start, stop)` or `range(start, stop, step)`.
+    // `stop` is excluded; a negative step counts down. Ranges are lazy.
+    module.register("range", |_, args| {
+        let args = args
+            .into_iter()
+            .map(|arg| match arg {
+                Object::Integer(i) => Ok(i),
+                _ => Err(EvaluatorError::UnexpectedType("Int", arg)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (start, stop, step) = match args[..] {
+            [stop] => (0, stop, 1),
+            [start, stop] => (start, stop, 1),
+            [start, stop, step] => (start, stop, step),
+            _ => return Err(EvaluatorError::WrongArgumentCount(3, args.len())),
+        };
+
+        if step == 0 {
+            return Err(EvaluatorError::ZeroStep);
+        }
+
+        let mut next = Some(start);
+        Ok(iterator(Iter::new(Vec::new(), move |_| {
+            let Some(i) = next.filter(|&i| (step > 0 && i < stop) || (step < 0 && i > stop)) else {
+                return Ok(None);
+            };
+
+            next = i.checked_add(step);
+            Ok(Some(Object::Integer(i)))
+        })))
+    });
+
+    // Makes an iterator out of a function returning the next item on every
+    // call, or null when there are no more.
+    module.register("iterator", |_, args| {
+        let [f] = arguments(args)?;
+
+        Ok(iterator(Iter::new(vec![f.clone()], move |ev| {
+            match ev.call_function(f.clone(), Vec::new())? {
+                Object::Null => Ok(None),
+                item => Ok(Some(item)),
+            }
+        })))
+    });
+
+    // Null once the iterator is exhausted.
+    module.register("next", |ev, args| {
+        let [iter] = arguments(args)?;
+        let Object::Iterator(iter) = iter else {
+            return Err(EvaluatorError::UnexpectedType("Iterator", iter));
+        };
+
+        Ok(iter.next(ev)?.unwrap_or(Object::Null))
+    });
+
+    module.register("take", |_, args| {
+        let [iterable, n] = arguments(args)?;
+        let (source, mut n) = (iterate(iterable)?, count(n)?);
+
+        let sources = vec![Object::Iterator(Shared::clone(&source))];
+        Ok(iterator(Iter::new(sources, move |ev| {
+            if n == 0 {
+                return Ok(None);
+            }
+
+            n -= 1;
+            source.next(ev)
+        })))
+    });
+
+    module.register("skip", |_, args| {
+        let [iterable, n] = arguments(args)?;
+        let (source, mut n) = (iterate(iterable)?, count(n)?);
+
+        let sources = vec![Object::Iterator(Shared::clone(&source))];
+        Ok(iterator(Iter::new(sources, move |ev| {
+            while n > 0 {
+                n -= 1;
+                if source.next(ev)?.is_none() {
+                    return Ok(None);
+                }
+            }
+
+            source.next(ev)
+        })))
+    });
+
+    module.register("collect", |ev, args| {
+        let [iterable] = arguments(args)?;
+        Ok(Object::array(drain(&*iterate(iterable)?, ev)?))
+    });
+
+    // Pairs each item, as a `for` loop would see it, with its index.
+    // Lazy, like `take` and `skip`.
+    module.register("enumerate", |_, args| {
+        let [iterable] = arguments(args)?;
+        let source = iterate(iterable)?;
+        let mut i = 0;
+
+        let sources = vec![Object::Iterator(Shared::clone(&source))];
+        Ok(iterator(Iter::new(sources, move |ev| {
+            let Some(item) = source.next(ev)? else {
+                return Ok(None);
+            };
+
+            let pair = ev.allocated(Object::array(vec![Object::Integer(i), item]))?;
+            i += 1;
+            Ok(Some(pair))
+        })))
+    });
+
+    // Stops at the end of the shortest argument. Lazy, like `enumerate`.
+    module.register("zip", |_, args| {
+        if args.is_empty() {
+            return Err(EvaluatorError::WrongArgumentCount(1, 0));
+        }
+
+        let iters = args
+            .into_iter()
+            .map(iterate)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let sources = iters
+            .iter()
+            .map(|iter| Object::Iterator(Shared::clone(iter)))
+            .collect();
+        // Once one argument runs out, the others aren't advanced anymore.
+        let mut done = false;
+        Ok(iterator(Iter::new(sources, move |ev| {
+            if done {
+                return Ok(None);
+            }
+
+            let mut tuple = Vec::with_capacity(iters.len());
+            for iter in &iters {
+                match iter.next(ev)? {
+                    Some(item) => tuple.push(item),
+                    None => {
+                        done = true;
+                        return Ok(None);
+                    }
+                }
+            }
+
+            ev.allocated(Object::array(tuple)).map(Some)
+        })))
+    });
+}
+
+fn iterator(iter: Iter) -> Object {
+    Object::Iterator(Shared::new(iter))
+}
+
+fn count(n: Object) -> Result<usize, EvaluatorError> {
+    match n {
+        Object::Integer(i) => Ok(usize::try_from(i).unwrap_or(0)),
+        _ => Err(EvaluatorError::UnexpectedType("Int", n)),
+    }
+}
@@ -0,0 +1,105 @@
+use super::{arguments, conversions::float_to_int, Module};
+use crate::{
+    error::EvaluatorError,
+    object::{Object, Type},
+};
+use belalang_core::token::Token;
+
+pub fn register(module: &mut Module) {
+    module.register_constant("PI", Object::Float(std::f64::consts::PI));
+    module.register_constant("E", Object::Float(std::f64::consts::E));
+
+    module.register("abs", |_, args| {
+        let [x] = arguments(args)?;
+
+        match x {
+            Object::Integer(i) => i
+                .checked_abs()
+                .map(Object::Integer)
+                .ok_or(EvaluatorError::IntegerOverflow),
+            Object::Float(f) => Ok(Object::Float(f.abs())),
+            _ => Err(EvaluatorError::UnexpectedType("Int or Float", x)),
+        }
+    });
+
+    module.register("min", |_, args| extreme(args, |a, b| b < a));
+    module.register("max", |_, args| extreme(args, |a, b| b > a));
+
+    // Ints stay ints unless the exponent is negative.
+    module.register("pow", |_, args| {
+        let [base, exp] = arguments(args)?;
+
+        match (&base, &exp) {
+            (Object::Integer(b), Object::Integer(e)) if *e >= 0 => u32::try_from(*e)
+                .ok()
+                .and_then(|e| b.checked_pow(e))
+                .map(Object::Integer)
+                .ok_or(EvaluatorError::IntegerOverflow),
+            _ => Ok(Object::Float(number(base)?.powf(number(exp)?))),
+        }
+    });
+
+    module.register("sqrt", |_, args| {
+        let [x] = arguments(args)?;
+        Ok(Object::Float(number(x)?.sqrt()))
+    });
+
+    module.register("floor", |_, args| {
+        let [x] = arguments(args)?;
+        to_integer(x, f64::floor)
+    });
+
+    module.register("ceil", |_, args| {
+        let [x] = arguments(args)?;
+        to_integer(x, f64::ceil)
+    });
+
+    // Halfway cases round away from zero.
+    module.register("round", |_, args| {
+        let [x] = arguments(args)?;
+        to_integer(x, f64::round)
+    });
+}
+
+fn number(x: Object) -> Result<f64, EvaluatorError> {
+    match x {
+        Object::Integer(i) => Ok(i as f64),
+        Object::Float(f) => Ok(f),
+        _ => Err(EvaluatorError::UnexpectedType("Int or Float", x)),
+    }
+}
+
+/// Rounds a number to an int with `f`.
+fn to_integer(x: Object, f: fn(f64) -> f64) -> Result<Object, EvaluatorError> {
+    match x {
+        Object::Integer(_) => Ok(x),
+        Object::Float(fl) => match float_to_int(f(fl)) {
+            Some(i) => Ok(Object::Integer(i)),
+            None => Err(EvaluatorError::InvalidConversion(x, Type::Int)),
+        },
+        _ => Err(EvaluatorError::UnexpectedType("Int or Float", x)),
+    }
+}
+
+/// The argument `replaces` prefers over all others, for `min` and `max`.
+fn extreme(args: Vec<Object>, replaces: fn(f64, f64) -> bool) -> Result<Object, EvaluatorError> {
+    let mut args = args.into_iter();
+    let Some(mut result) = args.next() else {
+        return Err(EvaluatorError::WrongArgumentCount(1, 0));
+    };
+    let mut value = number(result.clone())?;
+
+    for arg in args {
+        let n = number(arg.clone())?;
+
+        if n.is_nan() || value.is_nan() {
+            return Err(EvaluatorError::UnknownInfixOperator(result, Token::Lt, arg));
+        }
+
+        if replaces(value, n) {
+            (result, value) = (arg, n);
+        }
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,58 @@
+use super::{arguments, Module};
+use crate::{error::EvaluatorError, object::Object, random::Rng};
+
+pub fn register(module: &mut Module) {
+    module.register("random", |ev, args| {
+        let [] = arguments(args)?;
+        Ok(Object::Float(ev.rng().next_f64()))
+    });
+
+    // Both bounds are inclusive.
+    module.register("random_int", |ev, args| {
+        let [lo, hi] = arguments(args)?;
+
+        let (lo, hi) = match (lo, hi) {
+            (Object::Integer(lo), Object::Integer(hi)) => (lo, hi),
+            (Object::Integer(_), hi) => return Err(EvaluatorError::UnexpectedType("Int", hi)),
+            (lo, _) => return Err(EvaluatorError::UnexpectedType("Int", lo)),
+        };
+
+        if lo > hi {
+            return Err(EvaluatorError::EmptyRange(lo, hi));
+        }
+
+        let span = hi.abs_diff(lo);
+        let offset = match span.checked_add(1) {
+            Some(bound) => ev.rng().below(bound),
+            None => ev.rng().next_u64(),
+        };
+
+        Ok(Object::Integer(lo.wrapping_add_unsigned(offset)))
+    });
+
+    // Returns a shuffled copy, like `sort`.
+    module.register("shuffle", |ev, args| {
+        let [a] = arguments(args)?;
+        let Object::Array(a) = a else {
+            return Err(EvaluatorError::UnexpectedType("Array", a));
+        };
+
+        let mut elements = a.borrow().clone();
+        for i in (1..elements.len()).rev() {
+            let j = ev.rng().below(i as u64 + 1) as usize;
+            elements.swap(i, j);
+        }
+
+        Ok(Object::array(elements))
+    });
+
+    module.register("random_seed", |ev, args| {
+        let [seed] = arguments(args)?;
+        let Object::Integer(seed) = seed else {
+            return Err(EvaluatorError::UnexpectedType("Int", seed));
+        };
+
+        *ev.rng() = Rng::seeded(seed as u64);
+        Ok(Object::Null)
+    });
+}
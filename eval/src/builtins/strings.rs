@@ -0,0 +1,111 @@
+use super::{arguments, Module};
+use crate::{error::EvaluatorError, object::Object, sync::Shared};
+
+pub fn register(module: &mut Module) {
+    module.register("join", |ev, args| {
+        let [array, separator] = arguments(args)?;
+
+        let Object::Array(elements) = array else {
+            return Err(EvaluatorError::UnexpectedType("Array", array));
+        };
+        let separator = string(separator)?;
+
+        // Cloned so `__str__` hooks can't observe the array mid-borrow.
+        let elements = elements.borrow().clone();
+        let parts = elements
+            .iter()
+            .map(|element| ev.stringify(element))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        ev.allocated(Object::String(parts.join(&separator).into()))
+    });
+
+    // An empty separator splits into characters, like `chars`. The parts
+    // count towards the memory limit along with the array.
+    module.register("split", |ev, args| {
+        let [s, separator] = arguments(args)?;
+        let (s, separator) = (string(s)?, string(separator)?);
+        ev.count_memory(s.len())?;
+
+        if separator.is_empty() {
+            return ev.allocated(chars(&s));
+        }
+
+        ev.allocated(Object::array(
+            s.split(&*separator)
+                .map(|part| Object::String(part.into()))
+                .collect(),
+        ))
+    });
+
+    module.register("chars", |ev, args| {
+        let [s] = arguments(args)?;
+        let s = string(s)?;
+        ev.count_memory(s.len())?;
+
+        ev.allocated(chars(&s))
+    });
+
+    module.register("trim", |ev, args| {
+        let [s] = arguments(args)?;
+        ev.allocated(Object::String(string(s)?.trim().into()))
+    });
+
+    module.register("replace", |ev, args| {
+        let [s, from, to] = arguments(args)?;
+        let (s, from, to) = (string(s)?, string(from)?, string(to)?);
+
+        ev.allocated(Object::String(s.replace(&*from, &to).into()))
+    });
+
+    module.register("contains", |_, args| {
+        let [s, sub] = arguments(args)?;
+        Ok(Object::Boolean(string(s)?.contains(&*string(sub)?)))
+    });
+
+    module.register("starts_with", |_, args| {
+        let [s, prefix] = arguments(args)?;
+        Ok(Object::Boolean(string(s)?.starts_with(&*string(prefix)?)))
+    });
+
+    module.register("ends_with", |_, args| {
+        let [s, suffix] = arguments(args)?;
+        Ok(Object::Boolean(string(s)?.ends_with(&*string(suffix)?)))
+    });
+
+    module.register("to_upper", |ev, args| {
+        let [s] = arguments(args)?;
+        ev.allocated(Object::String(string(s)?.to_uppercase().into()))
+    });
+
+    module.register("to_lower", |ev, args| {
+        let [s] = arguments(args)?;
+        ev.allocated(Object::String(string(s)?.to_lowercase().into()))
+    });
+
+    // The index is in characters, like `len`; null if `sub` isn't found.
+    module.register("find", |_, args| {
+        let [s, sub] = arguments(args)?;
+        let (s, sub) = (string(s)?, string(sub)?);
+
+        Ok(match s.find(&*sub) {
+            Some(byte) => Object::Integer(s[..byte].chars().count() as i64),
+            None => Object::Null,
+        })
+    });
+}
+
+fn string(arg: Object) -> Result<Shared<str>, EvaluatorError> {
+    match arg {
+        Object::String(s) => Ok(s),
+        _ => Err(EvaluatorError::UnexpectedType("String", arg)),
+    }
+}
+
+fn chars(s: &str) -> Object {
+    Object::array(
+        s.chars()
+            .map(|c| Object::String(c.to_string().into()))
+            .collect(),
+    )
+}
@@ -0,0 +1,58 @@
+use super::{arguments, Module};
+use crate::{error::EvaluatorError, object::Object};
+use std::{
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub fn register(module: &mut Module) {
+    // Seconds since the Unix epoch.
+    module.register("time_now", |_, args| {
+        let [] = arguments(args)?;
+
+        let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs_f64(),
+            Err(err) => -err.duration().as_secs_f64(),
+        };
+
+        Ok(Object::Float(now))
+    });
+
+    // Seconds on a monotonic clock, only meaningful relative to another
+    // `clock()`.
+    module.register("clock", |_, args| {
+        let [] = arguments(args)?;
+
+        static START: OnceLock<Instant> = OnceLock::new();
+        let start = START.get_or_init(Instant::now);
+
+        Ok(Object::Float(start.elapsed().as_secs_f64()))
+    });
+
+    // Sleeping past the evaluator's timeout, see `Evaluator::set_timeout`,
+    // only sleeps until the timeout and fails.
+    module.register("sleep", |ev, args| {
+        let [ms] = arguments(args)?;
+
+        let duration = match ms {
+            Object::Integer(ms) => Duration::from_millis(ms.max(0) as u64),
+            Object::Float(ms) => {
+                Duration::try_from_secs_f64(ms.max(0.0) / 1000.0).unwrap_or(Duration::MAX)
+            }
+            _ => return Err(EvaluatorError::UnexpectedType("Int or Float", ms)),
+        };
+
+        if let Some(deadline) = ev.deadline() {
+            let now = Instant::now();
+
+            if now.checked_add(duration).is_none_or(|end| end > deadline) {
+                thread::sleep(deadline.saturating_duration_since(now));
+                return Err(EvaluatorError::Timeout);
+            }
+        }
+
+        thread::sleep(duration);
+        Ok(Object::Null)
+    });
+}
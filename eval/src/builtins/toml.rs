@@ -0,0 +1,46 @@
+//! `toml_parse`, behind the `toml` feature. Tables become dicts and arrays
+//! become arrays, keeping the order of the document; dates and times, which
+//! have no counterpart, become strings.
+
+use super::{arguments, Module};
+use crate::{
+    error::EvaluatorError,
+    object::{HashKey, Object},
+};
+use toml::{Table, Value};
+
+pub fn register(module: &mut Module) {
+    module.register("toml_parse", |_, args| {
+        let [text] = arguments(args)?;
+        let Object::String(text) = text else {
+            return Err(EvaluatorError::UnexpectedType("String", text));
+        };
+
+        let table = text
+            .parse::<Table>()
+            .map_err(|err| EvaluatorError::InvalidData("TOML", err.message().to_string()))?;
+
+        Ok(table_to_object(table))
+    });
+}
+
+fn table_to_object(table: Table) -> Object {
+    Object::dict(
+        table
+            .into_iter()
+            .map(|(key, value)| (HashKey::String(key.into()), to_object(value)))
+            .collect(),
+    )
+}
+
+fn to_object(value: Value) -> Object {
+    match value {
+        Value::String(s) => Object::String(s.into()),
+        Value::Integer(i) => Object::Integer(i),
+        Value::Float(f) => Object::Float(f),
+        Value::Boolean(b) => Object::Boolean(b),
+        Value::Datetime(dt) => Object::String(dt.to_string().into()),
+        Value::Array(values) => Object::array(values.into_iter().map(to_object).collect()),
+        Value::Table(table) => table_to_object(table),
+    }
+}
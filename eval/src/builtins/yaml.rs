@@ -0,0 +1,65 @@
+//! `yaml_parse`, behind the `yaml` feature. Mappings become dicts and
+//! sequences become arrays, like `toml_parse`. Only the first document of
+//! the input is returned, null for an empty input.
+
+use super::{arguments, Module};
+use crate::{
+    error::EvaluatorError,
+    object::{HashKey, Object},
+};
+use yaml_rust2::{Yaml, YamlLoader};
+
+pub fn register(module: &mut Module) {
+    module.register("yaml_parse", |_, args| {
+        let [text] = arguments(args)?;
+        let Object::String(text) = text else {
+            return Err(EvaluatorError::UnexpectedType("String", text));
+        };
+
+        let documents = YamlLoader::load_from_str(&text)
+            .map_err(|err| EvaluatorError::InvalidData("YAML", err.to_string()))?;
+
+        match documents.into_iter().next() {
+            Some(document) => to_object(document),
+            None => Ok(Object::Null),
+        }
+    });
+}
+
+fn to_object(value: Yaml) -> Result<Object, EvaluatorError> {
+    Ok(match value {
+        Yaml::Real(ref f) => match value.as_f64() {
+            Some(f) => Object::Float(f),
+            None => {
+                return Err(EvaluatorError::InvalidData(
+                    "YAML",
+                    format!("bad float {f}"),
+                ))
+            }
+        },
+        Yaml::Integer(i) => Object::Integer(i),
+        Yaml::String(s) => Object::String(s.into()),
+        Yaml::Boolean(b) => Object::Boolean(b),
+        Yaml::Null => Object::Null,
+        Yaml::Array(values) => Object::array(
+            values
+                .into_iter()
+                .map(to_object)
+                .collect::<Result<_, _>>()?,
+        ),
+        Yaml::Hash(pairs) => {
+            let pairs = pairs
+                .into_iter()
+                .map(|(key, value)| Ok((HashKey::try_from(to_object(key)?)?, to_object(value)?)))
+                .collect::<Result<_, EvaluatorError>>()?;
+
+            Object::dict(pairs)
+        }
+        Yaml::Alias(_) | Yaml::BadValue => {
+            return Err(EvaluatorError::InvalidData(
+                "YAML",
+                "unsupported value".into(),
+            ))
+        }
+    })
+}
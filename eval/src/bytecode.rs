@@ -0,0 +1,522 @@
+//! Compiled programs, made by [`crate::compiler`] and run by [`crate::vm`].
+
+use crate::sync::Shared;
+use belalang_core::{
+    ast::{FunctionLiteral, Statement},
+    symbol::Symbol,
+    token::Token,
+};
+use std::fmt::{self, Write};
+
+pub mod file;
+
+/// An instruction of the VM. Instructions with an operand are followed by it
+/// in the code as a big-endian `u16`: an index into the constants, a local
+/// slot, a global index, a count or the offset in the same code to jump to.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    /// Pushes a constant.
+    Constant,
+    Null,
+    True,
+    False,
+    Pop,
+    /// Pops that many elements and pushes an array of them.
+    Array,
+    /// Pops that many key-value pairs and pushes a dict of them.
+    Dict,
+
+    // Binary operators pop the right operand, then the left one.
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    In,
+    Is,
+    Not,
+    Neg,
+
+    /// Pushes what the name constant refers to.
+    GetName,
+    /// `name := value`, leaving the value on the stack.
+    DeclareName,
+    /// `name = value`, leaving the value on the stack.
+    SetName,
+    /// Pops a value and binds it in the current scope, like a loop variable.
+    BindName,
+    /// Pushes the value in a slot of the current frame.
+    GetLocal,
+    /// `local := value`, leaving the value on the stack.
+    DeclareLocal,
+    /// `local = value`, leaving the value on the stack.
+    SetLocal,
+    /// Pushes what a global refers to, which may be a builtin.
+    GetGlobal,
+    /// `global := value`, leaving the value on the stack.
+    DeclareGlobal,
+    /// `global = value`, leaving the value on the stack.
+    SetGlobal,
+    /// Fails for declaring the named local again in the same scope.
+    Redeclare,
+    /// Pushes the value of a variable the function captured.
+    GetUpvalue,
+    /// `upvalue = value`, leaving the value on the stack.
+    SetUpvalue,
+    /// Moves a local out of its slot into the upvalue capturing it, as its
+    /// scope ends.
+    CloseUpvalue,
+    /// Pops an index, then the indexed value.
+    Index,
+    /// Pops a value, an index and the indexed value, pushing the value back.
+    SetIndex,
+    /// Pops an object and pushes its member named by the constant.
+    Member,
+
+    Jump,
+    /// Pops a value and jumps unless it is `true`.
+    JumpIfFalse,
+    /// Jumps if the value on top of the stack is null, leaving it there.
+    JumpIfNull,
+    /// Jumps if the value on top of the stack isn't null, or pops it.
+    JumpIfNotNull,
+
+    /// Enters a new scope for a block.
+    PushScope,
+    PopScope,
+
+    /// Pops a value and pushes an iterator over it.
+    Iter,
+    /// Pushes the next item of the iterator on top of the stack, or pops the
+    /// iterator and jumps when it is done.
+    Next,
+
+    /// Pushes a function constant closed over the current scope.
+    Closure,
+    /// Pushes a generator function constant, which the evaluator runs,
+    /// closed over the current scope.
+    Generator,
+    /// Pops that many arguments, then the function to call.
+    Call,
+    Return,
+    /// Fails for a `yield` outside of a generator function.
+    Yield,
+
+    /// Pops an import path and pushes the module.
+    Import,
+    /// Like [`Opcode::Import`], also binding the module to its name.
+    ImportBind,
+}
+
+impl Opcode {
+    const ALL: [Opcode; 61] = [
+        Opcode::Constant,
+        Opcode::Null,
+        Opcode::True,
+        Opcode::False,
+        Opcode::Pop,
+        Opcode::Array,
+        Opcode::Dict,
+        Opcode::Add,
+        Opcode::Sub,
+        Opcode::Mul,
+        Opcode::Div,
+        Opcode::Mod,
+        Opcode::Eq,
+        Opcode::Ne,
+        Opcode::Lt,
+        Opcode::Le,
+        Opcode::Gt,
+        Opcode::Ge,
+        Opcode::And,
+        Opcode::Or,
+        Opcode::BitAnd,
+        Opcode::BitOr,
+        Opcode::BitXor,
+        Opcode::ShiftLeft,
+        Opcode::ShiftRight,
+        Opcode::In,
+        Opcode::Is,
+        Opcode::Not,
+        Opcode::Neg,
+        Opcode::GetName,
+        Opcode::DeclareName,
+        Opcode::SetName,
+        Opcode::BindName,
+        Opcode::GetLocal,
+        Opcode::DeclareLocal,
+        Opcode::SetLocal,
+        Opcode::GetGlobal,
+        Opcode::DeclareGlobal,
+        Opcode::SetGlobal,
+        Opcode::Redeclare,
+        Opcode::GetUpvalue,
+        Opcode::SetUpvalue,
+        Opcode::CloseUpvalue,
+        Opcode::Index,
+        Opcode::SetIndex,
+        Opcode::Member,
+        Opcode::Jump,
+        Opcode::JumpIfFalse,
+        Opcode::JumpIfNull,
+        Opcode::JumpIfNotNull,
+        Opcode::PushScope,
+        Opcode::PopScope,
+        Opcode::Iter,
+        Opcode::Next,
+        Opcode::Closure,
+        Opcode::Generator,
+        Opcode::Call,
+        Opcode::Return,
+        Opcode::Yield,
+        Opcode::Import,
+        Opcode::ImportBind,
+    ];
+
+    /// Whether the opcode's operand is an offset to jump to.
+    pub fn is_jump(self) -> bool {
+        matches!(
+            self,
+            Opcode::Jump
+                | Opcode::JumpIfFalse
+                | Opcode::JumpIfNull
+                | Opcode::JumpIfNotNull
+                | Opcode::Next
+        )
+    }
+
+    /// Whether the opcode is followed by an operand.
+    pub fn has_operand(self) -> bool {
+        matches!(
+            self,
+            Opcode::Constant
+                | Opcode::Array
+                | Opcode::Dict
+                | Opcode::GetName
+                | Opcode::DeclareName
+                | Opcode::SetName
+                | Opcode::BindName
+                | Opcode::GetLocal
+                | Opcode::DeclareLocal
+                | Opcode::SetLocal
+                | Opcode::GetGlobal
+                | Opcode::DeclareGlobal
+                | Opcode::SetGlobal
+                | Opcode::Redeclare
+                | Opcode::GetUpvalue
+                | Opcode::SetUpvalue
+                | Opcode::CloseUpvalue
+                | Opcode::Member
+                | Opcode::Jump
+                | Opcode::JumpIfFalse
+                | Opcode::JumpIfNull
+                | Opcode::JumpIfNotNull
+                | Opcode::Next
+                | Opcode::Closure
+                | Opcode::Generator
+                | Opcode::Call
+        )
+    }
+
+    /// The opcode of a binary operator, other than `??`.
+    pub fn infix(operator: &Token) -> Option<Opcode> {
+        Some(match operator {
+            Token::Add => Opcode::Add,
+            Token::Sub => Opcode::Sub,
+            Token::Mul => Opcode::Mul,
+            Token::Div => Opcode::Div,
+            Token::Mod => Opcode::Mod,
+            Token::Eq => Opcode::Eq,
+            Token::Ne => Opcode::Ne,
+            Token::Lt => Opcode::Lt,
+            Token::Le => Opcode::Le,
+            Token::Gt => Opcode::Gt,
+            Token::Ge => Opcode::Ge,
+            Token::And => Opcode::And,
+            Token::Or => Opcode::Or,
+            Token::BitAnd => Opcode::BitAnd,
+            Token::BitOr => Opcode::BitOr,
+            Token::BitXor => Opcode::BitXor,
+            Token::ShiftLeft => Opcode::ShiftLeft,
+            Token::ShiftRight => Opcode::ShiftRight,
+            Token::In => Opcode::In,
+            Token::Is => Opcode::Is,
+            _ => return None,
+        })
+    }
+
+    /// The operator of a binary or prefix operator opcode.
+    pub fn operator(self) -> Option<Token> {
+        Some(match self {
+            Opcode::Add => Token::Add,
+            Opcode::Sub | Opcode::Neg => Token::Sub,
+            Opcode::Mul => Token::Mul,
+            Opcode::Div => Token::Div,
+            Opcode::Mod => Token::Mod,
+            Opcode::Eq => Token::Eq,
+            Opcode::Ne => Token::Ne,
+            Opcode::Lt => Token::Lt,
+            Opcode::Le => Token::Le,
+            Opcode::Gt => Token::Gt,
+            Opcode::Ge => Token::Ge,
+            Opcode::And => Token::And,
+            Opcode::Or => Token::Or,
+            Opcode::BitAnd => Token::BitAnd,
+            Opcode::BitOr => Token::BitOr,
+            Opcode::BitXor => Token::BitXor,
+            Opcode::ShiftLeft => Token::ShiftLeft,
+            Opcode::ShiftRight => Token::ShiftRight,
+            Opcode::In => Token::In,
+            Opcode::Is => Token::Is,
+            Opcode::Not => Token::Not,
+            _ => return None,
+        })
+    }
+}
+
+impl TryFrom<u8> for Opcode {
+    type Error = u8;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        Opcode::ALL
+            .get(byte as usize)
+            .copied()
+            .filter(|op| *op as u8 == byte)
+            .ok_or(byte)
+    }
+}
+
+/// A compiled program or function body.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bytecode {
+    pub code: Vec<u8>,
+    pub constants: Vec<Constant>,
+    /// The names of the local slots, one per declaration.
+    pub locals: Vec<Symbol>,
+    /// The names of the program's globals by index, empty for functions,
+    /// which use the program's.
+    pub globals: Vec<Symbol>,
+    /// The statements by the offset their code starts at, in order, for
+    /// [`Hooks::on_statement`](crate::hooks::Hooks::on_statement).
+    pub statements: Vec<(u16, Syntax<Statement>)>,
+    /// The called expression of each call as written, by the offset of its
+    /// [`Opcode::Call`], for [`Hooks::on_call`](crate::hooks::Hooks::on_call).
+    pub calls: Vec<(u16, Shared<str>)>,
+}
+
+impl Bytecode {
+    /// The operand at `offset`.
+    pub fn operand(&self, offset: usize) -> u16 {
+        u16::from_be_bytes([self.code[offset], self.code[offset + 1]])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Integer(i64),
+    Float(f64),
+    String(Shared<str>),
+    /// The name of a variable or member.
+    Name(Symbol),
+    Function(Shared<CompiledFunction>),
+    /// A generator function literal, which [`Opcode::Generator`] makes a
+    /// function of for the evaluator to run, see [`crate::generator`].
+    Generator(Syntax<FunctionLiteral>),
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constant::Integer(i) => write!(f, "{i}"),
+            Constant::Float(fl) => write!(f, "{fl:?}"),
+            Constant::String(s) => write!(f, "{s:?}"),
+            Constant::Name(name) => write!(f, "{name}"),
+            Constant::Function(function) => {
+                let params = function
+                    .params
+                    .iter()
+                    .map(|param| param.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(f, "<fn({params})>")
+            }
+            Constant::Generator(generator) => {
+                let params = generator
+                    .params
+                    .iter()
+                    .map(|param| param.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(f, "<generator fn({params})>")
+            }
+        }
+    }
+}
+
+/// Syntax compiled code keeps as it is, compared by its source text, which
+/// is how [files](file) keep it.
+#[derive(Debug, Clone)]
+pub struct Syntax<T>(pub Shared<T>);
+
+impl<T> std::ops::Deref for Syntax<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: fmt::Display> PartialEq for Syntax<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Shared::ptr_eq(&self.0, &other.0) || self.0.to_string() == other.0.to_string()
+    }
+}
+
+/// A function literal, which [`Opcode::Closure`] turns into a function
+/// value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledFunction {
+    /// The variable the function literal was assigned to, if any.
+    pub name: Option<Symbol>,
+    pub params: Vec<Symbol>,
+    /// Whether the function keeps its variables in scopes by name rather
+    /// than in slots, for the modules it imports to be bound in.
+    pub by_name: bool,
+    /// The variables of enclosing functions it captures.
+    pub upvalues: Vec<Capture>,
+    pub bytecode: Bytecode,
+}
+
+/// Where a closure finds a variable it captures when it is made: a local
+/// of the function making it or one of that function's own upvalues.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    pub name: Symbol,
+    pub local: bool,
+    pub index: u16,
+}
+
+/// Lists the instructions of compiled code, one per line with its offset,
+/// the constants and variables they refer to and where jumps go. The bodies
+/// of the functions it defines follow, headed by the constant they are and
+/// what they capture.
+pub fn disassemble(bytecode: &Bytecode) -> String {
+    let mut out = String::new();
+    write_code(&mut out, bytecode, &[], &bytecode.globals, "");
+    out
+}
+
+fn write_code(
+    out: &mut String,
+    bytecode: &Bytecode,
+    upvalues: &[Capture],
+    globals: &[Symbol],
+    path: &str,
+) {
+    let mut offset = 0;
+
+    while let Some(&byte) = bytecode.code.get(offset) {
+        let Ok(op) = Opcode::try_from(byte) else {
+            let _ = writeln!(out, "{offset:04} <unknown opcode {byte}>");
+            offset += 1;
+            continue;
+        };
+
+        if !op.has_operand() {
+            let _ = writeln!(out, "{offset:04} {op:?}");
+            offset += 1;
+            continue;
+        }
+
+        if offset + 3 > bytecode.code.len() {
+            let _ = writeln!(out, "{offset:04} {op:?} <missing operand>");
+            break;
+        }
+
+        // Debug output of an enum ignores the width, so it's padded here.
+        let name = format!("{op:?}");
+        let operand = bytecode.operand(offset + 1);
+        let referred = match op {
+            Opcode::GetLocal | Opcode::DeclareLocal | Opcode::SetLocal | Opcode::CloseUpvalue => {
+                bytecode.locals.get(operand as usize).map(Symbol::to_string)
+            }
+            Opcode::GetGlobal | Opcode::DeclareGlobal | Opcode::SetGlobal => {
+                globals.get(operand as usize).map(Symbol::to_string)
+            }
+            Opcode::GetUpvalue | Opcode::SetUpvalue => upvalues
+                .get(operand as usize)
+                .map(|capture| capture.name.to_string()),
+            op if uses_constant(op) => bytecode
+                .constants
+                .get(operand as usize)
+                .map(Constant::to_string),
+            _ => None,
+        };
+
+        let _ = match referred {
+            _ if op.is_jump() => writeln!(out, "{offset:04} {name:<14} -> {operand:04}"),
+            Some(referred) => writeln!(out, "{offset:04} {name:<14} {operand} ({referred})"),
+            None => writeln!(out, "{offset:04} {name:<14} {operand}"),
+        };
+        offset += 3;
+    }
+
+    for (i, constant) in bytecode.constants.iter().enumerate() {
+        if let Constant::Function(function) = constant {
+            let path = format!("{path}{i}");
+            let _ = writeln!(out, "\nconstant {path}, {constant}:");
+
+            if !function.upvalues.is_empty() {
+                let captures = function
+                    .upvalues
+                    .iter()
+                    .map(|capture| match capture.local {
+                        true => format!("local {} ({})", capture.index, capture.name),
+                        false => format!("upvalue {} ({})", capture.index, capture.name),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let _ = writeln!(out, "captures {captures}");
+            }
+
+            write_code(
+                out,
+                &function.bytecode,
+                &function.upvalues,
+                globals,
+                &format!("{path}."),
+            );
+        }
+    }
+}
+
+fn uses_constant(op: Opcode) -> bool {
+    matches!(
+        op,
+        Opcode::Constant
+            | Opcode::GetName
+            | Opcode::DeclareName
+            | Opcode::SetName
+            | Opcode::BindName
+            | Opcode::Redeclare
+            | Opcode::Member
+            | Opcode::Closure
+            | Opcode::Generator
+    )
+}
@@ -0,0 +1,310 @@
+//! The `.blc` file format, for running compiled programs without parsing
+//! and compiling them again.
+//!
+//! A file is [`MAGIC`], the [`VERSION`] as a big-endian `u16` and then the
+//! program's bytecode: the length of its code as a `u32`, the code, the
+//! number of constants as a `u16` and the constants, then the names of the
+//! locals and of the globals, each list prefixed by its length as a `u16`,
+//! then the statements and the calls, each list prefixed by its length as a
+//! `u32`: their offset (`u16`) and their source as a string.
+//! Each constant is a tag byte followed by an `i64` or the bits of an `f64`,
+//! a string or name as its `u32` length and UTF-8 bytes, a function as its
+//! name (empty if it has none), its parameter count (`u16`), its parameter
+//! names, a byte that is 1 if it keeps its variables by name, the number of
+//! variables it captures (`u16`) and its own bytecode, or a generator
+//! function as its source. Each captured variable is its name, a byte that
+//! is 1 for a local and its index as a `u16`. Numbers are big-endian.
+
+use super::{Bytecode, Capture, CompiledFunction, Constant, Syntax};
+use crate::{error::LoadError, sync::Shared};
+use belalang_core::{
+    ast::{Expression, Statement},
+    lexer::Lexer,
+    parser::Parser,
+    symbol::Symbol,
+};
+use std::io::{self, Read, Write};
+
+pub const MAGIC: &[u8; 4] = b"BLC\0";
+
+/// Changes whenever the format or the opcodes do, files of other versions
+/// have to be compiled again.
+pub const VERSION: u16 = 5;
+
+/// How deep functions may be nested in a loaded file.
+const MAX_NESTING: usize = 256;
+
+const INTEGER: u8 = 0;
+const FLOAT: u8 = 1;
+const STRING: u8 = 2;
+const NAME: u8 = 3;
+const FUNCTION: u8 = 4;
+const GENERATOR: u8 = 5;
+
+impl Bytecode {
+    /// Writes a compiled program as a `.blc` file.
+    pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_be_bytes())?;
+        write_bytecode(&mut writer, self)
+    }
+
+    /// Reads a program written by [`Bytecode::save`].
+    pub fn load<R: Read>(mut reader: R) -> Result<Bytecode, LoadError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic).map_err(truncated)?;
+        if &magic != MAGIC {
+            return Err(LoadError::NotBytecode);
+        }
+
+        let version = read_u16(&mut reader)?;
+        if version != VERSION {
+            return Err(LoadError::Version(version));
+        }
+
+        read_bytecode(&mut reader, 0)
+    }
+}
+
+fn write_bytecode<W: Write>(writer: &mut W, bytecode: &Bytecode) -> io::Result<()> {
+    write_len(writer, bytecode.code.len())?;
+    writer.write_all(&bytecode.code)?;
+
+    let count = u16::try_from(bytecode.constants.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many constants"))?;
+    writer.write_all(&count.to_be_bytes())?;
+
+    for constant in &bytecode.constants {
+        match constant {
+            Constant::Integer(i) => {
+                writer.write_all(&[INTEGER])?;
+                writer.write_all(&i.to_be_bytes())?;
+            }
+            Constant::Float(f) => {
+                writer.write_all(&[FLOAT])?;
+                writer.write_all(&f.to_bits().to_be_bytes())?;
+            }
+            Constant::String(s) => {
+                writer.write_all(&[STRING])?;
+                write_str(writer, s)?;
+            }
+            Constant::Name(name) => {
+                writer.write_all(&[NAME])?;
+                write_str(writer, name.as_str())?;
+            }
+            Constant::Function(function) => {
+                writer.write_all(&[FUNCTION])?;
+                write_str(writer, function.name.as_ref().map_or("", Symbol::as_str))?;
+
+                let params = u16::try_from(function.params.len()).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "too many parameters")
+                })?;
+                writer.write_all(&params.to_be_bytes())?;
+
+                for param in &function.params {
+                    write_str(writer, param.as_str())?;
+                }
+
+                writer.write_all(&[function.by_name as u8])?;
+
+                let upvalues = u16::try_from(function.upvalues.len()).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "too many upvalues")
+                })?;
+                writer.write_all(&upvalues.to_be_bytes())?;
+
+                for capture in &function.upvalues {
+                    write_str(writer, capture.name.as_str())?;
+                    writer.write_all(&[capture.local as u8])?;
+                    writer.write_all(&capture.index.to_be_bytes())?;
+                }
+
+                write_bytecode(writer, &function.bytecode)?;
+            }
+            Constant::Generator(generator) => {
+                writer.write_all(&[GENERATOR])?;
+                write_str(writer, &generator.to_string())?;
+            }
+        }
+    }
+
+    write_names(writer, &bytecode.locals)?;
+    write_names(writer, &bytecode.globals)?;
+
+    write_len(writer, bytecode.statements.len())?;
+    for (offset, statement) in &bytecode.statements {
+        writer.write_all(&offset.to_be_bytes())?;
+        write_str(writer, &statement.to_string())?;
+    }
+
+    write_len(writer, bytecode.calls.len())?;
+    for (offset, function) in &bytecode.calls {
+        writer.write_all(&offset.to_be_bytes())?;
+        write_str(writer, function)?;
+    }
+
+    Ok(())
+}
+
+fn write_names<W: Write>(writer: &mut W, names: &[Symbol]) -> io::Result<()> {
+    let count = u16::try_from(names.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many variables"))?;
+    writer.write_all(&count.to_be_bytes())?;
+
+    for name in names {
+        write_str(writer, name.as_str())?;
+    }
+
+    Ok(())
+}
+
+fn write_len<W: Write>(writer: &mut W, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "longer than 4 GiB"))?;
+    writer.write_all(&len.to_be_bytes())
+}
+
+fn write_str<W: Write>(writer: &mut W, s: &str) -> io::Result<()> {
+    write_len(writer, s.len())?;
+    writer.write_all(s.as_bytes())
+}
+
+fn read_bytecode<R: Read>(reader: &mut R, depth: usize) -> Result<Bytecode, LoadError> {
+    if depth > MAX_NESTING {
+        return Err(LoadError::Invalid("functions nested too deeply".into()));
+    }
+
+    let code = read_bytes(reader)?;
+    let count = read_u16(reader)?;
+    let mut constants = Vec::with_capacity(count as usize);
+
+    for _ in 0..count {
+        let constant = match read_array::<1, _>(reader)? {
+            [INTEGER] => Constant::Integer(i64::from_be_bytes(read_array(reader)?)),
+            [FLOAT] => Constant::Float(f64::from_bits(u64::from_be_bytes(read_array(reader)?))),
+            [STRING] => Constant::String(read_string(reader)?.into()),
+            [NAME] => Constant::Name(read_string(reader)?.as_str().into()),
+            [FUNCTION] => {
+                let name = Some(read_string(reader)?)
+                    .filter(|name| !name.is_empty())
+                    .map(|name| name.as_str().into());
+                let params = read_names(reader)?;
+                let by_name = read_flag(reader)?;
+                let upvalues = (0..read_u16(reader)?)
+                    .map(|_| {
+                        Ok(Capture {
+                            name: read_string(reader)?.as_str().into(),
+                            local: read_flag(reader)?,
+                            index: read_u16(reader)?,
+                        })
+                    })
+                    .collect::<Result<_, LoadError>>()?;
+                let bytecode = read_bytecode(reader, depth + 1)?;
+
+                Constant::Function(Shared::new(CompiledFunction {
+                    name,
+                    params,
+                    by_name,
+                    upvalues,
+                    bytecode,
+                }))
+            }
+            [GENERATOR] => {
+                let source = read_string(reader)?;
+                match Parser::parse_expression_str(&source) {
+                    Ok(Expression::Function(fn_lit)) => {
+                        Constant::Generator(Syntax(Shared::new(fn_lit)))
+                    }
+                    _ => return Err(LoadError::Invalid(format!("invalid generator {source:?}"))),
+                }
+            }
+            [tag] => return Err(LoadError::Invalid(format!("unknown constant tag {tag}"))),
+        };
+
+        constants.push(constant);
+    }
+
+    let locals = read_names(reader)?;
+    let globals = read_names(reader)?;
+
+    let statements = (0..read_u32(reader)?)
+        .map(|_| Ok((read_u16(reader)?, read_statement(reader)?)))
+        .collect::<Result<_, LoadError>>()?;
+    let calls = (0..read_u32(reader)?)
+        .map(|_| Ok((read_u16(reader)?, read_string(reader)?.into())))
+        .collect::<Result<_, LoadError>>()?;
+
+    Ok(Bytecode {
+        code,
+        constants,
+        locals,
+        globals,
+        statements,
+        calls,
+    })
+}
+
+/// Parses a statement back from its source.
+fn read_statement<R: Read>(reader: &mut R) -> Result<Syntax<Statement>, LoadError> {
+    let source = read_string(reader)?;
+    let program = Parser::new(Lexer::new(source.as_bytes())).parse_program();
+
+    match program.map(|program| <[Statement; 1]>::try_from(program.statements)) {
+        Ok(Ok([statement])) => Ok(Syntax(Shared::new(statement))),
+        _ => Err(LoadError::Invalid(format!("invalid statement {source:?}"))),
+    }
+}
+
+fn read_names<R: Read>(reader: &mut R) -> Result<Vec<Symbol>, LoadError> {
+    (0..read_u16(reader)?)
+        .map(|_| read_string(reader).map(|name| name.as_str().into()))
+        .collect()
+}
+
+fn read_array<const N: usize, R: Read>(reader: &mut R) -> Result<[u8; N], LoadError> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes).map_err(truncated)?;
+    Ok(bytes)
+}
+
+fn read_flag<R: Read>(reader: &mut R) -> Result<bool, LoadError> {
+    match read_array(reader)? {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        [byte] => Err(LoadError::Invalid(format!("invalid flag {byte}"))),
+    }
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16, LoadError> {
+    Ok(u16::from_be_bytes(read_array(reader)?))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, LoadError> {
+    Ok(u32::from_be_bytes(read_array(reader)?))
+}
+
+/// Reads a length and that many bytes, without trusting the length to
+/// allocate up front.
+fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, LoadError> {
+    let len = u32::from_be_bytes(read_array(reader)?) as u64;
+
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+
+    if bytes.len() as u64 != len {
+        return Err(LoadError::Truncated);
+    }
+
+    Ok(bytes)
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String, LoadError> {
+    String::from_utf8(read_bytes(reader)?)
+        .map_err(|_| LoadError::Invalid("string is not UTF-8".into()))
+}
+
+fn truncated(err: io::Error) -> LoadError {
+    match err.kind() {
+        io::ErrorKind::UnexpectedEof => LoadError::Truncated,
+        _ => LoadError::Io(err),
+    }
+}
@@ -0,0 +1,796 @@
+//! Compiles programs to [`Bytecode`] for the [`crate::vm`], an alternative
+//! to evaluating the AST with the [`crate::evaluator::Evaluator`].
+//!
+//! Variables are resolved while compiling: those declared at the top of the
+//! program are globals, referred to by index, and the others are locals,
+//! kept in slots of the function's stack frame. Names that aren't declared
+//! anywhere are globals too, which at runtime may be builtins. Functions
+//! capture the locals of enclosing functions they use as upvalues. Functions
+//! that import modules keep their variables in scopes by name instead, which
+//! the modules are bound in.
+//!
+//! Globals are kept in the evaluator's scopes like evaluated variables, so
+//! evaluated code and builtins see them. Their indices refer to the names in
+//! [`Bytecode::globals`].
+//!
+//! Generator functions aren't compiled, the evaluator runs them as it can
+//! pause them, see [`crate::generator`]. Functions defining one keep their
+//! variables by name for it to see them.
+
+use crate::{
+    bytecode::{Bytecode, Capture, CompiledFunction, Constant, Opcode, Syntax},
+    error::CompileError,
+    generator,
+    sync::Shared,
+};
+use belalang_core::{
+    ast::{
+        visit::{self, Visitor},
+        BlockExpression, Expression, FunctionLiteral, Program, Statement,
+    },
+    symbol::Symbol,
+    token::Token,
+};
+use std::collections::HashMap;
+
+/// Constants are only added once. Floats are compared by their bits.
+#[derive(PartialEq, Eq, Hash)]
+enum Key {
+    Integer(i64),
+    Float(u64),
+    String(Shared<str>),
+    Name(Symbol),
+}
+
+#[derive(Default)]
+pub struct Compiler {
+    /// The functions being compiled, innermost last. The first one is the
+    /// program.
+    functions: Vec<Function>,
+    globals: Vec<Symbol>,
+    global_indices: HashMap<Symbol, u16>,
+}
+
+/// The state of a function being compiled.
+#[derive(Default)]
+struct Function {
+    bytecode: Bytecode,
+    constants: HashMap<Key, u16>,
+    /// The scopes being compiled, innermost last. The top of the program
+    /// isn't a scope, its variables are globals.
+    scopes: Vec<Scope>,
+    /// Whether variables are kept in scopes by name instead of slots.
+    by_name: bool,
+    upvalues: Vec<Capture>,
+}
+
+#[derive(Default)]
+struct Scope {
+    locals: Vec<Local>,
+    /// The variables declared directly in the scope that haven't been yet.
+    pending: Vec<Symbol>,
+}
+
+struct Local {
+    name: Symbol,
+    slot: u16,
+    /// Whether the declaration was compiled. Locals get their slot earlier
+    /// when a function defined before the declaration captures them.
+    declared: bool,
+    captured: bool,
+}
+
+/// Where a variable is found at runtime.
+enum Variable {
+    Local(u16),
+    Upvalue(u16),
+    Name,
+    Global(u16),
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compiles a program, which evaluates to the value of its last
+    /// statement.
+    pub fn compile_program(mut self, program: &Program) -> Result<Bytecode, CompileError> {
+        self.functions.push(Function {
+            by_name: imports(&program.statements) || generators(&program.statements),
+            ..Function::default()
+        });
+
+        self.statements(&program.statements)?;
+
+        let mut bytecode = self.functions.pop().unwrap_or_default().bytecode;
+        bytecode.globals = self.globals;
+        Ok(bytecode)
+    }
+
+    fn function(&mut self) -> &mut Function {
+        self.functions
+            .last_mut()
+            .expect("a function being compiled")
+    }
+
+    /// Compiles statements leaving the value of the last one on the stack,
+    /// or null if there are none.
+    fn statements(&mut self, statements: &[Statement]) -> Result<(), CompileError> {
+        if statements.is_empty() {
+            self.emit(Opcode::Null);
+        }
+
+        for (i, statement) in statements.iter().enumerate() {
+            if i > 0 {
+                self.emit(Opcode::Pop);
+            }
+
+            self.statement(statement)?;
+        }
+
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), CompileError> {
+        let offset = self.offset()?;
+        self.function()
+            .bytecode
+            .statements
+            .push((offset, Syntax(Shared::new(statement.clone()))));
+
+        match statement {
+            // A bare `import "path";` also binds the module to its name.
+            Statement::Expression(node) => match &node.expression {
+                Expression::Import(import) => {
+                    self.expression(&import.path)?;
+                    self.emit(Opcode::ImportBind);
+                }
+                expression => self.expression(expression)?,
+            },
+            Statement::Return(stmt) => {
+                self.expression(&stmt.return_value)?;
+                self.emit(Opcode::Return);
+            }
+            Statement::Yield(_) => self.emit(Opcode::Yield),
+            Statement::While(stmt) => {
+                let start = self.offset()?;
+                self.expression(&stmt.condition)?;
+                let exit = self.emit_jump(Opcode::JumpIfFalse);
+
+                self.block(&stmt.block)?;
+                self.emit(Opcode::Pop);
+                self.emit_with(Opcode::Jump, start);
+
+                self.patch(exit)?;
+                self.emit(Opcode::Null);
+            }
+            Statement::For(stmt) => {
+                self.expression(&stmt.iterable)?;
+                self.emit(Opcode::Iter);
+
+                let start = self.offset()?;
+                let exit = self.emit_jump(Opcode::Next);
+
+                self.enter_scope(declarations(&stmt.block.statements));
+                match self.declare(stmt.variable.value)? {
+                    Variable::Local(slot) => {
+                        self.emit_with(Opcode::SetLocal, slot);
+                        self.emit(Opcode::Pop);
+                    }
+                    _ => {
+                        let name = self.name(stmt.variable.value)?;
+                        self.emit_with(Opcode::BindName, name);
+                    }
+                }
+                self.statements(&stmt.block.statements)?;
+                self.leave_scope();
+
+                self.emit(Opcode::Pop);
+                self.emit_with(Opcode::Jump, start);
+
+                self.patch(exit)?;
+                self.emit(Opcode::Null);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Result<(), CompileError> {
+        match expression {
+            Expression::Integer(int_lit) => {
+                let constant = self.constant(Constant::Integer(int_lit.value))?;
+                self.emit_with(Opcode::Constant, constant);
+            }
+            Expression::Float(float_lit) => {
+                let constant = self.constant(Constant::Float(float_lit.value))?;
+                self.emit_with(Opcode::Constant, constant);
+            }
+            Expression::String(s) => {
+                let constant = self.constant(Constant::String(s.value.as_str().into()))?;
+                self.emit_with(Opcode::Constant, constant);
+            }
+            Expression::Boolean(bool_expr) => match bool_expr.value {
+                true => self.emit(Opcode::True),
+                false => self.emit(Opcode::False),
+            },
+            Expression::Null(_) => self.emit(Opcode::Null),
+            Expression::Array(arr) => {
+                for element in &arr.elements {
+                    self.expression(element)?;
+                }
+
+                let len = count(arr.elements.len(), "array elements")?;
+                self.emit_with(Opcode::Array, len);
+            }
+            Expression::Dict(dict) => {
+                for (key, value) in &dict.pairs {
+                    self.expression(key)?;
+                    self.expression(value)?;
+                }
+
+                let len = count(dict.pairs.len(), "dict pairs")?;
+                self.emit_with(Opcode::Dict, len);
+            }
+            Expression::Index(_) | Expression::Member(_) | Expression::Call(_) => {
+                let mut exits = Vec::new();
+                self.chain(expression, &mut exits)?;
+
+                for exit in exits {
+                    self.patch(exit)?;
+                }
+            }
+            Expression::IndexAssign(assign) => {
+                self.expression(&assign.left)?;
+                self.expression(&assign.index)?;
+                self.expression(&assign.value)?;
+                self.emit(Opcode::SetIndex);
+            }
+            Expression::Var(var) => {
+                let name = var.name.value;
+
+                match var.token {
+                    Token::ColonAssign => {
+                        if self.declared_here(name) && !self.function().by_name {
+                            self.expression(&var.value)?;
+                            let name = self.name(name)?;
+                            self.emit_with(Opcode::Redeclare, name);
+                            return Ok(());
+                        }
+
+                        // Functions are declared first, to capture themselves
+                        // and recurse.
+                        let variable = match &*var.value {
+                            Expression::Function(fn_lit) => {
+                                let variable = self.declare(name)?;
+                                self.function_literal(fn_lit, Some(name))?;
+                                variable
+                            }
+                            value => {
+                                self.expression(value)?;
+                                self.declare(name)?
+                            }
+                        };
+
+                        match variable {
+                            Variable::Local(slot) => self.emit_with(Opcode::DeclareLocal, slot),
+                            Variable::Name | Variable::Upvalue(_) => {
+                                let name = self.name(name)?;
+                                self.emit_with(Opcode::DeclareName, name);
+                            }
+                            Variable::Global(index) => self.emit_with(Opcode::DeclareGlobal, index),
+                        }
+                    }
+                    Token::Assign => {
+                        match &*var.value {
+                            Expression::Function(fn_lit) => {
+                                self.function_literal(fn_lit, Some(name))?
+                            }
+                            value => self.expression(value)?,
+                        }
+
+                        match self.resolve(name)? {
+                            Variable::Local(slot) => self.emit_with(Opcode::SetLocal, slot),
+                            Variable::Upvalue(index) => self.emit_with(Opcode::SetUpvalue, index),
+                            Variable::Name => {
+                                let name = self.name(name)?;
+                                self.emit_with(Opcode::SetName, name);
+                            }
+                            Variable::Global(index) => self.emit_with(Opcode::SetGlobal, index),
+                        }
+                    }
+                    _ => return Err(CompileError::Unsupported("assignment operator")),
+                }
+            }
+            Expression::Prefix(node) => {
+                let op = match node.operator {
+                    Token::Not => Opcode::Not,
+                    Token::Sub => Opcode::Neg,
+                    _ => return Err(CompileError::Unsupported("prefix operator")),
+                };
+
+                self.expression(&node.right)?;
+                self.emit(op);
+            }
+            Expression::Infix(infix_expr) if infix_expr.operator == Token::NullCoalesce => {
+                self.expression(&infix_expr.left)?;
+                let end = self.emit_jump(Opcode::JumpIfNotNull);
+                self.expression(&infix_expr.right)?;
+                self.patch(end)?;
+            }
+            Expression::Infix(infix_expr) => {
+                let op = Opcode::infix(&infix_expr.operator)
+                    .ok_or(CompileError::Unsupported("infix operator"))?;
+
+                self.expression(&infix_expr.left)?;
+                self.expression(&infix_expr.right)?;
+                self.emit(op);
+            }
+            Expression::If(expr) => {
+                self.expression(&expr.condition)?;
+                let alternative = self.emit_jump(Opcode::JumpIfFalse);
+
+                self.block(&expr.consequence)?;
+                let end = self.emit_jump(Opcode::Jump);
+
+                self.patch(alternative)?;
+                match &expr.alternative {
+                    Some(alternative) => self.expression(alternative)?,
+                    None => self.emit(Opcode::Null),
+                }
+
+                self.patch(end)?;
+            }
+            Expression::Function(fn_lit) => self.function_literal(fn_lit, None)?,
+            Expression::Identifier(ident) => match self.resolve(ident.value)? {
+                Variable::Local(slot) => self.emit_with(Opcode::GetLocal, slot),
+                Variable::Upvalue(index) => self.emit_with(Opcode::GetUpvalue, index),
+                Variable::Name => {
+                    let name = self.name(ident.value)?;
+                    self.emit_with(Opcode::GetName, name);
+                }
+                Variable::Global(index) => self.emit_with(Opcode::GetGlobal, index),
+            },
+            Expression::Block(block) => self.block(block)?,
+            Expression::Import(import) => {
+                self.expression(&import.path)?;
+                self.emit(Opcode::Import);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compiles a function literal, `name` being the variable it's
+    /// assigned to, for stack traces.
+    fn function_literal(
+        &mut self,
+        fn_lit: &FunctionLiteral,
+        name: Option<Symbol>,
+    ) -> Result<(), CompileError> {
+        if generator::yields(&fn_lit.body) {
+            let generator = Syntax(Shared::new(fn_lit.clone()));
+            let constant = self.constant(Constant::Generator(generator))?;
+            self.emit_with(Opcode::Generator, constant);
+            return Ok(());
+        }
+
+        let by_name = imports(&fn_lit.body.statements) || generators(&fn_lit.body.statements);
+        self.functions.push(Function {
+            by_name,
+            scopes: vec![Scope {
+                locals: Vec::new(),
+                pending: declarations(&fn_lit.body.statements),
+            }],
+            ..Function::default()
+        });
+
+        for param in &fn_lit.params {
+            self.declare(param.value)?;
+        }
+
+        self.statements(&fn_lit.body.statements)?;
+        self.emit(Opcode::Return);
+
+        let compiled = self.functions.pop().expect("the function being compiled");
+        let function = CompiledFunction {
+            name,
+            params: fn_lit.params.iter().map(|param| param.value).collect(),
+            by_name,
+            upvalues: compiled.upvalues,
+            bytecode: compiled.bytecode,
+        };
+
+        let constant = self.constant(Constant::Function(Shared::new(function)))?;
+        self.emit_with(Opcode::Closure, constant);
+
+        Ok(())
+    }
+
+    /// Compiles an index, member access or call, which may be part of an
+    /// optional chain. A `?.` or `?[` finding null jumps to the end of the
+    /// chain, the offsets of these jumps are added to `exits`.
+    fn chain(
+        &mut self,
+        expression: &Expression,
+        exits: &mut Vec<usize>,
+    ) -> Result<(), CompileError> {
+        match expression {
+            Expression::Index(idx) => {
+                self.chain(&idx.left, exits)?;
+
+                if idx.token == Token::OptionalBracket {
+                    exits.push(self.emit_jump(Opcode::JumpIfNull));
+                }
+
+                self.expression(&idx.index)?;
+                self.emit(Opcode::Index);
+            }
+            Expression::Member(member) => {
+                self.chain(&member.object, exits)?;
+
+                if member.token == Token::OptionalDot {
+                    exits.push(self.emit_jump(Opcode::JumpIfNull));
+                }
+
+                let name = self.name(member.property.value)?;
+                self.emit_with(Opcode::Member, name);
+            }
+            Expression::Call(call_expr) => {
+                self.chain(&call_expr.function, exits)?;
+
+                for arg in &call_expr.args {
+                    self.expression(arg)?;
+                }
+
+                let len = count(call_expr.args.len(), "arguments")?;
+                let offset = self.offset()?;
+                self.function()
+                    .bytecode
+                    .calls
+                    .push((offset, call_expr.function.to_string().into()));
+                self.emit_with(Opcode::Call, len);
+            }
+            expression => self.expression(expression)?,
+        }
+
+        Ok(())
+    }
+
+    /// Compiles a block in a scope of its own.
+    fn block(&mut self, block: &BlockExpression) -> Result<(), CompileError> {
+        self.enter_scope(declarations(&block.statements));
+        self.statements(&block.statements)?;
+        self.leave_scope();
+        Ok(())
+    }
+
+    fn enter_scope(&mut self, pending: Vec<Symbol>) {
+        self.function().scopes.push(Scope {
+            locals: Vec::new(),
+            pending,
+        });
+
+        if self.function().by_name {
+            self.emit(Opcode::PushScope);
+        }
+    }
+
+    /// Leaves the innermost scope, closing the upvalues of its locals so the
+    /// next time it's entered they are new variables.
+    fn leave_scope(&mut self) {
+        let scope = self.function().scopes.pop().unwrap_or_default();
+
+        if self.function().by_name {
+            self.emit(Opcode::PopScope);
+        }
+
+        for local in scope.locals.iter().filter(|local| local.captured) {
+            self.emit_with(Opcode::CloseUpvalue, local.slot);
+        }
+    }
+
+    /// Adds a variable to the innermost scope, giving it a slot of its own
+    /// if it's a local.
+    fn declare(&mut self, name: Symbol) -> Result<Variable, CompileError> {
+        let function = self
+            .functions
+            .last_mut()
+            .expect("a function being compiled");
+
+        let Some(scope) = function.scopes.last_mut() else {
+            return self.global(name).map(Variable::Global);
+        };
+
+        if function.by_name {
+            scope.locals.push(Local {
+                name,
+                slot: 0,
+                declared: true,
+                captured: false,
+            });
+            return Ok(Variable::Name);
+        }
+
+        let hoisted = scope
+            .locals
+            .iter_mut()
+            .find(|local| local.name == name && !local.declared);
+
+        if let Some(local) = hoisted {
+            local.declared = true;
+            return Ok(Variable::Local(local.slot));
+        }
+
+        let slot = slot(&mut function.bytecode.locals, name)?;
+        scope.locals.push(Local {
+            name,
+            slot,
+            declared: true,
+            captured: false,
+        });
+
+        Ok(Variable::Local(slot))
+    }
+
+    /// Whether a variable is already declared in the innermost scope.
+    fn declared_here(&mut self, name: Symbol) -> bool {
+        self.function().scopes.last().is_some_and(|scope| {
+            scope
+                .locals
+                .iter()
+                .any(|local| local.name == name && local.declared)
+        })
+    }
+
+    /// Finds the innermost variable called `name`: a local of the function
+    /// being compiled, one it captures from an enclosing function, or else a
+    /// global.
+    fn resolve(&mut self, name: Symbol) -> Result<Variable, CompileError> {
+        let innermost = self.functions.len() - 1;
+
+        if let Some(variable) = self.find(innermost, name, false)? {
+            return Ok(variable);
+        }
+
+        match self.capture(innermost, name)? {
+            Some(variable) => Ok(variable),
+            None => self.global(name).map(Variable::Global),
+        }
+    }
+
+    /// Looks for a variable in the scopes of the function at `depth`,
+    /// innermost first. With `hoist`, a variable declared further on in one
+    /// of the scopes gets its slot already, for a function defined before
+    /// the declaration to capture it.
+    fn find(
+        &mut self,
+        depth: usize,
+        name: Symbol,
+        hoist: bool,
+    ) -> Result<Option<Variable>, CompileError> {
+        let function = &mut self.functions[depth];
+
+        for scope in function.scopes.iter_mut().rev() {
+            let local = scope
+                .locals
+                .iter()
+                .rev()
+                .find(|local| local.name == name && (local.declared || hoist));
+
+            let slot = match local {
+                Some(local) => local.slot,
+                None if hoist && scope.pending.contains(&name) => {
+                    if function.by_name {
+                        return Ok(Some(Variable::Name));
+                    }
+
+                    let slot = slot(&mut function.bytecode.locals, name)?;
+                    scope.locals.push(Local {
+                        name,
+                        slot,
+                        declared: false,
+                        captured: false,
+                    });
+                    slot
+                }
+                None => continue,
+            };
+
+            return Ok(Some(match function.by_name {
+                true => Variable::Name,
+                false => Variable::Local(slot),
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// Captures a variable of a function enclosing the one at `depth`, also
+    /// as an upvalue of each function in between.
+    fn capture(&mut self, depth: usize, name: Symbol) -> Result<Option<Variable>, CompileError> {
+        let Some(enclosing) = depth.checked_sub(1) else {
+            return Ok(None);
+        };
+
+        let (local, index) = match self.find(enclosing, name, true)? {
+            Some(Variable::Local(slot)) => {
+                let locals = self.functions[enclosing]
+                    .scopes
+                    .iter_mut()
+                    .flat_map(|scope| scope.locals.iter_mut());
+
+                for local in locals.filter(|local| local.slot == slot) {
+                    local.captured = true;
+                }
+
+                (true, slot)
+            }
+            Some(variable) => return Ok(Some(variable)),
+            None => match self.capture(enclosing, name)? {
+                Some(Variable::Upvalue(index)) => (false, index),
+                variable => return Ok(variable),
+            },
+        };
+
+        let upvalues = &mut self.functions[depth].upvalues;
+        let position = upvalues
+            .iter()
+            .position(|capture| capture.local == local && capture.index == index);
+
+        let index = match position {
+            Some(position) => position,
+            None => {
+                upvalues.push(Capture { name, local, index });
+                upvalues.len() - 1
+            }
+        };
+
+        count(index, "captured variables").map(|index| Some(Variable::Upvalue(index)))
+    }
+
+    fn global(&mut self, name: Symbol) -> Result<u16, CompileError> {
+        if let Some(index) = self.global_indices.get(&name) {
+            return Ok(*index);
+        }
+
+        let index =
+            u16::try_from(self.globals.len()).map_err(|_| CompileError::TooMany("globals"))?;
+        self.globals.push(name);
+        self.global_indices.insert(name, index);
+        Ok(index)
+    }
+
+    fn emit(&mut self, op: Opcode) {
+        self.function().bytecode.code.push(op as u8);
+    }
+
+    fn emit_with(&mut self, op: Opcode, operand: u16) {
+        self.emit(op);
+        self.function().bytecode.code.extend(operand.to_be_bytes());
+    }
+
+    /// Emits a jump to be [patched](Compiler::patch) once the target is
+    /// known, returning where its operand is.
+    fn emit_jump(&mut self, op: Opcode) -> usize {
+        self.emit_with(op, u16::MAX);
+        self.function().bytecode.code.len() - 2
+    }
+
+    /// Makes the jump with its operand at `at` jump to the current offset.
+    fn patch(&mut self, at: usize) -> Result<(), CompileError> {
+        let target = self.offset()?.to_be_bytes();
+        self.function().bytecode.code[at..at + 2].copy_from_slice(&target);
+        Ok(())
+    }
+
+    /// The offset of the next instruction, as a jump target.
+    fn offset(&mut self) -> Result<u16, CompileError> {
+        u16::try_from(self.function().bytecode.code.len()).map_err(|_| CompileError::TooLong)
+    }
+
+    fn name(&mut self, name: Symbol) -> Result<u16, CompileError> {
+        self.constant(Constant::Name(name))
+    }
+
+    fn constant(&mut self, constant: Constant) -> Result<u16, CompileError> {
+        let function = self.function();
+
+        let key = match &constant {
+            Constant::Integer(i) => Some(Key::Integer(*i)),
+            Constant::Float(f) => Some(Key::Float(f.to_bits())),
+            Constant::String(s) => Some(Key::String(s.clone())),
+            Constant::Name(name) => Some(Key::Name(*name)),
+            Constant::Function(_) | Constant::Generator(_) => None,
+        };
+
+        if let Some(index) = key.as_ref().and_then(|key| function.constants.get(key)) {
+            return Ok(*index);
+        }
+
+        let index = u16::try_from(function.bytecode.constants.len())
+            .map_err(|_| CompileError::TooManyConstants)?;
+        function.bytecode.constants.push(constant);
+
+        if let Some(key) = key {
+            function.constants.insert(key, index);
+        }
+
+        Ok(index)
+    }
+}
+
+fn count(len: usize, what: &'static str) -> Result<u16, CompileError> {
+    u16::try_from(len).map_err(|_| CompileError::TooMany(what))
+}
+
+/// Gives a variable the next slot.
+fn slot(locals: &mut Vec<Symbol>, name: Symbol) -> Result<u16, CompileError> {
+    let slot = count(locals.len(), "local variables")?;
+    locals.push(name);
+    Ok(slot)
+}
+
+/// The variables declared directly among `statements`.
+fn declarations(statements: &[Statement]) -> Vec<Symbol> {
+    statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Expression(node) => match &node.expression {
+                Expression::Var(var) if var.token == Token::ColonAssign => Some(var.name.value),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether code imports modules, which are bound in its scope, so it has to
+/// keep its variables there by name. Functions it defines are left out,
+/// their imports are bound in their own scopes.
+fn imports(statements: &[Statement]) -> bool {
+    struct Finder(bool);
+
+    impl Visitor for Finder {
+        fn visit_expression(&mut self, expression: &Expression) {
+            match expression {
+                Expression::Import(_) => self.0 = true,
+                Expression::Function(_) => {}
+                expression => visit::walk_expression(self, expression),
+            }
+        }
+    }
+
+    let mut finder = Finder(false);
+    for statement in statements {
+        finder.visit_statement(statement);
+    }
+
+    finder.0
+}
+
+/// Whether code defines generator functions, which the evaluator runs and
+/// which see its variables by name, so it has to keep them there. Unlike
+/// for imports, functions it defines are looked in too, a generator in them
+/// may use its variables.
+fn generators(statements: &[Statement]) -> bool {
+    struct Finder(bool);
+
+    impl Visitor for Finder {
+        fn visit_expression(&mut self, expression: &Expression) {
+            if let Expression::Function(fn_lit) = expression {
+                self.0 |= generator::yields(&fn_lit.body);
+            }
+
+            visit::walk_expression(self, expression);
+        }
+    }
+
+    let mut finder = Finder(false);
+    for statement in statements {
+        finder.visit_statement(statement);
+    }
+
+    finder.0
+}
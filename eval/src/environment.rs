@@ -1,39 +1,149 @@
 use std::{
-    cell::{Ref, RefCell},
-    collections::HashMap,
-    rc::Rc,
+    collections::{HashMap, HashSet},
+    ops::{Deref, DerefMut},
 };
 
-use crate::object::Object;
+use crate::{
+    gc::{self, Node, Objects, Thresholds},
+    metrics::HeapMetrics,
+    object::{Collection, HashKey, Object},
+    sync::{Counter, Locked, Shared, Weak},
+    vm::Upvalue,
+};
+use belalang_core::symbol::Symbol;
+use indexmap::IndexMap;
+
+pub(crate) type Store = Shared<Locked<Scope>>;
+
+/// The bindings of a scope. It counts itself among the live scopes of its
+/// heap until it's dropped, so those are known without pruning.
+#[derive(Debug)]
+pub(crate) struct Scope {
+    bindings: HashMap<Symbol, Object>,
+    live: Shared<Counter>,
+}
+
+impl Deref for Scope {
+    type Target = HashMap<Symbol, Object>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.bindings
+    }
+}
+
+impl DerefMut for Scope {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.bindings
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        self.live.decrement();
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Environment {
-    stores: Vec<Rc<RefCell<HashMap<String, Object>>>>,
+    stores: Vec<Store>,
+    heap: Shared<Locked<Heap>>,
+}
+
+/// Every scope created from the same root environment, so scopes kept alive
+/// only by reference cycles can be found, see [`Environment::collect_garbage`].
+#[derive(Debug, Default)]
+struct Heap {
+    scopes: Vec<Weak<Locked<Scope>>>,
+    /// How many of `scopes` are alive.
+    live: Shared<Counter>,
+    /// The values the VM allocated, see [`crate::gc`].
+    objects: Objects,
+    metrics: HeapMetrics,
+}
+
+impl Heap {
+    fn alloc(&mut self) -> Store {
+        // Dead entries are pruned whenever the list would have to grow, which
+        // keeps it proportional to the number of live scopes.
+        if self.scopes.len() == self.scopes.capacity() {
+            self.scopes.retain(|scope| scope.strong_count() > 0);
+        }
+
+        let store = Shared::new(Locked::new(Scope {
+            bindings: HashMap::new(),
+            live: Shared::clone(&self.live),
+        }));
+        self.scopes.push(Shared::downgrade(&store));
+
+        let live = self.live.increment();
+        self.metrics.allocations += 1;
+        self.metrics.peak_scopes = self.metrics.peak_scopes.max(live);
+
+        store
+    }
+}
+
+/// Saved bindings of an environment's scope chain, see
+/// [`Environment::snapshot`] and [`Environment::snapshot_reachable`].
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    chain: Vec<Store>,
+    scopes: Vec<(Store, HashMap<Symbol, Object>)>,
+    collections: Vec<SavedCollection>,
+}
+
+/// An array or a dict with its contents and whether it was frozen.
+#[derive(Debug, Clone)]
+enum SavedCollection {
+    Array(Shared<Collection<Vec<Object>>>, Vec<Object>, bool),
+    Dict(
+        Shared<Collection<IndexMap<HashKey, Object>>>,
+        IndexMap<HashKey, Object>,
+        bool,
+    ),
 }
 
 impl Default for Environment {
     fn default() -> Self {
-        let stores = vec![Rc::new(RefCell::new(HashMap::new()))];
-        Self { stores }
+        let mut heap = Heap::default();
+        let stores = vec![heap.alloc()];
+
+        Self {
+            stores,
+            heap: Shared::new(Locked::new(heap)),
+        }
     }
 }
 
 impl Environment {
+    /// A new global scope that doesn't see any of this environment's
+    /// bindings, e.g. for a module. It shares this environment's heap, so
+    /// [`Environment::collect_garbage`] covers it.
+    pub fn fresh(&self) -> Environment {
+        Environment {
+            stores: vec![self.heap.borrow_mut().alloc()],
+            heap: Shared::clone(&self.heap),
+        }
+    }
+
     pub fn capture(&self) -> Environment {
         let mut stores = Vec::with_capacity(self.stores.len());
 
         for store in &self.stores {
-            stores.push(Rc::clone(store));
+            stores.push(Shared::clone(store));
         }
 
-        stores.push(Rc::new(RefCell::new(HashMap::new())));
+        stores.push(self.heap.borrow_mut().alloc());
 
-        Environment { stores }
+        Environment {
+            stores,
+            heap: Shared::clone(&self.heap),
+        }
     }
 
-    pub fn has(&self, key: &String) -> bool {
+    pub fn has(&self, key: Symbol) -> bool {
         for store in self.stores.iter().rev() {
-            if store.borrow().contains_key(key) {
+            if store.borrow().contains_key(&key) {
                 return true;
             }
         }
@@ -41,76 +151,494 @@ impl Environment {
         false
     }
 
-    pub fn has_here(&self, key: &String) -> bool {
+    pub fn has_here(&self, key: Symbol) -> bool {
         if let Some(store) = self.stores.last() {
-            return store.borrow().contains_key(key);
+            return store.borrow().contains_key(&key);
         }
 
         false
     }
 
-    pub fn get<'a>(&'a self, key: &String) -> Option<Ref<'a, Object>> {
-        for store in self.stores.iter().rev() {
-            let value = Ref::filter_map(store.borrow(), |v| v.get(key)).ok();
+    pub fn get(&self, key: Symbol) -> Option<Object> {
+        self.stores
+            .iter()
+            .rev()
+            .find_map(|store| store.borrow().get(&key).cloned())
+    }
+
+    /// Bindings of the innermost scope, sorted by name.
+    pub fn locals(&self) -> Vec<(Symbol, Object)> {
+        self.stores.last().map(Self::sorted).unwrap_or_default()
+    }
+
+    /// Bindings of the outermost scope, sorted by name.
+    pub fn globals(&self) -> Vec<(Symbol, Object)> {
+        self.stores.first().map(Self::sorted).unwrap_or_default()
+    }
 
-            if let Some(v) = value {
-                return Some(v);
+    /// Every binding visible from this scope, sorted by name. Inner bindings
+    /// shadow outer ones with the same name.
+    pub fn bindings(&self) -> Vec<(Symbol, Object)> {
+        let mut bindings = HashMap::new();
+
+        for store in &self.stores {
+            for (key, value) in store.borrow().iter() {
+                bindings.insert(*key, value.clone());
+            }
+        }
+
+        Self::sorted_bindings(bindings.into_iter())
+    }
+
+    /// Saves the bindings of every scope in the chain, so they can be put
+    /// back with [`Environment::restore`], e.g. after running untrusted code.
+    ///
+    /// Values are saved as they are; mutations made to a value in place are
+    /// not undone by restoring.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            chain: self.stores.clone(),
+            scopes: self
+                .stores
+                .iter()
+                .map(|store| (Shared::clone(store), store.borrow().clone()))
+                .collect(),
+            collections: Vec::new(),
+        }
+    }
+
+    /// Like [`Environment::snapshot`], but also saves everything reachable
+    /// from the scope chain and from `roots`: the scopes closures captured
+    /// and the contents of arrays and dicts, so that restoring undoes
+    /// mutations made in place too. Iterators and native objects keep their
+    /// state.
+    pub fn snapshot_reachable(&self, roots: &[&Object]) -> Snapshot {
+        let mut visited = HashSet::new();
+        let mut collections = Vec::new();
+        let mut pending = self.stores.clone();
+        let mut marked = HashSet::new();
+        let mut scopes = Vec::new();
+
+        for root in roots {
+            trace(root, &mut pending, &mut visited, &mut collections);
+        }
+
+        while let Some(store) = pending.pop() {
+            if marked.insert(Shared::as_ptr(&store)) {
+                let bindings = store.borrow().clone();
+                for value in bindings.values() {
+                    trace(value, &mut pending, &mut visited, &mut collections);
+                }
+                scopes.push((store, bindings));
+            }
+        }
+
+        let collections = collections
+            .into_iter()
+            .filter_map(|collection| match collection {
+                Object::Array(array) => {
+                    let items = array.borrow().clone();
+                    let frozen = array.is_frozen();
+                    Some(SavedCollection::Array(array, items, frozen))
+                }
+                Object::Dict(dict) => {
+                    let items = dict.borrow().clone();
+                    let frozen = dict.is_frozen();
+                    Some(SavedCollection::Dict(dict, items, frozen))
+                }
+                _ => None,
+            })
+            .collect();
+
+        Snapshot {
+            chain: self.stores.clone(),
+            scopes,
+            collections,
+        }
+    }
+
+    /// Rolls the scope chain back to a snapshot. Closures that captured one
+    /// of the saved scopes see the restored bindings too.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        for (store, bindings) in &snapshot.scopes {
+            **store.borrow_mut() = bindings.clone();
+        }
+
+        for collection in &snapshot.collections {
+            match collection {
+                SavedCollection::Array(array, items, frozen) => {
+                    array.replace(items.clone());
+                    array.set_frozen(*frozen);
+                }
+                SavedCollection::Dict(dict, items, frozen) => {
+                    dict.replace(items.clone());
+                    dict.set_frozen(*frozen);
+                }
+            }
+        }
+
+        self.stores = snapshot.chain.clone();
+    }
+
+    /// Frees scopes that are only kept alive by reference cycles, e.g. a
+    /// closure stored in the scope it captured, and the objects tracked for
+    /// the VM that are, see [`crate::gc`]. Scopes and objects reachable from
+    /// this environment or from `roots` are kept, and so are those something
+    /// else still holds, like the host; the cycles among the rest are broken
+    /// by emptying them. Returns the number of scopes and objects freed.
+    pub fn collect_garbage(&self, roots: &[&Object]) -> usize {
+        self.collect_garbage_in(roots, &[])
+    }
+
+    /// Like [`Environment::collect_garbage`], with `scopes` reachable too,
+    /// e.g. the ones a running VM will go back to.
+    pub(crate) fn collect_garbage_in(&self, roots: &[&Object], scopes: &[&Environment]) -> usize {
+        let mut marked = HashSet::new();
+        let mut collections = Vec::new();
+        let mut pending = self.stores.clone();
+
+        for env in scopes {
+            pending.extend(env.stores.iter().cloned());
+        }
+
+        for root in roots {
+            trace(root, &mut pending, &mut marked, &mut collections);
+        }
+
+        while let Some(store) = pending.pop() {
+            if marked.insert(Shared::as_ptr(&store) as *const ()) {
+                for value in store.borrow().values() {
+                    trace(value, &mut pending, &mut marked, &mut collections);
+                }
             }
         }
 
-        None
+        let mut heap = self.heap.borrow_mut();
+
+        let mut candidates = heap.objects.unmarked(&marked);
+        candidates.extend(
+            heap.scopes
+                .iter()
+                .filter_map(Weak::upgrade)
+                .filter(|store| !marked.contains(&(Shared::as_ptr(store) as *const ())))
+                .map(Node::Scope),
+        );
+        let freed = gc::free(gc::garbage(candidates, &marked));
+
+        heap.scopes.retain(|scope| scope.strong_count() > 0);
+        heap.objects.prune();
+        heap.metrics.collections += 1;
+        freed
+    }
+
+    /// Tracks an object the VM allocated, see [`Objects::track`].
+    pub(crate) fn track(&self, object: &Object) {
+        self.heap.borrow_mut().objects.track(object);
+    }
+
+    /// Whether enough objects are tracked for the VM to collect garbage.
+    pub(crate) fn collection_due(&self, thresholds: Thresholds) -> bool {
+        self.heap.borrow().objects.due(thresholds)
+    }
+
+    /// Counts allocations and the peak from now on.
+    pub(crate) fn reset_metrics(&self) {
+        let mut heap = self.heap.borrow_mut();
+        heap.metrics = HeapMetrics {
+            allocations: 0,
+            peak_scopes: heap.live.get(),
+            collections: 0,
+        };
+    }
+
+    /// Whether more than `max` scopes are alive.
+    pub(crate) fn scopes_over(&self, max: usize) -> bool {
+        self.heap.borrow().live.get() > max
+    }
+
+    pub(crate) fn metrics(&self) -> HeapMetrics {
+        self.heap.borrow().metrics
+    }
+
+    /// The scope chain, outermost first.
+    pub(crate) fn stores(&self) -> &[Store] {
+        &self.stores
+    }
+
+    fn sorted(store: &Store) -> Vec<(Symbol, Object)> {
+        let store = store.borrow();
+        Self::sorted_bindings(store.iter().map(|(k, v)| (*k, v.clone())))
     }
 
-    pub fn set(&mut self, key: &String, value: Object) {
+    fn sorted_bindings(bindings: impl Iterator<Item = (Symbol, Object)>) -> Vec<(Symbol, Object)> {
+        let mut bindings = bindings.collect::<Vec<_>>();
+        bindings.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+        bindings
+    }
+
+    /// Binds `key` in the innermost scope, shadowing any outer binding.
+    pub fn declare(&mut self, key: Symbol, value: Object) {
+        if let Some(store) = self.stores.last_mut() {
+            store.borrow_mut().insert(key, value);
+        }
+    }
+
+    /// Assigns to the innermost binding of `key`, or declares it if there is
+    /// none.
+    pub fn set(&mut self, key: Symbol, value: Object) {
         for store in self.stores.iter().rev() {
-            let mut store = store.borrow_mut();
-            if store.contains_key(key) {
-                store.insert(key.clone(), value);
+            if let Some(slot) = store.borrow_mut().get_mut(&key) {
+                *slot = value;
                 return;
             }
         }
 
         if let Some(store) = self.stores.last_mut() {
-            store.borrow_mut().insert(key.clone(), value);
+            store.borrow_mut().insert(key, value);
         }
     }
 }
 
+/// Queues the scopes an object keeps alive. `visited` holds the collections
+/// and upvalues already traced, since they may contain themselves; the
+/// arrays and dicts among them are added to `collections`.
+fn trace(
+    object: &Object,
+    pending: &mut Vec<Store>,
+    visited: &mut HashSet<*const ()>,
+    collections: &mut Vec<Object>,
+) {
+    match object {
+        Object::Function(f) => pending.extend(f.env.stores.iter().cloned()),
+        Object::Closure(c) if visited.insert(Shared::as_ptr(c) as *const ()) => {
+            pending.extend(c.env.stores.iter().cloned());
+
+            for upvalue in &c.upvalues {
+                if !visited.insert(Shared::as_ptr(upvalue) as *const ()) {
+                    continue;
+                }
+
+                if let Upvalue::Closed(value) = &*upvalue.borrow() {
+                    trace(value, pending, visited, collections);
+                }
+            }
+        }
+        Object::Array(elements) if visited.insert(Shared::as_ptr(elements) as *const ()) => {
+            collections.push(object.clone());
+            for element in elements.borrow().iter() {
+                trace(element, pending, visited, collections);
+            }
+        }
+        Object::Iterator(iter) if visited.insert(Shared::as_ptr(iter) as *const ()) => {
+            for source in iter.sources() {
+                trace(source, pending, visited, collections);
+            }
+
+            for env in iter.scopes() {
+                pending.extend(env.stores);
+            }
+        }
+        Object::Dict(dict) if visited.insert(Shared::as_ptr(dict) as *const ()) => {
+            collections.push(object.clone());
+            for value in dict.borrow().values() {
+                trace(value, pending, visited, collections);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Serialized as the list of scopes, outermost first. Arrays and dicts
+/// containing themselves are an error.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Environment {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(self.stores.len()))?;
+        for store in &self.stores {
+            seq.serialize_element(&**store.borrow())?;
+        }
+        seq.end()
+    }
+}
+
+/// Functions don't carry their captured environment when serialized, so
+/// every function is rebound to the scope it is stored in. Closures over
+/// scopes that are not part of the environment lose those bindings.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Environment {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let scopes = Vec::<HashMap<Symbol, Object>>::deserialize(deserializer)?;
+
+        let mut env = Environment {
+            stores: Vec::new(),
+            heap: Shared::default(),
+        };
+        for scope in scopes {
+            let store = env.heap.borrow_mut().alloc();
+            env.stores.push(store);
+
+            let rebound = scope
+                .into_iter()
+                .map(|(key, value)| (key, rebind(value, &env)))
+                .collect();
+
+            **env.stores.last().unwrap().borrow_mut() = rebound;
+        }
+
+        if env.stores.is_empty() {
+            return Ok(Environment::default());
+        }
+
+        Ok(env)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn rebind(value: Object, env: &Environment) -> Object {
+    use crate::object::Function;
+    use indexmap::IndexMap;
+
+    match value {
+        Object::Function(f) => Object::Function(Shared::new(Function {
+            params: f.params.clone(),
+            body: f.body.clone(),
+            env: env.clone(),
+        })),
+        Object::Array(elements) => Object::array(
+            elements
+                .replace(Vec::new())
+                .into_iter()
+                .map(|element| rebind(element, env))
+                .collect(),
+        ),
+        Object::Dict(dict) => Object::dict(
+            dict.replace(IndexMap::new())
+                .into_iter()
+                .map(|(key, value)| (key, rebind(value, env)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Environment;
-    use crate::object::Object;
+    use crate::{
+        object::{Function, Object},
+        sync::Shared,
+    };
+    use belalang_core::{ast::BlockExpression, source::Span, symbol::Symbol, token::Token};
 
     #[test]
     fn set() {
         let mut env = Environment::default();
-        env.set(&String::from("name"), Object::Integer(10));
+        env.set("name".into(), Object::Integer(10));
 
         assert_eq!(env.stores.len(), 1);
         let last_store = env.stores.last().unwrap().borrow();
-        assert_eq!(*last_store.get("name").unwrap(), Object::Integer(10));
+        assert_eq!(
+            *last_store.get(&"name".into()).unwrap(),
+            Object::Integer(10)
+        );
     }
 
     #[test]
     fn get() {
         let mut env = Environment::default();
-        env.set(&"name".into(), Object::Integer(10));
+        env.set("name".into(), Object::Integer(10));
+
+        let value = env.get("name".into()).unwrap();
+        assert_eq!(value, Object::Integer(10));
+    }
+
+    #[test]
+    fn bindings() {
+        let mut env = Environment::default();
+        env.set("b".into(), Object::Integer(1));
+        env.set("a".into(), Object::Integer(2));
+
+        let mut inner = env.capture();
+        inner.set("c".into(), Object::Integer(3));
+
+        let names = |bindings: Vec<(Symbol, Object)>| {
+            bindings
+                .into_iter()
+                .map(|(k, _)| k.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(inner.locals()), ["c"]);
+        assert_eq!(names(inner.globals()), ["a", "b"]);
+        assert_eq!(names(inner.bindings()), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut env = Environment::default();
+        env.set("name".into(), Object::Integer(10));
+
+        let captured_env = env.capture();
+        let snapshot = env.snapshot();
+
+        env.set("name".into(), Object::Integer(1));
+        env.set("other".into(), Object::Integer(2));
+        env = env.capture();
+
+        env.restore(&snapshot);
+
+        assert_eq!(env.stores.len(), 1);
+        assert_eq!(env.get("name".into()).unwrap(), Object::Integer(10));
+        assert!(!env.has("other".into()));
+        assert_eq!(
+            captured_env.get("name".into()).unwrap(),
+            Object::Integer(10)
+        );
+    }
+
+    #[test]
+    fn collect_garbage() {
+        let env = Environment::default();
+
+        // A scope holding a closure over itself is a reference cycle.
+        let scope = env.capture();
+        let closure = Object::Function(Shared::new(Function {
+            params: Vec::new(),
+            body: BlockExpression {
+                token: Token::LeftBrace,
+                span: Span::default(),
+                statements: Vec::new(),
+            },
+            env: scope.clone(),
+        }));
+        scope.clone().set("f".into(), closure.clone());
+
+        let weak = Shared::downgrade(scope.stores.last().unwrap());
+        drop(scope);
+
+        assert_eq!(env.collect_garbage(&[&closure]), 0);
+        assert!(weak.upgrade().is_some());
+
+        drop(closure);
+        assert!(weak.upgrade().is_some());
 
-        let value = env.get(&String::from("name")).unwrap();
-        assert_eq!(*value, Object::Integer(10));
+        assert_eq!(env.collect_garbage(&[]), 1);
+        assert!(weak.upgrade().is_none());
     }
 
     #[test]
     fn capture() {
         let mut env = Environment::default();
-        env.set(&String::from("name"), Object::Integer(10));
+        env.set("name".into(), Object::Integer(10));
 
         let captured_env = env.capture();
-        env.set(&String::from("name"), Object::Integer(1));
+        env.set("name".into(), Object::Integer(1));
 
         assert_eq!(
-            *env.get(&"name".into()).unwrap(),
-            *captured_env.get(&"name".into()).unwrap()
+            env.get("name".into()).unwrap(),
+            captured_env.get("name".into()).unwrap()
         );
     }
 }
@@ -1,20 +1,100 @@
-use crate::object::Object;
-use belalang_core::token::Token;
+use crate::{
+    object::{Object, Type},
+    vm::StackTrace,
+};
+use belalang_core::{
+    diagnostic::Diagnostic, error::SyntaxError, source::Span, symbol::Symbol, token::Token,
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum EvaluatorError {
-    #[error("unknown operator: {0}{1}")]
+    #[error("unknown operator: {0}{}", .1.inspect())]
     UnknownPrefixOperator(Token, Object),
 
-    #[error("unknown operator: {0} {1} {2}")]
+    #[error("unknown operator: {} {1} {}", .0.inspect(), .2.inspect())]
     UnknownInfixOperator(Object, Token, Object),
 
     #[error("unknown variable: {0}")]
-    UnknownVariable(String),
+    UnknownVariable(Symbol),
 
     #[error("not a function")]
     NotAFunction,
 
+    #[error("unknown member: {0}")]
+    UnknownMember(Symbol),
+
+    #[error("wrong number of arguments: expected {0}, got {1}")]
+    WrongArgumentCount(usize, usize),
+
+    #[error("expected {0}, got {}", .1.type_of())]
+    UnexpectedType(&'static str, Object),
+
+    #[error("cannot convert {} to {1}", .0.inspect())]
+    InvalidConversion(Object, Type),
+
+    #[error("unhashable key: {} of type {}", .0.inspect(), .0.type_of())]
+    UnhashableKey(Object),
+
+    #[error("repeated string would be longer than {0} bytes")]
+    RepeatTooLong(usize),
+
+    #[error("index {0} out of bounds for length {1}")]
+    IndexOutOfBounds(i64, usize),
+
+    #[error("cannot modify a frozen value")]
+    FrozenValue,
+
+    #[error("invalid format string: {0}")]
+    InvalidFormat(String),
+
+    #[error("invalid {0}: {1}")]
+    InvalidData(&'static str, String),
+
+    #[error("invalid date: {0}")]
+    InvalidDate(String),
+
+    #[error("date out of range")]
+    DateOutOfRange,
+
+    #[error("integer overflow")]
+    IntegerOverflow,
+
+    #[error("division by zero")]
+    DivisionByZero,
+
+    #[error("empty range: {0} to {1}")]
+    EmptyRange(i64, i64),
+
+    #[error("range step cannot be zero")]
+    ZeroStep,
+
+    #[error("iterator is already running")]
+    IteratorRunning,
+
+    #[error("builtin {0} is already running")]
+    BuiltinRunning(String),
+
+    #[error("yield outside of a generator")]
+    UnexpectedYield,
+
+    #[error("script timed out")]
+    Timeout,
+
+    #[error("script ran out of fuel")]
+    OutOfFuel,
+
+    #[error("call depth limit of {0} exceeded")]
+    DepthExceeded(usize),
+
+    #[error("scope limit of {0} exceeded")]
+    ScopesExceeded(usize),
+
+    #[error("memory limit of {0} bytes exceeded")]
+    MemoryExceeded(usize),
+
+    #[error("stack overflow: more than {0} calls in progress")]
+    StackOverflow(usize, StackTrace),
+
     #[error("not an array")]
     NotAnArray,
 
@@ -22,8 +102,246 @@ pub enum EvaluatorError {
     OverwriteBuiltin(String),
 
     #[error("variable redeclaration: {0}")]
-    VariableRedeclaration(String),
+    VariableRedeclaration(Symbol),
+
+    #[error("{0}: {1}")]
+    File(String, #[source] std::io::Error),
+
+    #[error("{0} is not allowed here")]
+    NotAllowed(String),
+
+    #[error("unknown module: {0}")]
+    UnknownModule(String),
 
-    #[error("illegal returning value: {0}")]
+    #[error("import cycle: {}", .0.join(" -> "))]
+    ImportCycle(Vec<String>),
+
+    #[error("{0}: {1}")]
+    ModuleSyntax(String, #[source] Box<SyntaxError>),
+
+    #[cfg(feature = "tokio")]
+    #[error("cannot start async runtime: {0}")]
+    Runtime(#[source] std::io::Error),
+
+    #[cfg(feature = "tokio")]
+    #[error(
+        "cannot wait for an async builtin on a current-thread runtime outside of evaluate_async"
+    )]
+    BlockedRuntime,
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("illegal returning value: {}", .0.inspect())]
     ReturningValue(Object),
+
+    /// An error raised while evaluating the expression at the span.
+    #[error("{0}")]
+    At(Box<EvaluatorError>, Span),
+}
+
+/// Lets builtins registered with [`crate::builtins::Builtins::register_fn`]
+/// take `Object` arguments, whose conversion can't fail.
+impl From<std::convert::Infallible> for EvaluatorError {
+    fn from(err: std::convert::Infallible) -> Self {
+        match err {}
+    }
+}
+
+impl EvaluatorError {
+    /// Marks the error as raised at `span`, unless it already has a span or
+    /// is a `return` on its way out of a function.
+    pub(crate) fn at(self, span: Span) -> Self {
+        match self {
+            EvaluatorError::At(..) | EvaluatorError::ReturningValue(_) => self,
+            err => EvaluatorError::At(Box::new(err), span),
+        }
+    }
+
+    /// The error without the span it was raised at.
+    pub fn inner(&self) -> &EvaluatorError {
+        match self {
+            EvaluatorError::At(err, _) => err.inner(),
+            err => err,
+        }
+    }
+
+    /// Where the error was raised, if it came from an expression.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            EvaluatorError::At(_, span) => Some(*span),
+            _ => None,
+        }
+    }
+
+    /// See [`Diagnostic::code`].
+    pub fn code(&self) -> &'static str {
+        match self {
+            EvaluatorError::UnknownPrefixOperator(..)
+            | EvaluatorError::UnknownInfixOperator(..) => "unknown-operator",
+            EvaluatorError::UnknownVariable(_) => "unknown-variable",
+            EvaluatorError::NotAFunction => "not-a-function",
+            EvaluatorError::UnknownMember(_) => "unknown-member",
+            EvaluatorError::WrongArgumentCount(..) => "wrong-argument-count",
+            EvaluatorError::UnexpectedType(..) => "unexpected-type",
+            EvaluatorError::InvalidConversion(..) => "invalid-conversion",
+            EvaluatorError::UnhashableKey(_) => "unhashable-key",
+            EvaluatorError::RepeatTooLong(_) => "repeat-too-long",
+            EvaluatorError::IndexOutOfBounds(..) => "index-out-of-bounds",
+            EvaluatorError::FrozenValue => "frozen-value",
+            EvaluatorError::InvalidFormat(_) => "invalid-format",
+            EvaluatorError::InvalidData(..) => "invalid-data",
+            EvaluatorError::InvalidDate(_) => "invalid-date",
+            EvaluatorError::DateOutOfRange => "date-out-of-range",
+            EvaluatorError::IntegerOverflow => "integer-overflow",
+            EvaluatorError::DivisionByZero => "division-by-zero",
+            EvaluatorError::EmptyRange(..) => "empty-range",
+            EvaluatorError::ZeroStep => "zero-step",
+            EvaluatorError::IteratorRunning => "iterator-running",
+            EvaluatorError::BuiltinRunning(_) => "builtin-running",
+            EvaluatorError::UnexpectedYield => "unexpected-yield",
+            EvaluatorError::Timeout => "timeout",
+            EvaluatorError::OutOfFuel => "out-of-fuel",
+            EvaluatorError::DepthExceeded(_) => "depth-exceeded",
+            EvaluatorError::ScopesExceeded(_) => "scopes-exceeded",
+            EvaluatorError::MemoryExceeded(_) => "memory-exceeded",
+            EvaluatorError::StackOverflow(..) => "stack-overflow",
+            EvaluatorError::NotAnArray => "not-an-array",
+            EvaluatorError::OverwriteBuiltin(_) => "overwrite-builtin",
+            EvaluatorError::VariableRedeclaration(_) => "variable-redeclaration",
+            EvaluatorError::File(..) => "file",
+            EvaluatorError::NotAllowed(_) => "not-allowed",
+            EvaluatorError::UnknownModule(_) => "unknown-module",
+            EvaluatorError::ImportCycle(_) => "import-cycle",
+            EvaluatorError::ModuleSyntax(_, err) => err.code(),
+            #[cfg(feature = "tokio")]
+            EvaluatorError::Runtime(_) => "async-runtime",
+            #[cfg(feature = "tokio")]
+            EvaluatorError::BlockedRuntime => "async-blocked",
+            EvaluatorError::Io(_) => "io",
+            EvaluatorError::ReturningValue(_) => "illegal-return",
+            EvaluatorError::At(err, _) => err.code(),
+        }
+    }
+}
+
+impl From<&EvaluatorError> for Diagnostic {
+    fn from(err: &EvaluatorError) -> Self {
+        match err {
+            EvaluatorError::ModuleSyntax(file, err) => {
+                Diagnostic::from(&**err).with_note(format!("in module {file}"))
+            }
+            EvaluatorError::StackOverflow(_, trace) => trace.to_string().lines().fold(
+                Diagnostic::error(err.code(), err.to_string()),
+                |diagnostic, line| diagnostic.with_note(line),
+            ),
+            EvaluatorError::At(err, span) => {
+                let diagnostic = Diagnostic::from(&**err);
+                match diagnostic.span {
+                    Some(_) => diagnostic,
+                    None => diagnostic.with_span(*span),
+                }
+            }
+            err => Diagnostic::error(err.code(), err.to_string()),
+        }
+    }
+}
+
+/// What [`crate::compiler::Compiler`] fails with.
+#[derive(thiserror::Error, Debug)]
+pub enum CompileError {
+    #[error("{0} cannot be compiled")]
+    Unsupported(&'static str),
+
+    #[error("more than {} constants", u16::MAX)]
+    TooManyConstants,
+
+    #[error("function is longer than {} bytes of code", u16::MAX)]
+    TooLong,
+
+    #[error("more than {} {0}", u16::MAX)]
+    TooMany(&'static str),
+}
+
+impl CompileError {
+    /// See [`Diagnostic::code`].
+    pub fn code(&self) -> &'static str {
+        match self {
+            CompileError::Unsupported(_) => "unsupported",
+            CompileError::TooManyConstants => "too-many-constants",
+            CompileError::TooLong => "code-too-long",
+            CompileError::TooMany(_) => "too-many-operands",
+        }
+    }
+}
+
+impl From<&CompileError> for Diagnostic {
+    fn from(err: &CompileError) -> Self {
+        Diagnostic::error(err.code(), err.to_string())
+    }
+}
+
+/// What [`crate::bytecode::Bytecode::load`] fails with.
+#[derive(thiserror::Error, Debug)]
+pub enum LoadError {
+    #[error("not a compiled belalang file")]
+    NotBytecode,
+
+    #[error(
+        "compiled for version {0} of the bytecode, expected {}",
+        crate::bytecode::file::VERSION
+    )]
+    Version(u16),
+
+    #[error("compiled file is truncated")]
+    Truncated,
+
+    #[error("invalid compiled file: {0}")]
+    Invalid(String),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// What [`crate::interpreter::Interpreter::eval_str`] fails with.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Syntax(Box<SyntaxError>),
+
+    #[error(transparent)]
+    Compile(#[from] CompileError),
+
+    #[error(transparent)]
+    Evaluator(#[from] EvaluatorError),
+}
+
+impl From<&Error> for Diagnostic {
+    fn from(err: &Error) -> Self {
+        match err {
+            Error::Syntax(err) => Diagnostic::from(&**err),
+            Error::Compile(err) => Diagnostic::from(err),
+            Error::Evaluator(err) => Diagnostic::from(err),
+        }
+    }
+}
+
+impl From<SyntaxError> for Error {
+    fn from(err: SyntaxError) -> Self {
+        Self::Syntax(Box::new(err))
+    }
+}
+
+/// What [`crate::builtins::Builtins::load_plugin`] fails with.
+#[cfg(feature = "plugins")]
+#[derive(thiserror::Error, Debug)]
+pub enum PluginError {
+    #[error("cannot load plugin: {0}")]
+    Load(#[from] libloading::Error),
+
+    #[error(
+        "plugin was built for belalang {0}, expected {}",
+        crate::plugin::VERSION
+    )]
+    Version(String),
 }
@@ -1,13 +1,80 @@
-use crate::{builtins::Builtins, environment::Environment, error::EvaluatorError, object::Object};
+use crate::{
+    builtins::Builtins,
+    environment::{self, Environment},
+    error::EvaluatorError,
+    gc,
+    generator::Generator,
+    hooks::{BoxedHooks, Hooks},
+    iterator::{iterate, Iter},
+    metrics::{Limits, Metrics},
+    module::{self, ParsedModules},
+    object::{operator_hook, Function, HashKey, Object, Type, DICT_SLOT, INDEX_HOOK, STR_HOOK},
+    random::Rng,
+    sandbox::Capability,
+    sync::{Locked, Shareable, Shared, Weak},
+    vm::{self, Vm},
+};
 use belalang_core::{
-    ast::{BlockExpression, Expression, Node, Program, Statement},
+    ast::{BlockExpression, Expression, ImportExpression, Node, Program, Statement},
+    symbol::Symbol,
     token::Token,
 };
+use indexmap::IndexMap;
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
 #[derive(Default)]
 pub struct Evaluator {
     env: Environment,
     builtins: Builtins,
+    rng: Rng,
+    deadline: Option<Instant>,
+    /// The script being evaluated, imports are relative to it.
+    file: Option<PathBuf>,
+    /// Evaluated modules by canonical path, see [`crate::module`].
+    modules: HashMap<PathBuf, Object>,
+    parsed_modules: ParsedModules,
+    /// Modules being evaluated, innermost last.
+    importing: Vec<PathBuf>,
+    handles: Vec<Weak<Object>>,
+    metrics: Option<Box<Metrics>>,
+    limits: Limits,
+    /// Steps taken since the limits were set.
+    fuel_used: u64,
+    /// Bytes counted towards [`Limits::memory`] since the limits were set.
+    memory_used: usize,
+    /// Script function calls in progress.
+    depth: usize,
+    gc: gc::Thresholds,
+    hooks: Option<BoxedHooks>,
+    /// The values of the VMs running on the evaluator.
+    stack: vm::Stack,
+    /// Whether it runs on a blocking thread, see [`Evaluator::evaluate_async`].
+    #[cfg(feature = "tokio")]
+    blocking: bool,
+}
+
+/// Saved evaluator state, see [`Evaluator::snapshot`].
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    env: environment::Snapshot,
+    modules: HashMap<PathBuf, Object>,
+    rng: Rng,
+}
+
+/// An object held by the host, see [`Evaluator::handle`].
+#[derive(Debug, Clone)]
+pub struct Handle(Shared<Object>);
+
+impl Handle {
+    pub fn object(&self) -> &Object {
+        &self.0
+    }
 }
 
 impl Evaluator {
@@ -15,7 +82,279 @@ impl Evaluator {
         Self {
             builtins,
             env: Environment::default(),
+            rng: Rng::default(),
+            deadline: None,
+            file: None,
+            modules: HashMap::new(),
+            parsed_modules: ParsedModules::default(),
+            importing: Vec::new(),
+            handles: Vec::new(),
+            metrics: None,
+            limits: Limits::default(),
+            fuel_used: 0,
+            memory_used: 0,
+            depth: 0,
+            gc: gc::Thresholds::default(),
+            hooks: None,
+            stack: vm::Stack::default(),
+            #[cfg(feature = "tokio")]
+            blocking: false,
+        }
+    }
+
+    pub fn builtins(&self) -> &Builtins {
+        &self.builtins
+    }
+
+    /// Redirects the output of a running evaluator, see
+    /// [`Builtins::set_stdout`].
+    pub fn set_stdout<W: Write + Shareable + 'static>(&mut self, out: Shared<Locked<W>>) {
+        self.builtins.set_stdout(out);
+    }
+
+    /// See [`Builtins::set_stderr`].
+    pub fn set_stderr<W: Write + Shareable + 'static>(&mut self, out: Shared<Locked<W>>) {
+        self.builtins.set_stderr(out);
+    }
+
+    /// The generator used by the random builtins. Replace it with a seeded
+    /// one, see [`Rng::seeded`], to make runs reproducible.
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// Makes evaluation fail with [`EvaluatorError::Timeout`] once `timeout`
+    /// has passed from now. The time is checked on every loop iteration and
+    /// function call. `None` removes the limit.
+    ///
+    /// There is no clock on wasm32-unknown-unknown, so there this does
+    /// nothing.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+            return;
+        }
+
+        self.deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub(crate) fn check_deadline(&self) -> Result<(), EvaluatorError> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(EvaluatorError::Timeout),
+            _ => Ok(()),
+        }
+    }
+
+    /// Starts recording [`Metrics`] from zero, or stops recording them.
+    /// Recording costs a little on every step, so it's off by default.
+    pub fn set_metrics(&mut self, enabled: bool) {
+        self.metrics = enabled.then(Box::default);
+        self.env.reset_metrics();
+    }
+
+    /// What was used since [`Evaluator::set_metrics`] enabled recording, or
+    /// `None` if it's disabled.
+    pub fn metrics(&self) -> Option<Metrics> {
+        let heap = self.env.metrics();
+
+        self.metrics.as_deref().map(|metrics| Metrics {
+            allocations: heap.allocations,
+            peak_scopes: heap.peak_scopes,
+            collections: heap.collections,
+            ..metrics.clone()
+        })
+    }
+
+    /// Sets what scripts may use from now on, with a full tank of fuel.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+        self.fuel_used = 0;
+        self.memory_used = 0;
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Script function calls in progress, by either backend.
+    pub(crate) fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub(crate) fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+    }
+
+    pub(crate) fn count_step(&mut self) -> Result<(), EvaluatorError> {
+        if let Some(metrics) = &mut self.metrics {
+            metrics.steps += 1;
+        }
+
+        if let Some(fuel) = self.limits.fuel {
+            if self.fuel_used >= fuel {
+                return Err(EvaluatorError::OutOfFuel);
+            }
+
+            self.fuel_used += 1;
         }
+
+        Ok(())
+    }
+
+    /// Counts `bytes` of strings or collection slots a script created, which
+    /// fails once they add up to more than [`Limits::memory`]. Builtins that
+    /// build values count them, see [`Evaluator::allocated`].
+    pub fn count_memory(&mut self, bytes: usize) -> Result<(), EvaluatorError> {
+        if let Some(metrics) = &mut self.metrics {
+            metrics.memory += bytes as u64;
+        }
+
+        if let Some(memory) = self.limits.memory {
+            self.memory_used = self.memory_used.saturating_add(bytes);
+
+            if self.memory_used > memory {
+                return Err(EvaluatorError::MemoryExceeded(memory));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Counts the [`Object::size`] of a value just created and returns it.
+    pub fn allocated(&mut self, object: Object) -> Result<Object, EvaluatorError> {
+        self.count_memory(object.size())?;
+        Ok(object)
+    }
+
+    /// Shares the parsed module files of `parsed_modules` with other
+    /// evaluators, see [`ParsedModules`].
+    pub fn set_parsed_modules(&mut self, parsed_modules: ParsedModules) {
+        self.parsed_modules = parsed_modules;
+    }
+
+    /// Sets the callbacks to invoke while evaluating, replacing any earlier
+    /// ones, see [`Hooks`].
+    pub fn set_hooks<H: Hooks + Shareable + 'static>(&mut self, hooks: H) {
+        self.hooks = Some(Box::new(hooks));
+    }
+
+    /// Removes the hooks, returning them.
+    pub fn take_hooks(&mut self) -> Option<BoxedHooks> {
+        self.hooks.take()
+    }
+
+    /// The hooks, for the VM to call.
+    pub(crate) fn hooks_mut(&mut self) -> Option<&mut BoxedHooks> {
+        self.hooks.as_mut()
+    }
+
+    /// Binds `ARGS`, an array of the arguments a script was run with, and
+    /// `SCRIPT_PATH`, the path of the script or null, in the current scope.
+    /// Call this before evaluating the script, while that's the global one.
+    pub fn set_script_args<S: AsRef<str>>(
+        &mut self,
+        path: Option<&str>,
+        args: impl IntoIterator<Item = S>,
+    ) {
+        let args = args
+            .into_iter()
+            .map(|arg| Object::String(arg.as_ref().into()))
+            .collect();
+
+        self.file = path.map(PathBuf::from);
+        self.env.set("ARGS".into(), Object::array(args));
+        self.env.set(
+            "SCRIPT_PATH".into(),
+            path.map_or(Object::Null, |path| Object::String(path.into())),
+        );
+    }
+
+    pub fn env(&self) -> &Environment {
+        &self.env
+    }
+
+    pub fn env_mut(&mut self) -> &mut Environment {
+        &mut self.env
+    }
+
+    pub(crate) fn stack_mut(&mut self) -> &mut vm::Stack {
+        &mut self.stack
+    }
+
+    /// Frees scopes that are unreachable from the environment but kept
+    /// alive by closures referencing them, and the unreachable objects the
+    /// VM allocated, see [`Environment::collect_garbage`]. Returns the
+    /// number of scopes and objects freed.
+    pub fn collect_garbage(&mut self) -> usize {
+        self.collect_garbage_in(&[])
+    }
+
+    /// Like [`Evaluator::collect_garbage`], with `scopes` reachable too.
+    pub(crate) fn collect_garbage_in(&mut self, scopes: &[&Environment]) -> usize {
+        let handles = self.live_handles();
+        let roots = self
+            .modules
+            .values()
+            .chain(self.builtins.constants())
+            .chain(handles.iter().map(|handle| &**handle))
+            .chain(self.stack.values())
+            .collect::<Vec<_>>();
+        self.env.collect_garbage_in(&roots, scopes)
+    }
+
+    /// Sets when the VM collects garbage on its own, see [`gc`].
+    pub fn set_gc_thresholds(&mut self, thresholds: gc::Thresholds) {
+        self.gc = thresholds;
+    }
+
+    pub fn gc_thresholds(&self) -> gc::Thresholds {
+        self.gc
+    }
+
+    fn live_handles(&mut self) -> Vec<Shared<Object>> {
+        self.handles.retain(|handle| handle.strong_count() > 0);
+        self.handles.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    /// Saves the state scripts can change: variables, including those
+    /// captured by closures, the contents of arrays and dicts, evaluated
+    /// modules and the random generator. See
+    /// [`Environment::snapshot_reachable`] for what isn't saved.
+    pub fn snapshot(&mut self) -> Snapshot {
+        let handles = self.live_handles();
+        let roots = self
+            .modules
+            .values()
+            .chain(handles.iter().map(|handle| &**handle))
+            .collect::<Vec<_>>();
+
+        Snapshot {
+            env: self.env.snapshot_reachable(&roots),
+            modules: self.modules.clone(),
+            rng: self.rng.clone(),
+        }
+    }
+
+    /// Rolls back to a snapshot, e.g. after speculative code failed.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.env.restore(&snapshot.env);
+        self.modules = snapshot.modules.clone();
+        self.rng = snapshot.rng.clone();
+    }
+
+    /// Keeps `object` alive through garbage collection for as long as the
+    /// returned handle is, e.g. a script function the host calls later.
+    pub fn handle(&mut self, object: Object) -> Handle {
+        if self.handles.len() == self.handles.capacity() {
+            self.handles.retain(|handle| handle.strong_count() > 0);
+        }
+
+        let handle = Shared::new(object);
+        self.handles.push(Shared::downgrade(&handle));
+        Handle(handle)
     }
 
     pub fn eval(&mut self, node: Node) -> Result<Object, EvaluatorError> {
@@ -27,152 +366,147 @@ impl Evaluator {
     }
 
     pub fn eval_program(&mut self, program: Program) -> Result<Object, EvaluatorError> {
+        self.feed(program)
+    }
+
+    /// Evaluates more statements in the current environment, e.g. each line
+    /// of a REPL or each cell of a notebook, and returns the value of the
+    /// last one. Statements stop at the first error, but what the ones
+    /// before it did is kept, so the host can carry on after reporting it.
+    pub fn feed<I>(&mut self, statements: I) -> Result<Object, EvaluatorError>
+    where
+        I: IntoIterator<Item = Statement>,
+    {
         let mut result: Object = Object::Null;
 
-        for statement in program.statements {
-            result = self.eval_statement(statement)?;
+        for statement in statements {
+            match self.eval_statement(statement) {
+                Ok(value) => result = value,
+                Err(err) => {
+                    // Errors in a module are reported by the importing
+                    // program.
+                    if let (Some(hooks), true) = (&mut self.hooks, self.importing.is_empty()) {
+                        hooks.on_error(&err);
+                    }
+
+                    return Err(err);
+                }
+            }
         }
 
         Ok(result)
     }
 
+    /// Evaluates `program` on a blocking thread of the current tokio runtime,
+    /// so that scripts calling async builtins don't stall other tasks.
+    /// Panics outside of a runtime. If the returned future is dropped before
+    /// it completes, the evaluator is left in its default state.
+    #[cfg(feature = "tokio")]
+    pub async fn evaluate_async(&mut self, program: Program) -> Result<Object, EvaluatorError> {
+        let mut evaluator = std::mem::take(self);
+        evaluator.blocking = true;
+
+        let (mut evaluator, result) = match tokio::task::spawn_blocking(move || {
+            let result = evaluator.eval_program(program);
+            (evaluator, result)
+        })
+        .await
+        {
+            Ok(done) => done,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        };
+
+        evaluator.blocking = false;
+        *self = evaluator;
+        result
+    }
+
+    /// Whether it runs on a blocking thread of a tokio runtime, where async
+    /// builtins can wait for their futures.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn is_blocking(&self) -> bool {
+        self.blocking
+    }
+
     pub fn eval_expression(&mut self, expression: Expression) -> Result<Object, EvaluatorError> {
+        let span = expression.span();
+        self.eval_expression_at(expression)
+            .map_err(|err| err.at(span))
+    }
+
+    fn eval_expression_at(&mut self, expression: Expression) -> Result<Object, EvaluatorError> {
+        self.count_step()?;
+
         match expression {
             Expression::Integer(int_lit) => Ok(Object::Integer(int_lit.value)),
             Expression::Float(float_lit) => Ok(Object::Float(float_lit.value)),
             Expression::Boolean(bool_expr) => Ok(Object::Boolean(bool_expr.value)),
-            Expression::String(s) => Ok(Object::String(s.value)),
+            Expression::String(s) => Ok(Object::String(s.value.into())),
             Expression::Null(_) => Ok(Object::Null),
-            Expression::Array(arr) => Ok(Object::Array(
-                arr.elements
+            Expression::Array(arr) => {
+                let elements = arr
+                    .elements
                     .into_iter()
                     .map(|el| self.eval_expression(el))
-                    .collect::<Result<Vec<_>, _>>()?,
-            )),
-            Expression::Index(idx) => {
-                let left = self.eval_expression(*idx.left)?;
-                let index = self.eval_expression(*idx.index)?;
+                    .collect::<Result<Vec<_>, _>>()?;
 
-                if let (Object::Array(objs), Object::Integer(idx)) = (left, index) {
-                    Ok(objs.get(idx as usize).unwrap_or(&Object::Null).clone())
-                } else {
-                    Err(EvaluatorError::NotAnArray)
+                self.allocated(Object::array(elements))
+            }
+            Expression::Dict(dict) => {
+                let mut pairs = IndexMap::with_capacity(dict.pairs.len());
+
+                for (key, value) in dict.pairs {
+                    let key = HashKey::try_from(self.eval_expression(key)?)?;
+                    let value = self.eval_expression(value)?;
+                    pairs.insert(key, value);
                 }
+
+                self.allocated(Object::dict(pairs))
+            }
+            Expression::Index(_) | Expression::Member(_) | Expression::Call(_) => {
+                Ok(self.eval_chain(expression)?.unwrap_or(Object::Null))
+            }
+            Expression::IndexAssign(assign) => {
+                let left = self.eval_expression(*assign.left)?;
+                let index = self.eval_expression(*assign.index)?;
+                let value = self.eval_expression(*assign.value)?;
+                index_assign(self, &left, index, value)
             }
             Expression::Var(var) => match var.token {
                 Token::ColonAssign => {
-                    let name = &var.name.value;
-
-                    if self.env.has_here(name) {
-                        return Err(EvaluatorError::VariableRedeclaration(name.clone()));
-                    }
-
-                    if self.builtins.has_fn(name) {
-                        return Err(EvaluatorError::OverwriteBuiltin(name.to_string()));
-                    }
+                    let name = var.name.value;
+                    self.check_declare(name)?;
 
                     let value = self.eval_expression(*var.value)?;
-                    self.env.set(&var.name.value, value.clone());
+                    self.env.declare(name, value.clone());
                     Ok(value)
                 }
                 Token::Assign => {
-                    let name = &var.name.value;
-
-                    if self.builtins.has_fn(name) {
-                        return Err(EvaluatorError::OverwriteBuiltin(name.to_string()));
-                    }
-
-                    if !self.env.has(name) {
-                        return Err(EvaluatorError::UnknownVariable(name.clone()));
-                    }
+                    let name = var.name.value;
+                    self.check_assign(name)?;
 
                     let value = self.eval_expression(*var.value)?;
-                    self.env.set(&var.name.value, value.clone());
+                    self.env.set(name, value.clone());
                     Ok(value)
                 }
                 _ => unreachable!(),
             },
             Expression::Prefix(node) => {
                 let right = self.eval_expression(*node.right)?;
-
-                match node.operator {
-                    Token::Not => match right {
-                        Object::Boolean(value) => Ok(Object::Boolean(!value)),
-                        _ => Err(EvaluatorError::UnknownPrefixOperator(node.operator, right)),
-                    },
-                    Token::Sub => match right {
-                        Object::Integer(value) => Ok(Object::Integer(-value)),
-                        _ => Err(EvaluatorError::UnknownPrefixOperator(node.operator, right)),
-                    },
-                    _ => Err(EvaluatorError::UnknownPrefixOperator(node.operator, right)),
-                }
+                self.prefix(&node.operator, right)
             }
             Expression::Infix(infix_expr) => {
                 let left = self.eval_expression(*infix_expr.left)?;
-                let right = self.eval_expression(*infix_expr.right)?;
 
-                match (&left, &right) {
-                    (Object::Integer(l), Object::Integer(r)) => match infix_expr.operator {
-                        Token::Add => Ok(Object::Integer(l + r)),
-                        Token::Sub => Ok(Object::Integer(l - r)),
-                        Token::Mul => Ok(Object::Integer(l * r)),
-                        Token::Div => Ok(Object::Integer(l / r)),
-                        Token::Mod => Ok(Object::Integer(l % r)),
-                        Token::Lt => Ok(Object::Boolean(l < r)),
-                        Token::Le => Ok(Object::Boolean(l <= r)),
-                        Token::Gt => Ok(Object::Boolean(l > r)),
-                        Token::Ge => Ok(Object::Boolean(l >= r)),
-                        Token::Eq => Ok(Object::Boolean(l == r)),
-                        Token::Ne => Ok(Object::Boolean(l != r)),
-                        _ => Err(EvaluatorError::UnknownInfixOperator(
-                            left,
-                            infix_expr.operator,
-                            right,
-                        )),
-                    },
-                    (Object::Float(l), Object::Float(r)) => match infix_expr.operator {
-                        Token::Add => Ok(Object::Float(l + r)),
-                        Token::Sub => Ok(Object::Float(l - r)),
-                        Token::Mul => Ok(Object::Float(l * r)),
-                        Token::Div => Ok(Object::Float(l / r)),
-                        Token::Mod => Ok(Object::Float(l % r)),
-                        Token::Lt => Ok(Object::Boolean(l < r)),
-                        Token::Le => Ok(Object::Boolean(l <= r)),
-                        Token::Gt => Ok(Object::Boolean(l > r)),
-                        Token::Ge => Ok(Object::Boolean(l >= r)),
-                        Token::Eq => Ok(Object::Boolean(l == r)),
-                        Token::Ne => Ok(Object::Boolean(l != r)),
-                        _ => Err(EvaluatorError::UnknownInfixOperator(
-                            left,
-                            infix_expr.operator,
-                            right,
-                        )),
-                    },
-                    (Object::Boolean(l), Object::Boolean(r)) => match infix_expr.operator {
-                        Token::And => Ok(Object::Boolean(*l && *r)),
-                        Token::Or => Ok(Object::Boolean(*l || *r)),
-                        Token::Eq => Ok(Object::Boolean(*l == *r)),
-                        Token::Ne => Ok(Object::Boolean(*l != *r)),
-                        _ => Err(EvaluatorError::UnknownInfixOperator(
-                            left,
-                            infix_expr.operator,
-                            right,
-                        )),
-                    },
-                    (Object::String(l), Object::String(r)) => match infix_expr.operator {
-                        Token::Add => Ok(Object::String(format!("{l}{r}"))),
-                        _ => Err(EvaluatorError::UnknownInfixOperator(
-                            left,
-                            infix_expr.operator,
-                            right,
-                        )),
-                    },
-                    (_, _) => Err(EvaluatorError::UnknownInfixOperator(
-                        left,
-                        infix_expr.operator,
-                        right,
-                    )),
+                if infix_expr.operator == Token::NullCoalesce {
+                    return match left {
+                        Object::Null => self.eval_expression(*infix_expr.right),
+                        left => Ok(left),
+                    };
                 }
+
+                let right = self.eval_expression(*infix_expr.right)?;
+                self.infix(&infix_expr.operator, left, right)
             }
             Expression::If(expr) => {
                 let condition = self.eval_expression(*expr.condition)?;
@@ -187,79 +521,592 @@ impl Evaluator {
 
                 Ok(Object::Null)
             }
+            Expression::Function(fn_lit) => Ok(Object::Function(Shared::new(Function {
+                params: fn_lit.params,
+                body: fn_lit.body,
+                env: self.env.clone(),
+            }))),
+            Expression::Identifier(ident) => self.lookup(ident.value),
+            Expression::Block(block) => self.eval_block(block, self.env.capture()),
+            Expression::Import(import) => self.eval_import(import).map(|(_, module)| module),
+        }
+    }
+
+    /// What a name refers to in the current scope: a variable, a builtin
+    /// function or constant, or a type.
+    pub fn lookup(&self, name: Symbol) -> Result<Object, EvaluatorError> {
+        if let Some(value) = self.env.get(name) {
+            return Ok(value);
+        }
+
+        if self.builtins.has_fn(name.as_str()) {
+            return Ok(Object::Builtin(name.as_str().to_string()));
+        }
+
+        if let Some(value) = self.builtins.constant(name.as_str()) {
+            return Ok(value);
+        }
+
+        Type::from_name(name.as_str())
+            .map(Object::Type)
+            .ok_or(EvaluatorError::UnknownVariable(name))
+    }
+
+    /// Evaluates a module, see [`crate::module`]. Returns the file it was
+    /// loaded from along with its namespace.
+    fn eval_import(
+        &mut self,
+        import: ImportExpression,
+    ) -> Result<(PathBuf, Object), EvaluatorError> {
+        let path = self.eval_expression(*import.path)?;
+        self.import(path)
+    }
+
+    /// Loads the module at an evaluated import path.
+    pub(crate) fn import(&mut self, path: Object) -> Result<(PathBuf, Object), EvaluatorError> {
+        let path = match path {
+            Object::String(path) => path,
+            path => return Err(EvaluatorError::UnexpectedType("String", path)),
+        };
+
+        if let Some(name) = path.strip_prefix("std/") {
+            let module = self.builtins.namespace(name);
+            let module = module.ok_or_else(|| EvaluatorError::UnknownModule(path.to_string()))?;
+            return Ok((PathBuf::from(&*path), module));
+        }
+
+        if !self.builtins.profile().allows(Capability::FilesystemRead) {
+            return Err(EvaluatorError::NotAllowed(format!("import {path:?}")));
+        }
+
+        let importer = self.importing.last().or(self.file.as_ref());
+        let file = module::resolve(importer.map(PathBuf::as_path), &path);
+        let file = fs::canonicalize(&file)
+            .map_err(|err| EvaluatorError::File(file.display().to_string(), err))?;
+
+        if let Some(module) = self.modules.get(&file) {
+            return Ok((file, module.clone()));
+        }
+
+        if let Some(start) = self.importing.iter().position(|f| *f == file) {
+            let cycle = self.importing[start..]
+                .iter()
+                .chain([&file])
+                .map(|f| f.display().to_string())
+                .collect();
+
+            return Err(EvaluatorError::ImportCycle(cycle));
+        }
+
+        let statements = self.parsed_modules.get(&file)?;
+
+        let env = self.env.fresh();
+        self.importing.push(file.clone());
+        let result = self.with_env(env.clone(), |ev| ev.feed(statements.iter().cloned()));
+        self.importing.pop();
+        result?;
+
+        let module = module::namespace(&env);
+        self.modules.insert(file.clone(), module.clone());
+        Ok((file, module))
+    }
+
+    /// Checks that `name := ...` may declare a variable in the current scope.
+    pub(crate) fn check_declare(&self, name: Symbol) -> Result<(), EvaluatorError> {
+        if self.env.has_here(name) {
+            return Err(EvaluatorError::VariableRedeclaration(name));
+        }
+
+        if self.is_reserved(name.as_str()) {
+            return Err(EvaluatorError::OverwriteBuiltin(name.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `name = ...` may assign to an existing variable.
+    pub(crate) fn check_assign(&self, name: Symbol) -> Result<(), EvaluatorError> {
+        if self.is_reserved(name.as_str()) {
+            return Err(EvaluatorError::OverwriteBuiltin(name.to_string()));
+        }
+
+        if !self.env.has(name) {
+            return Err(EvaluatorError::UnknownVariable(name));
+        }
+
+        Ok(())
+    }
+
+    /// Applies a binary operator to evaluated operands, calling the left
+    /// operand's hook if it has one. `??` short-circuits, so it is handled
+    /// by the callers.
+    pub(crate) fn infix(
+        &mut self,
+        operator: &Token,
+        left: Object,
+        right: Object,
+    ) -> Result<Object, EvaluatorError> {
+        if let Some(hook) = operator_hook(operator).and_then(|h| left.member(h)) {
+            let result = self.call_function(hook, vec![left, right])?;
+
+            return match (operator, result) {
+                (Token::Ne, Object::Boolean(b)) => Ok(Object::Boolean(!b)),
+                (_, result) => Ok(result),
+            };
+        }
+
+        if *operator == Token::In {
+            return contains(&right, left).map(Object::Boolean);
+        }
+
+        if *operator == Token::Is {
+            return match right {
+                Object::Type(t) => Ok(Object::Boolean(left.type_of() == t)),
+                _ => Err(EvaluatorError::UnexpectedType("Type", right)),
+            };
+        }
+
+        match (&left, &right) {
+            (Object::Integer(_), Object::Integer(0))
+                if matches!(operator, Token::Div | Token::Mod) =>
+            {
+                Err(EvaluatorError::DivisionByZero)
+            }
+            (Object::Integer(l), Object::Integer(r)) => match operator {
+                Token::Add => checked(l.checked_add(*r)),
+                Token::Sub => checked(l.checked_sub(*r)),
+                Token::Mul => checked(l.checked_mul(*r)),
+                Token::Div => checked(l.checked_div(*r)),
+                Token::Mod => checked(l.checked_rem(*r)),
+                Token::Lt => Ok(Object::Boolean(l < r)),
+                Token::Le => Ok(Object::Boolean(l <= r)),
+                Token::Gt => Ok(Object::Boolean(l > r)),
+                Token::Ge => Ok(Object::Boolean(l >= r)),
+                Token::Eq => Ok(Object::Boolean(l == r)),
+                Token::Ne => Ok(Object::Boolean(l != r)),
+                _ => Err(EvaluatorError::UnknownInfixOperator(
+                    left,
+                    operator.clone(),
+                    right,
+                )),
+            },
+            (Object::Float(l), Object::Float(r)) => match operator {
+                Token::Add => Ok(Object::Float(l + r)),
+                Token::Sub => Ok(Object::Float(l - r)),
+                Token::Mul => Ok(Object::Float(l * r)),
+                Token::Div => Ok(Object::Float(l / r)),
+                Token::Mod => Ok(Object::Float(l % r)),
+                Token::Lt => Ok(Object::Boolean(l < r)),
+                Token::Le => Ok(Object::Boolean(l <= r)),
+                Token::Gt => Ok(Object::Boolean(l > r)),
+                Token::Ge => Ok(Object::Boolean(l >= r)),
+                Token::Eq => Ok(Object::Boolean(l == r)),
+                Token::Ne => Ok(Object::Boolean(l != r)),
+                _ => Err(EvaluatorError::UnknownInfixOperator(
+                    left,
+                    operator.clone(),
+                    right,
+                )),
+            },
+            (Object::Boolean(l), Object::Boolean(r)) => match operator {
+                Token::And => Ok(Object::Boolean(*l && *r)),
+                Token::Or => Ok(Object::Boolean(*l || *r)),
+                Token::Eq => Ok(Object::Boolean(*l == *r)),
+                Token::Ne => Ok(Object::Boolean(*l != *r)),
+                _ => Err(EvaluatorError::UnknownInfixOperator(
+                    left,
+                    operator.clone(),
+                    right,
+                )),
+            },
+            // Strings compare byte by byte, which for UTF-8 is the
+            // same as comparing code point by code point.
+            (Object::String(l), Object::String(r)) => match operator {
+                Token::Add => {
+                    self.count_memory(l.len() + r.len())?;
+                    Ok(Object::String(format!("{l}{r}").into()))
+                }
+                Token::Lt => Ok(Object::Boolean(l < r)),
+                Token::Le => Ok(Object::Boolean(l <= r)),
+                Token::Gt => Ok(Object::Boolean(l > r)),
+                Token::Ge => Ok(Object::Boolean(l >= r)),
+                Token::Eq => Ok(Object::Boolean(l == r)),
+                Token::Ne => Ok(Object::Boolean(l != r)),
+                _ => Err(EvaluatorError::UnknownInfixOperator(
+                    left,
+                    operator.clone(),
+                    right,
+                )),
+            },
+            (Object::String(s), Object::Integer(n)) | (Object::Integer(n), Object::String(s))
+                if *operator == Token::Mul =>
+            {
+                let repeated = repeat(s, *n)?;
+                self.allocated(Object::String(repeated))
+            }
+            (Object::Type(_), Object::Type(_))
+            | (Object::Native(_), _)
+            | (_, Object::Native(_)) => match operator {
+                Token::Eq => Ok(Object::Boolean(left == right)),
+                Token::Ne => Ok(Object::Boolean(left != right)),
+                _ => Err(EvaluatorError::UnknownInfixOperator(
+                    left,
+                    operator.clone(),
+                    right,
+                )),
+            },
+            (_, _) => Err(EvaluatorError::UnknownInfixOperator(
+                left,
+                operator.clone(),
+                right,
+            )),
+        }
+    }
+
+    pub(crate) fn prefix(
+        &mut self,
+        operator: &Token,
+        right: Object,
+    ) -> Result<Object, EvaluatorError> {
+        match operator {
+            Token::Not => match right {
+                Object::Boolean(value) => Ok(Object::Boolean(!value)),
+                _ => Err(EvaluatorError::UnknownPrefixOperator(
+                    operator.clone(),
+                    right,
+                )),
+            },
+            Token::Sub => match right {
+                Object::Integer(value) => checked(value.checked_neg()),
+                Object::Float(value) => Ok(Object::Float(-value)),
+                _ => Err(EvaluatorError::UnknownPrefixOperator(
+                    operator.clone(),
+                    right,
+                )),
+            },
+            _ => Err(EvaluatorError::UnknownPrefixOperator(
+                operator.clone(),
+                right,
+            )),
+        }
+    }
+
+    /// Binds an imported module to the name of its file.
+    pub(crate) fn bind_module(
+        &mut self,
+        file: &Path,
+        module: Object,
+    ) -> Result<Object, EvaluatorError> {
+        let name = module::name(file).ok_or_else(|| {
+            EvaluatorError::InvalidData("module name", file.display().to_string())
+        })?;
+
+        if self.is_reserved(name) {
+            return Err(EvaluatorError::OverwriteBuiltin(name.to_string()));
+        }
+
+        self.env.set(name.into(), module.clone());
+        Ok(module)
+    }
+
+    /// Whether a name belongs to a builtin function or type, which scripts
+    /// can't redefine.
+    pub(crate) fn is_reserved(&self, name: &str) -> bool {
+        self.builtins.has_fn(name)
+            || self.builtins.constant(name).is_some()
+            || Type::from_name(name).is_some()
+    }
+
+    /// Evaluates an index, member access or call, which may be part of an
+    /// optional chain. `None` means a `?.` or `?[` found null, which
+    /// short-circuits the rest of the chain.
+    fn eval_chain(&mut self, expression: Expression) -> Result<Option<Object>, EvaluatorError> {
+        match expression {
+            Expression::Index(idx) => {
+                let Some(left) = self.eval_chain(*idx.left)? else {
+                    return Ok(None);
+                };
+
+                if idx.token == Token::OptionalBracket && matches!(left, Object::Null) {
+                    return Ok(None);
+                }
+
+                let index = self.eval_expression(*idx.index)?;
+                self.index(left, index).map(Some)
+            }
+            Expression::Member(member) => {
+                let Some(object) = self.eval_chain(*member.object)? else {
+                    return Ok(None);
+                };
+
+                if member.token == Token::OptionalDot && matches!(object, Object::Null) {
+                    return Ok(None);
+                }
+
+                object
+                    .member(member.property.value.as_str())
+                    .map(Some)
+                    .ok_or(EvaluatorError::UnknownMember(member.property.value))
+            }
             Expression::Call(call_expr) => {
-                let function = self.eval_expression(*call_expr.function)?;
+                let name = self.hooks.is_some().then(|| call_expr.function.to_string());
+
+                let Some(function) = self.eval_chain(*call_expr.function)? else {
+                    return Ok(None);
+                };
+
                 let args = call_expr
                     .args
                     .into_iter()
                     .map(|arg| self.eval_expression(arg))
                     .collect::<Result<Vec<_>, _>>()?;
 
-                match function {
-                    Object::Function { params, body, env } => {
-                        let mut env = env.capture();
-                        for (param, arg) in params.iter().zip(args) {
-                            env.set(&param.value, arg);
-                        }
-
-                        match self.eval_block(body, env) {
-                            Ok(v) => Ok(v),
-                            Err(EvaluatorError::ReturningValue(v)) => Ok(v),
-                            Err(e) => Err(e),
-                        }
+                if let (Some(hooks), Some(name)) = (&mut self.hooks, name) {
+                    hooks.on_call(&name, &args);
+                }
+
+                self.call_function(function, args).map(Some)
+            }
+            expression => self.eval_expression(expression).map(Some),
+        }
+    }
+
+    pub(crate) fn index(&mut self, left: Object, index: Object) -> Result<Object, EvaluatorError> {
+        if let Some(hook) = left.member(INDEX_HOOK) {
+            return self.call_function(hook, vec![left, index]);
+        }
+
+        match (left, index) {
+            (Object::Array(objs), Object::Integer(idx)) => Ok(objs
+                .borrow()
+                .get(idx as usize)
+                .cloned()
+                .unwrap_or(Object::Null)),
+            (Object::Dict(dict), key) => {
+                let key = HashKey::try_from(key)?;
+                Ok(dict.borrow().get(&key).cloned().unwrap_or(Object::Null))
+            }
+            _ => Err(EvaluatorError::NotAnArray),
+        }
+    }
+
+    /// Calls a function object with already evaluated arguments.
+    pub fn call_function(
+        &mut self,
+        function: Object,
+        args: Vec<Object>,
+    ) -> Result<Object, EvaluatorError> {
+        match function {
+            Object::Function(function) => {
+                self.count_call()?;
+
+                let mut env = function.env.capture();
+                for (param, arg) in function.params.iter().zip(args) {
+                    env.declare(param.value, arg);
+                }
+
+                if function.is_generator() {
+                    let generator = Generator::new(function.body.clone(), env);
+                    let iter = Iter::generator(Object::Function(function), generator);
+                    return Ok(Object::Iterator(Shared::new(iter)));
+                }
+
+                if let Some(depth) = self.limits.depth {
+                    if self.depth >= depth {
+                        return Err(EvaluatorError::DepthExceeded(depth));
                     }
-                    Object::Builtin(name) => Ok(self.builtins.call(name, args)),
-                    _ => Err(EvaluatorError::NotAFunction),
+                }
+
+                self.depth += 1;
+                let result = self.eval_block(function.body.clone(), env);
+                self.depth -= 1;
+
+                match result {
+                    Ok(v) => Ok(v),
+                    Err(EvaluatorError::ReturningValue(v)) => Ok(v),
+                    Err(e) => Err(e),
                 }
             }
-            Expression::Function(fn_lit) => Ok(Object::Function {
-                params: fn_lit.params,
-                body: fn_lit.body,
-                env: self.env.clone(),
-            }),
-            Expression::Identifier(ident) => match self.env.get(&ident.value) {
-                Some(value) => Ok(value.clone()),
-                None => match self.builtins.has_fn(&ident.value) {
-                    true => Ok(Object::Builtin(ident.value)),
-                    false => Err(EvaluatorError::UnknownVariable(ident.value)),
-                },
+            Object::Builtin(name) => match self.builtins.get(&name) {
+                Some(f) => {
+                    if let Some(metrics) = &mut self.metrics {
+                        *metrics.builtin_calls.entry(name).or_default() += 1;
+                    }
+
+                    f(self, args)
+                }
+                None => Err(EvaluatorError::NotAFunction),
             },
-            Expression::Block(block) => self.eval_block(block, self.env.capture()),
+            Object::Closure(closure) => Vm::new(self).call(closure, args),
+            Object::Native(native) => native.call(args),
+            _ => Err(EvaluatorError::NotAFunction),
+        }
+    }
+
+    /// Counts a call to a script function, which may time out.
+    pub(crate) fn count_call(&mut self) -> Result<(), EvaluatorError> {
+        self.check_deadline()?;
+
+        if let Some(metrics) = &mut self.metrics {
+            metrics.calls += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Converts an object to a string, honoring its `__str__` hook.
+    pub fn stringify(&mut self, object: &Object) -> Result<String, EvaluatorError> {
+        match object.member(STR_HOOK) {
+            Some(hook) => Ok(self.call_function(hook, vec![object.clone()])?.to_string()),
+            None => Ok(object.to_string()),
         }
     }
 
     pub fn eval_statement(&mut self, statement: Statement) -> Result<Object, EvaluatorError> {
+        self.count_step()?;
+
+        if let Some(hooks) = &mut self.hooks {
+            hooks.on_statement(&statement);
+        }
+
         match statement {
-            Statement::Expression(node) => self.eval_expression(node.expression),
+            // A bare `import "path";` also binds the module to its name.
+            Statement::Expression(node) => match node.expression {
+                Expression::Import(import) => {
+                    let (file, module) = self.eval_import(import)?;
+                    self.bind_module(&file, module)
+                }
+                expression => self.eval_expression(expression),
+            },
             Statement::Return(return_stmt) => {
                 let value = self.eval_expression(return_stmt.return_value)?;
                 Err(EvaluatorError::ReturningValue(value))
             }
+            Statement::Yield(_) => Err(EvaluatorError::UnexpectedYield),
             Statement::While(stmt) => {
                 while let Object::Boolean(true) = self.eval_expression(*stmt.condition.clone())? {
+                    self.check_deadline()?;
                     self.eval_block(stmt.block.clone(), self.env.capture())?;
                 }
 
+                Ok(Object::Null)
+            }
+            Statement::For(stmt) => {
+                let iter = iterate(self.eval_expression(*stmt.iterable)?)?;
+
+                while let Some(item) = iter.next(self)? {
+                    self.check_deadline()?;
+
+                    let mut env = self.env.capture();
+                    env.declare(stmt.variable.value, item);
+
+                    self.eval_block(stmt.block.clone(), env)?;
+                }
+
                 Ok(Object::Null)
             }
         }
     }
 
     pub fn eval_block(
-        &self,
+        &mut self,
         block: BlockExpression,
         env: Environment,
     ) -> Result<Object, EvaluatorError> {
-        let mut result = Object::Null;
-        let mut ev = Evaluator {
-            env,
-            ..Default::default()
-        };
+        if let Some(scopes) = self.limits.scopes {
+            if self.env.scopes_over(scopes) {
+                return Err(EvaluatorError::ScopesExceeded(scopes));
+            }
+        }
+
+        self.with_env(env, |ev| {
+            let mut result = Object::Null;
+
+            for statement in block.statements {
+                result = ev.eval_statement(statement)?;
+            }
+
+            Ok(result)
+        })
+    }
+
+    /// Runs `f` with `env` as the current scope.
+    pub(crate) fn with_env<T>(&mut self, env: Environment, f: impl FnOnce(&mut Self) -> T) -> T {
+        let env = std::mem::replace(&mut self.env, env);
+        let result = f(self);
+        self.env = env;
+        result
+    }
+}
 
-        for statement in block.statements {
-            result = ev.eval_statement(statement)?;
+/// Sets an element of an array or dict, returning the value set.
+pub(crate) fn index_assign(
+    ev: &mut Evaluator,
+    left: &Object,
+    index: Object,
+    value: Object,
+) -> Result<Object, EvaluatorError> {
+    match (left, index) {
+        (Object::Array(objs), Object::Integer(idx)) => {
+            let mut objs = objs.borrow_mut()?;
+            let len = objs.len();
+
+            match usize::try_from(idx).ok().and_then(|i| objs.get_mut(i)) {
+                Some(slot) => *slot = value.clone(),
+                None => return Err(EvaluatorError::IndexOutOfBounds(idx, len)),
+            }
         }
+        (Object::Dict(dict), key) => {
+            let key = HashKey::try_from(key)?;
+            let mut dict = dict.borrow_mut()?;
 
-        Ok(result)
+            if !dict.contains_key(&key) {
+                ev.count_memory(DICT_SLOT)?;
+            }
+
+            dict.insert(key, value.clone());
+        }
+        _ => {
+            return Err(EvaluatorError::UnexpectedType(
+                "Array or Dict",
+                left.clone(),
+            ))
+        }
+    }
+
+    Ok(value)
+}
+
+/// Membership test behind `in`: elements of an array, keys of a dict and
+/// substrings of a string.
+fn contains(container: &Object, item: Object) -> Result<bool, EvaluatorError> {
+    match (container, item) {
+        (Object::Array(elements), item) => Ok(elements.borrow().contains(&item)),
+        (Object::Dict(dict), key) => Ok(dict.borrow().contains_key(&HashKey::try_from(key)?)),
+        (Object::String(s), Object::String(sub)) => Ok(s.contains(&*sub)),
+        (Object::String(_), item) => Err(EvaluatorError::UnexpectedType("String", item)),
+        _ => Err(EvaluatorError::UnexpectedType(
+            "Array, Dict or String",
+            container.clone(),
+        )),
+    }
+}
+
+/// Longest string `*` may build, so a typo can't allocate gigabytes.
+pub const MAX_REPEAT_LEN: usize = 1 << 24;
+
+/// The result of integer arithmetic, `None` if it overflowed.
+fn checked(result: Option<i64>) -> Result<Object, EvaluatorError> {
+    result
+        .map(Object::Integer)
+        .ok_or(EvaluatorError::IntegerOverflow)
+}
+
+fn repeat(s: &str, n: i64) -> Result<Shared<str>, EvaluatorError> {
+    let n = usize::try_from(n).unwrap_or(0);
+
+    match s.len().checked_mul(n) {
+        Some(len) if len <= MAX_REPEAT_LEN => Ok(s.repeat(n).into()),
+        _ => Err(EvaluatorError::RepeatTooLong(MAX_REPEAT_LEN)),
     }
 }
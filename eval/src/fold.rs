@@ -0,0 +1,148 @@
+//! Constant folding: operators applied to literals are evaluated before the
+//! program runs, so `2 * 3 + x` becomes `6 + x`, and `if` expressions whose
+//! condition is a literal become the branch that would be taken.
+//!
+//! Folding gives the same results as evaluating. Operations that would fail
+//! or panic at runtime, like dividing by zero, are left for the runtime to
+//! report.
+
+use crate::{evaluator::Evaluator, object::Object, pass::Pass};
+use belalang_core::{
+    ast::{
+        visit::{walk_expression_mut, VisitorMut},
+        BooleanExpression, Expression, FloatLiteral, IntegerLiteral, NullLiteral, Program,
+        StringLiteral,
+    },
+    source::Span,
+    token::Token,
+};
+
+/// Strings longer than this are built at runtime, so that `"a" * 1000000`
+/// doesn't end up in the program.
+const MAX_STRING: usize = 256;
+
+/// Folds the constant subexpressions of `program`.
+pub fn fold_constants(program: &mut Program) {
+    Folder {
+        evaluator: Evaluator::default(),
+    }
+    .visit_program_mut(program);
+}
+
+/// [`fold_constants`] as a [`Pass`], named `fold`.
+pub struct ConstantFolding;
+
+impl Pass for ConstantFolding {
+    fn name(&self) -> &str {
+        "fold"
+    }
+
+    fn run(&self, program: &mut Program) {
+        fold_constants(program);
+    }
+}
+
+struct Folder {
+    /// Applies the operators, so that they mean the same as when evaluated.
+    evaluator: Evaluator,
+}
+
+impl VisitorMut for Folder {
+    fn visit_expression_mut(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression);
+
+        if let Some(folded) = self.fold(expression) {
+            *expression = folded;
+        }
+    }
+}
+
+impl Folder {
+    fn fold(&mut self, expression: &mut Expression) -> Option<Expression> {
+        let span = expression.span();
+
+        match expression {
+            Expression::Prefix(prefix) => {
+                let right = constant(&prefix.right)?;
+                let value = self.evaluator.prefix(&prefix.operator, right).ok()?;
+                literal(value, span)
+            }
+            Expression::Infix(infix) if infix.operator == Token::NullCoalesce => {
+                match constant(&infix.left)? {
+                    Object::Null => Some(take(&mut infix.right)),
+                    _ => Some(take(&mut infix.left)),
+                }
+            }
+            Expression::Infix(infix) => {
+                let left = constant(&infix.left)?;
+                let right = constant(&infix.right)?;
+                let value = self.evaluator.infix(&infix.operator, left, right).ok()?;
+                literal(value, span)
+            }
+            Expression::If(if_expr) => match constant(&if_expr.condition)? {
+                Object::Boolean(true) => Some(Expression::Block(if_expr.consequence.clone())),
+                _ => match if_expr.alternative.take() {
+                    Some(alternative) => Some(*alternative),
+                    None => literal(Object::Null, span),
+                },
+            },
+            _ => None,
+        }
+    }
+}
+
+/// The value of a literal.
+fn constant(expression: &Expression) -> Option<Object> {
+    match expression {
+        Expression::Boolean(b) => Some(Object::Boolean(b.value)),
+        Expression::Integer(i) => Some(Object::Integer(i.value)),
+        Expression::Float(f) => Some(Object::Float(f.value)),
+        Expression::String(s) => Some(Object::String(s.value.as_str().into())),
+        Expression::Null(_) => Some(Object::Null),
+        _ => None,
+    }
+}
+
+/// The literal for a folded value, if it can be written as one.
+fn literal(value: Object, span: Span) -> Option<Expression> {
+    let expression = match value {
+        Object::Boolean(value) => Expression::Boolean(BooleanExpression {
+            token: if value { Token::True } else { Token::False },
+            span,
+            value,
+        }),
+        Object::Integer(value) => Expression::Integer(IntegerLiteral {
+            token: Token::Int(value.to_string()),
+            span,
+            value,
+        }),
+        Object::Float(value) if value.is_finite() => Expression::Float(FloatLiteral {
+            token: Token::Float(value.to_string()),
+            span,
+            value,
+        }),
+        Object::String(value) if value.len() <= MAX_STRING => Expression::String(StringLiteral {
+            token: Token::String(value.to_string()),
+            span,
+            value: value.to_string(),
+        }),
+        Object::Null => Expression::Null(NullLiteral {
+            token: Token::Empty,
+            span,
+        }),
+        _ => return None,
+    };
+
+    Some(expression)
+}
+
+/// Moves an operand out of an expression that is being replaced.
+fn take(expression: &mut Expression) -> Expression {
+    std::mem::replace(
+        expression,
+        Expression::Null(NullLiteral {
+            token: Token::Empty,
+            span: Span::default(),
+        }),
+    )
+}
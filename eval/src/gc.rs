@@ -0,0 +1,284 @@
+//! The objects the [`crate::vm::Vm`] allocates on its heap, and when it
+//! collects the garbage among them.
+//!
+//! Arrays, dicts and the upvalues of closures are tracked by the heap
+//! without keeping them alive. Collecting marks what is reachable from the
+//! evaluator and the VMs running on it, see
+//! [`crate::evaluator::Evaluator::collect_garbage`], including the builtin
+//! constants. Among the tracked objects and scopes that weren't reached,
+//! the ones only referred to by each other are garbage: they are emptied,
+//! which frees cyclical structures like an array containing itself or a
+//! closure stored in a variable it captured. Strings can't refer to other
+//! values, so they are freed as soon as they're unused and aren't tracked.
+//!
+//! An object the host holds has a reference no other object explains, so
+//! it is never emptied, and neither is anything it refers to.
+
+use crate::{
+    environment::Store,
+    object::{Collection, Function, HashKey, Object},
+    sync::{Locked, Shared, Weak},
+    vm::{Closure, Upvalue},
+};
+use indexmap::IndexMap;
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+/// When the VM collects garbage on its own, see
+/// [`crate::evaluator::Evaluator::set_gc_thresholds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Tracked objects there may be before the first collection, `None` to
+    /// only collect when asked to.
+    pub initial: Option<usize>,
+    /// After a collection, the next one is once there are this many times
+    /// as many tracked objects as survived it, and at least `initial`.
+    pub growth: usize,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            initial: Some(10_000),
+            growth: 2,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Tracked {
+    Array(Weak<Collection<Vec<Object>>>),
+    Dict(Weak<Collection<IndexMap<HashKey, Object>>>),
+    Upvalue(Weak<Locked<Upvalue>>),
+}
+
+/// The tracked objects of a heap, by address.
+#[derive(Debug, Default)]
+pub(crate) struct Objects {
+    tracked: HashMap<usize, Tracked>,
+    /// How many were alive after the last collection.
+    survivors: usize,
+}
+
+impl Objects {
+    /// Tracks a new array or dict, or the upvalues of a new closure.
+    pub(crate) fn track(&mut self, object: &Object) {
+        match object {
+            Object::Array(array) => self.insert(
+                Shared::as_ptr(array) as *const () as usize,
+                Tracked::Array(Shared::downgrade(array)),
+            ),
+            Object::Dict(dict) => self.insert(
+                Shared::as_ptr(dict) as *const () as usize,
+                Tracked::Dict(Shared::downgrade(dict)),
+            ),
+            Object::Closure(closure) => {
+                for upvalue in &closure.upvalues {
+                    self.insert(
+                        Shared::as_ptr(upvalue) as *const () as usize,
+                        Tracked::Upvalue(Shared::downgrade(upvalue)),
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // An address can't be reused while it's tracked, the weak reference
+    // keeps the allocation.
+    fn insert(&mut self, address: usize, tracked: Tracked) {
+        self.tracked.entry(address).or_insert(tracked);
+    }
+
+    /// Whether there are enough tracked objects for a collection.
+    pub(crate) fn due(&self, thresholds: Thresholds) -> bool {
+        thresholds.initial.is_some_and(|initial| {
+            let next = self.survivors.saturating_mul(thresholds.growth);
+            self.tracked.len() >= next.max(initial)
+        })
+    }
+
+    /// The live tracked objects that aren't in `marked`.
+    pub(crate) fn unmarked(&self, marked: &HashSet<*const ()>) -> Vec<Node> {
+        self.tracked
+            .iter()
+            .filter(|(address, _)| !marked.contains(&(**address as *const ())))
+            .filter_map(|(_, tracked)| match tracked {
+                Tracked::Array(array) => array.upgrade().map(Node::Array),
+                Tracked::Dict(dict) => dict.upgrade().map(Node::Dict),
+                Tracked::Upvalue(upvalue) => upvalue.upgrade().map(Node::Upvalue),
+            })
+            .collect()
+    }
+
+    /// Stops tracking the objects that were freed.
+    pub(crate) fn prune(&mut self) {
+        self.tracked.retain(|_, tracked| match tracked {
+            Tracked::Array(array) => array.strong_count() > 0,
+            Tracked::Dict(dict) => dict.strong_count() > 0,
+            Tracked::Upvalue(upvalue) => upvalue.strong_count() > 0,
+        });
+        self.survivors = self.tracked.len();
+    }
+}
+
+/// A value that holds references to others, found while looking for
+/// garbage.
+pub(crate) enum Node {
+    Scope(Store),
+    Array(Shared<Collection<Vec<Object>>>),
+    Dict(Shared<Collection<IndexMap<HashKey, Object>>>),
+    Upvalue(Shared<Locked<Upvalue>>),
+    Closure(Shared<Closure>),
+    Function(Shared<Function>),
+}
+
+impl Node {
+    fn of(object: &Object) -> Option<Node> {
+        match object {
+            Object::Array(array) => Some(Node::Array(Shared::clone(array))),
+            Object::Dict(dict) => Some(Node::Dict(Shared::clone(dict))),
+            Object::Closure(closure) => Some(Node::Closure(Shared::clone(closure))),
+            Object::Function(function) => Some(Node::Function(Shared::clone(function))),
+            _ => None,
+        }
+    }
+
+    fn address(&self) -> *const () {
+        match self {
+            Node::Scope(store) => Shared::as_ptr(store) as *const (),
+            Node::Array(array) => Shared::as_ptr(array) as *const (),
+            Node::Dict(dict) => Shared::as_ptr(dict) as *const (),
+            Node::Upvalue(upvalue) => Shared::as_ptr(upvalue) as *const (),
+            Node::Closure(closure) => Shared::as_ptr(closure) as *const (),
+            Node::Function(function) => Shared::as_ptr(function) as *const (),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Scope(store) => Shared::strong_count(store),
+            Node::Array(array) => Shared::strong_count(array),
+            Node::Dict(dict) => Shared::strong_count(dict),
+            Node::Upvalue(upvalue) => Shared::strong_count(upvalue),
+            Node::Closure(closure) => Shared::strong_count(closure),
+            Node::Function(function) => Shared::strong_count(function),
+        }
+    }
+
+    /// The nodes this one holds a reference to, once per reference.
+    fn children(&self) -> Vec<Node> {
+        match self {
+            Node::Scope(store) => store.borrow().values().filter_map(Node::of).collect(),
+            Node::Array(array) => array.borrow().iter().filter_map(Node::of).collect(),
+            Node::Dict(dict) => dict.borrow().values().filter_map(Node::of).collect(),
+            Node::Upvalue(upvalue) => match &*upvalue.borrow() {
+                Upvalue::Closed(value) => Node::of(value).into_iter().collect(),
+                Upvalue::Open(_) => Vec::new(),
+            },
+            Node::Closure(closure) => closure
+                .upvalues
+                .iter()
+                .cloned()
+                .map(Node::Upvalue)
+                .chain(closure.env.stores().iter().cloned().map(Node::Scope))
+                .collect(),
+            Node::Function(function) => function
+                .env
+                .stores()
+                .iter()
+                .cloned()
+                .map(Node::Scope)
+                .collect(),
+        }
+    }
+
+    /// Drops what a scope, an array, a dict or a closed upvalue holds, which
+    /// breaks the cycles through it. Returns whether it was one of those.
+    /// Closures and functions can't be changed, they are freed with what
+    /// holds them.
+    fn empty(&self) -> bool {
+        match self {
+            Node::Scope(store) => drop(std::mem::take(&mut **store.borrow_mut())),
+            Node::Array(array) => drop(array.replace(Vec::new())),
+            Node::Dict(dict) => drop(dict.replace(IndexMap::new())),
+            Node::Upvalue(upvalue) => {
+                let closed =
+                    std::mem::replace(&mut *upvalue.borrow_mut(), Upvalue::Closed(Object::Null));
+                drop(closed);
+            }
+            Node::Closure(_) | Node::Function(_) => return false,
+        }
+
+        true
+    }
+}
+
+/// Finds the garbage among `candidates`, which weren't reached by marking:
+/// the values that nothing outside of them refers to, directly or through
+/// other candidates. Every reference to a value is counted, so one whose
+/// count isn't explained by the candidates holding it is kept, e.g. because
+/// the host holds it, together with what it refers to. Values held by
+/// something that isn't a node, like an iterator or a native object, are
+/// kept the same way.
+pub(crate) fn garbage(candidates: Vec<Node>, marked: &HashSet<*const ()>) -> Vec<Node> {
+    // each node with the references to it from other nodes
+    let mut nodes = HashMap::new();
+    let mut edges = HashMap::new();
+    let mut pending = Vec::new();
+
+    for node in candidates {
+        let address = node.address();
+        if !marked.contains(&address) && !nodes.contains_key(&address) {
+            nodes.insert(address, (node, 0));
+            pending.push(address);
+        }
+    }
+
+    while let Some(address) = pending.pop() {
+        let mut targets = Vec::new();
+
+        for child in nodes[&address].0.children() {
+            let target = child.address();
+            if marked.contains(&target) {
+                continue;
+            }
+
+            match nodes.entry(target) {
+                Entry::Occupied(mut entry) => entry.get_mut().1 += 1,
+                Entry::Vacant(entry) => {
+                    entry.insert((child, 1));
+                    pending.push(target);
+                }
+            }
+            targets.push(target);
+        }
+
+        edges.insert(address, targets);
+    }
+
+    // one reference to each node is the one in `nodes`
+    let mut alive = nodes
+        .iter()
+        .filter(|(_, (node, internal))| node.strong_count() > internal + 1)
+        .map(|(address, _)| *address)
+        .collect::<Vec<_>>();
+    let mut reached = HashSet::new();
+
+    while let Some(address) = alive.pop() {
+        if reached.insert(address) {
+            alive.extend(&edges[&address]);
+        }
+    }
+
+    nodes
+        .into_iter()
+        .filter(|(address, _)| !reached.contains(address))
+        .map(|(_, (node, _))| node)
+        .collect()
+}
+
+/// Empties the garbage, see [`Node::empty`]. Returns how many scopes and
+/// objects were emptied.
+pub(crate) fn free(garbage: Vec<Node>) -> usize {
+    garbage.iter().filter(|node| node.empty()).count()
+}
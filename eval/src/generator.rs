@@ -0,0 +1,225 @@
+//! Generator functions, i.e. functions with a `yield` statement in their
+//! body. Calling one returns an iterator; every `next()` runs the body until
+//! the following `yield` and returns its value.
+//!
+//! The evaluator is recursive, so it can't be paused in the middle of a
+//! function. Instead, a generator steps through the statements of the body
+//! itself, keeping the blocks and loops it is in on a stack of frames. It
+//! only descends into `while`, `for`, `if` and block statements; a `yield`
+//! anywhere else, e.g. in the block of an `if` used as a value, is an error.
+
+use crate::{
+    environment::Environment,
+    error::EvaluatorError,
+    evaluator::Evaluator,
+    iterator::{iterate, Iter},
+    object::Object,
+    sync::Shared,
+};
+use belalang_core::ast::{BlockExpression, Expression, ForStatement, Statement, WhileStatement};
+
+pub struct Generator {
+    frames: Vec<Frame>,
+}
+
+enum Frame {
+    Block {
+        statements: std::vec::IntoIter<Statement>,
+        env: Environment,
+    },
+    While {
+        statement: WhileStatement,
+        env: Environment,
+    },
+    For {
+        statement: ForStatement,
+        iter: Shared<Iter>,
+        env: Environment,
+    },
+}
+
+impl Frame {
+    fn block(block: BlockExpression, env: Environment) -> Self {
+        Frame::Block {
+            statements: block.statements.into_iter(),
+            env,
+        }
+    }
+
+    fn env(&self) -> &Environment {
+        match self {
+            Frame::Block { env, .. } | Frame::While { env, .. } | Frame::For { env, .. } => env,
+        }
+    }
+}
+
+impl Generator {
+    /// A generator that runs `body` in `env`, the scope of the call.
+    pub fn new(body: BlockExpression, env: Environment) -> Self {
+        Self {
+            frames: vec![Frame::block(body, env)],
+        }
+    }
+
+    /// The scopes the generator is suspended in.
+    pub fn scopes(&self) -> impl Iterator<Item = &Environment> {
+        self.frames.iter().map(Frame::env)
+    }
+
+    /// Runs the body up to the next `yield`. `None` once the body returned,
+    /// after which the generator stays finished. So does it after an error.
+    pub fn resume(&mut self, ev: &mut Evaluator) -> Result<Option<Object>, EvaluatorError> {
+        let result = self.run(ev);
+
+        if !matches!(result, Ok(Some(_))) {
+            self.frames.clear();
+        }
+
+        match result {
+            Err(EvaluatorError::ReturningValue(_)) => Ok(None),
+            result => result,
+        }
+    }
+
+    fn run(&mut self, ev: &mut Evaluator) -> Result<Option<Object>, EvaluatorError> {
+        while let Some(frame) = self.frames.last_mut() {
+            match frame {
+                Frame::Block { statements, env } => match statements.next() {
+                    Some(statement) => {
+                        let env = env.clone();
+
+                        if let Some(value) = self.step(ev, statement, env)? {
+                            return Ok(Some(value));
+                        }
+                    }
+                    None => {
+                        self.frames.pop();
+                    }
+                },
+                Frame::While { statement, env } => {
+                    ev.check_deadline()?;
+
+                    let (block, env) = (statement.block.clone(), env.clone());
+                    let condition = *statement.condition.clone();
+
+                    match ev.with_env(env.clone(), |ev| ev.eval_expression(condition))? {
+                        Object::Boolean(true) => {
+                            self.frames.push(Frame::block(block, env.capture()))
+                        }
+                        _ => {
+                            self.frames.pop();
+                        }
+                    }
+                }
+                Frame::For {
+                    statement,
+                    iter,
+                    env,
+                } => {
+                    ev.check_deadline()?;
+
+                    let (block, variable) = (statement.block.clone(), statement.variable.value);
+                    let (iter, env) = (Shared::clone(iter), env.clone());
+
+                    match ev.with_env(env.clone(), |ev| iter.next(ev))? {
+                        Some(item) => {
+                            let mut scope = env.capture();
+                            scope.declare(variable, item);
+                            self.frames.push(Frame::block(block, scope));
+                        }
+                        None => {
+                            self.frames.pop();
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Runs a statement of the body, returning the value it yields, if any.
+    fn step(
+        &mut self,
+        ev: &mut Evaluator,
+        statement: Statement,
+        env: Environment,
+    ) -> Result<Option<Object>, EvaluatorError> {
+        match statement {
+            Statement::Yield(stmt) => ev
+                .with_env(env, |ev| ev.eval_expression(stmt.value))
+                .map(Some),
+            Statement::While(statement) if yields(&statement.block) => {
+                self.frames.push(Frame::While { statement, env });
+                Ok(None)
+            }
+            Statement::For(statement) if yields(&statement.block) => {
+                let iterable = *statement.iterable.clone();
+                let iter = ev.with_env(env.clone(), |ev| iterate(ev.eval_expression(iterable)?))?;
+
+                self.frames.push(Frame::For {
+                    statement,
+                    iter,
+                    env,
+                });
+                Ok(None)
+            }
+            Statement::Expression(stmt) if expression_yields(&stmt.expression) => {
+                self.enter(ev, stmt.expression, env)
+            }
+            statement => ev
+                .with_env(env, |ev| ev.eval_statement(statement))
+                .map(|_| None),
+        }
+    }
+
+    /// Enters the block an `if` or block statement runs.
+    fn enter(
+        &mut self,
+        ev: &mut Evaluator,
+        expression: Expression,
+        env: Environment,
+    ) -> Result<Option<Object>, EvaluatorError> {
+        match expression {
+            Expression::Block(block) => self.frames.push(Frame::block(block, env.capture())),
+            Expression::If(expr) => {
+                let condition =
+                    ev.with_env(env.clone(), |ev| ev.eval_expression(*expr.condition))?;
+
+                if let Object::Boolean(true) = condition {
+                    self.frames
+                        .push(Frame::block(expr.consequence, env.capture()));
+                } else if let Some(alternative) = expr.alternative {
+                    return self.enter(ev, *alternative, env);
+                }
+            }
+            expression => {
+                ev.with_env(env, |ev| ev.eval_expression(expression))?;
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Whether a block has a `yield` the generator can stop at. Functions
+/// declared inside the block don't count.
+pub fn yields(block: &BlockExpression) -> bool {
+    block.statements.iter().any(|statement| match statement {
+        Statement::Yield(_) => true,
+        Statement::While(stmt) => yields(&stmt.block),
+        Statement::For(stmt) => yields(&stmt.block),
+        Statement::Expression(stmt) => expression_yields(&stmt.expression),
+        Statement::Return(_) => false,
+    })
+}
+
+fn expression_yields(expression: &Expression) -> bool {
+    match expression {
+        Expression::Block(block) => yields(block),
+        Expression::If(expr) => {
+            yields(&expr.consequence) || expr.alternative.as_deref().is_some_and(expression_yields)
+        }
+        _ => false,
+    }
+}
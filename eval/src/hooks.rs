@@ -0,0 +1,34 @@
+//! Callbacks the evaluator invokes as it goes, for tracing, profiling or
+//! audit logging, see [`Evaluator::set_hooks`](crate::evaluator::Evaluator::set_hooks).
+
+use crate::{error::EvaluatorError, object::Object};
+use belalang_core::ast::Statement;
+
+/// Every method does nothing by default, so implement only what's needed.
+pub trait Hooks {
+    /// Called before each statement is evaluated, including those in
+    /// function bodies and imported modules.
+    fn on_statement(&mut self, statement: &Statement) {
+        let _ = statement;
+    }
+
+    /// Called before each call written in a script, with the called
+    /// expression as written, e.g. `double` or `math.sqrt`, and the
+    /// evaluated arguments. Calls builtins make themselves, e.g. of the
+    /// callback given to `map`, aren't reported.
+    fn on_call(&mut self, function: &str, args: &[Object]) {
+        let _ = (function, args);
+    }
+
+    /// Called with the error a program fails with, once.
+    fn on_error(&mut self, error: &EvaluatorError) {
+        let _ = error;
+    }
+}
+
+/// What [`Evaluator::set_hooks`](crate::evaluator::Evaluator::set_hooks)
+/// stores.
+#[cfg(not(feature = "sync"))]
+pub type BoxedHooks = Box<dyn Hooks>;
+#[cfg(feature = "sync")]
+pub type BoxedHooks = Box<dyn Hooks + Send + Sync>;
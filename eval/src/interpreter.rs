@@ -0,0 +1,315 @@
+//! Source in, result out: the lexer, parser and evaluator behind one call.
+
+use crate::{
+    builtins::{Builtins, Module, TypedFn},
+    bytecode::Bytecode,
+    error::{Error, EvaluatorError},
+    evaluator::{Evaluator, Handle, Snapshot},
+    gc::Thresholds,
+    metrics::Limits,
+    module::ParsedModules,
+    object::Object,
+    pass::Passes,
+    sandbox::Profile,
+    sync::Shareable,
+    vm::Vm,
+};
+use belalang_core::{lexer::Lexer, parser::Parser};
+use std::time::Duration;
+
+/// Evaluates source code in an environment that persists between calls, so
+/// later code sees the variables and functions defined by earlier code.
+#[derive(Default)]
+pub struct Interpreter {
+    evaluator: Evaluator,
+    passes: Passes,
+}
+
+impl Interpreter {
+    pub fn new(builtins: Builtins) -> Self {
+        Self {
+            evaluator: Evaluator::new(builtins),
+            passes: Passes::default(),
+        }
+    }
+
+    /// Configures an interpreter in one place, e.g.
+    ///
+    /// ```
+    /// # use belalang_eval::{interpreter::Interpreter, sandbox::Profile};
+    /// let mut interpreter = Interpreter::builder()
+    ///     .profile(Profile::Pure)
+    ///     .fuel(10_000)
+    ///     .depth(64)
+    ///     .register_fn("answer", || 42)
+    ///     .build();
+    /// assert_eq!(interpreter.eval_str("answer();").unwrap().to_string(), "42");
+    /// ```
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::default()
+    }
+
+    /// Parses and evaluates `source`, returning the value of its last
+    /// statement. Nothing is evaluated if it doesn't parse.
+    pub fn eval_str(&mut self, source: &str) -> Result<Object, Error> {
+        let lexer = Lexer::new(source.as_bytes());
+        let mut program = Parser::new(lexer).parse_program()?;
+        self.passes.run(&mut program);
+
+        Ok(self.evaluator.eval_program(program)?)
+    }
+
+    /// Runs a program compiled by [`crate::compiler::Compiler`], e.g. one
+    /// loaded with [`Bytecode::load`], on the [`Vm`].
+    pub fn run_bytecode(&mut self, bytecode: &Bytecode) -> Result<Object, Error> {
+        Ok(Vm::new(&mut self.evaluator).run(bytecode)?)
+    }
+
+    /// Like [`Interpreter::eval_str`], but evaluates through
+    /// [`Evaluator::evaluate_async`].
+    #[cfg(feature = "tokio")]
+    pub async fn eval_str_async(&mut self, source: &str) -> Result<Object, Error> {
+        let lexer = Lexer::new(source.as_bytes());
+        let mut program = Parser::new(lexer).parse_program()?;
+        self.passes.run(&mut program);
+
+        Ok(self.evaluator.evaluate_async(program).await?)
+    }
+
+    /// Calls the function bound to `name`, e.g. a callback defined by a
+    /// script evaluated earlier.
+    pub fn call(&mut self, name: &str, args: Vec<Object>) -> Result<Object, Error> {
+        let function = self.evaluator.lookup(name.into())?;
+        Ok(self.evaluator.call_function(function, args)?)
+    }
+
+    /// Looks up the function bound to `name` once, for calling it with
+    /// [`Interpreter::call_handle`] even after the name is rebound.
+    pub fn function(&mut self, name: &str) -> Result<Handle, Error> {
+        match self.evaluator.lookup(name.into())? {
+            function @ (Object::Function(_)
+            | Object::Closure(_)
+            | Object::Builtin(_)
+            | Object::Native(_)) => Ok(self.evaluator.handle(function)),
+            _ => Err(EvaluatorError::NotAFunction.into()),
+        }
+    }
+
+    pub fn call_handle(&mut self, function: &Handle, args: Vec<Object>) -> Result<Object, Error> {
+        Ok(self
+            .evaluator
+            .call_function(function.object().clone(), args)?)
+    }
+
+    /// Saves the interpreter's state for [`Interpreter::restore`], see
+    /// [`Evaluator::snapshot`].
+    pub fn snapshot(&mut self) -> Snapshot {
+        self.evaluator.snapshot()
+    }
+
+    /// Rolls back to a snapshot, undoing what code evaluated since did.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.evaluator.restore(snapshot);
+    }
+
+    /// The passes programs are optimized with before they're evaluated.
+    pub fn passes_mut(&mut self) -> &mut Passes {
+        &mut self.passes
+    }
+
+    pub fn evaluator(&self) -> &Evaluator {
+        &self.evaluator
+    }
+
+    /// For what the interpreter doesn't cover itself, e.g. timeouts or
+    /// garbage collection.
+    pub fn evaluator_mut(&mut self) -> &mut Evaluator {
+        &mut self.evaluator
+    }
+}
+
+type Registration = Box<dyn FnOnce(&mut Builtins)>;
+
+/// Builds an [`Interpreter`], see [`Interpreter::builder`].
+#[derive(Default)]
+pub struct InterpreterBuilder {
+    builtins: Builtins,
+    profile: Option<Profile>,
+    /// Applied after the profile, so it doesn't disable them.
+    registrations: Vec<Registration>,
+    limits: Limits,
+    timeout: Option<Duration>,
+    metrics: bool,
+    gc: Thresholds,
+    passes: Passes,
+}
+
+impl InterpreterBuilder {
+    /// Starts from `builtins` instead of the default ones.
+    pub fn builtins(mut self, builtins: Builtins) -> Self {
+        self.builtins = builtins;
+        self
+    }
+
+    /// See [`Builtins::restrict`]. Builtins and modules added through the
+    /// builder stay enabled.
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// See [`Builtins::register`].
+    pub fn register<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: Fn(&mut Evaluator, Vec<Object>) -> Result<Object, EvaluatorError> + Shareable + 'static,
+    {
+        let name = name.to_string();
+        self.registrations
+            .push(Box::new(move |builtins| builtins.register(&name, f)));
+        self
+    }
+
+    /// See [`Builtins::register_fn`].
+    pub fn register_fn<F, Args>(mut self, name: &str, f: F) -> Self
+    where
+        F: TypedFn<Args> + Shareable + 'static,
+    {
+        let name = name.to_string();
+        self.registrations
+            .push(Box::new(move |builtins| builtins.register_fn(&name, f)));
+        self
+    }
+
+    /// See [`Builtins::register_module`].
+    pub fn register_module(mut self, name: &str, module: Module) -> Self {
+        let name = name.to_string();
+        self.registrations.push(Box::new(move |builtins| {
+            builtins.register_module(&name, module)
+        }));
+        self
+    }
+
+    /// See [`Limits::fuel`].
+    pub fn fuel(mut self, fuel: u64) -> Self {
+        self.limits.fuel = Some(fuel);
+        self
+    }
+
+    /// See [`Limits::depth`].
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.limits.depth = Some(depth);
+        self
+    }
+
+    /// See [`Limits::scopes`].
+    pub fn scopes(mut self, scopes: usize) -> Self {
+        self.limits.scopes = Some(scopes);
+        self
+    }
+
+    /// See [`Limits::memory`].
+    pub fn memory(mut self, bytes: usize) -> Self {
+        self.limits.memory = Some(bytes);
+        self
+    }
+
+    /// See [`Limits::frames`].
+    pub fn frames(mut self, frames: usize) -> Self {
+        self.limits.frames = Some(frames);
+        self
+    }
+
+    /// See [`Evaluator::set_timeout`]. The time starts when the interpreter
+    /// is built.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// See [`Evaluator::set_metrics`].
+    pub fn metrics(mut self, enabled: bool) -> Self {
+        self.metrics = enabled;
+        self
+    }
+
+    /// See [`Evaluator::set_gc_thresholds`].
+    pub fn gc(mut self, thresholds: Thresholds) -> Self {
+        self.gc = thresholds;
+        self
+    }
+
+    /// The passes that optimize programs before they're evaluated, none by
+    /// default. See [`Passes::standard`].
+    pub fn passes(mut self, passes: Passes) -> Self {
+        self.passes = passes;
+        self
+    }
+
+    pub fn build(self) -> Interpreter {
+        self.template().instantiate()
+    }
+
+    /// Finishes the configuration for building many interpreters from it,
+    /// e.g. one per request.
+    pub fn template(self) -> InterpreterTemplate {
+        let mut builtins = self.builtins;
+
+        if let Some(profile) = self.profile {
+            builtins.restrict(profile);
+        }
+
+        for register in self.registrations {
+            register(&mut builtins);
+        }
+
+        InterpreterTemplate {
+            builtins,
+            parsed_modules: ParsedModules::default(),
+            limits: self.limits,
+            timeout: self.timeout,
+            metrics: self.metrics,
+            gc: self.gc,
+            passes: self.passes,
+        }
+    }
+}
+
+/// Makes interpreters cheaply, see [`InterpreterBuilder::template`].
+///
+/// Its interpreters share what doesn't change while scripts run: the
+/// builtins, the parsed module files and, as everywhere, the interned
+/// identifiers. Everything scripts can change is their own, e.g. variables,
+/// evaluated modules and the random generator. Builtins stay shared though,
+/// so builtins with state of their own, see [`Builtins::register_mut`], and
+/// the output and input streams are shared too.
+#[derive(Clone)]
+pub struct InterpreterTemplate {
+    builtins: Builtins,
+    parsed_modules: ParsedModules,
+    limits: Limits,
+    timeout: Option<Duration>,
+    metrics: bool,
+    gc: Thresholds,
+    passes: Passes,
+}
+
+impl InterpreterTemplate {
+    /// A new interpreter with fresh limits; its timeout starts now.
+    pub fn instantiate(&self) -> Interpreter {
+        let mut evaluator = Evaluator::new(self.builtins.clone());
+        evaluator.set_parsed_modules(self.parsed_modules.clone());
+        evaluator.set_limits(self.limits);
+        evaluator.set_timeout(self.timeout);
+        evaluator.set_metrics(self.metrics);
+        evaluator.set_gc_thresholds(self.gc);
+
+        Interpreter {
+            evaluator,
+            passes: self.passes.clone(),
+        }
+    }
+
+    pub fn parsed_modules(&self) -> &ParsedModules {
+        &self.parsed_modules
+    }
+}
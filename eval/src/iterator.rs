@@ -0,0 +1,127 @@
+use crate::{
+    environment::Environment,
+    error::EvaluatorError,
+    evaluator::Evaluator,
+    generator::Generator,
+    object::{Object, ARRAY_SLOT},
+    sync::{Locked, Shareable, Shared},
+};
+
+#[cfg(not(feature = "sync"))]
+type NextFn = dyn FnMut(&mut Evaluator) -> Result<Option<Object>, EvaluatorError>;
+#[cfg(feature = "sync")]
+type NextFn = dyn FnMut(&mut Evaluator) -> Result<Option<Object>, EvaluatorError> + Send + Sync;
+
+enum Next {
+    Fn(Box<NextFn>),
+    Generator(Generator),
+}
+
+/// A lazy sequence of objects, see [`Object::Iterator`]. Iterators are
+/// consumed by `for` loops, `next()` and the iterator builtins, and can only
+/// be iterated once.
+pub struct Iter {
+    next: Locked<Next>,
+    /// Objects the iterator reads from, so the garbage collector can find
+    /// the scopes they keep alive.
+    sources: Vec<Object>,
+}
+
+impl Iter {
+    pub fn new<F>(sources: Vec<Object>, next: F) -> Self
+    where
+        F: FnMut(&mut Evaluator) -> Result<Option<Object>, EvaluatorError> + Shareable + 'static,
+    {
+        Self {
+            next: Locked::new(Next::Fn(Box::new(next))),
+            sources,
+        }
+    }
+
+    /// An iterator over what a call to a generator function yields.
+    /// `function` is the function called.
+    pub fn generator(function: Object, generator: Generator) -> Self {
+        Self {
+            next: Locked::new(Next::Generator(generator)),
+            sources: vec![function],
+        }
+    }
+
+    /// An iterator over already computed items. They are its sources too,
+    /// the garbage collector has to see those not iterated over yet.
+    pub fn from_items(items: Vec<Object>) -> Self {
+        let sources = items.clone();
+        let mut items = items.into_iter();
+        Self::new(sources, move |_| Ok(items.next()))
+    }
+
+    /// Advances the iterator, `None` once it is exhausted.
+    pub fn next(&self, ev: &mut Evaluator) -> Result<Option<Object>, EvaluatorError> {
+        let mut next = self
+            .next
+            .try_borrow_mut()
+            .map_err(|_| EvaluatorError::IteratorRunning)?;
+
+        match &mut *next {
+            Next::Fn(next) => next(ev),
+            Next::Generator(generator) => generator.resume(ev),
+        }
+    }
+
+    pub fn sources(&self) -> &[Object] {
+        &self.sources
+    }
+
+    /// The scopes a generator is suspended in. Empty for other iterators,
+    /// and while the iterator is running.
+    pub fn scopes(&self) -> Vec<Environment> {
+        match self.next.try_borrow().as_deref() {
+            Ok(Next::Generator(generator)) => generator.scopes().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Iter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Iter")
+            .field("sources", &self.sources)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Turns a value into an iterator: arrays go over their elements, dicts over
+/// their keys and strings over their characters. Arrays and dicts are copied
+/// first, so they may be modified while being iterated.
+pub fn iterate(iterable: Object) -> Result<Shared<Iter>, EvaluatorError> {
+    let items = match iterable {
+        Object::Iterator(iter) => return Ok(iter),
+        Object::Array(elements) => elements.borrow().clone(),
+        Object::Dict(dict) => dict.borrow().keys().cloned().map(Object::from).collect(),
+        Object::String(s) => s
+            .chars()
+            .map(|c| Object::String(c.to_string().into()))
+            .collect(),
+        _ => {
+            return Err(EvaluatorError::UnexpectedType(
+                "Array, Dict, String or Iterator",
+                iterable,
+            ))
+        }
+    };
+
+    Ok(Shared::new(Iter::from_items(items)))
+}
+
+/// Runs an iterator to the end, collecting its items. They count towards
+/// [`crate::metrics::Limits::memory`] as array slots.
+pub fn drain(iter: &Iter, ev: &mut Evaluator) -> Result<Vec<Object>, EvaluatorError> {
+    let mut items = Vec::new();
+
+    while let Some(item) = iter.next(ev)? {
+        ev.count_memory(ARRAY_SLOT)?;
+        items.push(item);
+    }
+
+    Ok(items)
+}
@@ -1,5 +1,26 @@
 pub mod builtins;
+pub mod bytecode;
+pub mod compiler;
 pub mod environment;
 pub mod error;
 pub mod evaluator;
+pub mod fold;
+pub mod gc;
+pub mod generator;
+pub mod hooks;
+pub mod interpreter;
+pub mod iterator;
+pub mod metrics;
+pub mod module;
+pub mod native;
 pub mod object;
+pub mod pass;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+pub mod random;
+pub mod repl;
+pub mod sandbox;
+pub mod sync;
+#[cfg(feature = "test-utils")]
+pub mod testing;
+pub mod vm;
@@ -0,0 +1,59 @@
+//! Resource usage of scripts, for hosts that bill, limit or inspect them.
+//! See [`crate::evaluator::Evaluator::set_metrics`] and
+//! [`crate::evaluator::Evaluator::set_limits`].
+
+use std::collections::HashMap;
+
+/// What an evaluator has used since metrics were enabled.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Statements and expressions the tree-walker evaluated, plus loop
+    /// iterations the [`crate::vm::Vm`] ran. The VM doesn't count the
+    /// expressions in between, so the same script takes fewer steps there.
+    pub steps: u64,
+    /// Calls of script functions.
+    pub calls: u64,
+    /// Calls of builtins by name, e.g. `"math.sqrt"`.
+    pub builtin_calls: HashMap<String, u64>,
+    /// Scopes created, one per function call and block.
+    pub allocations: u64,
+    /// The most scopes alive at once.
+    pub peak_scopes: usize,
+    /// Bytes of strings and collection slots created, counted like
+    /// [`Limits::memory`].
+    pub memory: u64,
+    /// Garbage collections, by the VM or
+    /// [`crate::evaluator::Evaluator::collect_garbage`].
+    pub collections: u64,
+}
+
+/// Caps on what a script may use. Going over one fails evaluation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Steps taken, counted like [`Metrics::steps`]. The VM takes fewer for
+    /// the same script, but one per loop iteration still, so either backend
+    /// stops a script that loops forever.
+    pub fuel: Option<u64>,
+    /// Script function calls in progress at once.
+    pub depth: Option<usize>,
+    /// Scopes alive at once, see [`Metrics::peak_scopes`]. Each one holds a
+    /// block's or a call's variables, so this bounds recursion and closures
+    /// but not the size of values, see [`Limits::memory`] for that.
+    pub scopes: Option<usize>,
+    /// Bytes of strings and collection slots a script may create, see
+    /// [`crate::object::Object::size`]. Growing a collection counts the
+    /// slots it gains. Nothing is given back when values are dropped, so
+    /// this caps what a script allocates in total, not what it holds.
+    pub memory: Option<usize>,
+    /// Calls in progress at once on the [`crate::vm::Vm`], beyond which it
+    /// fails with a stack overflow. [`crate::vm::MAX_FRAMES`] if not set.
+    pub frames: Option<usize>,
+}
+
+/// The part of [`Metrics`] counted by an environment's heap.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct HeapMetrics {
+    pub allocations: u64,
+    pub peak_scopes: usize,
+    pub collections: u64,
+}
@@ -0,0 +1,109 @@
+//! Modules, loaded with `import "path"`. A module is a file evaluated in its
+//! own global scope; its top-level bindings make up the namespace `import`
+//! returns, a frozen dict. Each module is evaluated once per evaluator;
+//! importing it again returns the same namespace.
+//!
+//! Relative paths are resolved against the directory of the importing file,
+//! or of the script set with
+//! [`Evaluator::set_script_args`](crate::evaluator::Evaluator::set_script_args), falling back to
+//! the working directory. A path without an extension gets `.bl`. Paths
+//! starting with `std/` name the modules of the builtins instead, see
+//! [`Builtins::namespace`](crate::builtins::Builtins::namespace).
+
+use crate::{
+    environment::Environment,
+    error::EvaluatorError,
+    object::{HashKey, Object},
+    sync::{Locked, Shared},
+};
+use belalang_core::{ast::Statement, lexer::Lexer, parser::Parser};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Parsed module files by canonical path. Parsing is the part of importing
+/// that doesn't depend on the evaluator, so evaluators running one script
+/// each can share it, see
+/// [`InterpreterTemplate`](crate::interpreter::InterpreterTemplate). Each
+/// evaluator still evaluates the modules itself, so none of them sees the
+/// others' module state. A file is parsed once per cache; changing it
+/// afterwards doesn't change the cached module.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedModules {
+    programs: Shared<Locked<HashMap<PathBuf, Shared<[Statement]>>>>,
+}
+
+impl ParsedModules {
+    /// The statements of `file`, a canonical path, parsed on first use.
+    pub(crate) fn get(&self, file: &Path) -> Result<Shared<[Statement]>, EvaluatorError> {
+        if let Some(statements) = self.programs.borrow().get(file) {
+            return Ok(Shared::clone(statements));
+        }
+
+        let source =
+            fs::read(file).map_err(|err| EvaluatorError::File(file.display().to_string(), err))?;
+        let program = Parser::new(Lexer::new(&source))
+            .parse_program()
+            .map_err(|err| {
+                EvaluatorError::ModuleSyntax(file.display().to_string(), Box::new(err))
+            })?;
+
+        let statements: Shared<[Statement]> = program.statements.into();
+        self.programs
+            .borrow_mut()
+            .insert(file.to_path_buf(), Shared::clone(&statements));
+
+        Ok(statements)
+    }
+
+    /// The number of files parsed.
+    pub fn len(&self) -> usize {
+        self.programs.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub fn resolve(importer: Option<&Path>, path: &str) -> PathBuf {
+    let mut file = match importer.and_then(Path::parent) {
+        Some(dir) => dir.join(path),
+        None => PathBuf::from(path),
+    };
+
+    if file.extension().is_none() {
+        file.set_extension("bl");
+    }
+
+    file
+}
+
+/// The name a bare `import "path";` statement binds the module to, the file
+/// name without its extension.
+pub fn name(file: &Path) -> Option<&str> {
+    let name = file.file_stem()?.to_str()?;
+    let mut chars = name.chars();
+
+    let valid = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+
+    valid.then_some(name)
+}
+
+pub fn namespace(env: &Environment) -> Object {
+    let namespace = Object::dict(
+        env.locals()
+            .into_iter()
+            .map(|(name, value)| (HashKey::String(name.as_str().into()), value))
+            .collect(),
+    );
+
+    if let Object::Dict(ref dict) = namespace {
+        dict.freeze();
+    }
+
+    namespace
+}
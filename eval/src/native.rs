@@ -0,0 +1,159 @@
+use std::any::Any;
+
+use crate::{
+    builtins::{arguments, IntoResult},
+    error::EvaluatorError,
+    object::Object,
+    sync::{Shareable, Shared},
+};
+
+#[cfg(feature = "derive")]
+pub use belalang_derive::ScriptObject;
+
+/// An object defined by the host application, e.g. a database connection or
+/// a file handle, that scripts can hold and pass around as an opaque value.
+///
+/// Natives are displayed through [`std::fmt::Display`]. Everything else has
+/// a default, so a handle that scripts only pass back to the host needs
+/// nothing more than [`NativeObject::type_name`] and [`NativeObject::as_any`].
+pub trait NativeObject: std::fmt::Debug + std::fmt::Display + Shareable {
+    /// Name of the type, used in error messages.
+    fn type_name(&self) -> &str;
+
+    /// Allows downcasting back to the concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Compares against another object. Two handles to the same native are
+    /// always equal, this is only consulted for distinct ones.
+    fn equals(&self, _other: &Object) -> bool {
+        false
+    }
+
+    /// Property access, `native.name`. Properties named after operator hooks
+    /// (`__add__`, `__eq__`, ...) overload those operators.
+    fn get(&self, _name: &str) -> Option<Object> {
+        None
+    }
+
+    /// Calls the native as a function, `native(args)`.
+    fn call(&self, _args: Vec<Object>) -> Result<Object, EvaluatorError> {
+        Err(EvaluatorError::NotAFunction)
+    }
+}
+
+/// A host struct whose fields scripts read as properties and whose methods
+/// they call, `object.field` and `object.method(args)`. Methods take `&self`,
+/// so state they change has to be behind a `Cell` or `RefCell`, or atomics
+/// and locks with the `sync` feature.
+///
+/// With the `derive` feature, `#[derive(ScriptObject)]` implements it: every
+/// field is exposed unless marked `#[script(skip)]`, and methods are listed
+/// as `#[script(methods(name, ...))]`. Fields convert with `Into<Object>`
+/// and method arguments like those of [`crate::builtins::TypedFn`].
+pub trait ScriptObject: std::fmt::Debug + Shareable + 'static {
+    /// Name of the type, used in error messages.
+    fn type_name(&self) -> &str;
+
+    fn field(&self, name: &str) -> Option<Object>;
+
+    fn has_method(&self, name: &str) -> bool;
+
+    /// Calls a method by name. Only called if [`ScriptObject::has_method`].
+    fn call_method(&self, name: &str, args: Vec<Object>) -> Result<Object, EvaluatorError>;
+
+    /// Hands the value to scripts. It's displayed with its `Debug` output
+    /// and downcasts back to `Self` through [`NativeObject::as_any`].
+    fn into_object(self) -> Object
+    where
+        Self: Sized,
+    {
+        Object::Native(Shared::new(Script(Shared::new(self))))
+    }
+}
+
+/// A method usable by a [`ScriptObject`], `Fn(&T, A, B, ...) -> R`.
+pub trait ScriptMethod<T, Args> {
+    fn invoke(&self, object: &T, args: Vec<Object>) -> Result<Object, EvaluatorError>;
+}
+
+macro_rules! script_method {
+    ($($arg:ident),*) => {
+        impl<F, T, R, $($arg),*> ScriptMethod<T, ($($arg,)*)> for F
+        where
+            F: Fn(&T, $($arg),*) -> R,
+            R: IntoResult,
+            $($arg: TryFrom<Object>, $arg::Error: Into<EvaluatorError>,)*
+        {
+            #[allow(non_snake_case)]
+            fn invoke(&self, object: &T, args: Vec<Object>) -> Result<Object, EvaluatorError> {
+                let [$($arg),*] = arguments(args)?;
+                self(object, $($arg::try_from($arg).map_err(Into::into)?),*).into_result()
+            }
+        }
+    };
+}
+
+script_method!();
+script_method!(A);
+script_method!(A, B);
+script_method!(A, B, C);
+script_method!(A, B, C, D);
+script_method!(A, B, C, D, E);
+script_method!(A, B, C, D, E, G);
+
+#[derive(Debug)]
+struct Script<T>(Shared<T>);
+
+impl<T: ScriptObject> std::fmt::Display for Script<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl<T: ScriptObject> NativeObject for Script<T> {
+    fn type_name(&self) -> &str {
+        self.0.type_name()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        &*self.0
+    }
+
+    fn get(&self, name: &str) -> Option<Object> {
+        if self.0.has_method(name) {
+            return Some(Object::Native(Shared::new(Method {
+                object: Shared::clone(&self.0),
+                name: name.into(),
+            })));
+        }
+
+        self.0.field(name)
+    }
+}
+
+/// A method of a [`ScriptObject`] bound to its value.
+#[derive(Debug)]
+struct Method<T> {
+    object: Shared<T>,
+    name: Box<str>,
+}
+
+impl<T: ScriptObject> std::fmt::Display for Method<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<method {}.{}>", self.object.type_name(), self.name)
+    }
+}
+
+impl<T: ScriptObject> NativeObject for Method<T> {
+    fn type_name(&self) -> &str {
+        "Method"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn call(&self, args: Vec<Object>) -> Result<Object, EvaluatorError> {
+        self.object.call_method(&self.name, args)
+    }
+}
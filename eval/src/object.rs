@@ -1,23 +1,625 @@
-use belalang_core::ast;
-use crate::environment::Environment;
+use crate::{
+    environment::Environment,
+    error::EvaluatorError,
+    generator,
+    iterator::Iter,
+    native::NativeObject,
+    sync::{Flag, Locked, Ref, RefMut, Shared},
+    vm::Closure,
+};
+use belalang_core::{ast, token::Token};
+use indexmap::IndexMap;
+use std::collections::HashMap;
 
+/// A function value. When serialized, only the parameters and body are
+/// kept; the captured environment is rebound on deserialization, see
+/// [`Environment`]'s `Deserialize` implementation.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Function {
+    pub params: Vec<ast::Identifier>,
+    pub body: ast::BlockExpression,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub env: Environment,
+}
+
+impl Function {
+    /// Whether the function has a `yield` in its body, which makes calling
+    /// it return an iterator, see [`crate::generator`].
+    pub fn is_generator(&self) -> bool {
+        generator::yields(&self.body)
+    }
+}
+
+/// A value. Arrays and dicts are shared by reference: assigning one or
+/// passing it to a function doesn't copy it, so mutations through any
+/// reference are seen through all of them. `clone()` and `deepcopy()` make
+/// copies explicitly, `freeze()` forbids further mutation.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Object {
     Null,
     Integer(i64),
     Float(f64),
     Boolean(bool),
-    String(String),
+    String(Shared<str>),
     Builtin(String),
-    Array(Vec<Object>),
+    #[cfg_attr(feature = "serde", serde(serialize_with = "acyclic::array"))]
+    Array(Shared<Collection<Vec<Object>>>),
+    #[cfg_attr(feature = "serde", serde(with = "dict_pairs"))]
+    Dict(Shared<Collection<IndexMap<HashKey, Object>>>),
+    Function(Shared<Function>),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Closure(Shared<Closure>),
+    Type(Type),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Iterator(Shared<Iter>),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Native(Shared<dyn NativeObject>),
+}
+
+/// The contents of an array or dict, which can be frozen to make them
+/// immutable. Freezing is shallow: collections nested inside a frozen one
+/// can still be mutated. Whether a collection is frozen isn't serialized.
+#[derive(Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Collection<T> {
+    items: Locked<T>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    frozen: Flag,
+}
+
+impl<T> Collection<T> {
+    pub fn new(items: T) -> Self {
+        Self {
+            items: Locked::new(items),
+            frozen: Flag::new(false),
+        }
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.items.borrow()
+    }
+
+    /// Borrows the contents for mutation, failing if the collection is
+    /// frozen.
+    pub fn borrow_mut(&self) -> Result<RefMut<'_, T>, EvaluatorError> {
+        if self.frozen.get() {
+            return Err(EvaluatorError::FrozenValue);
+        }
+
+        Ok(self.items.borrow_mut())
+    }
+
+    pub fn freeze(&self) {
+        self.frozen.set(true);
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.get()
+    }
+
+    pub(crate) fn replace(&self, items: T) -> T {
+        self.items.replace(items)
+    }
+
+    pub(crate) fn set_frozen(&self, frozen: bool) {
+        self.frozen.set(frozen);
+    }
+}
+
+/// A dict key. Only immutable values with a well-defined equality can be
+/// keys: integers, strings and booleans.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HashKey {
+    Integer(i64),
+    String(Shared<str>),
+    Boolean(bool),
+}
+
+impl TryFrom<Object> for HashKey {
+    type Error = EvaluatorError;
+
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        match value {
+            Object::Integer(i) => Ok(HashKey::Integer(i)),
+            Object::String(s) => Ok(HashKey::String(s)),
+            Object::Boolean(b) => Ok(HashKey::Boolean(b)),
+            _ => Err(EvaluatorError::UnhashableKey(value)),
+        }
+    }
+}
+
+impl From<HashKey> for Object {
+    fn from(value: HashKey) -> Self {
+        match value {
+            HashKey::Integer(i) => Object::Integer(i),
+            HashKey::String(s) => Object::String(s),
+            HashKey::Boolean(b) => Object::Boolean(b),
+        }
+    }
+}
+
+impl TryFrom<Object> for i64 {
+    type Error = EvaluatorError;
+
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        match value {
+            Object::Integer(i) => Ok(i),
+            _ => Err(EvaluatorError::UnexpectedType("Int", value)),
+        }
+    }
+}
+
+/// Ints are converted too, so that scripts don't have to write `1.0`.
+impl TryFrom<Object> for f64 {
+    type Error = EvaluatorError;
+
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        match value {
+            Object::Integer(i) => Ok(i as f64),
+            Object::Float(f) => Ok(f),
+            _ => Err(EvaluatorError::UnexpectedType("Int or Float", value)),
+        }
+    }
+}
+
+impl TryFrom<Object> for bool {
+    type Error = EvaluatorError;
+
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        match value {
+            Object::Boolean(b) => Ok(b),
+            _ => Err(EvaluatorError::UnexpectedType("Bool", value)),
+        }
+    }
+}
+
+impl TryFrom<Object> for Shared<str> {
+    type Error = EvaluatorError;
+
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        match value {
+            Object::String(s) => Ok(s),
+            _ => Err(EvaluatorError::UnexpectedType("String", value)),
+        }
+    }
+}
+
+impl TryFrom<Object> for String {
+    type Error = EvaluatorError;
+
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        Shared::<str>::try_from(value).map(|s| s.to_string())
+    }
+}
+
+impl From<()> for Object {
+    fn from(_: ()) -> Self {
+        Object::Null
+    }
+}
+
+impl From<i64> for Object {
+    fn from(value: i64) -> Self {
+        Object::Integer(value)
+    }
+}
+
+impl From<f64> for Object {
+    fn from(value: f64) -> Self {
+        Object::Float(value)
+    }
+}
+
+impl From<bool> for Object {
+    fn from(value: bool) -> Self {
+        Object::Boolean(value)
+    }
+}
+
+impl From<Shared<str>> for Object {
+    fn from(value: Shared<str>) -> Self {
+        Object::String(value)
+    }
+}
+
+impl From<String> for Object {
+    fn from(value: String) -> Self {
+        Object::String(value.into())
+    }
+}
+
+impl From<&str> for Object {
+    fn from(value: &str) -> Self {
+        Object::String(value.into())
+    }
+}
+
+/// Arrays are converted element by element.
+impl<T> TryFrom<Object> for Vec<T>
+where
+    T: TryFrom<Object>,
+    T::Error: Into<EvaluatorError>,
+{
+    type Error = EvaluatorError;
+
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        let Object::Array(ref elements) = value else {
+            return Err(EvaluatorError::UnexpectedType("Array", value));
+        };
+
+        let elements = elements.borrow().clone();
+        elements
+            .into_iter()
+            .map(|element| T::try_from(element).map_err(Into::into))
+            .collect()
+    }
+}
+
+/// Only dicts whose keys are all strings convert.
+impl<T> TryFrom<Object> for HashMap<String, T>
+where
+    T: TryFrom<Object>,
+    T::Error: Into<EvaluatorError>,
+{
+    type Error = EvaluatorError;
+
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        let Object::Dict(ref dict) = value else {
+            return Err(EvaluatorError::UnexpectedType("Dict", value));
+        };
+
+        let pairs = dict.borrow().clone();
+        pairs
+            .into_iter()
+            .map(|(key, value)| {
+                let key = String::try_from(Object::from(key))?;
+                Ok((key, T::try_from(value).map_err(Into::into)?))
+            })
+            .collect()
+    }
+}
+
+/// Null converts to `None`. `Option<Object>` is covered by the standard
+/// `From<T> for Option<T>` instead, which makes null `Some(Object::Null)`.
+macro_rules! try_from_optional {
+    ($($ty:ty $(where $param:ident)?),* $(,)?) => {$(
+        impl$(<$param>)? TryFrom<Object> for Option<$ty>
+        where
+            $ty: TryFrom<Object>,
+            <$ty as TryFrom<Object>>::Error: Into<EvaluatorError>,
+        {
+            type Error = EvaluatorError;
+
+            fn try_from(value: Object) -> Result<Self, Self::Error> {
+                match value {
+                    Object::Null => Ok(None),
+                    value => <$ty>::try_from(value).map(Some).map_err(Into::into),
+                }
+            }
+        }
+    )*};
+}
+
+try_from_optional!(
+    i64,
+    f64,
+    bool,
+    Shared<str>,
+    String,
+    Vec<T> where T,
+    HashMap<String, T> where T,
+);
+
+impl<T: Into<Object>> From<Vec<T>> for Object {
+    fn from(value: Vec<T>) -> Self {
+        Object::array(value.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Keys are sorted, since a `HashMap` has no order of its own.
+impl<T: Into<Object>> From<HashMap<String, T>> for Object {
+    fn from(value: HashMap<String, T>) -> Self {
+        let mut pairs = value.into_iter().collect::<Vec<_>>();
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Object::dict(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (HashKey::String(key.into()), value.into()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: Into<Object>> From<Option<T>> for Object {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Object::Null, Into::into)
+    }
+}
+
+/// Dicts are serialized as a list of key-value pairs, since most formats
+/// only allow string keys.
+#[cfg(feature = "serde")]
+mod dict_pairs {
+    use super::{Collection, HashKey, Object, Shared};
+    use indexmap::IndexMap;
+    use serde::Deserialize;
+
+    pub fn serialize<S: serde::Serializer>(
+        dict: &Shared<Collection<IndexMap<HashKey, Object>>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let _visit = super::acyclic::Visit::enter(dict)?;
+        serializer.collect_seq(dict.borrow().iter())
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Shared<Collection<IndexMap<HashKey, Object>>>, D::Error> {
+        let pairs = Vec::<(HashKey, Object)>::deserialize(deserializer)?;
+        Ok(Shared::new(Collection::new(pairs.into_iter().collect())))
+    }
+}
+
+/// Arrays and dicts containing themselves can't be serialized, which is an
+/// error instead of recursing forever.
+#[cfg(feature = "serde")]
+mod acyclic {
+    use super::{Collection, Object, Shared};
+    use serde::ser::Error;
+    use std::{cell::RefCell, collections::HashSet};
+
+    thread_local! {
+        /// The collections being serialized on this thread, by address.
+        static VISITING: RefCell<HashSet<usize>> = RefCell::default();
+    }
+
+    /// A collection being serialized, until dropped.
+    pub struct Visit(usize);
+
+    impl Visit {
+        pub fn enter<T, E: Error>(collection: &Shared<Collection<T>>) -> Result<Self, E> {
+            let address = Shared::as_ptr(collection) as *const () as usize;
+
+            if VISITING.with_borrow_mut(|visiting| visiting.insert(address)) {
+                Ok(Visit(address))
+            } else {
+                Err(E::custom("cyclic value"))
+            }
+        }
+    }
+
+    impl Drop for Visit {
+        fn drop(&mut self) {
+            VISITING.with_borrow_mut(|visiting| visiting.remove(&self.0));
+        }
+    }
+
+    pub fn array<S: serde::Serializer>(
+        array: &Shared<Collection<Vec<Object>>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let _visit = Visit::enter(array)?;
+        serializer.collect_seq(array.borrow().iter())
+    }
+}
+
+/// The type of an object, as returned by the `type()` builtin. Types are
+/// values themselves and are bound to their names, e.g. `Int`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Type {
+    Null,
+    Int,
+    Float,
+    Bool,
+    String,
+    Array,
+    Dict,
+    Function,
+    Type,
+    Iterator,
+    /// Native objects are typed by their [`NativeObject::type_name`].
+    Native(Shared<str>),
+}
+
+impl Type {
+    /// Looks up a type by the name scripts refer to it with.
+    pub fn from_name(name: &str) -> Option<Type> {
+        Some(match name {
+            "Null" => Type::Null,
+            "Int" => Type::Int,
+            "Float" => Type::Float,
+            "Bool" => Type::Bool,
+            "String" => Type::String,
+            "Array" => Type::Array,
+            "Dict" => Type::Dict,
+            "Function" => Type::Function,
+            "Type" => Type::Type,
+            "Iterator" => Type::Iterator,
+            _ => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Type::Null => "Null",
+            Type::Int => "Int",
+            Type::Float => "Float",
+            Type::Bool => "Bool",
+            Type::String => "String",
+            Type::Array => "Array",
+            Type::Dict => "Dict",
+            Type::Function => "Function",
+            Type::Type => "Type",
+            Type::Iterator => "Iterator",
+            Type::Native(name) => name,
+        })
+    }
+}
+
+/// Bytes an element takes in an array, see [`Object::size`].
+pub(crate) const ARRAY_SLOT: usize = std::mem::size_of::<Object>();
+
+/// Bytes an entry takes in a dict, see [`Object::size`].
+pub(crate) const DICT_SLOT: usize = std::mem::size_of::<(HashKey, Object)>();
+
+impl Object {
+    pub fn array(elements: Vec<Object>) -> Object {
+        Object::Array(Shared::new(Collection::new(elements)))
+    }
+
+    pub fn dict(pairs: IndexMap<HashKey, Object>) -> Object {
+        Object::Dict(Shared::new(Collection::new(pairs)))
+    }
+
+    /// Bytes of the object's text or collection slots, without what the
+    /// slots point to. What [`crate::metrics::Limits::memory`] counts.
+    pub fn size(&self) -> usize {
+        match self {
+            Object::String(s) => s.len(),
+            Object::Array(elements) => elements.borrow().len() * ARRAY_SLOT,
+            Object::Dict(pairs) => pairs.borrow().len() * DICT_SLOT,
+            _ => 0,
+        }
+    }
+
+    pub fn type_of(&self) -> Type {
+        match self {
+            Object::Null => Type::Null,
+            Object::Integer(_) => Type::Int,
+            Object::Float(_) => Type::Float,
+            Object::Boolean(_) => Type::Bool,
+            Object::String(_) => Type::String,
+            Object::Array(_) => Type::Array,
+            Object::Dict(_) => Type::Dict,
+            Object::Builtin(_) | Object::Function(_) | Object::Closure(_) => Type::Function,
+            Object::Type(_) => Type::Type,
+            Object::Iterator(_) => Type::Iterator,
+            Object::Native(native) => Type::Native(native.type_name().into()),
+        }
+    }
+
+    /// Truthiness, as used by `bool()`: null, `false`, zero and empty
+    /// strings and collections are falsy, everything else is truthy.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Object::Null => false,
+            Object::Boolean(b) => *b,
+            Object::Integer(i) => *i != 0,
+            Object::Float(f) => *f != 0.0,
+            Object::String(s) => !s.is_empty(),
+            Object::Array(elements) => !elements.borrow().is_empty(),
+            Object::Dict(dict) => !dict.borrow().is_empty(),
+            _ => true,
+        }
+    }
+
+    /// Looks up a member by name. Members are how objects hook into
+    /// operators, see [`operator_hook`]. The members of a dict are its
+    /// string-keyed entries, which lets dicts act as records.
+    pub fn member(&self, name: &str) -> Option<Object> {
+        match self {
+            Object::Dict(dict) => dict.borrow().get(&HashKey::String(name.into())).cloned(),
+            Object::Native(native) => native.get(name),
+            _ => None,
+        }
+    }
+
+    /// Copies an array or dict, sharing its elements with the original.
+    /// Other values are returned as they are. Copies are never frozen.
+    pub fn shallow_copy(&self) -> Object {
+        match self {
+            Object::Array(elements) => Object::array(elements.borrow().clone()),
+            Object::Dict(dict) => Object::dict(dict.borrow().clone()),
+            object => object.clone(),
+        }
+    }
+
+    /// Copies an array or dict along with every array and dict nested in it.
+    /// Values shared within the original, including cycles, are shared the
+    /// same way within the copy. Copies are never frozen.
+    pub fn deep_copy(&self) -> Object {
+        self.deep_copy_with(&mut HashMap::new())
+    }
+
+    fn deep_copy_with(&self, copies: &mut HashMap<*const (), Object>) -> Object {
+        match self {
+            Object::Array(elements) => {
+                let ptr = Shared::as_ptr(elements) as *const ();
+                if let Some(copy) = copies.get(&ptr) {
+                    return copy.clone();
+                }
+
+                let copy = Shared::new(Collection::new(Vec::new()));
+                copies.insert(ptr, Object::Array(Shared::clone(&copy)));
+
+                let elements = elements
+                    .borrow()
+                    .iter()
+                    .map(|element| element.deep_copy_with(copies))
+                    .collect();
+                copy.replace(elements);
+
+                Object::Array(copy)
+            }
+            Object::Dict(dict) => {
+                let ptr = Shared::as_ptr(dict) as *const ();
+                if let Some(copy) = copies.get(&ptr) {
+                    return copy.clone();
+                }
+
+                let copy = Shared::new(Collection::new(IndexMap::new()));
+                copies.insert(ptr, Object::Dict(Shared::clone(&copy)));
+
+                let pairs = dict
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.deep_copy_with(copies)))
+                    .collect();
+                copy.replace(pairs);
+
+                Object::Dict(copy)
+            }
+            object => object.clone(),
+        }
+    }
+}
 
-    Function {
-        params: Vec<ast::Identifier>,
-        body: ast::BlockExpression,
-        env: Environment,
-    },
+/// Name of the member an object can define to overload an infix operator.
+///
+/// `!=` has no hook of its own; it is the negation of `__eq__`.
+pub fn operator_hook(operator: &Token) -> Option<&'static str> {
+    Some(match operator {
+        Token::Add => "__add__",
+        Token::Sub => "__sub__",
+        Token::Mul => "__mul__",
+        Token::Div => "__div__",
+        Token::Mod => "__mod__",
+        Token::Eq | Token::Ne => "__eq__",
+        Token::Lt => "__lt__",
+        Token::Le => "__le__",
+        Token::Gt => "__gt__",
+        Token::Ge => "__ge__",
+        _ => return None,
+    })
 }
 
+/// Member used to overload indexing, `obj[index]`.
+pub const INDEX_HOOK: &str = "__index__";
+
+/// Member used to overload string conversion.
+pub const STR_HOOK: &str = "__str__";
+
 impl std::fmt::Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -26,9 +628,109 @@ impl std::fmt::Display for Object {
             Self::Float(fl) => f.write_str(&format!("{}", fl)),
             Self::Boolean(b) => f.write_str(&format!("{}", b)),
             Self::String(s) => f.write_str(s),
-            Self::Array(a) => f.write_str(&format!("{:?}", a)),
-            _ => f.write_str(""),
+            Self::Builtin(name) => write!(f, "<builtin {}>", name),
+            Self::Array(_) | Self::Dict(_) => write!(f, "{}", self.inspect()),
+            Self::Function(function) => {
+                let params = function
+                    .params
+                    .iter()
+                    .map(|param| param.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(f, "<fn({})>", params)
+            }
+            Self::Closure(closure) => {
+                let params = closure
+                    .function
+                    .params
+                    .iter()
+                    .map(|param| param.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(f, "<fn({})>", params)
+            }
+            Self::Type(t) => write!(f, "{}", t),
+            Self::Iterator(_) => f.write_str("<iterator>"),
+            Self::Native(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl Object {
+    /// Formats the object the way the REPL and error messages show it:
+    /// strings are quoted and escaped, floats keep their decimal point and
+    /// collection elements are inspected too. [`std::fmt::Display`] is for
+    /// the raw text that gets printed.
+    pub fn inspect(&self) -> Inspect<'_> {
+        Inspect(self)
+    }
+}
+
+pub struct Inspect<'a>(&'a Object);
+
+impl std::fmt::Display for Inspect<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_inspect(self.0, f, &mut Vec::new())
+    }
+}
+
+/// `seen` holds the collections currently being written, so a collection
+/// containing itself is shown as `[...]` instead of recursing forever.
+fn write_inspect(
+    object: &Object,
+    f: &mut std::fmt::Formatter<'_>,
+    seen: &mut Vec<*const ()>,
+) -> std::fmt::Result {
+    match object {
+        Object::String(s) => write!(f, "{:?}", s),
+        Object::Float(fl) => write!(f, "{:?}", fl),
+        Object::Array(elements) => {
+            let ptr = Shared::as_ptr(elements) as *const ();
+            if seen.contains(&ptr) {
+                return f.write_str("[...]");
+            }
+            seen.push(ptr);
+
+            f.write_str("[")?;
+            for (i, element) in elements.borrow().iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write_inspect(element, f, seen)?;
+            }
+            f.write_str("]")?;
+
+            seen.pop();
+            Ok(())
+        }
+        Object::Dict(dict) => {
+            let ptr = Shared::as_ptr(dict) as *const ();
+            if seen.contains(&ptr) {
+                return f.write_str("[...]");
+            }
+
+            let dict = dict.borrow();
+            if dict.is_empty() {
+                return f.write_str("[:]");
+            }
+            seen.push(ptr);
+
+            f.write_str("[")?;
+            for (i, (key, value)) in dict.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}: ", Object::from(key.clone()).inspect())?;
+                write_inspect(value, f, seen)?;
+            }
+            f.write_str("]")?;
+
+            seen.pop();
+            Ok(())
         }
+        object => write!(f, "{}", object),
     }
 }
 
@@ -36,8 +738,15 @@ impl PartialEq for Object {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Object::Integer(a), Object::Integer(b)) => a == b,
+            (Object::Float(a), Object::Float(b)) => a == b,
             (Object::Boolean(a), Object::Boolean(b)) => a == b,
+            (Object::String(a), Object::String(b)) => a == b,
             (Object::Null, Object::Null) => true,
+            (Object::Type(a), Object::Type(b)) => a == b,
+            (Object::Iterator(a), Object::Iterator(b)) => Shared::ptr_eq(a, b),
+            (Object::Native(a), Object::Native(b)) if Shared::ptr_eq(a, b) => true,
+            (Object::Native(a), _) => a.equals(other),
+            (_, Object::Native(b)) => b.equals(self),
             _ => false,
         }
     }
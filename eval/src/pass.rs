@@ -0,0 +1,86 @@
+//! Optimizations of the AST, run in order on a program before it is
+//! evaluated or compiled.
+//!
+//! ```
+//! # use belalang_eval::{interpreter::Interpreter, pass::Passes};
+//! let mut passes = Passes::standard();
+//! passes.set_enabled("fold", false);
+//!
+//! let mut interpreter = Interpreter::builder().passes(passes).build();
+//! assert_eq!(interpreter.eval_str("2 * 3 + 4;").unwrap().to_string(), "10");
+//! ```
+
+use crate::{
+    fold::ConstantFolding,
+    sync::{Shareable, Shared},
+};
+use belalang_core::ast::Program;
+
+/// Rewrites a program into one that does the same.
+pub trait Pass: Shareable {
+    /// What the pass is enabled and disabled by.
+    fn name(&self) -> &str;
+
+    fn run(&self, program: &mut Program);
+}
+
+/// Passes run in the order they were added, each on what the ones before it
+/// made. There are none by default.
+#[derive(Clone, Default)]
+pub struct Passes {
+    passes: Vec<Entry>,
+}
+
+#[derive(Clone)]
+struct Entry {
+    pass: Shared<dyn Pass>,
+    enabled: bool,
+}
+
+impl Passes {
+    /// Constant folding.
+    pub fn standard() -> Self {
+        let mut passes = Self::default();
+        passes.add(ConstantFolding);
+        passes
+    }
+
+    /// Adds an enabled pass that runs after the ones already added.
+    pub fn add<P: Pass + 'static>(&mut self, pass: P) {
+        self.passes.push(Entry {
+            pass: Shared::new(pass),
+            enabled: true,
+        });
+    }
+
+    /// Enables or disables the passes named `name`. Returns whether there
+    /// were any.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        let mut found = false;
+
+        for entry in &mut self.passes {
+            if entry.pass.name() == name {
+                entry.enabled = enabled;
+                found = true;
+            }
+        }
+
+        found
+    }
+
+    /// The names of the enabled passes, in the order they run.
+    pub fn enabled(&self) -> impl Iterator<Item = &str> {
+        self.passes
+            .iter()
+            .filter(|entry| entry.enabled)
+            .map(|entry| entry.pass.name())
+    }
+
+    pub fn run(&self, program: &mut Program) {
+        for entry in &self.passes {
+            if entry.enabled {
+                entry.pass.run(program);
+            }
+        }
+    }
+}
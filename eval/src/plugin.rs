@@ -0,0 +1,72 @@
+//! Builtins loaded from shared libraries at runtime.
+//!
+//! A plugin is a `cdylib` crate depending on this one that exports its
+//! builtins with [`export_plugin!`](crate::export_plugin):
+//!
+//! ```ignore
+//! use belalang_eval::builtins::Builtins;
+//!
+//! fn register(builtins: &mut Builtins) {
+//!     builtins.register_fn("shout", |s: String| s.to_uppercase());
+//! }
+//!
+//! belalang_eval::export_plugin!(register);
+//! ```
+//!
+//! Builtins cross the library boundary as Rust values, so a plugin has to be
+//! built with the same compiler, the same version of this crate and the same
+//! features as the host. Only the crate version is checked.
+
+use crate::{builtins::Builtins, error::PluginError};
+use libloading::{Library, Symbol};
+use std::path::Path;
+
+/// The version of this crate, which plugins are checked against.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The symbol holding the plugin's `fn() -> &'static str` returning
+/// [`VERSION`].
+pub const VERSION_SYMBOL: &[u8] = b"belalang_plugin_version";
+
+/// The symbol holding the plugin's `fn(&mut Builtins)`.
+pub const REGISTER_SYMBOL: &[u8] = b"belalang_plugin_register";
+
+/// Exports `$register`, a `fn(&mut Builtins)`, as the plugin's entry point.
+#[macro_export]
+macro_rules! export_plugin {
+    ($register:path) => {
+        #[no_mangle]
+        pub fn belalang_plugin_version() -> &'static str {
+            $crate::plugin::VERSION
+        }
+
+        #[no_mangle]
+        pub fn belalang_plugin_register(builtins: &mut $crate::builtins::Builtins) {
+            $register(builtins)
+        }
+    };
+}
+
+/// See [`Builtins::load_plugin`].
+///
+/// # Safety
+///
+/// Loading a library runs its initialization code, and the plugin must
+/// match the host as described in the [module docs](self).
+pub(crate) unsafe fn load(builtins: &mut Builtins, path: &Path) -> Result<(), PluginError> {
+    let library = Library::new(path)?;
+
+    let version: Symbol<fn() -> &'static str> = library.get(VERSION_SYMBOL)?;
+    if version() != VERSION {
+        return Err(PluginError::Version(version().to_string()));
+    }
+
+    let register: Symbol<fn(&mut Builtins)> = library.get(REGISTER_SYMBOL)?;
+    register(builtins);
+
+    // The registered builtins point into the library, and clones of them
+    // can end up anywhere, so it's never unloaded.
+    std::mem::forget(library);
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use std::hash::{BuildHasher, RandomState};
+
+/// The pseudo-random number generator behind the random builtins,
+/// SplitMix64. Not suitable for cryptography.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Default for Rng {
+    /// Seeded from the same source of entropy as `HashMap`.
+    fn default() -> Self {
+        Self::seeded(RandomState::new().hash_one(0u8))
+    }
+}
+
+impl Rng {
+    pub fn seeded(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A float in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// An integer in `[0, bound)`, without modulo bias. `bound` must not be
+    /// zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+
+        loop {
+            let n = self.next_u64();
+            if n < zone {
+                return n % bound;
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! The read-eval-print loop without the reading and printing, so any
+//! frontend can drive it: a terminal, a GUI, a web page or a test.
+
+use crate::{builtins::Builtins, error::Error, interpreter::Interpreter};
+use belalang_core::{
+    error::{LexError, SyntaxError},
+    token::Token,
+};
+
+/// What the REPL answers to a line of input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    /// The value of the input, inspected.
+    Value(String),
+    /// The error message of the input.
+    Error(String),
+    /// The input isn't complete yet, e.g. a block is still open. The next
+    /// line continues it.
+    Incomplete,
+}
+
+/// Evaluates input line by line in an environment that persists between
+/// lines. Input that doesn't parse because it ends early is kept and
+/// continued by the next line.
+#[derive(Default)]
+pub struct ReplEngine {
+    interpreter: Interpreter,
+    pending: String,
+}
+
+impl ReplEngine {
+    pub fn new(builtins: Builtins) -> Self {
+        Self {
+            interpreter: Interpreter::new(builtins),
+            pending: String::new(),
+        }
+    }
+
+    pub fn eval_line(&mut self, line: &str) -> Reply {
+        self.pending.push_str(line);
+        self.pending.push('\n');
+
+        let result = self.interpreter.eval_str(&self.pending);
+
+        if let Err(Error::Syntax(err)) = &result {
+            if matches!(
+                **err,
+                SyntaxError::UnexpectedEOF(_)
+                    | SyntaxError::UnexpectedToken(Token::EOF, _)
+                    | SyntaxError::Lex(
+                        LexError::UnclosedComment(..) | LexError::UnclosedString(..)
+                    )
+            ) {
+                return Reply::Incomplete;
+            }
+        }
+
+        self.pending.clear();
+        self.interpreter.evaluator_mut().collect_garbage();
+
+        match result {
+            Ok(value) => Reply::Value(value.inspect().to_string()),
+            Err(err) => Reply::Error(err.to_string()),
+        }
+    }
+
+    /// The prompt to show before the next line: `>> `, or `.. ` while
+    /// continuing incomplete input.
+    pub fn prompt(&self) -> &'static str {
+        if self.pending.is_empty() {
+            ">> "
+        } else {
+            ".. "
+        }
+    }
+
+    /// Drops incomplete input, e.g. when the user presses Ctrl-C.
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn interpreter(&self) -> &Interpreter {
+        &self.interpreter
+    }
+
+    pub fn interpreter_mut(&mut self) -> &mut Interpreter {
+        &mut self.interpreter
+    }
+}
@@ -0,0 +1,69 @@
+//! What builtins may reach outside the interpreter, for running untrusted
+//! scripts. See [`Builtins::restrict`].
+
+use crate::{builtins::Builtins, error::EvaluatorError};
+
+/// Something outside the interpreter a builtin can reach.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Reading files and directories, including importing script files.
+    FilesystemRead,
+    /// Creating, copying and removing files and directories.
+    FilesystemWrite,
+    /// Reading and setting the process environment.
+    Environment,
+    /// Reading the host's input, see [`Builtins::set_stdin`].
+    Input,
+}
+
+/// A set of capabilities, from none to all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Profile {
+    /// Computing and printing only.
+    Pure,
+    /// [`Profile::Pure`], plus reading the filesystem.
+    FilesystemRead,
+    #[default]
+    Full,
+}
+
+impl Profile {
+    pub fn allows(self, capability: Capability) -> bool {
+        match self {
+            Profile::Pure => false,
+            Profile::FilesystemRead => capability == Capability::FilesystemRead,
+            Profile::Full => true,
+        }
+    }
+}
+
+/// The builtins needing a capability, by qualified name.
+const BUILTINS: &[(&str, Capability)] = &[
+    ("input", Capability::Input),
+    ("read_line", Capability::Input),
+    ("fs.exists", Capability::FilesystemRead),
+    ("fs.list_dir", Capability::FilesystemRead),
+    ("fs.mkdir", Capability::FilesystemWrite),
+    ("fs.remove", Capability::FilesystemWrite),
+    ("fs.copy", Capability::FilesystemWrite),
+    ("env.env_get", Capability::Environment),
+    ("env.env_all", Capability::Environment),
+    ("env.env_set", Capability::Environment),
+];
+
+/// Replaces the builtins `profile` doesn't allow with ones failing with
+/// [`EvaluatorError::NotAllowed`].
+pub(crate) fn restrict(builtins: &mut Builtins, profile: Profile) {
+    for &(name, capability) in BUILTINS {
+        if profile.allows(capability) || builtins.get(name).is_none() {
+            continue;
+        }
+
+        let (module, f) = match name.split_once('.') {
+            Some((module, f)) => (builtins.module_mut(module), f),
+            None => (builtins.globals_mut(), name),
+        };
+
+        module.register(f, move |_, _| Err(EvaluatorError::NotAllowed(name.into())));
+    }
+}
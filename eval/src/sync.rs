@@ -0,0 +1,181 @@
+//! Shared ownership and interior mutability. These are `Rc` and `RefCell`
+//! by default; the `sync` feature swaps them for `Arc` and a read-write lock
+//! so that objects, and with them an [`crate::evaluator::Evaluator`], are
+//! `Send` and `Sync`. Builtins, natives and outputs then have to be too.
+
+#[cfg(not(feature = "sync"))]
+pub use std::{
+    cell::{Ref, RefCell as Locked, RefMut},
+    rc::{Rc as Shared, Weak},
+};
+
+#[cfg(feature = "sync")]
+pub use self::locked::{Locked, Ref, RefMut};
+#[cfg(feature = "sync")]
+pub use std::sync::{Arc as Shared, Weak};
+
+/// What values held by objects must be: anything by default, `Send + Sync`
+/// with the `sync` feature.
+#[cfg(not(feature = "sync"))]
+pub trait Shareable {}
+
+#[cfg(not(feature = "sync"))]
+impl<T: ?Sized> Shareable for T {}
+
+/// What values held by objects must be: anything by default, `Send + Sync`
+/// with the `sync` feature.
+#[cfg(feature = "sync")]
+pub trait Shareable: Send + Sync {}
+
+#[cfg(feature = "sync")]
+impl<T: ?Sized + Send + Sync> Shareable for T {}
+
+/// A `Cell<bool>`, or an atomic one with the `sync` feature.
+#[derive(Debug, Default)]
+pub struct Flag(
+    #[cfg(not(feature = "sync"))] std::cell::Cell<bool>,
+    #[cfg(feature = "sync")] std::sync::atomic::AtomicBool,
+);
+
+impl Flag {
+    pub fn new(value: bool) -> Self {
+        Self(value.into())
+    }
+
+    #[cfg(not(feature = "sync"))]
+    pub fn get(&self) -> bool {
+        self.0.get()
+    }
+
+    #[cfg(not(feature = "sync"))]
+    pub fn set(&self, value: bool) {
+        self.0.set(value);
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn get(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn set(&self, value: bool) {
+        self.0.store(value, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// A `Cell<usize>`, or an atomic one with the `sync` feature.
+#[derive(Debug, Default)]
+pub struct Counter(
+    #[cfg(not(feature = "sync"))] std::cell::Cell<usize>,
+    #[cfg(feature = "sync")] std::sync::atomic::AtomicUsize,
+);
+
+impl Counter {
+    #[cfg(not(feature = "sync"))]
+    pub fn get(&self) -> usize {
+        self.0.get()
+    }
+
+    /// Adds one, returning the new count.
+    #[cfg(not(feature = "sync"))]
+    pub fn increment(&self) -> usize {
+        self.0.set(self.0.get() + 1);
+        self.0.get()
+    }
+
+    #[cfg(not(feature = "sync"))]
+    pub fn decrement(&self) {
+        self.0.set(self.0.get() - 1);
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn get(&self) -> usize {
+        self.0.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Adds one, returning the new count.
+    #[cfg(feature = "sync")]
+    pub fn increment(&self) -> usize {
+        self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn decrement(&self) {
+        self.0.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "sync")]
+mod locked {
+    use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+
+    pub type Ref<'a, T> = RwLockReadGuard<'a, T>;
+    pub type RefMut<'a, T> = RwLockWriteGuard<'a, T>;
+
+    /// The value is borrowed in a conflicting way, see
+    /// [`Locked::try_borrow`] and [`Locked::try_borrow_mut`].
+    #[derive(Debug)]
+    pub struct BorrowError;
+
+    /// A `RefCell` lookalike over an `RwLock`. Where a `RefCell` would
+    /// panic on a conflicting borrow, this blocks instead, so the same
+    /// rules about not holding borrows across calls apply.
+    #[derive(Debug, Default)]
+    pub struct Locked<T: ?Sized>(RwLock<T>);
+
+    impl<T> Locked<T> {
+        pub fn new(value: T) -> Self {
+            Self(RwLock::new(value))
+        }
+
+        pub fn replace(&self, value: T) -> T {
+            std::mem::replace(&mut *self.borrow_mut(), value)
+        }
+
+        pub fn into_inner(self) -> T {
+            self.0.into_inner().unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+
+    impl<T: ?Sized> Locked<T> {
+        pub fn borrow(&self) -> Ref<'_, T> {
+            self.0.read().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        pub fn borrow_mut(&self) -> RefMut<'_, T> {
+            self.0.write().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        /// Fails instead of blocking if the value is mutably borrowed.
+        pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+            match self.0.try_read() {
+                Ok(guard) => Ok(guard),
+                Err(TryLockError::Poisoned(err)) => Ok(err.into_inner()),
+                Err(TryLockError::WouldBlock) => Err(BorrowError),
+            }
+        }
+
+        /// Fails instead of blocking if the value is borrowed.
+        pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowError> {
+            match self.0.try_write() {
+                Ok(guard) => Ok(guard),
+                Err(TryLockError::Poisoned(err)) => Ok(err.into_inner()),
+                Err(TryLockError::WouldBlock) => Err(BorrowError),
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<T: serde::Serialize> serde::Serialize for Locked<T> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.borrow().serialize(serializer)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Locked<T> {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            T::deserialize(deserializer).map(Self::new)
+        }
+    }
+}
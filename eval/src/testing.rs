@@ -0,0 +1,40 @@
+//! Helpers for testing embedded scripts, behind the `test-utils` feature.
+//! The parsing ones are in `belalang_core::testing`.
+
+use crate::{error::EvaluatorError, evaluator::Evaluator, object::Object};
+
+/// Parses and evaluates `input` with a default evaluator, panicking on a
+/// syntax error.
+pub fn eval(input: &str) -> Result<Object, EvaluatorError> {
+    let program = belalang_core::testing::parse(input);
+    Evaluator::default().eval_program(program)
+}
+
+/// Evaluates `$input` and checks the result: `eval!(input, Object::Integer =
+/// 5)`, `eval!(input, Object::Null)` or `eval!(input, Err => "message")`.
+#[macro_export]
+macro_rules! eval {
+    ($input:expr, $variant:path = $expected:expr) => {
+        match $crate::testing::eval(&$input) {
+            Ok($variant(value)) => assert_eq!(value, $expected),
+            Ok(unexpected) => panic!("got unexpected object. got={}", unexpected),
+            Err(err) => panic!("got errors instead. got={}", err),
+        }
+    };
+    ($input:expr, Err => $expected:expr) => {
+        match $crate::testing::eval(&$input) {
+            Ok(unexpected) => panic!("got ok instead. got={}", unexpected),
+            Err(err) => assert_eq!(err.to_string(), $expected),
+        }
+    };
+    ($input:expr, $variant:pat) => {
+        match $crate::testing::eval(&$input) {
+            Ok(obj) => assert!(
+                matches!(obj, $variant),
+                "got unexpected object. got={}",
+                obj
+            ),
+            Err(err) => panic!("got errors instead. got={}", err),
+        }
+    };
+}
@@ -0,0 +1,823 @@
+//! A stack machine running [`Bytecode`] from the [`crate::compiler`]. It
+//! runs on an [`Evaluator`], sharing its scopes, builtins and modules, so
+//! compiled code and evaluated code see the same variables and values.
+//!
+//! Locals live on the stack, in the slots of their function's frame after
+//! its arguments. Closures capture the locals of enclosing functions as
+//! upvalues, which refer to the slot while it's in use and then keep the
+//! value, so they outlive the frame. Globals and the variables of functions
+//! that keep them by name live in the evaluator's scopes.
+//!
+//! Like the evaluator, the VM calls the evaluator's [`crate::hooks::Hooks`],
+//! finding the statements and calls to report in the bytecode's tables.
+
+use crate::{
+    bytecode::{Bytecode, CompiledFunction, Constant, Opcode},
+    environment::Environment,
+    error::EvaluatorError,
+    evaluator::{index_assign, Evaluator},
+    iterator::iterate,
+    object::{Function, HashKey, Object},
+    sync::{Locked, Shared},
+};
+use belalang_core::{symbol::Symbol, token::Token};
+use indexmap::IndexMap;
+use std::{fmt, ops::RangeBounds};
+
+/// A function value made by the VM, closed over the scope it was made in.
+#[derive(Debug)]
+pub struct Closure {
+    pub function: Shared<CompiledFunction>,
+    pub env: Environment,
+    /// The names of the globals of the program it was made by.
+    pub globals: Shared<[Symbol]>,
+    pub upvalues: Vec<Shared<Locked<Upvalue>>>,
+}
+
+/// A variable captured by closures.
+#[derive(Debug)]
+pub enum Upvalue {
+    /// Still in the slot at this index of the stack.
+    Open(usize),
+    /// Moved out of its slot once the slot went out of use.
+    Closed(Object),
+}
+
+/// The values of the VMs running on an evaluator. A VM started while
+/// another runs, e.g. for a builtin calling a closure, pushes on top of it,
+/// so upvalues still open can be reached from any of them.
+#[derive(Debug, Default)]
+pub(crate) struct Stack {
+    values: Vec<Object>,
+    /// The upvalues referring to slots of the stack.
+    open: Vec<Shared<Locked<Upvalue>>>,
+    /// How many VMs are running.
+    running: usize,
+}
+
+impl Stack {
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Object> {
+        self.values.iter()
+    }
+
+    /// The upvalue for the slot at `index`, shared by every closure
+    /// capturing it.
+    fn capture(&mut self, index: usize) -> Shared<Locked<Upvalue>> {
+        let open = self
+            .open
+            .iter()
+            .find(|upvalue| matches!(*upvalue.borrow(), Upvalue::Open(i) if i == index));
+
+        if let Some(upvalue) = open {
+            return upvalue.clone();
+        }
+
+        let upvalue = Shared::new(Locked::new(Upvalue::Open(index)));
+        self.open.push(upvalue.clone());
+        upvalue
+    }
+
+    /// Closes the upvalues of the slots in `slots`.
+    fn close(&mut self, slots: impl RangeBounds<usize>) {
+        let values = &self.values;
+
+        self.open.retain(|upvalue| {
+            let mut upvalue = upvalue.borrow_mut();
+
+            match *upvalue {
+                Upvalue::Open(index) if slots.contains(&index) => {
+                    let value = values.get(index).cloned().unwrap_or(Object::Null);
+                    *upvalue = Upvalue::Closed(value);
+                    false
+                }
+                _ => true,
+            }
+        });
+    }
+}
+
+/// How many calls may be in progress at once, unless [`Limits::frames`]
+/// says otherwise.
+///
+/// [`Limits::frames`]: crate::metrics::Limits::frames
+pub const MAX_FRAMES: usize = 10_000;
+
+/// A call in progress, as shown in stack traces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameInfo {
+    /// The name of the function called, `<fn>` if it has none or
+    /// `<program>` for the program itself.
+    pub function: String,
+    /// The offset of the instruction the frame is at in its code.
+    pub offset: usize,
+}
+
+/// The calls in progress when an error happened, innermost first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackTrace(pub Vec<FrameInfo>);
+
+impl StackTrace {
+    /// How many of the innermost calls are shown.
+    const SHOWN: usize = 10;
+}
+
+/// One line per call, eliding all but the innermost ones.
+impl fmt::Display for StackTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, frame) in self.0.iter().take(Self::SHOWN).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "in {} at {:04}", frame.function, frame.offset)?;
+        }
+
+        if let Some(more) = self
+            .0
+            .len()
+            .checked_sub(Self::SHOWN)
+            .filter(|more| *more > 0)
+        {
+            write!(f, "\n... and {more} more")?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Vm<'a> {
+    ev: &'a mut Evaluator,
+    /// How much of the evaluator's stack was in use when the VM started.
+    start: usize,
+    frames: Vec<Frame>,
+    /// The scopes blocks were entered from, innermost last.
+    scopes: Vec<Environment>,
+    /// The names of the globals of the program being run.
+    globals: Shared<[Symbol]>,
+    /// Whether the VM collects garbage when enough objects were allocated.
+    /// Only a VM that runs while nothing else does knows every value in
+    /// use.
+    collects: bool,
+}
+
+struct Frame {
+    /// The function being called, `None` for the program.
+    closure: Option<Shared<Closure>>,
+    /// Where to carry on in the frame once a call from it returns.
+    ip: usize,
+    /// Where the frame's slots start on the stack. The function called is
+    /// just below them.
+    base: usize,
+    /// How many scopes were entered when the frame was.
+    scopes: usize,
+    /// The caller's scope.
+    env: Environment,
+}
+
+impl<'a> Vm<'a> {
+    pub fn new(ev: &'a mut Evaluator) -> Self {
+        Self {
+            start: ev.stack_mut().values.len(),
+            ev,
+            frames: Vec::new(),
+            scopes: Vec::new(),
+            globals: Shared::from([]),
+            collects: false,
+        }
+    }
+
+    /// Runs a program in the evaluator's current scope and returns the value
+    /// of its last statement.
+    ///
+    /// Unless another VM or a script function is running, it collects
+    /// garbage as set by [`Evaluator::set_gc_thresholds`].
+    pub fn run(&mut self, bytecode: &Bytecode) -> Result<Object, EvaluatorError> {
+        let env = self.ev.env().clone();
+        self.globals = Shared::from(bytecode.globals.as_slice());
+        self.collects = self.ev.stack_mut().running == 0 && self.ev.depth() == 0;
+        self.ev.stack_mut().running += 1;
+
+        let stack = self.stack();
+        let base = stack.len();
+        stack.resize(base + bytecode.locals.len(), Object::Null);
+
+        self.frames.push(Frame {
+            closure: None,
+            ip: 0,
+            base,
+            scopes: self.scopes.len(),
+            env: env.clone(),
+        });
+
+        let result = self.execute(bytecode);
+        self.reset(env);
+
+        if let (Err(err), Some(hooks)) = (&result, self.ev.hooks_mut()) {
+            hooks.on_error(err);
+        }
+
+        result
+    }
+
+    /// Calls a function the VM made, e.g. for a builtin it was passed to.
+    pub fn call(
+        &mut self,
+        closure: Shared<Closure>,
+        args: Vec<Object>,
+    ) -> Result<Object, EvaluatorError> {
+        let env = self.ev.env().clone();
+
+        let argc = args.len();
+        self.ev.stack_mut().running += 1;
+        self.stack().push(Object::Closure(closure.clone()));
+        self.stack().extend(args);
+
+        let result = match self.enter(closure, argc) {
+            Ok(()) => self.execute(&Bytecode::default()),
+            Err(err) => Err(err),
+        };
+
+        self.reset(env);
+        result
+    }
+
+    /// Goes back to the scope the VM started in, also after an error.
+    fn reset(&mut self, env: Environment) {
+        *self.ev.env_mut() = env;
+
+        let stack = self.ev.stack_mut();
+        stack.close(self.start..);
+        stack.values.truncate(self.start);
+        stack.running -= 1;
+
+        let calls = self.frames.iter().filter(|frame| frame.closure.is_some());
+        let depth = self.ev.depth().saturating_sub(calls.count());
+        self.ev.set_depth(depth);
+
+        self.frames.clear();
+        self.scopes.clear();
+    }
+
+    /// Calls a closure with the `argc` arguments on top of the stack, which
+    /// has the closure below them. Arguments a function keeping its
+    /// variables in slots isn't passed are null.
+    fn enter(&mut self, closure: Shared<Closure>, argc: usize) -> Result<(), EvaluatorError> {
+        self.ev.count_call()?;
+
+        let depth = self.ev.depth();
+        let limits = self.ev.limits();
+
+        if let Some(max) = limits.depth {
+            if depth >= max {
+                return Err(EvaluatorError::DepthExceeded(max));
+            }
+        }
+
+        let max = limits.frames.unwrap_or(MAX_FRAMES);
+        if depth >= max {
+            return Err(EvaluatorError::StackOverflow(max, self.trace()));
+        }
+
+        self.ev.set_depth(depth + 1);
+
+        let function = &closure.function;
+        let env = match function.by_name {
+            true => {
+                let mut env = closure.env.capture();
+                for (param, arg) in function.params.iter().zip(self.pop_n(argc)?) {
+                    env.declare(*param, arg);
+                }
+                env
+            }
+            false => {
+                let stack = self.stack();
+                let base = stack.len() - argc;
+                stack.truncate(base + function.params.len());
+                stack.resize(base + function.bytecode.locals.len(), Object::Null);
+                closure.env.clone()
+            }
+        };
+
+        let base = match function.by_name {
+            true => self.stack().len(),
+            false => self.stack().len() - function.bytecode.locals.len(),
+        };
+
+        let caller = std::mem::replace(self.ev.env_mut(), env);
+        self.frames.push(Frame {
+            closure: Some(closure),
+            ip: 0,
+            base,
+            scopes: self.scopes.len(),
+            env: caller,
+        });
+
+        Ok(())
+    }
+
+    /// Runs the innermost frame until the program ends or the outermost
+    /// call returns. `main` is the program's code.
+    fn execute(&mut self, main: &Bytecode) -> Result<Object, EvaluatorError> {
+        let frame = self.frames.last().expect("a frame to run");
+        let mut closure = frame.closure.clone();
+        let mut ip = frame.ip;
+        let mut base = frame.base;
+        let main_globals = self.globals.clone();
+        let hooked = self.ev.hooks_mut().is_some();
+        // Whether a loop went round, back to a statement already reported.
+        let mut looped = false;
+
+        loop {
+            let (bytecode, globals) = match &closure {
+                Some(closure) => (&closure.function.bytecode, &closure.globals),
+                None => (main, &main_globals),
+            };
+
+            // Only the program runs off its end, functions return.
+            let Some(&byte) = bytecode.code.get(ip) else {
+                return self.pop();
+            };
+
+            if hooked && !std::mem::take(&mut looped) {
+                self.report_statements(bytecode, ip);
+            }
+
+            let op = Opcode::try_from(byte)
+                .map_err(|byte| invalid(format!("unknown opcode {byte} at {ip}")))?;
+            ip += 1;
+
+            let operand = match op.has_operand() {
+                true if ip + 2 <= bytecode.code.len() => {
+                    ip += 2;
+                    bytecode.operand(ip - 2) as usize
+                }
+                true => return Err(invalid(format!("missing operand at {ip}"))),
+                false => 0,
+            };
+
+            match op {
+                Opcode::Constant => {
+                    let value = match constant(bytecode, operand)? {
+                        Constant::Integer(i) => Object::Integer(*i),
+                        Constant::Float(f) => Object::Float(*f),
+                        Constant::String(s) => Object::String(s.clone()),
+                        _ => return Err(invalid(format!("constant {operand} is not a value"))),
+                    };
+
+                    self.stack().push(value);
+                }
+                Opcode::Null => self.stack().push(Object::Null),
+                Opcode::True => self.stack().push(Object::Boolean(true)),
+                Opcode::False => self.stack().push(Object::Boolean(false)),
+                Opcode::Pop => {
+                    self.pop()?;
+                }
+                Opcode::Array => {
+                    let elements = self.pop_n(operand)?;
+                    let array = self.ev.allocated(Object::array(elements))?;
+                    self.alloc(array);
+                }
+                Opcode::Dict => {
+                    let mut items = self.pop_n(operand * 2)?.into_iter();
+                    let mut pairs = IndexMap::with_capacity(operand);
+
+                    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+                        pairs.insert(HashKey::try_from(key)?, value);
+                    }
+
+                    let dict = self.ev.allocated(Object::dict(pairs))?;
+                    self.alloc(dict);
+                }
+                Opcode::Not | Opcode::Neg => {
+                    let right = self.pop()?;
+                    let operator = op.operator().unwrap_or(Token::Not);
+                    let value = self.ev.prefix(&operator, right)?;
+                    self.stack().push(value);
+                }
+                Opcode::GetName => {
+                    let name = name(bytecode, operand)?;
+                    let value = self.ev.lookup(name)?;
+                    self.stack().push(value);
+                }
+                Opcode::DeclareName => {
+                    let name = name(bytecode, operand)?;
+                    let value = self.peek()?.clone();
+                    self.ev.check_declare(name)?;
+                    self.ev.env_mut().declare(name, value);
+                }
+                Opcode::SetName => {
+                    let name = name(bytecode, operand)?;
+                    let value = self.peek()?.clone();
+                    self.ev.check_assign(name)?;
+                    self.ev.env_mut().set(name, value);
+                }
+                Opcode::BindName => {
+                    let name = name(bytecode, operand)?;
+                    let value = self.pop()?;
+                    self.ev.env_mut().declare(name, value);
+                }
+                Opcode::GetLocal => {
+                    let value = self.local(base, operand)?.clone();
+                    self.stack().push(value);
+                }
+                Opcode::DeclareLocal => {
+                    let name = local_name(bytecode, operand)?;
+                    if self.ev.is_reserved(name.as_str()) {
+                        return Err(EvaluatorError::OverwriteBuiltin(name.to_string()));
+                    }
+
+                    let value = self.peek()?.clone();
+                    *self.local(base, operand)? = value;
+                }
+                Opcode::SetLocal => {
+                    let value = self.peek()?.clone();
+                    *self.local(base, operand)? = value;
+                }
+                Opcode::GetGlobal => {
+                    let name = global(globals, operand)?;
+                    let value = self.ev.lookup(name)?;
+                    self.stack().push(value);
+                }
+                Opcode::DeclareGlobal => {
+                    let name = global(globals, operand)?;
+                    let value = self.peek()?.clone();
+                    self.ev.check_declare(name)?;
+                    self.ev.env_mut().declare(name, value);
+                }
+                Opcode::SetGlobal => {
+                    let name = global(globals, operand)?;
+                    let value = self.peek()?.clone();
+                    self.ev.check_assign(name)?;
+                    self.ev.env_mut().set(name, value);
+                }
+                Opcode::GetUpvalue => {
+                    let value = match &*upvalue(&closure, operand)?.borrow() {
+                        Upvalue::Open(index) => self.stack()[*index].clone(),
+                        Upvalue::Closed(value) => value.clone(),
+                    };
+
+                    self.stack().push(value);
+                }
+                Opcode::SetUpvalue => {
+                    let value = self.peek()?.clone();
+
+                    match &mut *upvalue(&closure, operand)?.borrow_mut() {
+                        Upvalue::Open(index) => self.stack()[*index] = value,
+                        Upvalue::Closed(closed) => *closed = value,
+                    }
+                }
+                Opcode::CloseUpvalue => {
+                    let index = base + operand;
+                    self.ev.stack_mut().close(index..=index);
+                }
+                Opcode::Redeclare => {
+                    let name = name(bytecode, operand)?;
+                    return Err(EvaluatorError::VariableRedeclaration(name));
+                }
+                Opcode::Index => {
+                    let index = self.pop()?;
+                    let left = self.pop()?;
+                    let value = self.ev.index(left, index)?;
+                    self.stack().push(value);
+                }
+                Opcode::SetIndex => {
+                    let value = self.pop()?;
+                    let index = self.pop()?;
+                    let left = self.pop()?;
+                    let value = index_assign(self.ev, &left, index, value)?;
+                    self.stack().push(value);
+                }
+                Opcode::Member => {
+                    let name = name(bytecode, operand)?;
+                    let object = self.pop()?;
+                    let member = object
+                        .member(name.as_str())
+                        .ok_or(EvaluatorError::UnknownMember(name))?;
+                    self.stack().push(member);
+                }
+                Opcode::Jump => {
+                    // Jumping back is how loops go round.
+                    if operand < ip {
+                        self.ev.check_deadline()?;
+                        self.ev.count_step()?;
+                        looped = true;
+                    }
+
+                    ip = operand;
+                }
+                Opcode::JumpIfFalse => {
+                    if !matches!(self.pop()?, Object::Boolean(true)) {
+                        ip = operand;
+                    }
+                }
+                Opcode::JumpIfNull => {
+                    if matches!(self.peek()?, Object::Null) {
+                        ip = operand;
+                    }
+                }
+                Opcode::JumpIfNotNull => {
+                    if matches!(self.peek()?, Object::Null) {
+                        self.pop()?;
+                    } else {
+                        ip = operand;
+                    }
+                }
+                Opcode::PushScope => {
+                    let scope = self.ev.env().capture();
+                    let outer = std::mem::replace(self.ev.env_mut(), scope);
+                    self.scopes.push(outer);
+                }
+                Opcode::PopScope => {
+                    let outer = self
+                        .scopes
+                        .pop()
+                        .ok_or_else(|| invalid("no scope to leave"))?;
+                    *self.ev.env_mut() = outer;
+                }
+                Opcode::Iter => {
+                    let iterable = self.pop()?;
+                    self.stack().push(Object::Iterator(iterate(iterable)?));
+                }
+                Opcode::Next => {
+                    let iter = match self.peek()? {
+                        Object::Iterator(iter) => iter.clone(),
+                        _ => return Err(invalid(format!("no iterator at {ip}"))),
+                    };
+
+                    match iter.next(self.ev)? {
+                        Some(item) => self.stack().push(item),
+                        None => {
+                            self.pop()?;
+                            ip = operand;
+                        }
+                    }
+                }
+                Opcode::Closure => {
+                    let Constant::Function(function) = constant(bytecode, operand)? else {
+                        return Err(invalid(format!("constant {operand} is not a function")));
+                    };
+
+                    let upvalues = function
+                        .upvalues
+                        .iter()
+                        .map(|capture| {
+                            let index = capture.index as usize;
+
+                            match capture.local {
+                                true if base + index < self.stack().len() => {
+                                    Ok(self.ev.stack_mut().capture(base + index))
+                                }
+                                true => Err(invalid(format!("no local {index}"))),
+                                false => upvalue(&closure, index).cloned(),
+                            }
+                        })
+                        .collect::<Result<_, _>>()?;
+
+                    let closure = Closure {
+                        function: function.clone(),
+                        env: self.ev.env().clone(),
+                        globals: globals.clone(),
+                        upvalues,
+                    };
+
+                    self.alloc(Object::Closure(Shared::new(closure)));
+                }
+                Opcode::Generator => {
+                    let Constant::Generator(generator) = constant(bytecode, operand)? else {
+                        return Err(invalid(format!("constant {operand} is not a generator")));
+                    };
+
+                    let function = Function {
+                        params: generator.params.clone(),
+                        body: generator.body.clone(),
+                        env: self.ev.env().clone(),
+                    };
+
+                    self.alloc(Object::Function(Shared::new(function)));
+                }
+                Opcode::Call => {
+                    let callee = self
+                        .stack()
+                        .len()
+                        .checked_sub(operand + 1)
+                        .ok_or_else(|| invalid("stack underflow"))?;
+
+                    if hooked {
+                        self.report_call(bytecode, ip - 3, callee + 1);
+                    }
+
+                    let callee = match &self.stack()[callee] {
+                        Object::Closure(callee) => Some(callee.clone()),
+                        _ => None,
+                    };
+
+                    match callee {
+                        Some(callee) => {
+                            if let Some(frame) = self.frames.last_mut() {
+                                frame.ip = ip;
+                            }
+
+                            self.enter(callee.clone(), operand)?;
+                            closure = Some(callee);
+                            ip = 0;
+                            base = self.frames.last().expect("the frame entered").base;
+                        }
+                        None => {
+                            let args = self.pop_n(operand)?;
+                            let function = self.pop()?;
+                            let value = self.ev.call_function(function, args)?;
+                            self.alloc(value);
+                        }
+                    }
+                }
+                Opcode::Return => {
+                    let value = self.pop()?;
+                    let frame = self.frames.pop().expect("a frame to return from");
+
+                    if frame.closure.is_none() {
+                        return Err(EvaluatorError::ReturningValue(value));
+                    }
+
+                    *self.ev.env_mut() = frame.env;
+                    self.scopes.truncate(frame.scopes);
+                    self.ev.set_depth(self.ev.depth() - 1);
+
+                    let stack = self.ev.stack_mut();
+                    stack.close(frame.base..);
+                    stack.values.truncate(frame.base - 1);
+
+                    let Some(caller) = self.frames.last() else {
+                        return Ok(value);
+                    };
+
+                    closure = caller.closure.clone();
+                    ip = caller.ip;
+                    base = caller.base;
+                    self.stack().push(value);
+                }
+                Opcode::Yield => return Err(EvaluatorError::UnexpectedYield),
+                Opcode::Import => {
+                    let path = self.pop()?;
+                    let (_, module) = self.ev.import(path)?;
+                    self.stack().push(module);
+                }
+                Opcode::ImportBind => {
+                    let path = self.pop()?;
+                    let (file, module) = self.ev.import(path)?;
+                    let module = self.ev.bind_module(&file, module)?;
+                    self.stack().push(module);
+                }
+                op => {
+                    let right = self.pop()?;
+                    let left = self.pop()?;
+                    let operator = op.operator().expect("a binary operator");
+                    let value = self.ev.infix(&operator, left, right)?;
+                    self.stack().push(value);
+                }
+            }
+        }
+    }
+
+    /// Tells the hooks about the statements whose code starts at `ip`.
+    fn report_statements(&mut self, bytecode: &Bytecode, ip: usize) {
+        let Some(hooks) = self.ev.hooks_mut() else {
+            return;
+        };
+
+        let start = bytecode
+            .statements
+            .partition_point(|(offset, _)| (*offset as usize) < ip);
+        let statements = bytecode.statements[start..]
+            .iter()
+            .take_while(|(offset, _)| *offset as usize == ip);
+
+        for (_, statement) in statements {
+            hooks.on_statement(statement);
+        }
+    }
+
+    /// Tells the hooks about the call at `offset`, whose arguments are on
+    /// the stack from `args` on.
+    fn report_call(&mut self, bytecode: &Bytecode, offset: usize, args: usize) {
+        let Ok(i) = bytecode
+            .calls
+            .binary_search_by_key(&offset, |(offset, _)| *offset as usize)
+        else {
+            return;
+        };
+
+        let args = self.stack()[args..].to_vec();
+        if let Some(hooks) = self.ev.hooks_mut() {
+            hooks.on_call(&bytecode.calls[i].1, &args);
+        }
+    }
+
+    /// The calls in progress, innermost first.
+    fn trace(&self) -> StackTrace {
+        let frames = self.frames.iter().rev().map(|frame| FrameInfo {
+            function: match &frame.closure {
+                Some(closure) => match closure.function.name {
+                    Some(name) => name.to_string(),
+                    None => "<fn>".into(),
+                },
+                None => "<program>".into(),
+            },
+            // Callers are left right after their call instruction.
+            offset: frame.ip.saturating_sub(3),
+        });
+
+        StackTrace(frames.collect())
+    }
+
+    /// Pushes a value the VM made, tracking it for the garbage collector,
+    /// and collects garbage if it's due. Everything in use is on the stack
+    /// or in the VM's scopes then.
+    fn alloc(&mut self, value: Object) {
+        self.ev.env().track(&value);
+        self.stack().push(value);
+
+        if self.collects && self.ev.env().collection_due(self.ev.gc_thresholds()) {
+            let scopes = self
+                .scopes
+                .iter()
+                .chain(self.frames.iter().map(|frame| &frame.env))
+                .collect::<Vec<_>>();
+            self.ev.collect_garbage_in(&scopes);
+        }
+    }
+
+    fn stack(&mut self) -> &mut Vec<Object> {
+        &mut self.ev.stack_mut().values
+    }
+
+    fn pop(&mut self) -> Result<Object, EvaluatorError> {
+        self.stack().pop().ok_or_else(|| invalid("stack underflow"))
+    }
+
+    fn local(&mut self, base: usize, slot: usize) -> Result<&mut Object, EvaluatorError> {
+        self.stack()
+            .get_mut(base + slot)
+            .ok_or_else(|| invalid(format!("no local {slot}")))
+    }
+
+    fn peek(&mut self) -> Result<&Object, EvaluatorError> {
+        self.stack()
+            .last()
+            .ok_or_else(|| invalid("stack underflow"))
+    }
+
+    /// Pops `n` values, returning them in the order they were pushed.
+    fn pop_n(&mut self, n: usize) -> Result<Vec<Object>, EvaluatorError> {
+        let start = self
+            .stack()
+            .len()
+            .checked_sub(n)
+            .ok_or_else(|| invalid("stack underflow"))?;
+
+        Ok(self.stack().split_off(start))
+    }
+}
+
+fn constant(bytecode: &Bytecode, index: usize) -> Result<&Constant, EvaluatorError> {
+    bytecode
+        .constants
+        .get(index)
+        .ok_or_else(|| invalid(format!("no constant {index}")))
+}
+
+fn name(bytecode: &Bytecode, index: usize) -> Result<Symbol, EvaluatorError> {
+    match constant(bytecode, index)? {
+        Constant::Name(name) => Ok(*name),
+        _ => Err(invalid(format!("constant {index} is not a name"))),
+    }
+}
+
+fn upvalue(
+    closure: &Option<Shared<Closure>>,
+    index: usize,
+) -> Result<&Shared<Locked<Upvalue>>, EvaluatorError> {
+    closure
+        .as_ref()
+        .and_then(|closure| closure.upvalues.get(index))
+        .ok_or_else(|| invalid(format!("no upvalue {index}")))
+}
+
+fn local_name(bytecode: &Bytecode, slot: usize) -> Result<Symbol, EvaluatorError> {
+    bytecode
+        .locals
+        .get(slot)
+        .copied()
+        .ok_or_else(|| invalid(format!("no local {slot}")))
+}
+
+fn global(globals: &[Symbol], index: usize) -> Result<Symbol, EvaluatorError> {
+    globals
+        .get(index)
+        .copied()
+        .ok_or_else(|| invalid(format!("no global {index}")))
+}
+
+/// Bytecode the compiler wouldn't make, e.g. from a corrupted file.
+fn invalid(message: impl Into<String>) -> EvaluatorError {
+    EvaluatorError::InvalidData("bytecode", message.into())
+}
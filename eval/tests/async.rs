@@ -0,0 +1,77 @@
+#![cfg(feature = "tokio")]
+
+use belalang_eval::{
+    builtins::Builtins, error::EvaluatorError, interpreter::Interpreter, object::Object,
+};
+use std::time::Duration;
+
+fn builtins() -> Builtins {
+    let mut builtins = Builtins::default();
+    builtins.register_async("sleep_then", |args| async move {
+        let [ms, value]: [Object; 2] = args
+            .try_into()
+            .map_err(|args: Vec<Object>| EvaluatorError::WrongArgumentCount(2, args.len()))?;
+        tokio::time::sleep(Duration::from_millis(i64::try_from(ms)? as u64)).await;
+        Ok(value)
+    });
+    builtins
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn evaluate_async() {
+    let mut interpreter = Interpreter::new(builtins());
+
+    interpreter
+        .eval_str_async("x := sleep_then(10, 41);")
+        .await
+        .unwrap();
+    let result = interpreter.eval_str_async("x + 1;").await.unwrap();
+    assert!(matches!(result, Object::Integer(42)));
+
+    let err = interpreter
+        .eval_str_async("sleep_then(10);")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "wrong number of arguments: expected 2, got 1"
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn evaluate_async_on_current_thread() {
+    let mut interpreter = Interpreter::new(builtins());
+
+    let (result, _) = tokio::join!(
+        interpreter.eval_str_async("sleep_then(20, \"done\");"),
+        tokio::time::sleep(Duration::from_millis(1)),
+    );
+    assert_eq!(result.unwrap().to_string(), "done");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn inside_a_multi_thread_runtime() {
+    let mut interpreter = Interpreter::new(builtins());
+
+    let result = interpreter.eval_str("sleep_then(1, 2);").unwrap();
+    assert!(matches!(result, Object::Integer(2)));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn inside_a_current_thread_runtime() {
+    let mut interpreter = Interpreter::new(builtins());
+
+    let err = interpreter.eval_str("sleep_then(1, 2);").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot wait for an async builtin on a current-thread runtime outside of evaluate_async"
+    );
+}
+
+#[test]
+fn outside_of_a_runtime() {
+    let mut interpreter = Interpreter::new(builtins());
+
+    let result = interpreter.eval_str("sleep_then(1, true);").unwrap();
+    assert!(matches!(result, Object::Boolean(true)));
+}
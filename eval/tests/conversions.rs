@@ -0,0 +1,47 @@
+use belalang_eval::{error::EvaluatorError, interpreter::Interpreter, object::Object};
+use std::collections::HashMap;
+
+#[test]
+fn into_object() {
+    assert_eq!(Object::from(3), Object::Integer(3));
+    assert_eq!(Object::from("a"), Object::String("a".into()));
+    assert_eq!(Object::from(None::<bool>), Object::Null);
+    assert_eq!(Object::from(Some(1.5)), Object::Float(1.5));
+    assert_eq!(
+        Object::from(vec![vec![1], vec![]]).inspect().to_string(),
+        "[[1], []]"
+    );
+
+    let map = HashMap::from([("b".to_string(), Some(true)), ("a".to_string(), None)]);
+    assert_eq!(
+        Object::from(map).inspect().to_string(),
+        r#"["a": null, "b": true]"#
+    );
+}
+
+#[test]
+fn from_object() {
+    let mut interpreter = Interpreter::default();
+    let mut eval = |code: &str| interpreter.eval_str(code).unwrap();
+
+    assert_eq!(i64::try_from(eval("1 + 1;")).unwrap(), 2);
+    assert_eq!(f64::try_from(eval("2;")).unwrap(), 2.0);
+    assert_eq!(String::try_from(eval(r#""hi";"#)).unwrap(), "hi");
+    assert_eq!(
+        Vec::<Option<i64>>::try_from(eval("[1, if (false) { 1 }];")).unwrap(),
+        vec![Some(1), None]
+    );
+
+    let map = HashMap::<String, Vec<bool>>::try_from(eval(r#"["x": [true], "y": []];"#));
+    assert_eq!(
+        map.unwrap(),
+        HashMap::from([("x".into(), vec![true]), ("y".into(), vec![])])
+    );
+
+    let err = Vec::<i64>::try_from(eval(r#"[1, "2"];"#)).unwrap_err();
+    assert!(matches!(err, EvaluatorError::UnexpectedType("Int", _)));
+    let err = HashMap::<String, i64>::try_from(eval(r#"[1: 1];"#)).unwrap_err();
+    assert_eq!(err.to_string(), "expected String, got Int");
+    let err = bool::try_from(eval("[];")).unwrap_err();
+    assert_eq!(err.to_string(), "expected Bool, got Array");
+}
@@ -1,14 +1,21 @@
 use belalang_core::{lexer, parser};
-use belalang_eval::{error::EvaluatorError, evaluator, object};
+use belalang_eval::{
+    builtins::{Builtins, Module},
+    error::EvaluatorError,
+    evaluator,
+    native::NativeObject,
+    object,
+    sync::{Locked, Shared},
+};
+use std::any::Any;
 
 pub fn test_eval(input: String) -> Result<object::Object, EvaluatorError> {
-    let input = input.as_bytes().into();
-    let lexer = lexer::Lexer::new(input);
+    let lexer = lexer::Lexer::new(input.as_bytes());
     let mut parser = parser::Parser::new(lexer);
     let program = parser.parse_program().expect("parser errors");
 
     let mut ev = evaluator::Evaluator::default();
-    return ev.eval_program(program);
+    ev.eval_program(program)
 }
 
 #[macro_export]
@@ -55,6 +62,15 @@ fn integer() {
     eval!("5 * -2;", object::Object::Integer = -10);
     eval!("-5 * -2;", object::Object::Integer = 10);
     eval!("5 % 2;", object::Object::Integer = 1);
+
+    eval!("1 / 0;", Err => "division by zero");
+    eval!("5 % 0;", Err => "division by zero");
+    eval!("9223372036854775807 + 1;", Err => "integer overflow");
+    eval!("-9223372036854775807 - 2;", Err => "integer overflow");
+    eval!("4611686018427387904 * 2;", Err => "integer overflow");
+    eval!("x := -9223372036854775807 - 1; x / -1;", Err => "integer overflow");
+    eval!("x := -9223372036854775807 - 1; x % -1;", Err => "integer overflow");
+    eval!("x := -9223372036854775807 - 1; -x;", Err => "integer overflow");
 }
 
 #[test]
@@ -76,19 +92,991 @@ fn boolean() {
     eval!("1 - 2 < 1;", object::Object::Boolean = true);
     eval!("1 + 2 > 1;", object::Object::Boolean = true);
 
-    eval!("true == true;", object::Object::Boolean = true);
-    eval!("false == false;", object::Object::Boolean = true);
-    eval!("true == false;", object::Object::Boolean = false);
-    eval!("true != false;", object::Object::Boolean = true);
-    eval!("1 < 2 == true;", object::Object::Boolean = true);
+    eval!("true == true;", object::Object::Boolean = true);
+    eval!("false == false;", object::Object::Boolean = true);
+    eval!("true == false;", object::Object::Boolean = false);
+    eval!("true != false;", object::Object::Boolean = true);
+    eval!("1 < 2 == true;", object::Object::Boolean = true);
+
+    eval!("2 <= 1;", object::Object::Boolean = false);
+    eval!("2 <= 2;", object::Object::Boolean = true);
+    eval!("2 <= 3;", object::Object::Boolean = true);
+
+    eval!("2 >= 1;", object::Object::Boolean = true);
+    eval!("2 >= 2;", object::Object::Boolean = true);
+    eval!("2 >= 3;", object::Object::Boolean = false);
+}
+
+#[test]
+fn string_comparison() {
+    eval!(r#""a" < "b";"#, object::Object::Boolean = true);
+    eval!(r#""ab" < "b";"#, object::Object::Boolean = true);
+    eval!(r#""a" < "ab";"#, object::Object::Boolean = true);
+    eval!(r#""B" < "a";"#, object::Object::Boolean = true);
+    eval!(r#""z" < "é";"#, object::Object::Boolean = true);
+    eval!(r#""b" >= "b";"#, object::Object::Boolean = true);
+    eval!(r#""b" <= "a";"#, object::Object::Boolean = false);
+    eval!(r#""b" > "a";"#, object::Object::Boolean = true);
+    eval!(r#""a" == "a";"#, object::Object::Boolean = true);
+    eval!(r#""a" != "a";"#, object::Object::Boolean = false);
+}
+
+#[test]
+fn strings() {
+    eval!(r#""a" + "b";"#, object::Object::String = "ab".into());
+    eval!(
+        r#"import "std/strings"; strings.join(["a", "b", "c"], ", ");"#,
+        object::Object::String = "a, b, c".into()
+    );
+    eval!(
+        r#"import "std/strings"; strings.join([1, 2.5, true], "");"#,
+        object::Object::String = "12.5true".into()
+    );
+    eval!(
+        r#"import "std/strings"; strings.join([], "-");"#,
+        object::Object::String = "".into()
+    );
+
+    eval!(r#""ab" * 3;"#, object::Object::String = "ababab".into());
+    eval!(r#"2 * "ab";"#, object::Object::String = "abab".into());
+    eval!(r#""ab" * 0;"#, object::Object::String = "".into());
+    eval!(r#""ab" * -1;"#, object::Object::String = "".into());
+    eval!(
+        r#""ab" * 100000000;"#,
+        Err => "repeated string would be longer than 16777216 bytes"
+    );
+
+    eval!(
+        r#"import "std/strings"; strings.join(strings.split("a,b,,c", ","), "|");"#,
+        object::Object::String = "a|b||c".into()
+    );
+    eval!(
+        r#"import "std/strings"; len(strings.split("héllo", ""));"#,
+        object::Object::Integer = 5
+    );
+    eval!(
+        r#"import "std/strings"; strings.chars("hé")[1];"#,
+        object::Object::String = "é".into()
+    );
+    eval!(
+        r#"import "std/strings"; strings.trim("  a b \n");"#,
+        object::Object::String = "a b".into()
+    );
+    eval!(
+        r#"import "std/strings"; strings.replace("a-b-c", "-", "+");"#,
+        object::Object::String = "a+b+c".into()
+    );
+    eval!(
+        r#"import "std/strings"; strings.contains("hello", "ell");"#,
+        object::Object::Boolean = true
+    );
+    eval!(
+        r#"import "std/strings"; strings.starts_with("hello", "he");"#,
+        object::Object::Boolean = true
+    );
+    eval!(
+        r#"import "std/strings"; strings.ends_with("hello", "he");"#,
+        object::Object::Boolean = false
+    );
+    eval!(
+        r#"import "std/strings"; strings.to_upper("straße");"#,
+        object::Object::String = "STRASSE".into()
+    );
+    eval!(
+        r#"import "std/strings"; strings.to_lower("ABC");"#,
+        object::Object::String = "abc".into()
+    );
+    eval!(
+        r#"import "std/strings"; strings.find("héllo", "l");"#,
+        object::Object::Integer = 2
+    );
+    eval!(
+        r#"import "std/strings"; strings.find("hello", "z");"#,
+        object::Object::Null
+    );
+
+    eval!(r#"import "std/strings"; strings.trim(1);"#, Err => "expected String, got Int");
+    eval!(r#"import "std/strings"; strings.replace("a", "b");"#, Err => "wrong number of arguments: expected 3, got 2");
+
+    eval!(r#"import "std/strings"; strings.join("abc", "");"#, Err => "expected Array, got String");
+    eval!("import \"std/strings\"; strings.join([], 1);", Err => "expected String, got Int");
+}
+
+#[test]
+fn format() {
+    eval!(
+        r#"format("x={}, y={}", 1, "a");"#,
+        object::Object::String = "x=1, y=a".into()
+    );
+    eval!(
+        r#"format("{1}{0}{1}", "a", "b");"#,
+        object::Object::String = "bab".into()
+    );
+    eval!(r#"format("{{}}");"#, object::Object::String = "{}".into());
+    eval!(
+        r#"format("[{:5}]", 42);"#,
+        object::Object::String = "[   42]".into()
+    );
+    eval!(
+        r#"format("[{:5}]", "ab");"#,
+        object::Object::String = "[ab   ]".into()
+    );
+    eval!(
+        r#"format("[{:*^6}]", "ab");"#,
+        object::Object::String = "[**ab**]".into()
+    );
+    eval!(
+        r#"format("[{:0>4}]", 7);"#,
+        object::Object::String = "[0007]".into()
+    );
+    eval!(
+        r#"format("{:.2}", 3.14159);"#,
+        object::Object::String = "3.14".into()
+    );
+    eval!(
+        r#"format("{:.1}", 2);"#,
+        object::Object::String = "2.0".into()
+    );
+    eval!(
+        r#"format("[{:<8.3}]", 1.5);"#,
+        object::Object::String = "[1.500   ]".into()
+    );
+    eval!(
+        r#"format("{:.2}", "hello");"#,
+        object::Object::String = "he".into()
+    );
+
+    eval!(
+        r#"format("{} {}", 1);"#,
+        Err => "invalid format string: argument 1 is used, but only 1 given"
+    );
+    eval!(r#"format("{}", 1, 2);"#, Err => "invalid format string: argument 1 is never used");
+    eval!(r#"format("{", 1);"#, Err => "invalid format string: unclosed `{`");
+    eval!(r#"format("}");"#, Err => "invalid format string: unmatched `}`");
+    eval!(r#"format("{x}", 1);"#, Err => "invalid format string: invalid argument position `x`");
+    eval!(r#"format("{:?}", 1);"#, Err => "invalid format string: unknown format spec `?`");
+    eval!(r#"format("{:5.}", 1);"#, Err => "invalid format string: missing precision in `5.`");
+    eval!(
+        r#"format("{:.1}", true);"#,
+        Err => "invalid format string: precision is not supported for Bool"
+    );
+    eval!("format(1);", Err => "expected String, got Int");
+    eval!("format();", Err => "wrong number of arguments: expected 1, got 0");
+}
+
+#[test]
+fn print() {
+    let output = Shared::new(Locked::new(Vec::new()));
+
+    let mut builtins = Builtins::default();
+    builtins.set_stdout(Shared::clone(&output));
+
+    let lexer = lexer::Lexer::new(br#"print("a", 1); print(2); println(); println([1], "b");"#);
+    let mut parser = parser::Parser::new(lexer);
+    let program = parser.parse_program().expect("parser errors");
+
+    let mut ev = evaluator::Evaluator::new(builtins);
+    ev.eval_program(program).unwrap();
+
+    assert_eq!(String::from_utf8_lossy(&output.borrow()), "a 12\n[1] b\n");
+}
+
+#[test]
+fn feed() {
+    let parse = |code: &str| {
+        let lexer = lexer::Lexer::new(code.as_bytes());
+        parser::Parser::new(lexer).parse_program().unwrap()
+    };
+
+    let mut ev = evaluator::Evaluator::default();
+    ev.feed(parse("x := 1; inc := fn() { x = x + 1; };"))
+        .unwrap();
+    ev.feed(parse("inc();")).unwrap();
+
+    let err = ev.feed(parse("inc(); undefined; inc();")).unwrap_err();
+    assert_eq!(err.to_string(), "unknown variable: undefined");
+
+    let mut statements = parse("x; x * 10;").statements;
+    statements.truncate(1);
+    assert_eq!(ev.feed(statements).unwrap(), object::Object::Integer(3));
+}
+
+#[test]
+fn eprint() {
+    let output = Shared::new(Locked::new(Vec::new()));
+    let errors = Shared::new(Locked::new(Vec::new()));
+
+    let mut ev = evaluator::Evaluator::default();
+    ev.set_stdout(Shared::clone(&output));
+    ev.set_stderr(Shared::clone(&errors));
+
+    let lexer = lexer::Lexer::new(br#"println("out"); eprint("err", 1); eprintln(2);"#);
+    let program = parser::Parser::new(lexer).parse_program().unwrap();
+    ev.eval_program(program).unwrap();
+
+    assert_eq!(String::from_utf8_lossy(&output.borrow()), "out\n");
+    assert_eq!(String::from_utf8_lossy(&errors.borrow()), "err 12\n");
+}
+
+#[test]
+fn input() {
+    let input = Shared::new(Locked::new(std::io::Cursor::new("Ann\r\n42\nlast")));
+    let output = Shared::new(Locked::new(Vec::new()));
+
+    let mut builtins = Builtins::default();
+    builtins.set_stdin(input);
+    builtins.set_stdout(Shared::clone(&output));
+
+    let lexer = lexer::Lexer::new(
+        br#"name := input("name? "); n := int(read_line()); [name, n, read_line(), read_line()];"#,
+    );
+    let mut parser = parser::Parser::new(lexer);
+    let program = parser.parse_program().expect("parser errors");
+
+    let mut ev = evaluator::Evaluator::new(builtins);
+    let result = ev.eval_program(program).unwrap();
+
+    assert_eq!(result.inspect().to_string(), r#"["Ann", 42, "last", null]"#);
+    assert_eq!(String::from_utf8_lossy(&output.borrow()), "name? ");
+}
+
+#[test]
+fn fs() {
+    let dir = std::env::temp_dir().join(format!("belalang-fs-{}", std::process::id()));
+    let dir = dir.to_str().unwrap().replace('\\', "/");
+    let run = |code: &str| test_eval(format!("dir := \"{dir}\"; {code}"));
+
+    run(r#"import "std/fs"; fs.mkdir(dir + "/sub/deeper");"#).unwrap();
+    std::fs::write(format!("{dir}/a.txt"), "hello").unwrap();
+
+    assert_eq!(
+        run(r#"import "std/fs"; fs.copy(dir + "/a.txt", dir + "/b.txt");"#).unwrap(),
+        object::Object::Integer(5)
+    );
+    assert_eq!(
+        run("import \"std/fs\"; fs.list_dir(dir);")
+            .unwrap()
+            .inspect()
+            .to_string(),
+        r#"["a.txt", "b.txt", "sub"]"#
+    );
+
+    run(r#"import "std/fs"; fs.remove(dir + "/a.txt"); fs.remove(dir + "/sub/deeper");"#).unwrap();
+    assert_eq!(
+        run(r#"import "std/fs"; [fs.exists(dir + "/a.txt"), fs.exists(dir + "/b.txt"), fs.exists(dir + "/sub/deeper")];"#)
+            .unwrap()
+            .inspect()
+            .to_string(),
+        "[false, true, false]"
+    );
+
+    let err = run(r#"import "std/fs"; fs.remove(dir + "/missing");"#).unwrap_err();
+    assert!(err.to_string().starts_with(&format!("{dir}/missing: ")));
+    let err = run("import \"std/fs\"; fs.remove(dir);").unwrap_err();
+    assert!(matches!(err.inner(), EvaluatorError::File(..)));
+    eval!("import \"std/fs\"; fs.exists(1);", Err => "expected String, got Int");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn time() {
+    eval!(
+        "import \"std/time\"; time.time_now() > 1600000000.0;",
+        object::Object::Boolean = true
+    );
+    eval!(
+        "import \"std/time\"; start := time.clock(); time.sleep(5); time.clock() - start >= 0.005;",
+        object::Object::Boolean = true
+    );
+    eval!(
+        "import \"std/time\"; time.sleep(0.5);",
+        object::Object::Null
+    );
+    eval!(r#"import "std/time"; time.sleep("1");"#, Err => "expected Int or Float, got String");
+
+    let program = |input: &str| {
+        let lexer = lexer::Lexer::new(input.as_bytes());
+        parser::Parser::new(lexer)
+            .parse_program()
+            .expect("parser errors")
+    };
+
+    let mut ev = evaluator::Evaluator::default();
+    ev.set_timeout(Some(std::time::Duration::from_millis(20)));
+    let err = ev.eval_program(program("while (true) { 1; }")).unwrap_err();
+    assert!(matches!(err, EvaluatorError::Timeout));
+
+    let mut ev = evaluator::Evaluator::default();
+    ev.set_timeout(Some(std::time::Duration::from_millis(20)));
+    let start = std::time::Instant::now();
+    let err = ev
+        .eval_program(program("import \"std/time\"; time.sleep(10000);"))
+        .unwrap_err();
+    assert!(matches!(err.inner(), EvaluatorError::Timeout));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+    ev.set_timeout(None);
+    assert!(ev
+        .eval_program(program("import \"std/time\"; time.sleep(1);"))
+        .is_ok());
+}
+
+#[test]
+fn datetime() {
+    let inspect = |input: &str| test_eval(input.into()).unwrap().to_string();
+    assert_eq!(
+        inspect("import \"std/datetime\"; datetime.datetime(2024, 2, 29);"),
+        "2024-02-29T00:00:00"
+    );
+    assert_eq!(
+        inspect("import \"std/datetime\"; datetime.datetime(86400.5);"),
+        "1970-01-02T00:00:00.500"
+    );
+    assert_eq!(
+        inspect(
+            r#"import "std/datetime"; datetime.parse_datetime("2024-03-01 13:05", "%Y-%m-%d %H:%M");"#
+        ),
+        "2024-03-01T13:05:00"
+    );
+    assert_eq!(
+        inspect(r#"import "std/datetime"; datetime.parse_datetime("01/03/2024", "%d/%m/%Y");"#),
+        "2024-03-01T00:00:00"
+    );
+    assert_eq!(
+        inspect(
+            r#"import "std/datetime"; datetime.datetime(2024, 1, 5, 9, 30, 0).format("%A %d %B %Y, %H:%M");"#
+        ),
+        "Friday 05 January 2024, 09:30"
+    );
+
+    assert_eq!(
+        inspect("import \"std/datetime\"; d := datetime.datetime(2024, 1, 5, 9, 30, 15); [d.year, d.month, d.day, d.hour, d.minute, d.second, d.weekday];"),
+        "[2024, 1, 5, 9, 30, 15, 5]"
+    );
+    eval!(
+        "import \"std/datetime\"; datetime.datetime(2000, 1, 1).timestamp;",
+        object::Object::Integer = 946684800
+    );
+    eval!(
+        "import \"std/datetime\"; type(datetime.now()) == type(datetime.datetime(0));",
+        object::Object::Boolean = true
+    );
+
+    assert_eq!(
+        inspect("import \"std/datetime\"; datetime.datetime(2024, 2, 28) + 86400;"),
+        "2024-02-29T00:00:00"
+    );
+    assert_eq!(
+        inspect("import \"std/datetime\"; datetime.datetime(2024, 3, 1) - 1.5;"),
+        "2024-02-29T23:59:58.500"
+    );
+    eval!(
+        "import \"std/datetime\"; datetime.datetime(2024, 3, 1) - datetime.datetime(2024, 2, 1);",
+        object::Object::Float = 29.0 * 86400.0
+    );
+    eval!(
+        "import \"std/datetime\"; datetime.datetime(2024, 1, 1) < datetime.datetime(2024, 1, 2);",
+        object::Object::Boolean = true
+    );
+    eval!(
+        "import \"std/datetime\"; datetime.datetime(2024, 1, 1) == datetime.datetime(1704067200);",
+        object::Object::Boolean = true
+    );
+
+    eval!("import \"std/datetime\"; datetime.datetime(2023, 2, 29);", Err => "invalid date: 2023-02-29 00:00:00");
+    eval!(r#"import "std/datetime"; datetime.parse_datetime("soon", "%Y");"#, Err => r#"invalid date: "soon" (input contains invalid characters)"#);
+    eval!(r#"import "std/datetime"; datetime.datetime(0).format("%Q");"#, Err => "invalid format string: %Q");
+    eval!("import \"std/datetime\"; datetime.datetime(0) + 9223372036854775807;", Err => "date out of range");
+    eval!("import \"std/datetime\"; datetime.datetime(0) < 1;", Err => "expected DateTime, got Int");
+}
+
+#[test]
+fn csv() {
+    let inspect = |input: &str| test_eval(input.into()).unwrap().inspect().to_string();
+    assert_eq!(
+        inspect(r#"import "std/csv"; csv.csv_parse("a,b\n1,\"x, y\"\n");"#),
+        r#"[["a", "b"], ["1", "x, y"]]"#
+    );
+    assert_eq!(
+        inspect(r#"import "std/csv"; csv.csv_parse("name,age\nAnn,42\nBo,7", true);"#),
+        r#"[["name": "Ann", "age": "42"], ["name": "Bo", "age": "7"]]"#
+    );
+    eval!(
+        r#"import "std/csv"; csv.csv_write([["a", 1], [true, "x, y"]]);"#,
+        object::Object::String = "a,1\ntrue,\"x, y\"\n".into()
+    );
+    eval!(
+        r#"import "std/csv"; csv.csv_write([["name": "Ann", "age": 42], ["age": 7, "name": "Bo"], ["name": "Cy"]]);"#,
+        object::Object::String = "name,age\nAnn,42\nBo,7\nCy,\n".into()
+    );
+    eval!(
+        r#"import "std/csv"; rows := csv.csv_parse("k,v\n1,2", true); csv.csv_write(rows) == "k,v\n1,2\n";"#,
+        object::Object::Boolean = true
+    );
+
+    eval!(r#"import "std/csv"; csv.csv_parse("a", 1);"#, Err => "expected Bool, got Int");
+    eval!("import \"std/csv\"; csv.csv_write([1]);", Err => "expected Array or Dict, got Int");
+}
+
+#[test]
+fn env() {
+    eval!(
+        r#"import "std/env"; env.env_set("BELALANG_TEST_VAR", "x=1"); env.env_get("BELALANG_TEST_VAR");"#,
+        object::Object::String = "x=1".into()
+    );
+    eval!(
+        r#"import "std/env"; env.env_all()["BELALANG_TEST_VAR"];"#,
+        object::Object::String = "x=1".into()
+    );
+    eval!(
+        r#"import "std/env"; env.env_get("BELALANG_TEST_UNSET");"#,
+        object::Object::Null
+    );
+    eval!(r#"import "std/env"; env.env_set("A=B", "1");"#, Err => "invalid environment variable: A=B=1");
+    eval!("import \"std/env\"; env.env_get(1);", Err => "expected String, got Int");
+
+    let mut builtins = Builtins::default();
+    assert!(builtins.unregister_module("env"));
+    assert!(!builtins.unregister_module("env"));
+
+    let lexer = lexer::Lexer::new(br#"import "std/env"; env.env_get("HOME");"#);
+    let program = parser::Parser::new(lexer).parse_program().unwrap();
+    let err = evaluator::Evaluator::new(builtins)
+        .eval_program(program)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "unknown module: std/env");
+}
+
+#[test]
+fn typed_builtins() {
+    let mut builtins = Builtins::default();
+    builtins.register_fn("clamp", |x: i64, lo: i64, hi: i64| x.max(lo).min(hi));
+    builtins.register_fn("half", |x: f64| x / 2.0);
+    builtins.register_fn(
+        "shout",
+        |s: String, loud: bool| {
+            if loud {
+                s.to_uppercase()
+            } else {
+                s
+            }
+        },
+    );
+    builtins.register_fn("checked_add", |a: i64, b: i64| {
+        a.checked_add(b).ok_or(EvaluatorError::IntegerOverflow)
+    });
+    builtins.register_fn("first", |a: object::Object| a.type_of().to_string());
+    builtins.register_fn("nothing", || ());
+
+    let run = |code: &str| {
+        let lexer = lexer::Lexer::new(code.as_bytes());
+        let program = parser::Parser::new(lexer).parse_program().unwrap();
+        evaluator::Evaluator::new(builtins.clone()).eval_program(program)
+    };
+
+    assert_eq!(
+        run("clamp(12, 0, 10);").unwrap(),
+        object::Object::Integer(10)
+    );
+    assert_eq!(run("half(3);").unwrap(), object::Object::Float(1.5));
+    assert_eq!(
+        run(r#"shout("hi", true);"#).unwrap(),
+        object::Object::String("HI".into())
+    );
+    assert_eq!(
+        run("checked_add(7, 2);").unwrap(),
+        object::Object::Integer(9)
+    );
+    assert_eq!(
+        run("first([1]);").unwrap(),
+        object::Object::String("Array".into())
+    );
+    assert_eq!(run("nothing();").unwrap(), object::Object::Null);
+
+    let err = |code: &str| run(code).unwrap_err().to_string();
+    assert_eq!(
+        err("checked_add(9223372036854775807, 1);"),
+        "integer overflow"
+    );
+    assert_eq!(
+        err("clamp(1, 2);"),
+        "wrong number of arguments: expected 3, got 2"
+    );
+    assert_eq!(err(r#"clamp(1, "a", 2);"#), "expected Int, got String");
+    assert_eq!(err("half(true);"), "expected Int or Float, got Bool");
+}
+
+#[test]
+fn stateful_builtins() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut total = 0;
+
+    let mut builtins = Builtins::default();
+    builtins.register_mut("add", move |_, args| {
+        for arg in args {
+            total += i64::try_from(arg)?;
+        }
+        Ok(object::Object::Integer(total))
+    });
+    builtins.register_mut("report", move |ev, args| {
+        let [f] = <[object::Object; 1]>::try_from(args).unwrap();
+        let value = ev.call_function(f, Vec::new())?;
+        tx.send(value).unwrap();
+        Ok(object::Object::Null)
+    });
+
+    let run = |code: &str| {
+        let lexer = lexer::Lexer::new(code.as_bytes());
+        let program = parser::Parser::new(lexer).parse_program().unwrap();
+        evaluator::Evaluator::new(builtins.clone()).eval_program(program)
+    };
+
+    assert_eq!(
+        run("add(1, 2); add(3);").unwrap(),
+        object::Object::Integer(6)
+    );
+    assert_eq!(run("add(4);").unwrap(), object::Object::Integer(10));
+
+    run(r#"report(fn() { "done" });"#).unwrap();
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        object::Object::String("done".into())
+    );
+
+    let err = run("report(fn() { report(fn() { 1 }) });").unwrap_err();
+    assert_eq!(err.to_string(), "builtin report is already running");
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn host_modules() {
+    let mut app = Module::default();
+    app.register("greet", |_, args| {
+        let [name] = <[object::Object; 1]>::try_from(args).unwrap();
+        Ok(object::Object::String(format!("hi {name}").into()))
+    });
+    app.register_constant("VERSION", object::Object::Integer(2));
+
+    let mut builtins = Builtins::default();
+    builtins.register_module("app", app);
+    let run = |builtins: &Builtins, code: &str| {
+        let lexer = lexer::Lexer::new(code.as_bytes());
+        let program = parser::Parser::new(lexer).parse_program().unwrap();
+        evaluator::Evaluator::new(builtins.clone()).eval_program(program)
+    };
+
+    assert_eq!(
+        run(
+            &builtins,
+            r#"import "std/app"; app.greet("ann") + " " + str(app.VERSION);"#
+        )
+        .unwrap(),
+        object::Object::String("hi ann 2".into())
+    );
+    assert_eq!(
+        run(
+            &builtins,
+            r#"greet := "mine"; VERSION := 1; a := import "std/app"; a.greet(greet);"#
+        )
+        .unwrap(),
+        object::Object::String("hi mine".into())
+    );
+    assert_eq!(
+        run(&builtins, "greet;").unwrap_err().to_string(),
+        "unknown variable: greet"
+    );
+
+    builtins.register_module("app", Module::default());
+    let result = run(&builtins, r#"len(import "std/app");"#).unwrap();
+    assert_eq!(result, object::Object::Integer(0));
+}
+
+#[test]
+fn script_args() {
+    let program = || {
+        let lexer = lexer::Lexer::new(b"[SCRIPT_PATH, ARGS, len(ARGS)];");
+        parser::Parser::new(lexer).parse_program().unwrap()
+    };
+
+    let mut ev = evaluator::Evaluator::default();
+    ev.set_script_args(Some("main.bl"), ["-v", "x"]);
+    let result = ev.eval_program(program()).unwrap();
+    assert_eq!(
+        result.inspect().to_string(),
+        r#"["main.bl", ["-v", "x"], 2]"#
+    );
+
+    let mut ev = evaluator::Evaluator::default();
+    ev.set_script_args(None, Vec::<String>::new());
+    let result = ev.eval_program(program()).unwrap();
+    assert_eq!(result.inspect().to_string(), "[null, [], 0]");
+}
+
+#[test]
+fn modules() {
+    let dir = std::env::temp_dir().join(format!("belalang-modules-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    std::fs::write(
+        dir.join("lib/utils.bl"),
+        r#"helpers := import "helpers"; square := fn(x) { helpers.twice(x) / 2 * x }; name := "utils";"#,
+    )
+    .unwrap();
+    std::fs::write(dir.join("lib/helpers.bl"), "twice := fn(x) { x * 2 };").unwrap();
+    std::fs::write(dir.join("lib/broken.bl"), "x := ;").unwrap();
+    std::fs::write(dir.join("lib/my-mod.bl"), "x := 1;").unwrap();
+    std::fs::write(dir.join("lib/state.bl"), "items := [];").unwrap();
+    std::fs::write(dir.join("lib/a.bl"), r#"import "b";"#).unwrap();
+    std::fs::write(dir.join("lib/b.bl"), r#"import "a";"#).unwrap();
+
+    let run = |code: &str| {
+        let lexer = lexer::Lexer::new(code.as_bytes());
+        let program = parser::Parser::new(lexer).parse_program().unwrap();
+
+        let mut ev = evaluator::Evaluator::default();
+        let main = dir.join("main.bl");
+        ev.set_script_args(main.to_str(), Vec::<String>::new());
+        ev.eval_program(program)
+    };
+
+    let result = run(r#"import "lib/utils"; [utils.square(3), utils.name];"#).unwrap();
+    assert_eq!(result.inspect().to_string(), r#"[9, "utils"]"#);
+
+    let result = run(r#"u := import("lib/utils.bl"); len(u);"#).unwrap();
+    assert_eq!(result, object::Object::Integer(3));
+
+    let result = run(r#"import("lib/utils").square(4);"#).unwrap();
+    assert_eq!(result, object::Object::Integer(16));
+
+    let err = run(r#"u := import "lib/utils"; u["name"] = "x";"#).unwrap_err();
+    assert!(matches!(err.inner(), EvaluatorError::FrozenValue));
+
+    let err = run(r#"import "lib/missing";"#).unwrap_err();
+    assert!(matches!(err.inner(), EvaluatorError::File(path, _) if path.ends_with("missing.bl")));
+
+    let err = run(r#"import "lib/broken";"#).unwrap_err();
+    assert!(matches!(err.inner(), EvaluatorError::ModuleSyntax(..)));
+
+    let err = run(r#"import "lib/my-mod";"#).unwrap_err();
+    assert!(err.to_string().starts_with("invalid module name: "));
+    assert!(run(r#"m := import "lib/my-mod"; m.x;"#).is_ok());
+
+    let result =
+        run(r#"s := import "lib/state"; push(s.items, 1); len(import("lib/state").items);"#);
+    assert_eq!(result.unwrap(), object::Object::Integer(1));
+
+    let err = run(r#"import "lib/a";"#).unwrap_err().to_string();
+    let lib = std::fs::canonicalize(dir.join("lib")).unwrap();
+    let (a, b) = (lib.join("a.bl"), lib.join("b.bl"));
+    assert_eq!(
+        err,
+        format!(
+            "import cycle: {} -> {} -> {}",
+            a.display(),
+            b.display(),
+            a.display()
+        )
+    );
+
+    // cached modules survive garbage collection even when nothing binds them
+    let program = |input: &str| {
+        let lexer = lexer::Lexer::new(input.as_bytes());
+        parser::Parser::new(lexer).parse_program().unwrap()
+    };
+    let mut ev = evaluator::Evaluator::default();
+    ev.set_script_args(dir.join("main.bl").to_str(), Vec::<String>::new());
+    ev.eval_program(program(r#"import("lib/utils").name;"#))
+        .unwrap();
+    ev.collect_garbage();
+    let result = ev.eval_program(program(r#"import("lib/utils").square(2);"#));
+    assert_eq!(result.unwrap(), object::Object::Integer(4));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn math() {
+    eval!(
+        "import \"std/math\"; math.abs(-3);",
+        object::Object::Integer = 3
+    );
+    eval!(
+        "import \"std/math\"; math.abs(-1.5);",
+        object::Object::Float = 1.5
+    );
+    eval!(
+        "import \"std/math\"; math.min(3, 1.5, 2);",
+        object::Object::Float = 1.5
+    );
+    eval!(
+        "import \"std/math\"; math.max(3, 1.5, 2);",
+        object::Object::Integer = 3
+    );
+    eval!(
+        "import \"std/math\"; math.pow(2, 10);",
+        object::Object::Integer = 1024
+    );
+    eval!(
+        "import \"std/math\"; math.pow(2, -1);",
+        object::Object::Float = 0.5
+    );
+    eval!(
+        "import \"std/math\"; math.pow(4.0, 0.5);",
+        object::Object::Float = 2.0
+    );
+    eval!(
+        "import \"std/math\"; math.sqrt(16);",
+        object::Object::Float = 4.0
+    );
+    eval!(
+        "import \"std/math\"; math.floor(-1.5);",
+        object::Object::Integer = -2
+    );
+    eval!(
+        "import \"std/math\"; math.ceil(1.2);",
+        object::Object::Integer = 2
+    );
+    eval!(
+        "import \"std/math\"; math.round(2.5);",
+        object::Object::Integer = 3
+    );
+    eval!(
+        "import \"std/math\"; math.round(7);",
+        object::Object::Integer = 7
+    );
+    eval!(
+        "import \"std/math\"; math.PI > 3.14 && math.PI < 3.15;",
+        object::Object::Boolean = true
+    );
+    eval!(
+        "import \"std/math\"; math.E > 2.71 && math.E < 2.72;",
+        object::Object::Boolean = true
+    );
+    assert_eq!(
+        test_eval(r#"import "std/math"; [math.sqrt, math.PI == math["PI"]];"#.into())
+            .unwrap()
+            .inspect()
+            .to_string(),
+        "[<builtin math.sqrt>, true]"
+    );
+    eval!(r#"import "std/nope";"#, Err => "unknown module: std/nope");
+    eval!("PI := 3; sqrt := PI; sqrt;", object::Object::Integer = 3);
+
+    eval!(r#"import "std/math"; math["PI"] = 3;"#, Err => "cannot modify a frozen value");
+    eval!("import \"std/math\"; math.pow(10, 100);", Err => "integer overflow");
+    eval!("import \"std/math\"; math.min();", Err => "wrong number of arguments: expected 1, got 0");
+    eval!(r#"import "std/math"; math.max(1, "a");"#, Err => "expected Int or Float, got String");
+    eval!("import \"std/math\"; math.floor(math.sqrt(-1.0));", Err => "cannot convert NaN to Int");
+    eval!("import \"std/math\"; math.ceil(-1.0 / 0.0);", Err => "cannot convert -inf to Int");
+    eval!("import \"std/math\"; math.round(-10000000000000000000.0);", Err => "cannot convert -1e19 to Int");
+}
+
+#[test]
+fn random() {
+    eval!(
+        "import \"std/random\"; random.random_seed(7); a := [random.random(), random.random_int(1, 6)]; random.random_seed(7); b := [random.random(), random.random_int(1, 6)]; a[0] == b[0] && a[1] == b[1];",
+        object::Object::Boolean = true
+    );
+    eval!(
+        "import \"std/random\"; x := random.random(); x >= 0.0 && x < 1.0;",
+        object::Object::Boolean = true
+    );
+    eval!(
+        "import \"std/random\"; x := random.random_int(-2, 2); x >= -2 && x <= 2;",
+        object::Object::Boolean = true
+    );
+    eval!(
+        "import \"std/random\"; random.random_int(3, 3);",
+        object::Object::Integer = 3
+    );
+    eval!(
+        "import \"std/random\"; random.random_seed(1); s := random.shuffle([1, 2, 3, 4]); len(s) == 4 && sort(s)[3] == 4;",
+        object::Object::Boolean = true
+    );
+
+    eval!("import \"std/random\"; random.random_int(2, 1);", Err => "empty range: 2 to 1");
+    eval!("import \"std/random\"; random.random_int(1, 2.0);", Err => "expected Int, got Float");
+}
+
+#[test]
+fn r#for() {
+    eval!(
+        "t := 0; for (x in [1, 2, 3]) { t += x; }; t;",
+        object::Object::Integer = 6
+    );
+    eval!(
+        r#"t := ""; for (k in ["a": 1, "b": 2]) { t += k; }; t;"#,
+        object::Object::String = "ab".into()
+    );
+    eval!(
+        r#"n := 0; for (c in "héllo") { n += 1; }; n;"#,
+        object::Object::Integer = 5
+    );
+    eval!(
+        "a := [1]; for (x in a) { push(a, x); }; len(a);",
+        object::Object::Integer = 2
+    );
+    eval!("for (x in 1) { x; };", Err => "expected Array, Dict, String or Iterator, got Int");
+}
+
+#[test]
+fn range() {
+    let inspect = |input: &str| test_eval(input.into()).unwrap().inspect().to_string();
+    assert_eq!(inspect("collect(range(3));"), "[0, 1, 2]");
+    assert_eq!(inspect("collect(range(2, 5));"), "[2, 3, 4]");
+    assert_eq!(inspect("collect(range(0, 10, 4));"), "[0, 4, 8]");
+    assert_eq!(inspect("collect(range(3, 0, -1));"), "[3, 2, 1]");
+    assert_eq!(inspect("collect(range(3, 5, -1));"), "[]");
+    assert_eq!(
+        inspect("collect(map(range(3), fn(x) { x * x }));"),
+        "[0, 1, 4]"
+    );
+    eval!(
+        "t := 0; for (i in range(1, 5)) { t += i; }; t;",
+        object::Object::Integer = 10
+    );
+
+    assert_eq!(
+        inspect(r#"collect(enumerate(["a", "b"]));"#),
+        r#"[[0, "a"], [1, "b"]]"#
+    );
+    assert_eq!(
+        inspect(r#"collect(zip([1, 2, 3], "ab"));"#),
+        r#"[[1, "a"], [2, "b"]]"#
+    );
+    assert_eq!(inspect("collect(zip([1], [2], [3]));"), "[[1, 2, 3]]");
+    // both are lazy, so they work on endless iterators
+    assert_eq!(
+        inspect("ones := iterator(fn() { 1 }); collect(take(enumerate(ones), 2));"),
+        "[[0, 1], [1, 1]]"
+    );
+    assert_eq!(
+        inspect("collect(zip(iterator(fn() { 1 }), range(2)));"),
+        "[[1, 0], [1, 1]]"
+    );
+    assert_eq!(
+        inspect("r := range(3); z := zip(range(1), r); collect(z); next(z); collect(r);"),
+        "[1, 2]"
+    );
+    eval!(
+        "t := 0; for (p in enumerate([5, 5])) { t += p[0] * p[1]; }; t;",
+        object::Object::Integer = 5
+    );
+    eval!("zip();", Err => "wrong number of arguments: expected 1, got 0");
+    eval!("enumerate(1);", Err => "expected Array, Dict, String or Iterator, got Int");
+
+    eval!("range(1, 2, 0);", Err => "range step cannot be zero");
+    eval!("range();", Err => "wrong number of arguments: expected 3, got 0");
+    eval!("range(1.5);", Err => "expected Int, got Float");
+}
+
+#[test]
+fn iterators() {
+    let inspect = |input: &str| test_eval(input.into()).unwrap().inspect().to_string();
+    eval!(
+        "type(range(3)) == Iterator;",
+        object::Object::Boolean = true
+    );
+    eval!(
+        "it := range(2); next(it); next(it);",
+        object::Object::Integer = 1
+    );
+    eval!(
+        "it := range(2); next(it); next(it); next(it);",
+        object::Object::Null
+    );
+    eval!(
+        "it := range(3); collect(it); len(collect(it));",
+        object::Object::Integer = 0
+    );
+
+    assert_eq!(
+        inspect("collect(take(range(1000000000000), 3));"),
+        "[0, 1, 2]"
+    );
+    assert_eq!(inspect("collect(skip(range(5), 3));"), "[3, 4]");
+    assert_eq!(
+        inspect("collect(take(skip([1, 2, 3, 4], 1), 2));"),
+        "[2, 3]"
+    );
+    assert_eq!(inspect(r#"collect("ab");"#), r#"["a", "b"]"#);
+    assert_eq!(
+        inspect("collect(take(filter(range(1000000000000), fn(x) { x % 2 == 0 }), 3));"),
+        "[0, 2, 4]"
+    );
+    eval!(
+        "calls := 0; it := map(range(10), fn(x) { calls += 1; x }); next(it); calls;",
+        object::Object::Integer = 1
+    );
+    eval!(
+        "reduce(range(5), fn(a, b) { a + b }, 0);",
+        object::Object::Integer = 10
+    );
+
+    assert_eq!(
+        inspect("n := 0; collect(iterator(fn() { n += 1; if (n <= 3) { n } }));"),
+        "[1, 2, 3]"
+    );
+    eval!(
+        "n := 0; t := 0; for (x in iterator(fn() { n += 1; if (n <= 3) { n } })) { t += x; }; t;",
+        object::Object::Integer = 6
+    );
 
-    eval!("2 <= 1;", object::Object::Boolean = false);
-    eval!("2 <= 2;", object::Object::Boolean = true);
-    eval!("2 <= 3;", object::Object::Boolean = true);
+    eval!("next([1]);", Err => "expected Iterator, got Array");
+    eval!("take(range(3), 1.0);", Err => "expected Int, got Float");
+    eval!(
+        "it := range(3); it = map(it, fn(x) { next(it) }); next(it);",
+        Err => "iterator is already running"
+    );
+}
 
-    eval!("2 >= 1;", object::Object::Boolean = true);
-    eval!("2 >= 2;", object::Object::Boolean = true);
-    eval!("2 >= 3;", object::Object::Boolean = false);
+#[test]
+fn generators() {
+    let inspect = |input: &str| test_eval(input.into()).unwrap().inspect().to_string();
+    eval!(
+        "gen := fn() { yield 1; }; type(gen()) == Iterator;",
+        object::Object::Boolean = true
+    );
+    assert_eq!(
+        inspect("gen := fn(n) { yield n; yield n + 1; }; collect(gen(5));"),
+        "[5, 6]"
+    );
+    assert_eq!(
+        inspect(
+            "count := fn(n) { i := 0; while (true) { i += 1; yield i * n; }; };
+            collect(take(count(2), 3));"
+        ),
+        "[2, 4, 6]"
+    );
+    assert_eq!(
+        inspect(
+            "evens := fn(a) { for (x in a) { if (x % 2 == 0) { yield x; } else { 0; } } };
+            collect(evens(range(7)));"
+        ),
+        "[0, 2, 4, 6]"
+    );
+    assert_eq!(
+        inspect("gen := fn() { yield 1; return 2; yield 3; }; collect(gen());"),
+        "[1]"
+    );
+    eval!(
+        "ran := 0; gen := fn() { ran += 1; yield 1; }; it := gen(); ran;",
+        object::Object::Integer = 0
+    );
+    eval!(
+        "gen := fn() { yield 1; }; it := gen(); next(it); next(it); next(it);",
+        object::Object::Null
+    );
+    eval!(
+        "t := 0; gen := fn() { yield 1; yield 2; }; for (x in gen()) { t += x; }; t;",
+        object::Object::Integer = 3
+    );
+
+    eval!("yield 1;", Err => "yield outside of a generator");
+    eval!(
+        "gen := fn() { x := if (true) { yield 1; }; }; next(gen());",
+        Err => "yield outside of a generator"
+    );
 }
 
 #[test]
@@ -138,6 +1126,383 @@ fn error_handling() {
         "b;",
         Err => "unknown variable: b"
     );
+    eval!(
+        r#""a" - 1.0;"#,
+        Err => r#"unknown operator: "a" - 1.0"#
+    );
+}
+
+#[test]
+fn types() {
+    eval!("type(1) == Int;", object::Object::Boolean = true);
+    eval!("type(1.5) == Float;", object::Object::Boolean = true);
+    eval!("type(true) == Bool;", object::Object::Boolean = true);
+    eval!(r#"type("a") == String;"#, object::Object::Boolean = true);
+    eval!("type([]) == Array;", object::Object::Boolean = true);
+
+    eval!("1 is Int;", object::Object::Boolean = true);
+    eval!(r#""a" is String;"#, object::Object::Boolean = true);
+    eval!("1.5 is Int;", object::Object::Boolean = false);
+    eval!(
+        "[:] is Dict && [] is Array;",
+        object::Object::Boolean = true
+    );
+    eval!("Int is Type;", object::Object::Boolean = true);
+    eval!(
+        "x := 1; if (x is Int) { 2 } else { 3 };",
+        object::Object::Integer = 2
+    );
+    eval!("1 is 1;", Err => "expected Type, got Int");
+    eval!("type(fn() {}) == Function;", object::Object::Boolean = true);
+    eval!("type(println) == Function;", object::Object::Boolean = true);
+    eval!("type(Int) == Type;", object::Object::Boolean = true);
+    eval!("type(1) != String;", object::Object::Boolean = true);
+    eval!("t := type(1); t == Int;", object::Object::Boolean = true);
+
+    assert_eq!(test_eval("type(1);".into()).unwrap().to_string(), "Int");
+
+    eval!("type();", Err => "wrong number of arguments: expected 1, got 0");
+    eval!("Int := 1;", Err => "overwriting builtin: Int");
+}
+
+#[test]
+fn conversions() {
+    eval!(r#"int("42");"#, object::Object::Integer = 42);
+    eval!(r#"int(" -7 ");"#, object::Object::Integer = -7);
+    eval!("int(3.9);", object::Object::Integer = 3);
+    eval!("int(true);", object::Object::Integer = 1);
+    eval!(r#"int("4x");"#, Err => r#"cannot convert "4x" to Int"#);
+    eval!("int([]);", Err => "cannot convert [] to Int");
+    eval!(
+        "int(-9223372036854775808.0);",
+        object::Object::Integer = i64::MIN
+    );
+    eval!("int(9223372036854775807.0);", Err => "cannot convert 9.223372036854776e18 to Int");
+    eval!("int(0.0 / 0.0);", Err => "cannot convert NaN to Int");
+    eval!("int(1.0 / 0.0);", Err => "cannot convert inf to Int");
+
+    eval!(r#"float("2.5");"#, object::Object::Float = 2.5);
+    eval!("float(2);", object::Object::Float = 2.0);
+    eval!("float(fn() {});", Err => "cannot convert <fn()> to Float");
+
+    assert_eq!(
+        test_eval("str(10);".into()).unwrap().inspect().to_string(),
+        r#""10""#
+    );
+    assert_eq!(
+        test_eval("str([1, 2]);".into()).unwrap().to_string(),
+        "[1, 2]"
+    );
+    eval!(
+        r#"type(str(1.5)) == String;"#,
+        object::Object::Boolean = true
+    );
+
+    eval!("bool(0);", object::Object::Boolean = false);
+    eval!("bool(2);", object::Object::Boolean = true);
+    eval!(r#"bool("");"#, object::Object::Boolean = false);
+    eval!("bool([0]);", object::Object::Boolean = true);
+
+    eval!("int(1, 2);", Err => "wrong number of arguments: expected 1, got 2");
+}
+
+#[test]
+fn display_and_inspect() {
+    let object = test_eval(r#"[1, 2.5, "a\"b", [true]];"#.into()).unwrap();
+
+    assert_eq!(object.to_string(), r#"[1, 2.5, "a\"b", [true]]"#);
+    assert_eq!(object.inspect().to_string(), r#"[1, 2.5, "a\"b", [true]]"#);
+
+    let object = test_eval(r#""line\n";"#.into()).unwrap();
+
+    assert_eq!(object.to_string(), "line\n");
+    assert_eq!(object.inspect().to_string(), r#""line\n""#);
+
+    let object = test_eval("fn(a, b) { a };".into()).unwrap();
+
+    assert_eq!(object.to_string(), "<fn(a, b)>");
+}
+
+#[test]
+fn dicts() {
+    eval!(r#"["a": 1, "b": 2]["b"];"#, object::Object::Integer = 2);
+    eval!("[1: true, false: 0][1];", object::Object::Boolean = true);
+    eval!(r#"["a": 1]["z"];"#, object::Object::Null);
+    eval!(r#"p := ["x": 3]; p.x;"#, object::Object::Integer = 3);
+    eval!("type([:]) == Dict;", object::Object::Boolean = true);
+    eval!("bool([:]);", object::Object::Boolean = false);
+
+    eval!(
+        r#"v := ["x": 1, "__add__": fn(a, b) { a.x + b.x }]; v + ["x": 41];"#,
+        object::Object::Integer = 42
+    );
+
+    let object = test_eval(r#"["a": 1, 2: [:]];"#.into()).unwrap();
+    assert_eq!(object.inspect().to_string(), r#"["a": 1, 2: [:]]"#);
+
+    let inspect = |input: &str| test_eval(input.into()).unwrap().inspect().to_string();
+    assert_eq!(inspect(r#"keys(["b": 1, 2: 2]);"#), r#"["b", 2]"#);
+    assert_eq!(inspect(r#"values(["b": 1, 2: 2]);"#), "[1, 2]");
+    assert_eq!(
+        inspect(r#"a := ["x": 1, "y": 2]; b := merge(a, ["y": 3, "z": 4]); [a, b];"#),
+        r#"[["x": 1, "y": 2], ["x": 1, "y": 3, "z": 4]]"#
+    );
+    assert_eq!(
+        inspect(r#"d := ["a": 1, "b": 2, "c": 3]; delete(d, "b"); d;"#),
+        r#"["a": 1, "c": 3]"#
+    );
+    eval!(r#"has_key(["a": 1], "a");"#, object::Object::Boolean = true);
+    eval!(r#"has_key(["a": 1], 1);"#, object::Object::Boolean = false);
+    eval!(r#"delete(["a": 1], "a");"#, object::Object::Integer = 1);
+    eval!(r#"delete(["a": 1], "b");"#, object::Object::Null);
+    eval!(r#"delete(freeze(["a": 1]), "a");"#, Err => "cannot modify a frozen value");
+    eval!("keys([]);", Err => "expected Dict, got Array");
+
+    eval!("[[1]: 2];", Err => "unhashable key: [1] of type Array");
+    eval!("[1.5: 2];", Err => "unhashable key: 1.5 of type Float");
+    eval!(r#"["a": 1][2.0];"#, Err => "unhashable key: 2.0 of type Float");
+}
+
+#[test]
+fn operator_hooks() {
+    let vec = r#"vec := fn(x, y) { [
+        "x": x,
+        "y": y,
+        "__add__": fn(a, b) { vec(a.x + b.x, a.y + b.y) },
+        "__eq__": fn(a, b) { a.x == b.x && a.y == b.y },
+        "__index__": fn(v, i) { if (i == 0) { v.x } else { v.y } },
+        "__str__": fn(v) { "(" + str(v.x) + ", " + str(v.y) + ")" }
+    ] };"#;
+    let eval = |input: &str| test_eval(format!("{vec} {input}")).unwrap().to_string();
+
+    assert_eq!(eval("str(vec(1, 2) + vec(3, 4));"), "(4, 6)");
+    assert_eq!(eval("vec(1, 2) == vec(1, 2);"), "true");
+    assert_eq!(eval("vec(1, 2) != vec(1, 2);"), "false");
+    assert_eq!(eval("vec(1, 2) == vec(2, 1);"), "false");
+    assert_eq!(eval("vec(1, 2)[1];"), "2");
+    assert_eq!(eval(r#"format("at {}", vec(5, 6));"#), "at (5, 6)");
+}
+
+#[test]
+fn len() {
+    eval!(r#"len("hello");"#, object::Object::Integer = 5);
+    eval!(r#"len("héllo");"#, object::Object::Integer = 5);
+    eval!("len([1, 2, 3]);", object::Object::Integer = 3);
+    eval!("len([:]);", object::Object::Integer = 0);
+    eval!(r#"len(["a": 1, "b": 2]);"#, object::Object::Integer = 2);
+
+    eval!("len(5);", Err => "expected String, Array or Dict, got Int");
+}
+
+#[test]
+fn arrays() {
+    eval!(
+        "a := [1]; push(a, 2); push(a, 3); a;",
+        object::Object::Array(_)
+    );
+    eval!("a := [1]; push(a, 2); a[1];", object::Object::Integer = 2);
+    eval!("a := [1, 2]; pop(a) + len(a);", object::Object::Integer = 3);
+    eval!("pop([]);", object::Object::Null);
+    eval!(
+        "a := [1, 3]; insert(a, 1, 2); a[1] + len(a);",
+        object::Object::Integer = 5
+    );
+    eval!(
+        "a := [1, 2, 3]; remove(a, 0) + len(a);",
+        object::Object::Integer = 3
+    );
+
+    let inspect = |input: &str| test_eval(input.into()).unwrap().inspect().to_string();
+    assert_eq!(inspect("slice([1, 2, 3, 4], 1, 3);"), "[2, 3]");
+    assert_eq!(inspect("slice([1, 2, 3, 4], -2, 10);"), "[3, 4]");
+    assert_eq!(inspect("slice([1, 2, 3, 4], 3, 1);"), "[]");
+    assert_eq!(
+        inspect("a := [1]; b := concat(a, [2]); [a, b];"),
+        "[[1], [1, 2]]"
+    );
+    assert_eq!(
+        inspect("a := [1, 2]; b := reverse(a); [a, b];"),
+        "[[1, 2], [2, 1]]"
+    );
+
+    eval!(r#"index_of(["a", "b"], "b");"#, object::Object::Integer = 1);
+    eval!("index_of([1], 2);", object::Object::Null);
+
+    assert_eq!(inspect("map([1, 2], fn(x) { x * 2 });"), "[2, 4]");
+    assert_eq!(inspect("map([1], str);"), r#"["1"]"#);
+    assert_eq!(
+        inspect("filter([1, 2, 3, 4], fn(x) { x % 2 == 0 });"),
+        "[2, 4]"
+    );
+    eval!(
+        "reduce([1, 2, 3], fn(acc, x) { acc + x }, 10);",
+        object::Object::Integer = 16
+    );
+    eval!(
+        "reduce([], fn(acc, x) { acc + x }, 0);",
+        object::Object::Integer = 0
+    );
+    eval!(
+        "total := 0; each([1, 2], fn(x) { total += x; }); total;",
+        object::Object::Integer = 3
+    );
+    eval!(
+        "a := [1, 2]; each(a, fn(x) { push(a, x); }); len(a);",
+        object::Object::Integer = 4
+    );
+    eval!("map([1], 1);", Err => "not a function");
+
+    assert_eq!(inspect("sort([3, 1.5, 2]);"), "[1.5, 2, 3]");
+    assert_eq!(inspect(r#"sort(["b", "a", "B"]);"#), r#"["B", "a", "b"]"#);
+    assert_eq!(inspect("a := [2, 1]; sort(a); a;"), "[2, 1]");
+    assert_eq!(inspect("sort([1, 3, 2], fn(a, b) { b - a });"), "[3, 2, 1]");
+    assert_eq!(
+        inspect(r#"sort([[2, "a"], [1, "b"], [2, "c"], [1, "d"]], fn(a, b) { a[0] < b[0] });"#),
+        r#"[[1, "b"], [1, "d"], [2, "a"], [2, "c"]]"#
+    );
+    eval!(r#"sort([1, "a"]);"#, Err => r#"unknown operator: "a" < 1"#);
+    eval!(
+        r#"sort([1, 2], fn(a, b) { "x" });"#,
+        Err => "expected Int or Bool, got String"
+    );
+    eval!("sort();", Err => "wrong number of arguments: expected 1, got 0");
+
+    // comparators needn't be consistent, and the first error stops sorting
+    eval!(
+        r#"import "std/random";
+        len(sort(random.shuffle(collect(range(200))), fn(x, y) { random.random_int(0, 2) - 1 }));"#,
+        object::Object::Integer = 200
+    );
+    assert_eq!(inspect("sort([3, 1, 2], fn(x, y) { 0 });"), "[3, 1, 2]");
+    let mut ev = evaluator::Evaluator::default();
+    let input =
+        "calls := 0; sort([4, 3, 2, 1], fn(a, b) { calls += 1; if (calls == 2) { nope }; a - b });";
+    ev.eval_program(
+        parser::Parser::new(lexer::Lexer::new(input.as_bytes()))
+            .parse_program()
+            .unwrap(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        ev.eval_program(
+            parser::Parser::new(lexer::Lexer::new(b"calls;"))
+                .parse_program()
+                .unwrap()
+        ),
+        Ok(object::Object::Integer(2))
+    ));
+
+    eval!("insert([], 1, 0);", Err => "index 1 out of bounds for length 0");
+    eval!("remove([1], -1);", Err => "index -1 out of bounds for length 1");
+    eval!("push(freeze([]), 1);", Err => "cannot modify a frozen value");
+    eval!("push(1, 1);", Err => "expected Array, got Int");
+}
+
+#[test]
+fn aliasing() {
+    eval!(
+        "a := [1, 2]; b := a; b[0] = 5; a[0];",
+        object::Object::Integer = 5
+    );
+    eval!(
+        r#"d := ["k": 1]; e := d; e["k"] = 2; d["k"];"#,
+        object::Object::Integer = 2
+    );
+    eval!(
+        r#"d := [:]; d["new"] = 1; len(d);"#,
+        object::Object::Integer = 1
+    );
+    eval!(
+        "a := [1]; set := fn(xs) { xs[0] = 9; }; set(a); a[0];",
+        object::Object::Integer = 9
+    );
+
+    eval!(
+        "a := [[1]]; b := clone(a); b[0] = 2; a[0][0];",
+        object::Object::Integer = 1
+    );
+    eval!(
+        "a := [[1]]; b := clone(a); b[0][0] = 2; a[0][0];",
+        object::Object::Integer = 2
+    );
+    eval!(
+        "a := [[1]]; b := deepcopy(a); b[0][0] = 2; a[0][0];",
+        object::Object::Integer = 1
+    );
+    eval!(
+        r#"a := [1]; d := ["x": a, "y": a]; c := deepcopy(d); c["x"][0] = 2; c["y"][0];"#,
+        object::Object::Integer = 2
+    );
+    eval!("clone(5);", object::Object::Integer = 5);
+
+    let object = test_eval("a := [1]; a[0] = a; deepcopy(a);".into()).unwrap();
+    assert_eq!(object.inspect().to_string(), "[[...]]");
+
+    eval!("a := [1]; a[1] = 2;", Err => "index 1 out of bounds for length 1");
+    eval!("a := 1; a[0] = 2;", Err => "expected Array or Dict, got Int");
+}
+
+#[test]
+fn freeze() {
+    eval!("a := freeze([1]); a[0] = 2;", Err => "cannot modify a frozen value");
+    eval!(r#"d := freeze(["k": 1]); d["k"] = 2;"#, Err => "cannot modify a frozen value");
+    eval!("a := [1]; b := a; freeze(a); b[0] = 2;", Err => "cannot modify a frozen value");
+
+    eval!(
+        "a := freeze([[1]]); a[0][0] = 2; a[0][0];",
+        object::Object::Integer = 2
+    );
+    eval!(
+        "a := clone(freeze([1])); a[0] = 2; a[0];",
+        object::Object::Integer = 2
+    );
+    eval!("freeze(1);", object::Object::Integer = 1);
+}
+
+#[test]
+fn null_coalescing() {
+    eval!(r#"["a": 1]["b"] ?? 2;"#, object::Object::Integer = 2);
+    eval!(r#"["a": 1]["a"] ?? 2;"#, object::Object::Integer = 1);
+    eval!("false ?? 2;", object::Object::Boolean = false);
+    eval!("[][0] ?? [][1] ?? 3;", object::Object::Integer = 3);
+    eval!("1 ?? undefined_variable;", object::Object::Integer = 1);
+}
+
+#[test]
+fn optional_chaining() {
+    eval!(
+        r#"p := ["a": ["b": 1]]; p?.a?.b;"#,
+        object::Object::Integer = 1
+    );
+    eval!(r#"n := [:]["x"]; n?.b;"#, object::Object::Null);
+    eval!(r#"p := ["a": 1]; p["z"]?["b"];"#, object::Object::Null);
+    eval!(r#"p := ["a": 1]; p["z"]?.b.c["d"];"#, object::Object::Null);
+    eval!(r#"p := ["a": 1]; p["z"]?.f(1);"#, object::Object::Null);
+    eval!(
+        r#"p := ["a": 1]; p["z"]?.b ?? 2;"#,
+        object::Object::Integer = 2
+    );
+
+    eval!(r#"p := ["a": 1]; p["z"].b;"#, Err => "unknown member: b");
+    eval!(r#"p := ["a": 1]; p?.b;"#, Err => "unknown member: b");
+}
+
+#[test]
+fn membership() {
+    eval!("2 in [1, 2, 3];", object::Object::Boolean = true);
+    eval!("4 in [1, 2, 3];", object::Object::Boolean = false);
+    eval!(r#""b" in ["a", "b"];"#, object::Object::Boolean = true);
+    eval!("1.5 in [1.5];", object::Object::Boolean = true);
+    eval!(r#""k" in ["k": 1];"#, object::Object::Boolean = true);
+    eval!(r#"1 in ["k": 1];"#, object::Object::Boolean = false);
+    eval!(r#""ell" in "hello";"#, object::Object::Boolean = true);
+    eval!(r#""" in "hello";"#, object::Object::Boolean = true);
+    eval!(r#""x" in "hello";"#, object::Object::Boolean = false);
+    eval!("1 + 1 in [2] == true;", object::Object::Boolean = true);
+
+    eval!(r#"1 in "hello";"#, Err => "expected String, got Int");
+    eval!("1 in 1;", Err => "expected Array, Dict or String, got Int");
+    eval!(r#"[1] in ["k": 1];"#, Err => "unhashable key: [1] of type Array");
 }
 
 #[test]
@@ -161,6 +1526,39 @@ fn assignment_ops() {
     eval!("a := 10; a %= 3; a;", object::Object::Integer = 1);
 }
 
+#[test]
+fn only_declarations_create_variables() {
+    eval!("x = 1;", Err => "unknown variable: x");
+    eval!("f := fn() { y = 1 }; f();", Err => "unknown variable: y");
+    eval!("z += 1;", Err => "unknown variable: z");
+}
+
+#[test]
+fn declarations_shadow() {
+    eval!(
+        "x := 1; f := fn() { x := 2; x }; f() * 10 + x;",
+        object::Object::Integer = 21
+    );
+    eval!(
+        "x := 1; f := fn(x) { x }; f(5); x;",
+        object::Object::Integer = 1
+    );
+    eval!(
+        "x := 1; for (x in [7]) { x; }; x;",
+        object::Object::Integer = 1
+    );
+    eval!(
+        "x := 1; if (true) { x := 2; }; x;",
+        object::Object::Integer = 1
+    );
+
+    // assignment still reaches the outer variable
+    eval!(
+        "x := 1; f := fn() { x = 2; }; f(); x;",
+        object::Object::Integer = 2
+    );
+}
+
 #[test]
 fn logical_ops() {
     eval!("true && true;", object::Object::Boolean = true);
@@ -173,3 +1571,215 @@ fn logical_ops() {
     eval!("false || true;", object::Object::Boolean = true);
     eval!("false || false;", object::Object::Boolean = false);
 }
+
+#[test]
+fn scope_introspection() {
+    let scope = |input: &str| match test_eval(input.into()) {
+        Ok(scope) => scope.inspect().to_string(),
+        Err(err) => panic!("got errors instead. got={}", err),
+    };
+
+    assert_eq!(scope("b := 1; a := 2; globals();"), r#"["a": 2, "b": 1]"#);
+    assert_eq!(scope("locals();"), "[:]");
+    assert_eq!(
+        scope("x := 1; f := fn(y) { z := 2; locals() }; f(3);"),
+        r#"["y": 3, "z": 2]"#
+    );
+    assert_eq!(
+        scope("x := 1; f := fn(y) { keys(globals()) }; f(3);"),
+        r#"["f", "x"]"#
+    );
+    assert_eq!(scope(r#"x := 1; g := globals(); g["x"] = 2; x;"#), "1");
+}
+
+#[test]
+fn collect_garbage() {
+    let program = |input: &str| {
+        let lexer = lexer::Lexer::new(input.as_bytes());
+        parser::Parser::new(lexer)
+            .parse_program()
+            .expect("parser errors")
+    };
+
+    let mut ev = evaluator::Evaluator::default();
+
+    // every call leaves behind a scope that holds a closure over itself
+    ev.eval_program(program(
+        "make := fn(n) { g := fn() { n + g_calls() }; g }; g_calls := fn() { 0 }; make(1); make(2); kept := make(3);",
+    ))
+    .unwrap();
+
+    assert_eq!(ev.collect_garbage(), 2);
+    assert_eq!(ev.collect_garbage(), 0);
+
+    let kept = ev.eval_program(program("kept();")).unwrap();
+    assert_eq!(kept, object::Object::Integer(3));
+
+    // a suspended generator keeps the scopes it is running in
+    ev.eval_program(program(
+        "gen := fn() { for (i in range(3)) { x := i * 2; yield i; yield x; } }; it := gen(); next(it);",
+    ))
+    .unwrap();
+    ev.collect_garbage();
+
+    let x = ev.eval_program(program("next(it);")).unwrap();
+    assert_eq!(x, object::Object::Integer(0));
+}
+
+#[derive(Debug)]
+struct Money {
+    cents: i64,
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "${}.{:02}", self.cents / 100, self.cents % 100)
+    }
+}
+
+impl NativeObject for Money {
+    fn type_name(&self) -> &str {
+        "Money"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn equals(&self, other: &object::Object) -> bool {
+        match other {
+            object::Object::Native(n) => n
+                .as_any()
+                .downcast_ref::<Money>()
+                .is_some_and(|m| m.cents == self.cents),
+            _ => false,
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<object::Object> {
+        match name {
+            "cents" => Some(object::Object::Integer(self.cents)),
+            "__add__" => Some(object::Object::Native(Shared::new(AddMoney))),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct AddMoney;
+
+impl std::fmt::Display for AddMoney {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<add>")
+    }
+}
+
+impl NativeObject for AddMoney {
+    fn type_name(&self) -> &str {
+        "AddMoney"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn call(&self, args: Vec<object::Object>) -> Result<object::Object, EvaluatorError> {
+        let cents = args
+            .iter()
+            .map(|arg| match arg.member("cents") {
+                Some(object::Object::Integer(c)) => c,
+                _ => 0,
+            })
+            .sum();
+
+        Ok(object::Object::Native(Shared::new(Money { cents })))
+    }
+}
+
+fn test_eval_with_money(input: &str) -> Result<object::Object, EvaluatorError> {
+    let lexer = lexer::Lexer::new(input.as_bytes());
+    let mut parser = parser::Parser::new(lexer);
+    let program = parser.parse_program().expect("parser errors");
+
+    let mut ev = evaluator::Evaluator::default();
+    ev.env_mut().set(
+        "price".into(),
+        object::Object::Native(Shared::new(Money { cents: 250 })),
+    );
+    ev.env_mut().set(
+        "tip".into(),
+        object::Object::Native(Shared::new(Money { cents: 75 })),
+    );
+    ev.eval_program(program)
+}
+
+#[test]
+fn native() {
+    let result = test_eval_with_money("price.cents;").unwrap();
+    assert_eq!(result, object::Object::Integer(250));
+
+    let result = test_eval_with_money("price;").unwrap();
+    assert_eq!(result.to_string(), "$2.50");
+
+    let result = test_eval_with_money("price == price;").unwrap();
+    assert_eq!(result, object::Object::Boolean(true));
+
+    let result = test_eval_with_money("(price + tip).cents;").unwrap();
+    assert_eq!(result, object::Object::Integer(325));
+
+    let err = test_eval_with_money("price.owner;").unwrap_err();
+    assert_eq!(err.to_string(), "unknown member: owner");
+
+    let err = test_eval_with_money("price();").unwrap_err();
+    assert_eq!(err.to_string(), "not a function");
+}
+
+#[test]
+fn deeply_nested() {
+    let depth = belalang_core::parser::MAX_NESTING / 4;
+    let input = format!("{}1{};", "(1 + ".repeat(depth), ")".repeat(depth));
+
+    eval!(input, object::Object::Integer = depth as i64 + 1);
+}
+
+#[test]
+fn single_expression() {
+    let mut ev = evaluator::Evaluator::default();
+    ev.eval_program(
+        parser::Parser::new(lexer::Lexer::new(b"price := 10;"))
+            .parse_program()
+            .unwrap(),
+    )
+    .unwrap();
+
+    let formula = parser::Parser::parse_expression_str("price * 2 + 1").unwrap();
+    assert!(matches!(
+        ev.eval_expression(formula),
+        Ok(object::Object::Integer(21))
+    ));
+}
+
+#[test]
+fn error_span() {
+    use belalang_core::{diagnostic::Diagnostic, source::Span};
+
+    let err = test_eval("x := 1;\nx + \"a\";".into()).unwrap_err();
+    assert_eq!(err.to_string(), r#"unknown operator: 1 + "a""#);
+    assert_eq!(err.span(), Some(Span { start: 8, end: 15 }));
+    assert!(matches!(
+        err.inner(),
+        EvaluatorError::UnknownInfixOperator(..)
+    ));
+    assert!(Diagnostic::from(&err)
+        .to_json()
+        .contains(r#""span":{"start":8,"end":15}"#));
+
+    // the innermost expression, not the call that led to it
+    let err = test_eval("f := fn() { 1 / 0 };\n[f()];".into()).unwrap_err();
+    assert_eq!(err.span(), Some(Span { start: 12, end: 17 }));
+
+    eval!(
+        "fn() { if (true) { return 1; } 2 }();",
+        object::Object::Integer = 1
+    );
+}
@@ -0,0 +1,81 @@
+use belalang_core::{lexer::Lexer, parser::Parser};
+use belalang_eval::{evaluator::Evaluator, fold::fold_constants};
+
+fn parse(input: &str) -> belalang_core::ast::Program {
+    Parser::new(Lexer::new(input.as_bytes()))
+        .parse_program()
+        .expect("parser errors")
+}
+
+fn folded(input: &str) -> String {
+    let mut program = parse(input);
+    fold_constants(&mut program);
+    program.to_string()
+}
+
+/// Checks that a folded program gets what the original does.
+fn same(input: &str) {
+    let eval = |program| {
+        Evaluator::default()
+            .eval_program(program)
+            .map(|value| value.inspect().to_string())
+            .map_err(|err| err.to_string())
+    };
+
+    let mut program = parse(input);
+    fold_constants(&mut program);
+    assert_eq!(eval(program), eval(parse(input)), "{input}");
+}
+
+#[test]
+fn folds_operators() {
+    assert_eq!(folded("2 * 3 + x;"), folded("6 + x;"));
+    assert_eq!(folded("x + 2 * 3;"), folded("x + 6;"));
+    assert_eq!(folded("-(1 + 2);"), folded("-3;"));
+    assert_eq!(folded("!(1 < 2);"), folded("false;"));
+    assert_eq!(folded("1.5 * 2.0;"), folded("3.0;"));
+    assert_eq!(folded(r#""a" + "b" * 2;"#), folded(r#""abb";"#));
+    assert_eq!(folded(r#""b" in "abc";"#), folded("true;"));
+    assert_eq!(folded("f(1 + 1, [2 * 2]);"), folded("f(2, [4]);"));
+    assert_eq!(folded("fn(x) { x * (2 + 2) };"), folded("fn(x) { x * 4 };"));
+}
+
+#[test]
+fn folds_conditions() {
+    assert_eq!(folded("if (1 < 2) { a } else { b };"), folded("{ a };"));
+    assert_eq!(folded("if (1 > 2) { a } else { b };"), folded("{ b };"));
+    assert_eq!(
+        folded("if (false) { a } else if (x) { b };"),
+        folded("if (x) { b };")
+    );
+    assert_eq!(folded("if (1) { a };"), "null;");
+    assert_eq!(folded("if (x) { 1 + 1 };"), folded("if (x) { 2 };"));
+}
+
+#[test]
+fn leaves_runtime_errors() {
+    assert_eq!(folded("1 / 0;"), "(1 / 0);");
+    assert_eq!(folded("1 % 0;"), "(1 % 0);");
+    assert_eq!(
+        folded("9223372036854775807 + 1;"),
+        "(9223372036854775807 + 1);"
+    );
+    assert_eq!(folded("1 + true;"), "(1 + true);");
+    assert_eq!(folded("!1;"), "(!1);");
+    assert_eq!(folded("1.0 / 0.0;"), "(1.0 / 0.0);");
+    assert!(folded(r#""ab" * 1000;"#).contains("1000"));
+}
+
+#[test]
+fn same_results() {
+    same("2 * 3 + 4 - 10 / 3 % 2;");
+    same("x := 5; 2 * 3 + x;");
+    same("1.5 + 2.25 * 2.0 - -1.0;");
+    same(r#""a" * 3 + "b"; "ab" < "b";"#);
+    same("true && false || !false; 1 == 1.0;");
+    same("if (2 > 1) { x := 1; x } else { 0 };");
+    same("x := 1; if (false) { x := 2; }; x;");
+    same("if (false) { 1 } else if (true) { 2 } else { 3 };");
+    same("f := fn(n) { if (1 + 1 == 2) { n * (3 - 1) } }; f(4);");
+    same("1 + true;");
+}
@@ -0,0 +1,53 @@
+#![cfg(any(feature = "toml", feature = "yaml"))]
+
+use belalang_core::{lexer, parser};
+use belalang_eval::{error::EvaluatorError, evaluator::Evaluator, object::Object};
+
+fn eval(input: &str) -> Result<Object, EvaluatorError> {
+    let lexer = lexer::Lexer::new(input.as_bytes());
+    let mut parser = parser::Parser::new(lexer);
+    let program = parser.parse_program().expect("parser errors");
+
+    Evaluator::default().eval_program(program)
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml_parse() {
+    let config = eval(
+        r#"import "std/toml"; toml.toml_parse("name = \"app\"\nport = 8080\n[db]\nhosts = [\"a\", \"b\"]\nratio = 0.5\nwhen = 1979-05-27\n");"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.inspect().to_string(),
+        r#"["name": "app", "port": 8080, "db": ["hosts": ["a", "b"], "ratio": 0.5, "when": "1979-05-27"]]"#
+    );
+
+    let err = eval(r#"import "std/toml"; toml.toml_parse("a = ");"#).unwrap_err();
+    assert!(err.to_string().starts_with("invalid TOML: "));
+    let err = eval("import \"std/toml\"; toml.toml_parse(1);").unwrap_err();
+    assert_eq!(err.to_string(), "expected String, got Int");
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_parse() {
+    let config =
+        eval(r#"import "std/yaml"; yaml.yaml_parse("name: app\nport: 8080\ntags: [a, b]\n1: ~\nratio: 0.5\n");"#).unwrap();
+
+    assert_eq!(
+        config.inspect().to_string(),
+        r#"["name": "app", "port": 8080, "tags": ["a", "b"], 1: null, "ratio": 0.5]"#
+    );
+
+    assert_eq!(
+        eval(r#"import "std/yaml"; yaml.yaml_parse("");"#).unwrap(),
+        Object::Null
+    );
+
+    let err = eval(r#"import "std/yaml"; yaml.yaml_parse("a: [");"#).unwrap_err();
+    assert!(err.to_string().starts_with("invalid YAML: "));
+    let err = eval(r#"import "std/yaml"; yaml.yaml_parse("[1]: x");"#).unwrap_err();
+    assert!(matches!(err.inner(), EvaluatorError::UnhashableKey(_)));
+}
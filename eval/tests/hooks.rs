@@ -0,0 +1,85 @@
+use belalang_core::{ast::Statement, lexer::Lexer, parser::Parser};
+use belalang_eval::{
+    compiler::Compiler,
+    error::EvaluatorError,
+    hooks::Hooks,
+    interpreter::Interpreter,
+    object::Object,
+    sync::{Locked, Shared},
+};
+
+#[derive(Clone, Default)]
+struct Recorder(Shared<Locked<Vec<String>>>);
+
+impl Hooks for Recorder {
+    fn on_statement(&mut self, statement: &Statement) {
+        self.0.borrow_mut().push(format!("statement {statement}"));
+    }
+
+    fn on_call(&mut self, function: &str, args: &[Object]) {
+        let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        self.0
+            .borrow_mut()
+            .push(format!("call {function}({})", args.join(", ")));
+    }
+
+    fn on_error(&mut self, error: &EvaluatorError) {
+        self.0.borrow_mut().push(format!("error {error}"));
+    }
+}
+
+#[test]
+fn hooks() {
+    let recorder = Recorder::default();
+    let mut interpreter = Interpreter::default();
+    interpreter.evaluator_mut().set_hooks(recorder.clone());
+
+    interpreter
+        .eval_str("double := fn(x) { x * 2 }; double(double(1));")
+        .unwrap();
+    let err = interpreter.eval_str("len(nope);").unwrap_err();
+    assert_eq!(err.to_string(), "unknown variable: nope");
+
+    assert_eq!(
+        *recorder.0.borrow(),
+        [
+            "statement double := fn(x) { (x * 2) };",
+            "statement double(double(1));",
+            "call double(1)",
+            "statement (x * 2);",
+            "call double(2)",
+            "statement (x * 2);",
+            "statement len(nope);",
+            "error unknown variable: nope",
+        ]
+    );
+
+    assert!(interpreter.evaluator_mut().take_hooks().is_some());
+    interpreter.eval_str("double(3);").unwrap();
+    assert_eq!(recorder.0.borrow().len(), 8);
+}
+
+#[test]
+fn compiled() {
+    let source = r#"i := 0; while (i < 2) { i = i + 1; { f := fn(x) { [x] }; f(i); }; };
+                  for (x in [1]) { str(x); }; n := [:]["x"]; n?.f(1); yield 1;"#;
+
+    let evaluated = Recorder::default();
+    let mut interpreter = Interpreter::default();
+    interpreter.evaluator_mut().set_hooks(evaluated.clone());
+    let err = interpreter.eval_str(source).unwrap_err();
+    assert_eq!(err.to_string(), "yield outside of a generator");
+
+    let program = Parser::new(Lexer::new(source.as_bytes()))
+        .parse_program()
+        .unwrap();
+    let bytecode = Compiler::new().compile_program(&program).unwrap();
+
+    let compiled = Recorder::default();
+    let mut interpreter = Interpreter::default();
+    interpreter.evaluator_mut().set_hooks(compiled.clone());
+    interpreter.run_bytecode(&bytecode).unwrap_err();
+
+    assert_eq!(*compiled.0.borrow(), *evaluated.0.borrow());
+    assert_eq!(compiled.0.borrow().len(), 26);
+}
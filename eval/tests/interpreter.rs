@@ -0,0 +1,231 @@
+use belalang_eval::{
+    error::Error, interpreter::Interpreter, metrics::Limits, object::Object, sandbox::Profile,
+};
+
+#[test]
+fn eval_str() {
+    let mut interpreter = Interpreter::default();
+
+    assert_eq!(interpreter.eval_str("1 + 2;").unwrap(), Object::Integer(3));
+    interpreter
+        .eval_str("x := 2; double := fn(n) { n * x };")
+        .unwrap();
+    assert_eq!(
+        interpreter.eval_str("double(21);").unwrap(),
+        Object::Integer(42)
+    );
+
+    let err = interpreter.eval_str("x = 5; y := ;").unwrap_err();
+    assert!(matches!(err, Error::Syntax(_)));
+    assert_eq!(interpreter.eval_str("x;").unwrap(), Object::Integer(2));
+
+    let err = interpreter.eval_str("missing;").unwrap_err();
+    assert!(matches!(err, Error::Evaluator(_)));
+    assert_eq!(err.to_string(), "unknown variable: missing");
+}
+
+#[test]
+fn call() {
+    let mut interpreter = Interpreter::default();
+    interpreter
+        .eval_str("add := fn(a, b) { a + b }; make := fn() { n := 0; fn() { n = n + 1; n } }; counter := make();")
+        .unwrap();
+
+    assert_eq!(
+        interpreter
+            .call("add", vec![Object::Integer(1), Object::Integer(2)])
+            .unwrap(),
+        Object::Integer(3)
+    );
+    assert_eq!(
+        interpreter.call("len", vec![Object::from("abc")]).unwrap(),
+        Object::Integer(3)
+    );
+    let err = interpreter.call("missing", Vec::new()).unwrap_err();
+    assert_eq!(err.to_string(), "unknown variable: missing");
+
+    let counter = interpreter.function("counter").unwrap();
+    interpreter.eval_str("counter = 0;").unwrap();
+    interpreter.evaluator_mut().collect_garbage();
+    assert_eq!(
+        interpreter.call_handle(&counter, Vec::new()).unwrap(),
+        Object::Integer(1)
+    );
+    assert_eq!(
+        interpreter.call_handle(&counter, Vec::new()).unwrap(),
+        Object::Integer(2)
+    );
+
+    let err = interpreter.function("counter").unwrap_err();
+    assert_eq!(err.to_string(), "not a function");
+}
+
+#[test]
+fn builder() {
+    let mut interpreter = Interpreter::builder()
+        .profile(Profile::Pure)
+        .register_fn("input", || "from host")
+        .metrics(true)
+        .build();
+
+    assert_eq!(
+        interpreter.eval_str("input();").unwrap().to_string(),
+        "from host"
+    );
+    assert_eq!(
+        interpreter
+            .eval_str("read_line();")
+            .unwrap_err()
+            .to_string(),
+        "read_line is not allowed here"
+    );
+    assert!(interpreter.evaluator().metrics().unwrap().steps > 0);
+}
+
+#[test]
+fn limits() {
+    let mut interpreter = Interpreter::builder().fuel(100).build();
+    let err = interpreter.eval_str("while (true) {}").unwrap_err();
+    assert_eq!(err.to_string(), "script ran out of fuel");
+
+    interpreter.evaluator_mut().set_limits(Limits::default());
+    assert_eq!(interpreter.eval_str("1;").unwrap(), Object::Integer(1));
+
+    let mut interpreter = Interpreter::builder().depth(10).build();
+    interpreter
+        .eval_str("f := fn(n) { if (n > 0) { f(n - 1) } else { n } };")
+        .unwrap();
+    assert_eq!(interpreter.eval_str("f(9);").unwrap(), Object::Integer(0));
+    let err = interpreter.eval_str("f(10);").unwrap_err();
+    assert_eq!(err.to_string(), "call depth limit of 10 exceeded");
+    // The depth is back to zero after the error.
+    assert_eq!(interpreter.eval_str("f(9);").unwrap(), Object::Integer(0));
+
+    let mut interpreter = Interpreter::builder().scopes(50).build();
+    interpreter
+        .eval_str("keep := []; i := 0; while (i < 10) { push(keep, fn() { i }); i = i + 1; }")
+        .unwrap();
+    let err = interpreter
+        .eval_str("while (true) { push(keep, fn() { i }); }")
+        .unwrap_err();
+    assert_eq!(err.to_string(), "scope limit of 50 exceeded");
+
+    let mut interpreter = Interpreter::builder().memory(1 << 20).build();
+    interpreter
+        .eval_str(r#"s := "a" * 1000; s = s + s;"#)
+        .unwrap();
+    let err = interpreter
+        .eval_str(r#"while (true) { s = s + "a"; }"#)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "memory limit of 1048576 bytes exceeded");
+
+    for grow in [
+        "keep := []; while (true) { push(keep, 1); }",
+        "keep := [:]; i := 0; while (true) { keep[i] = i; i = i + 1; }",
+        "while (true) { collect(range(1000)); }",
+        r#"keep := []; while (true) { keep = concat(keep, ["a"]); }"#,
+    ] {
+        let mut interpreter = Interpreter::builder().memory(1 << 16).build();
+        let err = interpreter.eval_str(grow).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "memory limit of 65536 bytes exceeded",
+            "{grow}"
+        );
+    }
+}
+
+#[test]
+fn template() {
+    let dir = std::env::temp_dir().join(format!("belalang-template-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("counter.bl"), "items := [];").unwrap();
+    let import = format!(
+        r#"counter := import "{}/counter.bl";"#,
+        dir.to_string_lossy().replace('\\', "/")
+    );
+
+    let template = Interpreter::builder().fuel(1_000).template();
+    let mut a = template.instantiate();
+    let mut b = template.instantiate();
+
+    a.eval_str("x := 1;").unwrap();
+    assert_eq!(
+        b.eval_str("x;").unwrap_err().to_string(),
+        "unknown variable: x"
+    );
+
+    a.eval_str(&import).unwrap();
+    a.eval_str("push(counter.items, 1);").unwrap();
+    b.eval_str(&import).unwrap();
+    assert_eq!(template.parsed_modules().len(), 1);
+    assert_eq!(
+        a.eval_str("len(counter.items);").unwrap(),
+        Object::Integer(1)
+    );
+    assert_eq!(
+        b.eval_str("len(counter.items);").unwrap(),
+        Object::Integer(0)
+    );
+
+    // Each interpreter gets its own fuel.
+    let spin = |n| format!("i := 0; while (i < {n}) {{ i = i + 1; }}");
+    assert!(a.eval_str(&spin(1_000)).is_err());
+    assert!(template.instantiate().eval_str(&spin(50)).is_ok());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn snapshot() {
+    let mut interpreter = Interpreter::default();
+    interpreter
+        .eval_str(
+            r#"
+            import "std/random";
+            items := [1, ["nested"]];
+            counter := fn() { n := 0; fn() { n = n + 1 } }();
+            counter();
+            "#,
+        )
+        .unwrap();
+
+    let snapshot = interpreter.snapshot();
+    let roll = |interpreter: &mut Interpreter| {
+        interpreter
+            .eval_str("random.random_int(0, 1000000);")
+            .unwrap()
+    };
+    let first = roll(&mut interpreter);
+
+    interpreter
+        .eval_str(
+            r#"
+            push(items, 2);
+            push(items[1], "more");
+            freeze(items);
+            counter();
+            added := true;
+            "#,
+        )
+        .unwrap();
+    interpreter.restore(&snapshot);
+
+    assert_eq!(
+        interpreter
+            .eval_str("items;")
+            .unwrap()
+            .inspect()
+            .to_string(),
+        r#"[1, ["nested"]]"#
+    );
+    assert_eq!(
+        interpreter.eval_str("counter();").unwrap(),
+        Object::Integer(2)
+    );
+    assert!(interpreter.eval_str("added;").is_err());
+    assert!(interpreter.eval_str("push(items, 3);").is_ok());
+
+    interpreter.restore(&snapshot);
+    assert_eq!(roll(&mut interpreter), first);
+}
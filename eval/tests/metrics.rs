@@ -0,0 +1,53 @@
+use belalang_eval::{interpreter::Interpreter, object::Object};
+
+#[test]
+fn metrics() {
+    let mut interpreter = Interpreter::default();
+    assert_eq!(interpreter.evaluator().metrics(), None);
+
+    interpreter.eval_str("double := fn(x) { x * 2 };").unwrap();
+    interpreter.evaluator_mut().set_metrics(true);
+
+    let metrics = interpreter.evaluator().metrics().unwrap();
+    assert_eq!(metrics.steps, 0);
+    assert_eq!(metrics.allocations, 0);
+
+    interpreter
+        .eval_str(
+            r#"
+            import "std/math";
+            i := 0;
+            while (i < 3) {
+                println(double(math.abs(i)));
+                i = i + 1;
+            }
+            "#,
+        )
+        .unwrap();
+
+    let metrics = interpreter.evaluator().metrics().unwrap();
+    assert!(metrics.steps > 20, "{metrics:?}");
+    assert_eq!(metrics.calls, 3);
+    assert_eq!(metrics.builtin_calls["println"], 3);
+    assert_eq!(metrics.builtin_calls["math.abs"], 3);
+    // A scope for each loop body and each call.
+    assert_eq!(metrics.allocations, 6);
+    assert!(metrics.peak_scopes >= 2, "{metrics:?}");
+    assert_eq!(metrics.memory, 0);
+
+    // Scopes that died don't count towards the peak, however many there were.
+    interpreter.evaluator_mut().set_metrics(true);
+    let live = interpreter.evaluator().metrics().unwrap().peak_scopes;
+    interpreter
+        .eval_str(r#"j := 0; while (j < 100) { s := "ab" + "cd"; a := [j, j]; j = j + 1; }"#)
+        .unwrap();
+
+    let metrics = interpreter.evaluator().metrics().unwrap();
+    assert_eq!(metrics.allocations, 100);
+    assert_eq!(metrics.peak_scopes, live + 1);
+    let array = Object::array(vec![Object::Null; 2]);
+    assert_eq!(metrics.memory, 100 * (4 + array.size() as u64));
+
+    interpreter.evaluator_mut().set_metrics(false);
+    assert_eq!(interpreter.evaluator().metrics(), None);
+}
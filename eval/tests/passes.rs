@@ -0,0 +1,105 @@
+use belalang_core::{
+    ast::{Expression, Program, Statement},
+    lexer::Lexer,
+    parser::Parser,
+};
+use belalang_eval::{
+    interpreter::Interpreter,
+    pass::{Pass, Passes},
+};
+
+fn parse(input: &str) -> Program {
+    Parser::new(Lexer::new(input.as_bytes()))
+        .parse_program()
+        .expect("parser errors")
+}
+
+fn optimized(passes: &Passes, input: &str) -> String {
+    let mut program = parse(input);
+    passes.run(&mut program);
+    program.to_string()
+}
+
+/// Replaces every integer statement with 0.
+struct Zero;
+
+impl Pass for Zero {
+    fn name(&self) -> &str {
+        "zero"
+    }
+
+    fn run(&self, program: &mut Program) {
+        for statement in &mut program.statements {
+            if let Statement::Expression(stmt) = statement {
+                if let Expression::Integer(int) = &mut stmt.expression {
+                    int.value = 0;
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn standard() {
+    let passes = Passes::standard();
+    assert_eq!(passes.enabled().collect::<Vec<_>>(), ["fold"]);
+    assert_eq!(
+        optimized(&passes, "2 * 3 + x;"),
+        optimized(&passes, "6 + x;")
+    );
+    assert_eq!(optimized(&passes, "return 1 + 1; x;"), "return 2;x;");
+}
+
+#[test]
+fn toggling() {
+    let mut passes = Passes::standard();
+    assert!(passes.set_enabled("fold", false));
+    assert!(!passes.set_enabled("inline", false));
+    assert!(passes.enabled().next().is_none());
+    assert_eq!(optimized(&passes, "return 1 + 1; x;"), "return (1 + 1);x;");
+
+    passes.set_enabled("fold", true);
+    assert_eq!(optimized(&passes, "return 1 + 1; x;"), "return 2;x;");
+
+    assert_eq!(optimized(&Passes::default(), "1 + 1;"), "(1 + 1);");
+}
+
+#[test]
+fn ordering() {
+    // folding first makes an integer for the next pass to zero
+    let mut passes = Passes::standard();
+    passes.add(Zero);
+    assert_eq!(optimized(&passes, "1 + 2;"), "0;");
+
+    let mut passes = Passes::default();
+    passes.add(Zero);
+    passes.add(belalang_eval::fold::ConstantFolding);
+    assert_eq!(optimized(&passes, "1 + 2;"), "3;");
+}
+
+#[test]
+fn interpreter() {
+    let mut passes = Passes::default();
+    passes.add(Zero);
+
+    let mut interpreter = Interpreter::builder().passes(passes).build();
+    assert_eq!(interpreter.eval_str("5;").unwrap().to_string(), "0");
+
+    interpreter.passes_mut().set_enabled("zero", false);
+    assert_eq!(interpreter.eval_str("5;").unwrap().to_string(), "5");
+
+    let template = Interpreter::builder().passes(Passes::standard()).template();
+    let mut interpreter = template.instantiate();
+    assert_eq!(
+        interpreter
+            .eval_str("f := fn(n) { return n * (2 + 3); n }; f(2);")
+            .unwrap()
+            .to_string(),
+        "10"
+    );
+
+    assert_eq!(
+        Interpreter::default().eval_str("5;").unwrap().to_string(),
+        "5"
+    );
+}
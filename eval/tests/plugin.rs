@@ -0,0 +1,15 @@
+#![cfg(all(feature = "plugins", target_os = "linux"))]
+
+use belalang_eval::{builtins::Builtins, error::PluginError};
+
+#[test]
+fn load_plugin_errors() {
+    let mut builtins = Builtins::default();
+
+    let err = unsafe { builtins.load_plugin("./no-such-plugin.so") }.unwrap_err();
+    assert!(matches!(err, PluginError::Load(_)));
+
+    // A library that isn't a plugin.
+    let err = unsafe { builtins.load_plugin("libc.so.6") }.unwrap_err();
+    assert!(err.to_string().contains("belalang_plugin_version"), "{err}");
+}
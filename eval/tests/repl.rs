@@ -0,0 +1,45 @@
+use belalang_eval::{
+    builtins::Builtins,
+    repl::{ReplEngine, Reply},
+    sync::{Locked, Shared},
+};
+
+#[test]
+fn repl() {
+    let output = Shared::new(Locked::new(Vec::new()));
+    let mut builtins = Builtins::default();
+    builtins.set_stdout(Shared::clone(&output));
+
+    let mut repl = ReplEngine::new(builtins);
+    assert_eq!(repl.prompt(), ">> ");
+
+    assert_eq!(repl.eval_line("x := 20;"), Reply::Value("20".into()));
+    assert_eq!(repl.eval_line("double := fn(n) {"), Reply::Incomplete);
+    assert_eq!(repl.prompt(), ".. ");
+    assert_eq!(repl.eval_line("  n * 2"), Reply::Incomplete);
+    assert!(matches!(repl.eval_line("};"), Reply::Value(_)));
+    assert_eq!(repl.prompt(), ">> ");
+
+    assert_eq!(
+        repl.eval_line(r#"println("hi"); [double(x + 1), "s"];"#),
+        Reply::Value(r#"[42, "s"]"#.into())
+    );
+    assert_eq!(String::from_utf8_lossy(&output.borrow()), "hi\n");
+
+    assert_eq!(
+        repl.eval_line("y;"),
+        Reply::Error("unknown variable: y".into())
+    );
+    assert!(matches!(repl.eval_line(")"), Reply::Error(_)));
+
+    assert_eq!(repl.eval_line("s := \"two"), Reply::Incomplete);
+    assert_eq!(
+        repl.eval_line("lines\";"),
+        Reply::Value("\"two\\nlines\"".into())
+    );
+
+    assert_eq!(repl.eval_line("if (true) {"), Reply::Incomplete);
+    repl.cancel();
+    assert_eq!(repl.prompt(), ">> ");
+    assert_eq!(repl.eval_line("x;"), Reply::Value("20".into()));
+}
@@ -0,0 +1,96 @@
+use belalang_eval::{
+    builtins::Builtins,
+    interpreter::Interpreter,
+    object::Object,
+    sandbox::{Capability, Profile},
+};
+use std::fs;
+
+fn interpreter(profile: Profile) -> Interpreter {
+    let mut builtins = Builtins::default();
+    builtins.restrict(profile);
+    Interpreter::new(builtins)
+}
+
+fn error(interpreter: &mut Interpreter, code: &str) -> String {
+    interpreter.eval_str(code).unwrap_err().to_string()
+}
+
+#[test]
+fn profiles() {
+    assert!(!Profile::Pure.allows(Capability::FilesystemRead));
+    assert!(Profile::FilesystemRead.allows(Capability::FilesystemRead));
+    assert!(!Profile::FilesystemRead.allows(Capability::FilesystemWrite));
+    assert!(Profile::Full.allows(Capability::Environment));
+    assert_eq!(Builtins::default().profile(), Profile::Full);
+}
+
+#[test]
+fn pure() {
+    let mut pure = interpreter(Profile::Pure);
+
+    assert_eq!(
+        pure.eval_str(r#"import "std/math"; math.abs(-2) + len("abc");"#)
+            .unwrap(),
+        Object::Integer(5)
+    );
+    assert_eq!(
+        error(&mut pure, r#"import "std/env"; env.env_get("HOME");"#),
+        "env.env_get is not allowed here"
+    );
+    assert_eq!(
+        error(&mut pure, r#"import "std/fs"; fs.exists(".");"#),
+        "fs.exists is not allowed here"
+    );
+    assert_eq!(error(&mut pure, "input();"), "input is not allowed here");
+    assert_eq!(
+        error(&mut pure, r#"import "lib.bl";"#),
+        r#"import "lib.bl" is not allowed here"#
+    );
+}
+
+#[test]
+fn filesystem_read() {
+    let dir = std::env::temp_dir().join(format!("belalang-sandbox-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("lib.bl"), "x := 1;").unwrap();
+    let dir = dir.to_string_lossy().replace('\\', "/");
+
+    let mut read = interpreter(Profile::FilesystemRead);
+    assert_eq!(
+        read.eval_str(&format!(r#"import "std/fs"; fs.exists("{dir}");"#))
+            .unwrap(),
+        Object::Boolean(true)
+    );
+    assert_eq!(
+        read.eval_str(&format!(r#"(import "{dir}/lib.bl").x;"#))
+            .unwrap(),
+        Object::Integer(1)
+    );
+    assert_eq!(
+        error(&mut read, &format!(r#"fs.mkdir("{dir}/sub");"#)),
+        "fs.mkdir is not allowed here"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn restrict_only_narrows() {
+    let mut builtins = Builtins::default();
+    builtins.restrict(Profile::Pure);
+    builtins.restrict(Profile::Full);
+    assert_eq!(builtins.profile(), Profile::Pure);
+
+    // The host can still hand out what it trusts.
+    builtins.register_fn("input", || "canned");
+    let mut interpreter = Interpreter::new(builtins);
+    assert_eq!(
+        interpreter.eval_str("input();").unwrap().to_string(),
+        "canned"
+    );
+    assert_eq!(
+        error(&mut interpreter, "read_line();"),
+        "read_line is not allowed here"
+    );
+}
@@ -0,0 +1,137 @@
+use belalang_eval::{
+    error::EvaluatorError,
+    interpreter::Interpreter,
+    native::{ScriptMethod, ScriptObject},
+    object::Object,
+};
+use std::sync::atomic::{AtomicI64, Ordering};
+
+#[derive(Debug)]
+struct Counter {
+    step: i64,
+    count: AtomicI64,
+}
+
+impl Counter {
+    fn bump(&self, times: i64) -> i64 {
+        self.count.fetch_add(self.step * times, Ordering::Relaxed) + self.step * times
+    }
+}
+
+impl ScriptObject for Counter {
+    fn type_name(&self) -> &str {
+        "Counter"
+    }
+
+    fn field(&self, name: &str) -> Option<Object> {
+        match name {
+            "step" => Some(self.step.into()),
+            _ => None,
+        }
+    }
+
+    fn has_method(&self, name: &str) -> bool {
+        name == "bump"
+    }
+
+    fn call_method(&self, name: &str, args: Vec<Object>) -> Result<Object, EvaluatorError> {
+        match name {
+            "bump" => Self::bump.invoke(self, args),
+            _ => Err(EvaluatorError::NotAFunction),
+        }
+    }
+}
+
+#[test]
+fn script_object() {
+    let mut interpreter = Interpreter::default();
+    let counter = Counter {
+        step: 2,
+        count: AtomicI64::new(0),
+    };
+    interpreter
+        .evaluator_mut()
+        .env_mut()
+        .set("counter".into(), counter.into_object());
+
+    let mut eval = |code: &str| interpreter.eval_str(code);
+    assert_eq!(
+        eval("counter.bump(1); counter.bump(3) + counter.step;").unwrap(),
+        Object::Integer(10)
+    );
+    assert_eq!(
+        eval("[type(counter), counter.bump];")
+            .unwrap()
+            .inspect()
+            .to_string(),
+        "[Counter, <method Counter.bump>]"
+    );
+    assert_eq!(
+        eval("counter;").unwrap().to_string(),
+        "Counter { step: 2, count: 8 }"
+    );
+
+    let mut err = |code: &str| interpreter.eval_str(code).unwrap_err().to_string();
+    assert_eq!(err("counter.count;"), "unknown member: count");
+    assert_eq!(err(r#"counter.bump("x");"#), "expected Int, got String");
+    assert_eq!(
+        err("counter.bump();"),
+        "wrong number of arguments: expected 1, got 0"
+    );
+
+    let Object::Native(native) = interpreter.eval_str("counter;").unwrap() else {
+        panic!("expected a native");
+    };
+    let counter = native.as_any().downcast_ref::<Counter>().unwrap();
+    assert_eq!(counter.count.load(Ordering::Relaxed), 8);
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive() {
+    #[derive(Debug, ScriptObject)]
+    #[script(name = "Person", methods(greet, visit))]
+    struct User {
+        name: String,
+        age: i64,
+        #[script(skip)]
+        visits: AtomicI64,
+    }
+
+    impl User {
+        fn greet(&self, other: String) -> String {
+            format!("hi {other}, I'm {}", self.name)
+        }
+
+        fn visit(&self) {
+            self.visits.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let user = User {
+        name: "ann".into(),
+        age: 30,
+        visits: AtomicI64::new(0),
+    };
+
+    let mut interpreter = Interpreter::default();
+    interpreter
+        .evaluator_mut()
+        .env_mut()
+        .set("user".into(), user.into_object());
+
+    let result = interpreter.eval_str(r#"user.visit(); [type(user), user.age, user.greet("bo")];"#);
+    assert_eq!(
+        result.unwrap().inspect().to_string(),
+        r#"[Person, 30, "hi bo, I'm ann"]"#
+    );
+
+    let err = interpreter.eval_str("user.visits;").unwrap_err();
+    assert_eq!(err.to_string(), "unknown member: visits");
+
+    let Object::Native(native) = interpreter.eval_str("user;").unwrap() else {
+        panic!("expected a native");
+    };
+    let user = native.as_any().downcast_ref::<User>().unwrap();
+    assert_eq!(user.visits.load(Ordering::Relaxed), 1);
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "serde")]
+
+use belalang_core::{lexer, parser};
+use belalang_eval::{environment::Environment, evaluator::Evaluator, object::Object};
+
+fn eval_in(ev: &mut Evaluator, input: &str) -> Object {
+    let lexer = lexer::Lexer::new(input.as_bytes());
+    let mut parser = parser::Parser::new(lexer);
+    let program = parser.parse_program().expect("parser errors");
+
+    ev.eval_program(program).expect("evaluator errors")
+}
+
+#[test]
+fn object_roundtrip() {
+    let object = Object::array(vec![
+        Object::Integer(1),
+        Object::Float(1.5),
+        Object::String("hi".into()),
+        Object::Null,
+    ]);
+
+    let json = serde_json::to_string(&object).unwrap();
+    let restored: Object = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(restored.to_string(), object.to_string());
+
+    let object = eval_in(&mut Evaluator::default(), r#"[1: "a", "b": [true: 2.5]];"#);
+
+    let json = serde_json::to_string(&object).unwrap();
+    let restored: Object = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(restored.inspect().to_string(), object.inspect().to_string());
+}
+
+#[test]
+fn environment_roundtrip() {
+    let mut ev = Evaluator::default();
+    eval_in(
+        &mut ev,
+        r#"base := 10; name := "belalang"; add := fn(x) { x + base };"#,
+    );
+
+    let json = serde_json::to_string(ev.env()).unwrap();
+    let env: Environment = serde_json::from_str(&json).unwrap();
+
+    let mut restored = Evaluator::default();
+    *restored.env_mut() = env;
+
+    assert_eq!(eval_in(&mut restored, "add(5);"), Object::Integer(15));
+    assert_eq!(
+        eval_in(&mut restored, "base = 1; add(5);"),
+        Object::Integer(6)
+    );
+    assert_eq!(eval_in(&mut restored, "name;").to_string(), "belalang");
+}
+
+#[test]
+fn cyclic_values() {
+    let mut ev = Evaluator::default();
+    let shared = eval_in(&mut ev, "a := [1]; [a, [\"a\": a]];");
+    assert_eq!(
+        serde_json::to_string(&shared).unwrap(),
+        serde_json::to_string(&eval_in(&mut ev, "[[1], [\"a\": [1]]];")).unwrap()
+    );
+
+    for input in ["push(a, a); a;", "d := [:]; d[0] = [d]; d;"] {
+        let cyclic = eval_in(&mut ev, input);
+        let err = serde_json::to_string(&cyclic).unwrap_err();
+        assert_eq!(err.to_string(), "cyclic value");
+    }
+
+    let err = serde_json::to_string(ev.env()).unwrap_err();
+    assert_eq!(err.to_string(), "cyclic value");
+}
+
+#[test]
+fn diagnostic_json() {
+    use belalang_core::diagnostic::{Diagnostic, Span};
+    use belalang_eval::error::EvaluatorError;
+
+    let diagnostic = Diagnostic::from(&EvaluatorError::UnknownVariable("x".into()))
+        .with_span(Span { start: 0, end: 1 })
+        .with_note("note");
+
+    assert_eq!(
+        serde_json::to_string(&diagnostic).unwrap(),
+        diagnostic.to_json()
+    );
+    assert_eq!(
+        serde_json::from_str::<Diagnostic>(&diagnostic.to_json()).unwrap(),
+        diagnostic
+    );
+}
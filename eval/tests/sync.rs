@@ -0,0 +1,37 @@
+#![cfg(feature = "sync")]
+
+use belalang_eval::{
+    builtins::Builtins,
+    interpreter::Interpreter,
+    object::Object,
+    sync::{Locked, Shared},
+};
+use std::thread;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn send_and_sync() {
+    assert_send_sync::<Object>();
+    assert_send_sync::<Builtins>();
+    assert_send_sync::<Interpreter>();
+
+    let output = Shared::new(Locked::new(Vec::new()));
+    let mut builtins = Builtins::default();
+    builtins.set_stdout(Shared::clone(&output));
+
+    let mut interpreter = Interpreter::new(builtins);
+    interpreter
+        .eval_str("items := [1, 2]; total := fn() { items[0] + items[1] };")
+        .unwrap();
+
+    let items = thread::spawn(move || {
+        interpreter.eval_str("println(total());").unwrap();
+        interpreter.eval_str("push(items, 3); items;").unwrap()
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(Vec::<i64>::try_from(items).unwrap(), vec![1, 2, 3]);
+    assert_eq!(&*output.borrow(), b"3\n");
+}
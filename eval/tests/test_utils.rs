@@ -0,0 +1,17 @@
+#![cfg(feature = "test-utils")]
+
+use belalang_eval::{eval, object::Object};
+
+#[test]
+fn macros() {
+    eval!("1 + 2;", Object::Integer = 3);
+    eval!(String::from("\"a\" + \"b\";"), Object::String = "ab".into());
+    eval!("[1];", Object::Array(_));
+    eval!("x;", Err => "unknown variable: x");
+}
+
+#[test]
+#[should_panic(expected = "got unexpected object")]
+fn unexpected_object() {
+    eval!("1;", Object::Null);
+}
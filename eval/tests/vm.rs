@@ -0,0 +1,498 @@
+use belalang_core::{lexer::Lexer, parser::Parser};
+use belalang_eval::{
+    builtins::Builtins,
+    bytecode::{disassemble, file, Bytecode},
+    compiler::Compiler,
+    error::{EvaluatorError, LoadError},
+    evaluator::Evaluator,
+    gc::Thresholds,
+    interpreter::Interpreter,
+    metrics::Limits,
+    object::Object,
+    sync::{Locked, Shared},
+    vm::Vm,
+};
+
+fn parse(input: &str) -> belalang_core::ast::Program {
+    Parser::new(Lexer::new(input.as_bytes()))
+        .parse_program()
+        .expect("parser errors")
+}
+
+fn run(input: &str) -> Result<String, String> {
+    let bytecode = Compiler::new()
+        .compile_program(&parse(input))
+        .map_err(|err| err.to_string())?;
+
+    let mut ev = Evaluator::default();
+    let result = Vm::new(&mut ev).run(&bytecode);
+    result
+        .map(|value| value.inspect().to_string())
+        .map_err(|err| err.to_string())
+}
+
+/// Checks that the VM gets what the evaluator does.
+fn same(input: &str) {
+    let evaluated = Evaluator::default()
+        .eval_program(parse(input))
+        .map(|value| value.inspect().to_string())
+        .map_err(|err| err.to_string());
+
+    assert_eq!(run(input), evaluated, "{input}");
+}
+
+#[test]
+fn expressions() {
+    same("1 + 2 * 3 - 4 / 2 % 3;");
+    same("-5; --5.5;");
+    same("!true == false && 1 < 2 || 3 >= 4;");
+    same(r#""a" + "b" * 3;"#);
+    same("[1, 2 + 3, [4]];");
+    same(r#"["a": 1, "b": [2]];"#);
+    same("[1, 2, 3][1];");
+    same(r#"["a": 1]["b"];"#);
+    same("2 in [1, 2]; 3 is Int;");
+    same("1 + true;");
+    same(r#"[[1]: 2];"#);
+    same("1 / 0;");
+    same("x := 0; 5 % x;");
+    same("9223372036854775807 + 1;");
+    same("x := -9223372036854775807 - 1; [x / -1];");
+    same("x := -9223372036854775807 - 1; -x;");
+    same(
+        r#"v := ["x": 1, "__add__": fn(a, b) { a.x + b.x }, "__index__": fn(v, i) { i }];
+        [v + v, v[3], str(["__str__": fn(v) { "s" }])];"#,
+    );
+    same("");
+}
+
+#[test]
+fn variables() {
+    same("a := 1; a = a + 1; a;");
+    same("a := 1; { a := 2; a = 3; }; a;");
+    same("a := 1; a := 2;");
+    same("b = 1;");
+    same("print := 1;");
+    same("x;");
+    same("a := [1, 2]; a[0] = 5; a;");
+    same(r#"d := [:]; d["k"] = 1; d;"#);
+}
+
+#[test]
+fn control_flow() {
+    same("if (1 < 2) { 10 } else { 20 };");
+    same("if (1 > 2) { 10 };");
+    same("if (1 > 2) { 10 } else if (true) { 30 } else { 20 };");
+    same("if (1) { 10 } else { 20 };");
+    same("i := 0; s := 0; while (i < 10) { s = s + i; i = i + 1; }; s;");
+    same("s := 0; for (x in [1, 2, 3]) { s = s + x; }; s;");
+    same(r#"s := ""; for (c in "abc") { s = c + s; }; s;"#);
+    same("for (x in [1]) { x := 2; };");
+    same("for (x in 5) {};");
+    same("return 1;");
+}
+
+#[test]
+fn null_handling() {
+    same(r#"d := ["a": ["b": 1]]; d?.a; d["x"]?["y"]; d["x"]?.y.z;"#);
+    same(r#"d := ["a": 1]; d["b"] ?? 2; d["a"] ?? 2;"#);
+    same("null?.x ?? 3;");
+}
+
+#[test]
+fn functions() {
+    same("add := fn(a, b) { a + b }; add(1, 2);");
+    same("f := fn(n) { if (n < 2) { return n; }; f(n - 1) + f(n - 2) }; f(15);");
+    same("make := fn(n) { fn(x) { x + n } }; make(3)(4);");
+    same(
+        "counter := fn() { c := 0; fn() { c = c + 1; c } }; \
+         next := counter(); next(); next(); next();",
+    );
+    same("f := fn() { for (x in [1, 2, 3]) { if (x == 2) { return x * 10; }; }; 0 }; f();");
+    same("map([1, 2, 3], fn(x) { x * 2 });");
+    same("f := fn(a, b) { b }; f;");
+    same("len([1, 2]) + len(\"abc\");");
+    same("1(2);");
+    same("f := fn() { undefined }; f();");
+    same("f := fn(a) { b := a * 2; { c := b + 1; b = c; }; b }; f(3);");
+    same("f := fn(a) { a := 1; }; f(0);");
+    same("f := fn() { print := 1; }; f();");
+    same("f := fn() { { x := 1; }; x }; f();");
+    same("f := fn() { s := 0; for (x in [1, 2]) { s = s + x; }; s }; f();");
+    same("f := fn() { i := 0; while (i < 3) { j := i; i = j + 1; }; i }; f();");
+    same("g := 5; f := fn() { g = g + 1; g }; f(); g;");
+    same("{ a := 1; { b := a + 1; b } };");
+
+    // a function keeping its variables in slots isn't passed every
+    // argument, they are null
+    assert_eq!(run("f := fn(a, b) { b }; f(1);"), Ok("null".into()));
+    assert_eq!(run("f := fn(a) { a }; f(1, 2);"), Ok("1".into()));
+}
+
+#[test]
+fn closures() {
+    same("f := fn(a) { fn(b) { fn(c) { a + b + c } } }; f(1)(2)(3);");
+    same("f := fn() { x := 1; g := fn() { x }; x = 5; g() }; f();");
+    same(
+        "f := fn() { n := 0; inc := fn() { n = n + 1; }; get := fn() { n }; \
+         inc(); inc(); get() }; f();",
+    );
+    same(
+        "f := fn() { fact := fn(n) { if (n < 2) { return 1; }; n * fact(n - 1) }; \
+         fact(5) }; f();",
+    );
+    same(
+        "f := fn() { \
+           even := fn(n) { if (n == 0) { return true; }; odd(n - 1) }; \
+           odd := fn(n) { if (n == 0) { return false; }; even(n - 1) }; \
+           even(10) }; f();",
+    );
+    same("f := fn() { a := 1; { g := fn() { a }; a := 2; g() } }; f();");
+    same("f := fn() { k := 10; map([1, 2], fn(x) { x + k }) }; f();");
+
+    // every iteration has variables of its own
+    same("fs := [:]; for (i in [1, 2, 3]) { fs[i] = fn() { i }; }; fs[1]() + fs[3]();");
+    same(
+        "f := fn() { i := 0; fs := [:]; \
+         while (i < 3) { j := i; fs[i] = fn() { j }; i = i + 1; }; \
+         fs[0]() + fs[2]() }; f();",
+    );
+}
+
+#[test]
+fn shares_evaluator_state() {
+    let mut ev = Evaluator::default();
+    ev.eval_program(parse("base := 10; twice := fn(x) { x * 2 };"))
+        .unwrap();
+
+    let bytecode = Compiler::new()
+        .compile_program(&parse("shift := fn(x) { twice(x) + base }; shift(1);"))
+        .unwrap();
+    let result = Vm::new(&mut ev).run(&bytecode).unwrap();
+    assert_eq!(result, Object::Integer(12));
+
+    // functions made by the VM can be called by the evaluator
+    let result = ev.eval_program(parse("shift(2);")).unwrap();
+    assert_eq!(result, Object::Integer(14));
+
+    // and keep what they captured
+    let bytecode = Compiler::new()
+        .compile_program(&parse("make := fn(n) { fn() { n } }; seven := make(7);"))
+        .unwrap();
+    Vm::new(&mut ev).run(&bytecode).unwrap();
+    let result = ev.eval_program(parse("seven();")).unwrap();
+    assert_eq!(result, Object::Integer(7));
+}
+
+#[test]
+fn errors_leave_the_scope() {
+    let mut ev = Evaluator::default();
+
+    let bytecode = Compiler::new()
+        .compile_program(&parse("a := 1; { b := 2; c; };"))
+        .unwrap();
+    assert!(Vm::new(&mut ev).run(&bytecode).is_err());
+
+    assert!(ev.env().has("a".into()));
+    assert!(!ev.env().has("b".into()));
+}
+
+#[test]
+fn stack_overflow() {
+    let bytecode = Compiler::new()
+        .compile_program(&parse("f := fn(n) { g := fn() { f(n + 1) }; g() }; f(0);"))
+        .unwrap();
+
+    let mut ev = Evaluator::default();
+    ev.set_limits(Limits {
+        frames: Some(50),
+        ..Limits::default()
+    });
+
+    let err = Vm::new(&mut ev).run(&bytecode).unwrap_err();
+    let EvaluatorError::StackOverflow(50, trace) = &err else {
+        panic!("not a stack overflow: {err}");
+    };
+    assert_eq!(trace.0.len(), 51);
+    assert_eq!(trace.0[0].function, "g");
+    assert_eq!(trace.0[1].function, "f");
+    assert_eq!(trace.0[50].function, "<program>");
+    assert_eq!(
+        trace.to_string().lines().take(3).collect::<Vec<_>>(),
+        ["in g at 0010", "in f at 0010", "in g at 0010"]
+    );
+    assert!(trace.to_string().ends_with("... and 41 more"));
+
+    // the calls were left, so there is room for more
+    let bytecode = Compiler::new()
+        .compile_program(&parse("h := fn(n) { if (n > 0) { h(n - 1) } }; h(40);"))
+        .unwrap();
+    assert!(Vm::new(&mut ev).run(&bytecode).is_ok());
+
+    ev.set_limits(Limits {
+        depth: Some(10),
+        ..Limits::default()
+    });
+    let bytecode = Compiler::new().compile_program(&parse("h(40);")).unwrap();
+    let err = Vm::new(&mut ev).run(&bytecode).unwrap_err();
+    assert!(matches!(err, EvaluatorError::DepthExceeded(10)));
+
+    ev.set_limits(Limits {
+        memory: Some(4096),
+        ..Limits::default()
+    });
+    let bytecode = Compiler::new()
+        .compile_program(&parse(
+            "keep := [:]; i := 0; while (true) { keep[i] = [i]; i = i + 1; }",
+        ))
+        .unwrap();
+    let err = Vm::new(&mut ev).run(&bytecode).unwrap_err();
+    assert!(matches!(err, EvaluatorError::MemoryExceeded(4096)));
+}
+
+#[test]
+fn garbage_collection() {
+    let run_on = |ev: &mut Evaluator, input: &str| {
+        let bytecode = Compiler::new().compile_program(&parse(input)).unwrap();
+        let result = Vm::new(ev).run(&bytecode);
+        result.unwrap().inspect().to_string()
+    };
+
+    let mut ev = Evaluator::default();
+    ev.set_gc_thresholds(Thresholds {
+        initial: None,
+        ..Thresholds::default()
+    });
+
+    // an array containing itself, and a closure holding itself as an upvalue
+    run_on(
+        &mut ev,
+        "a := [1]; push(a, a); a = 0; \
+         f := fn() { g := fn() { g }; g }; h := f(); h = 0;",
+    );
+    assert_eq!(ev.collect_garbage(), 2);
+    assert_eq!(ev.collect_garbage(), 0);
+
+    run_on(&mut ev, "kept := [[1], [\"a\": 2]]; get := f();");
+    assert_eq!(ev.collect_garbage(), 0);
+    assert_eq!(
+        run_on(&mut ev, "[kept, get()()];"),
+        r#"[[[1], ["a": 2]], <fn()>]"#
+    );
+
+    // nothing is collected while running unless it's due
+    ev.set_metrics(true);
+    let cycles = "i = 0; while (i < 100) { a := [i]; push(a, a); i = i + 1; };";
+    run_on(&mut ev, "i := 0;");
+    run_on(&mut ev, cycles);
+    assert_eq!(ev.metrics().unwrap().collections, 0);
+    assert_eq!(ev.collect_garbage(), 100);
+
+    ev.set_gc_thresholds(Thresholds {
+        initial: Some(10),
+        growth: 2,
+    });
+    ev.set_metrics(true);
+    run_on(&mut ev, cycles);
+    assert!(ev.metrics().unwrap().collections >= 5);
+    assert!(ev.collect_garbage() < 20);
+}
+
+#[test]
+fn collecting_keeps_values_in_use() {
+    let thresholds = Thresholds {
+        initial: Some(1),
+        growth: 1,
+    };
+
+    for input in [
+        "map([1, 2, 3, 4, 5, 6], fn(x) { [x, [x]] });",
+        "f := fn(a) { b := [a]; { c := [b]; g := fn() { c }; [g()[0][0], len(b)] } }; f(7);",
+        "f := fn(n) { if (n == 0) { return [:]; }; d := [\"n\": [n]]; d[\"next\"] = f(n - 1); d }; f(5);",
+        "make := fn() { c := [0]; fn() { c[0] = c[0] + 1; [c[0]] } }; inc := make(); inc(); inc()[0];",
+        "s := 0; for (x in [[1], [2], [3]]) { y := [x]; s = s + y[0][0]; }; s;",
+    ] {
+        let mut ev = Evaluator::default();
+        ev.set_gc_thresholds(thresholds);
+        ev.set_metrics(true);
+
+        let bytecode = Compiler::new().compile_program(&parse(input)).unwrap();
+        let result = Vm::new(&mut ev).run(&bytecode).unwrap();
+        assert!(ev.metrics().unwrap().collections > 0, "{input}");
+
+        let evaluated = Evaluator::default().eval_program(parse(input)).unwrap();
+        assert_eq!(
+            result.inspect().to_string(),
+            evaluated.inspect().to_string(),
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn collecting_keeps_values_the_host_holds() {
+    let compile = |input: &str| Compiler::new().compile_program(&parse(input)).unwrap();
+    let garbage = compile("i := 0; while (i < 30000) { a := [i]; push(a, a); i = i + 1; };");
+
+    // the result of a run
+    let mut interpreter = Interpreter::builder()
+        .gc(Thresholds {
+            initial: Some(1),
+            growth: 1,
+        })
+        .build();
+    let kept = interpreter
+        .run_bytecode(&compile("f := fn() { [[1], [\"a\": 2]] }; f();"))
+        .unwrap();
+    interpreter.run_bytecode(&garbage).unwrap();
+    assert_eq!(kept.inspect().to_string(), r#"[[1], ["a": 2]]"#);
+
+    // an argument a builtin kept
+    let kept = Shared::new(Locked::new(Vec::new()));
+    let mut interpreter = Interpreter::builder()
+        .register("keep", {
+            let kept = kept.clone();
+            move |_, args| {
+                kept.borrow_mut().extend(args);
+                Ok(Object::Null)
+            }
+        })
+        .gc(Thresholds {
+            initial: Some(1),
+            growth: 1,
+        })
+        .build();
+    interpreter
+        .run_bytecode(&compile(
+            "f := fn() { a := [1, 2]; push(a, a); keep(a); }; f();",
+        ))
+        .unwrap();
+    interpreter.run_bytecode(&garbage).unwrap();
+    assert_eq!(kept.borrow()[0].inspect().to_string(), "[1, 2, [...]]");
+
+    // a constant shared by the interpreters of a template
+    let mut builtins = Builtins::default();
+    builtins.register_constant("CONF", Object::array(vec![Object::Integer(1)]));
+    let template = Interpreter::builder().builtins(builtins).template();
+
+    let mut interpreter = template.instantiate();
+    interpreter.run_bytecode(&compile("freeze(CONF);")).unwrap();
+    interpreter.run_bytecode(&garbage).unwrap();
+    assert_eq!(
+        template
+            .instantiate()
+            .run_bytecode(&compile("CONF;"))
+            .unwrap()
+            .inspect()
+            .to_string(),
+        "[1]"
+    );
+}
+
+#[test]
+fn generators() {
+    same("gen := fn(n) { yield n; yield n + 1; }; collect(gen(5));");
+    same("f := fn(a) { for (x in a) { if (x % 2 == 0) { yield x; } else { 0; } } }; collect(f([1, 2, 4]));");
+    same("f := fn(n) { m := n * 2; g := fn() { yield m; yield n; }; collect(g()) }; f(3);");
+    same("f := fn(n) { h := fn() { fn() { yield n; } }; collect(h()()) }; f(1);");
+    same("t := []; i := 0; while (i < 2) { j := i; t = t + collect((fn() { yield j; })()); i += 1; }; t;");
+    same("gen := fn() { yield 1; }; it := gen(); next(it); next(it);");
+    same("yield 1;");
+    same("f := fn() { [1].map(fn(x) { yield x; }) }; f();");
+}
+
+#[test]
+fn disassembly() {
+    let bytecode = Compiler::new()
+        .compile_program(&parse(
+            "f := fn(x) { g := fn() { x + 1 }; g() }; if (f(1) > 1) { \"big\" };",
+        ))
+        .unwrap();
+
+    assert_eq!(
+        disassemble(&bytecode),
+        "0000 Closure        0 (<fn(x)>)
+0003 DeclareGlobal  0 (f)
+0006 Pop
+0007 GetGlobal      0 (f)
+0010 Constant       1 (1)
+0013 Call           1
+0016 Constant       1 (1)
+0019 Gt
+0020 JumpIfFalse    -> 0029
+0023 Constant       2 (\"big\")
+0026 Jump           -> 0030
+0029 Null
+
+constant 0, <fn(x)>:
+0000 Closure        0 (<fn()>)
+0003 DeclareLocal   1 (g)
+0006 Pop
+0007 GetLocal       1 (g)
+0010 Call           0
+0013 Return
+
+constant 0.0, <fn()>:
+captures local 0 (x)
+0000 GetUpvalue     0 (x)
+0003 Constant       0 (1)
+0006 Add
+0007 Return
+"
+    );
+}
+
+#[test]
+fn saved_files() {
+    let bytecode = Compiler::new()
+        .compile_program(&parse(
+            r#"f := fn(x, y) { [x * 2.5, y + "!"] }; f(2.0, "hi")[1];"#,
+        ))
+        .unwrap();
+
+    let mut saved = Vec::new();
+    bytecode.save(&mut saved).unwrap();
+    assert!(saved.starts_with(file::MAGIC));
+
+    let loaded = Bytecode::load(saved.as_slice()).unwrap();
+    assert_eq!(loaded, bytecode);
+
+    let generator = Compiler::new()
+        .compile_program(&parse(
+            "gen := fn(n) { while (true) { yield n; }; }; next(gen(1));",
+        ))
+        .unwrap();
+    let mut saved_generator = Vec::new();
+    generator.save(&mut saved_generator).unwrap();
+    let loaded_generator = Bytecode::load(saved_generator.as_slice()).unwrap();
+    assert_eq!(loaded_generator, generator);
+    let mut ev = Evaluator::default();
+    let result = Vm::new(&mut ev).run(&loaded_generator).unwrap();
+    assert_eq!(result.inspect().to_string(), "1");
+
+    let mut ev = Evaluator::default();
+    let result = Vm::new(&mut ev).run(&loaded).unwrap();
+    assert_eq!(result.inspect().to_string(), r#""hi!""#);
+
+    let err = Bytecode::load(&saved[..saved.len() - 1]).unwrap_err();
+    assert!(matches!(err, LoadError::Truncated));
+
+    let err = Bytecode::load(&b"#!/usr/bin/env belalang"[..]).unwrap_err();
+    assert!(matches!(err, LoadError::NotBytecode));
+
+    saved[5] += 1;
+    let err = Bytecode::load(saved.as_slice()).unwrap_err();
+    assert!(matches!(err, LoadError::Version(6)));
+}
+
+#[test]
+fn invalid_bytecode() {
+    let bytecode = Bytecode {
+        code: vec![0, 0, 7, 255],
+        ..Bytecode::default()
+    };
+
+    let mut ev = Evaluator::default();
+    let err = Vm::new(&mut ev).run(&bytecode).unwrap_err();
+    assert_eq!(err.to_string(), "invalid bytecode: no constant 7");
+}
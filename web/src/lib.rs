@@ -1,9 +1,10 @@
-use belalang_core::{lexer::Lexer, parser::Parser};
+use belalang_core::{diagnostic, lint::Linter, parser::Parser};
 use belalang_eval::{
-    builtins::{self, Builtins},
-    evaluator::Evaluator,
-    object::Object,
+    builtins::Builtins,
+    interpreter::Interpreter,
+    sync::{Locked, Shared},
 };
+use std::io;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -11,43 +12,88 @@ extern "C" {
     fn println(value: &str);
 }
 
-#[wasm_bindgen(start)]
-pub fn start() {
-    let mut fns = builtins::BUILTIN_FUNCTIONS.lock().unwrap();
-
-    fns.insert(
-        "println".into(),
-        Box::new(|args| {
-            println(
-                &args
-                    .iter()
-                    .map(|arg| arg.to_string())
-                    .collect::<Vec<_>>()
-                    .join(" "),
-            );
-
-            Object::Null
-        }),
-    );
+/// Passes script output to the page's `println` one line at a time.
+#[derive(Default)]
+struct PageOutput {
+    line: Vec<u8>,
+}
+
+impl io::Write for PageOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            if byte == b'\n' {
+                println(&String::from_utf8_lossy(&self.line));
+                self.line.clear();
+            } else {
+                self.line.push(byte);
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.line.is_empty() {
+            println(&String::from_utf8_lossy(&self.line));
+            self.line.clear();
+        }
+
+        Ok(())
+    }
 }
 
 #[wasm_bindgen]
 pub fn run_code(input: String) {
-    let lexer = Lexer::new(input.as_bytes());
-    let mut parser = Parser::new(lexer);
+    let output = Shared::new(Locked::new(PageOutput::default()));
 
-    match parser.parse_program() {
-        Ok(program) => {
-            let builtins = Builtins::default();
-            let mut ev = Evaluator::new(builtins);
+    let mut builtins = Builtins::default();
+    builtins.set_stdout(Shared::clone(&output));
+    builtins.set_stderr(Shared::clone(&output));
 
-            if let Err(err) = ev.eval_program(program) {
-                println(&err.to_string());
-            }
-        }
-        Err(err) => {
-            println(&err.to_string());
-            return;
-        }
+    let result = Interpreter::new(builtins).eval_str(&input);
+
+    let _ = io::Write::flush(&mut *output.borrow_mut());
+
+    if let Err(err) = result {
+        println(&err.to_string());
     }
 }
+
+/// Evaluates `input` and returns what it printed, followed by the error
+/// message if it failed.
+#[wasm_bindgen]
+pub fn eval(input: &str) -> String {
+    let output = Shared::new(Locked::new(Vec::new()));
+
+    let mut builtins = Builtins::default();
+    builtins.set_stdout(Shared::clone(&output));
+    builtins.set_stderr(Shared::clone(&output));
+
+    let result = Interpreter::new(builtins).eval_str(input);
+
+    let mut text = String::from_utf8_lossy(&output.borrow()).into_owned();
+    if let Err(err) = result {
+        text.push_str(&err.to_string());
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Checks `input` without running it. Returns every syntax error, or the
+/// lint warnings if there are none, as a JSON array of
+/// [`diagnostic::Diagnostic::to_json`] objects. Spans are byte offsets into
+/// `input`.
+#[wasm_bindgen]
+pub fn diagnostics(input: &str) -> String {
+    let (program, mut diagnostics) = Parser::parse_program_lossy(input.as_bytes());
+
+    // statements with errors are left out, so linting would report their
+    // variables as unknown
+    if diagnostics.is_empty() {
+        let builtins = Builtins::default();
+        diagnostics = Linter::new().with_builtins(builtins.names()).lint(&program);
+    }
+
+    diagnostic::to_json(&diagnostics)
+}